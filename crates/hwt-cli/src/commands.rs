@@ -1,7 +1,22 @@
 //! CLI command implementations.
 
-use anyhow::{bail, Result};
-use hwt_core::{HardwareDomain, Project};
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use hwt_core::constraint::Constraint;
+use hwt_core::drc_export::{DrcExporter, ReportFormat};
+use hwt_core::drc_rules::CustomRuleSet;
+use hwt_core::fab::{find_profile, load_profiles, profile_dirs, FabChecker};
+use hwt_core::kicad::KicadPcbImporter;
+use hwt_core::layout::{Pad, PlacedComponent, Trace};
+use hwt_core::net::Net;
+use hwt_core::pcb_drc::{DrcCheck, PcbDesignRules, PcbDrcChecker};
+use hwt_core::ratsnest::Ratsnest;
+use hwt_core::{CircuitJson, HardwareDomain, Layout, LengthUnit, Position, Project};
+
+/// Project file looked for in a directory given as input.
+const PROJECT_FILE: &str = "project.hwt";
 
 /// Create a new project.
 pub fn new_project(name: &str, domain: &str, output: Option<&str>) -> Result<()> {
@@ -9,7 +24,7 @@ pub fn new_project(name: &str, domain: &str, output: Option<&str>) -> Result<()>
     let project = Project::new(name, domain);
 
     let output_dir = output.unwrap_or(".");
-    let project_path = format!("{}/{}/project.hwt", output_dir, name);
+    let project_path = format!("{}/{}/{}", output_dir, name, PROJECT_FILE);
 
     println!("Creating new {} project: {}", domain.display_name(), name);
     println!("  Domain: {} {}", domain.icon(), domain.display_name());
@@ -83,30 +98,340 @@ pub fn export_project(format: &str, output: &str) -> Result<()> {
 }
 
 /// Run design rule check.
-pub fn run_drc(path: &str, report: Option<&str>) -> Result<()> {
+pub fn run_drc(path: &str, report: Option<&str>, format: Option<&str>) -> Result<()> {
+    let path = &input_file(path)?;
     println!("Running DRC on {}...", path);
 
-    if let Some(report_path) = report {
-        println!("  Report: {}", report_path);
+    let report_format = match report {
+        Some(report_path) => {
+            println!("  Report: {}", report_path);
+            Some(report_format(report_path, format)?)
+        }
+        None => None,
+    };
+
+    let input = load_drc_input(path)?;
+    let layout = &input.layout;
+    let drc = input.checker().check_all();
+
+    println!("\nViolations: {}", drc.violations.len());
+    for violation in &drc.violations {
+        println!("  [{}] {}", violation.rule, violation.message);
     }
 
-    // TODO: Implement DRC
-    println!("\n(DRC not yet implemented - coming soon!)");
+    let ratsnest = Ratsnest::compute(layout);
+    println!(
+        "\nRouting completion: {:.1}% ({} unrouted connection(s))",
+        ratsnest.completion_percent(),
+        ratsnest.unrouted_count()
+    );
+    for open in ratsnest.open_connections() {
+        println!(
+            "  {}: ({:.3}, {:.3}) -> ({:.3}, {:.3}) {:.3}mm",
+            open.net, open.from.x, open.from.y, open.to.x, open.to.y, open.length
+        );
+    }
+
+    let exporter = DrcExporter::new(&drc)
+        .with_layout(layout)
+        .with_rules(PcbDrcChecker::available_rules())
+        .with_source(path);
+    if let (Some(report_path), Some(report_format)) = (report, report_format) {
+        exporter.write(Path::new(report_path), report_format)
+            .with_context(|| format!("Failed to write {}", report_path))?;
+        println!("\n✓ Report written to {}", report_path);
+    }
+
+    let errors = exporter.error_count();
+    if errors > 0 {
+        bail!("DRC failed with {} error(s)", errors);
+    }
 
     Ok(())
 }
 
+/// File to check: `path` itself, or the project file of a directory.
+fn input_file(path: &str) -> Result<String> {
+    let dir = Path::new(path);
+    if !dir.is_dir() {
+        return Ok(path.to_string());
+    }
+    let project = dir.join(PROJECT_FILE);
+    if !project.is_file() {
+        bail!("{} is a directory without a {}; pass a project or layout file", path, PROJECT_FILE);
+    }
+    Ok(project.to_string_lossy().into_owned())
+}
+
+/// Report format from `--format`, or else the report file name.
+fn report_format(path: &str, format: Option<&str>) -> Result<ReportFormat> {
+    match format {
+        Some(name) => ReportFormat::from_name(name)
+            .with_context(|| format!("Unknown report format '{}' (expected json, junit, sarif or html)", name)),
+        None => ReportFormat::from_path(Path::new(path))
+            .with_context(|| format!("Cannot infer report format of {}; use --format", path)),
+    }
+}
+
+/// A layout with the rules and circuit data it is checked against.
+struct DrcInput {
+    layout: Layout,
+    design_rules: PcbDesignRules,
+    rules: CustomRuleSet,
+    nets: Vec<Net>,
+    constraints: Vec<Constraint>,
+}
+
+impl DrcInput {
+    /// Layout with default design rules and no circuit data or custom rules.
+    fn from_layout(layout: Layout) -> Self {
+        Self {
+            layout,
+            design_rules: PcbDesignRules::default(),
+            rules: CustomRuleSet::default(),
+            nets: Vec::new(),
+            constraints: Vec::new(),
+        }
+    }
+
+    /// DRC checker for the layout and its rules.
+    fn checker(&self) -> PcbDrcChecker<'_> {
+        let net_classes: HashMap<String, String> = self.nets.iter()
+            .filter_map(|net| Some((net.name.clone(), net.class.clone()?)))
+            .collect();
+        PcbDrcChecker::new(&self.layout, self.design_rules.clone())
+            .with_custom_rules(self.rules.clone())
+            .with_net_classes(net_classes)
+            .with_nets(self.nets.clone())
+            .with_constraints(self.constraints.clone())
+    }
+}
+
+/// Load the layout, rules and circuit data to check. A project file supplies
+/// its PCB layout, custom rules and the design rules of its fab profile at
+/// the heaviest stackup copper weight; other paths are loaded as layouts
+/// with default design rules.
+fn load_drc_input(path: &str) -> Result<DrcInput> {
+    if !(path.ends_with(".hwt") || path.ends_with(".toml")) {
+        return load_layout(path);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    let project = Project::from_toml(&content)
+        .with_context(|| format!("Failed to parse {}", path))?;
+    let Some(pcb) = project.pcb else {
+        bail!("{} has no PCB configuration", path);
+    };
+    let rules = CustomRuleSet::compile(&pcb.rules)
+        .with_context(|| format!("Invalid custom rule in {}", path))?;
+    let project_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let layout_path = project_dir.join(&pcb.layout);
+    let mut input = load_layout(&layout_path.to_string_lossy())?;
+    let mut copper_weight = 1.0;
+    if let Some(stackup) = &pcb.stackup {
+        let stackup = stackup.resolve().with_context(|| format!("Invalid stackup in {}", path))?;
+        stackup.apply_to(&mut input.layout)
+            .with_context(|| format!("Stackup {} does not fit {}", stackup.name, layout_path.display()))?;
+        copper_weight = stackup.copper_layers()
+            .filter_map(|l| l.copper_weight)
+            .reduce(f64::max)
+            .unwrap_or(copper_weight);
+    }
+    let design_rules = match &pcb.fab {
+        Some(name) => find_profile(name, Some(project_dir))?
+            .design_rules(copper_weight)
+            .with_context(|| format!("Fab profile of {} does not fit its stackup", path))?,
+        None => PcbDesignRules::default(),
+    };
+    Ok(DrcInput { design_rules, rules, ..input })
+}
+
+/// Load a layout, with its nets and constraints from a Circuit JSON file,
+/// or alone from a KiCad PCB.
+fn load_layout(path: &str) -> Result<DrcInput> {
+    if path.ends_with(".kicad_pcb") {
+        let layout = KicadPcbImporter::import(path)
+            .with_context(|| format!("Failed to import {}", path))?;
+        return Ok(DrcInput::from_layout(layout));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    let circuit = CircuitJson::from_json(&content)
+        .with_context(|| format!("Failed to parse {}", path))?;
+    match circuit.layout {
+        Some(layout) => Ok(DrcInput {
+            nets: circuit.nets,
+            constraints: circuit.constraints,
+            ..DrcInput::from_layout(layout)
+        }),
+        None => bail!("{} has no layout data", path),
+    }
+}
+
+/// List the fab profiles available to a project.
+pub fn list_fab_profiles(path: &str) -> Result<()> {
+    let dirs = profile_dirs(Some(Path::new(path)));
+    let profiles = load_profiles(&dirs)?;
+
+    println!("Fab profiles:");
+    for profile in &profiles {
+        let weights: Vec<String> = profile.copper.iter().map(|c| format!("{}oz", c.weight_oz)).collect();
+        println!(
+            "  {:<12} {} (up to {} layers, {})",
+            profile.name,
+            profile.description.as_deref().unwrap_or(""),
+            profile.max_layers,
+            weights.join("/")
+        );
+    }
+    println!("\nSearched:");
+    for dir in &dirs {
+        println!("  {}", dir.display());
+    }
+
+    Ok(())
+}
+
+/// Check a layout against a fab profile and explain what exceeds it.
+pub fn run_fab_check(path: &str, profile: Option<&str>, copper_weight: f64, thickness: Option<f64>) -> Result<()> {
+    let path = &input_file(path)?;
+    let project_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let name = match profile {
+        Some(name) => name.to_string(),
+        None => project_fab_profile(path)?,
+    };
+    let profile = find_profile(&name, Some(project_dir))?;
+    let layout = load_drc_input(path)?.layout;
+
+    let mut checker = FabChecker::new(&layout, &profile).with_copper_weight(copper_weight);
+    if let Some(thickness) = thickness {
+        checker = checker.with_board_thickness(thickness);
+    }
+    let report = checker.check();
+
+    println!(
+        "Checking {} against {} ({}oz copper, {:.2}mm board)...",
+        path, profile.name, copper_weight, checker.board_thickness()
+    );
+    if report.violations.is_empty() {
+        println!("\nAll board features are within {} capabilities.", profile.name);
+        return Ok(());
+    }
+    println!("\nFeatures exceeding capabilities: {}", report.violations.len());
+    for violation in &report.violations {
+        println!("  [{}] {}", violation.rule, violation.message);
+    }
+
+    bail!("{} feature(s) exceed {} capabilities", report.violations.len(), profile.name);
+}
+
+/// Fab profile named in a project file.
+fn project_fab_profile(path: &str) -> Result<String> {
+    if !(path.ends_with(".hwt") || path.ends_with(".toml")) {
+        bail!("No fab profile given; pass --profile or a project file");
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path))?;
+    let project = Project::from_toml(&content)
+        .with_context(|| format!("Failed to parse {}", path))?;
+    match project.pcb.and_then(|pcb| pcb.fab) {
+        Some(name) => Ok(name),
+        None => bail!("{} names no fab profile; pass --profile", path),
+    }
+}
+
 /// Run benchmarks.
-pub fn run_benchmark(suite: &str, iterations: u32) -> Result<()> {
+pub fn run_benchmark(suite: &str, iterations: u32, input: Option<&str>, threads: Option<usize>) -> Result<()> {
     println!("Running {} benchmark suite ({} iterations)...", suite, iterations);
 
     println!("\nBenchmark Results");
     println!("═════════════════");
-    println!("(Benchmarks not yet implemented - coming soon!)");
+    match suite {
+        "drc" | "full" => benchmark_drc(iterations.max(1), input, threads)?,
+        _ => println!("(Benchmarks not yet implemented - coming soon!)"),
+    }
+
+    Ok(())
+}
+
+/// Time PCB DRC with a per-check breakdown, averaged over the iterations.
+fn benchmark_drc(iterations: u32, input: Option<&str>, threads: Option<usize>) -> Result<()> {
+    let input = match input {
+        Some(path) => load_drc_input(&input_file(path)?)?,
+        None => DrcInput::from_layout(benchmark_layout()),
+    };
+    let layout = &input.layout;
+    let mut checker = input.checker();
+    if let Some(threads) = threads {
+        checker = checker.with_threads(threads);
+    }
+
+    let mut totals: Vec<Duration> = vec![Duration::ZERO; DrcCheck::ALL.len()];
+    let mut prepare = Duration::ZERO;
+    let mut wall = Duration::ZERO;
+    let mut last = None;
+    for _ in 0..iterations {
+        let (report, timings) = checker.check_all_timed();
+        for (total, timing) in totals.iter_mut().zip(&timings.checks) {
+            *total += timing.duration;
+        }
+        prepare += timings.prepare;
+        wall += timings.total;
+        last = Some((report, timings));
+    }
+    let Some((report, timings)) = last else { return Ok(()) };
+
+    println!("DRC: {} traces, {} vias, {} components, {} thread(s)",
+        layout.traces.len(), layout.vias.len(), layout.components.len(), timings.threads);
+    println!("\n  {:<18} {:>10} {:>7} {:>10}", "Check", "Time (ms)", "Share", "Violations");
+    let busy: Duration = prepare + totals.iter().sum::<Duration>();
+    let share = |total: Duration| if busy.is_zero() { 0.0 } else { total.as_secs_f64() / busy.as_secs_f64() * 100.0 };
+    println!("  {:<18} {:>10.3} {:>6.1}% {:>10}",
+        "Prepare", prepare.as_secs_f64() * 1000.0 / iterations as f64, share(prepare), "-");
+    for (timing, &total) in timings.checks.iter().zip(&totals) {
+        println!("  {:<18} {:>10.3} {:>6.1}% {:>10}",
+            timing.check.name(), total.as_secs_f64() * 1000.0 / iterations as f64, share(total), timing.violations);
+    }
+    let avg = wall.as_secs_f64() * 1000.0 / iterations as f64;
+    let cpu = busy.as_secs_f64() * 1000.0 / iterations as f64;
+    println!("\n  Wall time: {:.3}ms (CPU {:.3}ms, {:.1}x parallel)", avg, cpu, if avg > 0.0 { cpu / avg } else { 0.0 });
+    println!("  Violations: {}", report.violations.len());
 
     Ok(())
 }
 
+/// Generated board with rows of resistors chained by tracks.
+fn benchmark_layout() -> Layout {
+    let mut layout = Layout::with_board_size(200.0, 150.0, LengthUnit::Mm);
+    for row in 0..40 {
+        for col in 0..40 {
+            let (x, y) = (5.0 + col as f64 * 4.8, 5.0 + row as f64 * 3.5);
+            let net = |i: usize| format!("N{}_{}", row, col + i);
+            layout.components.push(
+                PlacedComponent::new(format!("R{}", row * 40 + col + 1), "10k", "R_0603")
+                    .at(x, y)
+                    .with_pad(Pad::smd("1", -0.8, 0.0, 0.9, 0.9).with_net(net(0)))
+                    .with_pad(Pad::smd("2", 0.8, 0.0, 0.9, 0.9).with_net(net(1))),
+            );
+            if col == 39 {
+                continue;
+            }
+            // Chain each resistor to the next in its row
+            layout.traces.push(Trace {
+                net: net(1),
+                layer: "F.Cu".to_string(),
+                start: Position::new(x + 0.8, y),
+                end: Position::new(x + 4.0, y),
+                width: 0.25,
+                unit: LengthUnit::Mm,
+            });
+        }
+    }
+    layout
+}
+
 /// Validate design data.
 pub fn validate_data(path: &str, strict: bool) -> Result<()> {
     println!("Validating {}...", path);
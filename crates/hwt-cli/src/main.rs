@@ -72,13 +72,23 @@ enum Commands {
 
     /// Run design rule check
     Drc {
-        /// Project file path
+        /// Project or layout file path, or a directory holding project.hwt
         #[arg(default_value = ".")]
         path: String,
 
-        /// Output report file
+        /// Output report file (.json, .xml, .sarif, .html)
         #[arg(short, long)]
         report: Option<String>,
+
+        /// Report format (json, junit, sarif, html); inferred from the file name by default
+        #[arg(short, long)]
+        format: Option<String>,
+    },
+
+    /// Manufacturer capability profiles
+    Fab {
+        #[command(subcommand)]
+        command: FabCommands,
     },
 
     /// Run benchmarks
@@ -90,6 +100,14 @@ enum Commands {
         /// Number of iterations
         #[arg(short, long, default_value = "10")]
         iterations: u32,
+
+        /// Board or project to check (drc suite); a generated board by default
+        #[arg(long)]
+        input: Option<String>,
+
+        /// Worker threads (defaults to all cores)
+        #[arg(short = 'j', long)]
+        threads: Option<usize>,
     },
 
     /// Data model operations
@@ -102,6 +120,34 @@ enum Commands {
     Info,
 }
 
+#[derive(Subcommand)]
+enum FabCommands {
+    /// List available profiles
+    List {
+        /// Project directory
+        #[arg(default_value = ".")]
+        path: String,
+    },
+
+    /// Check a layout against a profile
+    Check {
+        /// Project or layout file path, or a directory holding project.hwt
+        path: String,
+
+        /// Profile name (defaults to the project's fab profile)
+        #[arg(short, long)]
+        profile: Option<String>,
+
+        /// Copper weight (oz)
+        #[arg(long, default_value = "1")]
+        copper_weight: f64,
+
+        /// Board thickness (mm, defaults to the layer stack)
+        #[arg(long)]
+        thickness: Option<f64>,
+    },
+}
+
 #[derive(Subcommand)]
 enum DataCommands {
     /// Validate design data
@@ -160,11 +206,19 @@ fn main() -> Result<()> {
         Commands::Export { format, output } => {
             commands::export_project(&format, &output)?;
         }
-        Commands::Drc { path, report } => {
-            commands::run_drc(&path, report.as_deref())?;
+        Commands::Drc { path, report, format } => {
+            commands::run_drc(&path, report.as_deref(), format.as_deref())?;
         }
-        Commands::Benchmark { suite, iterations } => {
-            commands::run_benchmark(&suite, iterations)?;
+        Commands::Fab { command } => match command {
+            FabCommands::List { path } => {
+                commands::list_fab_profiles(&path)?;
+            }
+            FabCommands::Check { path, profile, copper_weight, thickness } => {
+                commands::run_fab_check(&path, profile.as_deref(), copper_weight, thickness)?;
+            }
+        },
+        Commands::Benchmark { suite, iterations, input, threads } => {
+            commands::run_benchmark(&suite, iterations, input.as_deref(), threads)?;
         }
         Commands::Data { command } => match command {
             DataCommands::Validate { path, strict } => {
@@ -0,0 +1,665 @@
+//! Automatic Placement Optimizer.
+//!
+//! Simulated-annealing placer that moves, rotates and swaps the unlocked
+//! footprints of a layout to minimize weighted half-perimeter wirelength
+//! and routing congestion. Courtyard clearance
+//! (`PcbDesignRules::min_courtyard_clearance`), the board outline and
+//! footprint keepouts enter the cost as area penalties that dominate
+//! wirelength. Annealing is a heuristic and may still end with penalties
+//! left, for example on a crowded board; those components are listed in
+//! `AutoplaceReport::violations`.
+//!
+//! Congestion is estimated with RUDY: each net spreads its wirelength
+//! evenly over its bounding box, and the demand in every grid bin beyond
+//! the routing capacity is penalized.
+//!
+//! Moves are drawn from a seeded generator so the same layout and
+//! configuration always produce the same placement.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::drc_rules::COURTYARD_MARGIN;
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D};
+use crate::layout::{ComponentLayer, KeepoutKind, Layout};
+use crate::pcb_drc::PcbDesignRules;
+use crate::rng::SplitMix64;
+
+/// Autoplacement error.
+#[derive(Debug, Clone)]
+pub struct AutoplaceError {
+    /// Error description
+    pub message: String,
+}
+
+impl AutoplaceError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for AutoplaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Autoplace error: {}", self.message)
+    }
+}
+
+impl std::error::Error for AutoplaceError {}
+
+/// Result type for autoplacement.
+pub type AutoplaceResult<T> = Result<T, AutoplaceError>;
+
+/// Autoplacer settings (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoplaceConfig {
+    /// Minimum gap between courtyards on the same side
+    pub courtyard_clearance: f64,
+
+    /// Position grid
+    pub grid: f64,
+
+    /// Number of annealing moves
+    pub iterations: usize,
+
+    /// Temperature of the first move, in cost units
+    pub start_temperature: f64,
+
+    /// Temperature of the last move
+    pub end_temperature: f64,
+
+    /// Allow 90 degree rotations
+    pub allow_rotation: bool,
+
+    /// Cost per mm of weighted wirelength
+    pub wirelength_weight: f64,
+
+    /// Cost per mm of wiring demand above capacity
+    pub congestion_weight: f64,
+
+    /// Cost per mm² of courtyard overlap, off-board or keepout area
+    pub overlap_weight: f64,
+
+    /// Congestion grid bin size
+    pub congestion_bin: f64,
+
+    /// Wiring the board can carry, in mm of track per mm² of area
+    pub routing_capacity: f64,
+
+    /// Seed for move generation
+    pub seed: u64,
+
+    /// Wirelength weight per net (default 1)
+    pub net_weights: HashMap<String, f64>,
+
+    /// Nets ignored for wirelength and congestion (e.g., planes)
+    pub skip_nets: Vec<String>,
+}
+
+impl Default for AutoplaceConfig {
+    fn default() -> Self {
+        Self {
+            courtyard_clearance: PcbDesignRules::default().min_courtyard_clearance,
+            grid: 0.25,
+            iterations: 20000,
+            start_temperature: 10.0,
+            end_temperature: 0.01,
+            allow_rotation: true,
+            wirelength_weight: 1.0,
+            congestion_weight: 1.0,
+            overlap_weight: 100.0,
+            congestion_bin: 2.0,
+            routing_capacity: 2.0,
+            seed: 1,
+            net_weights: HashMap::new(),
+            skip_nets: Vec::new(),
+        }
+    }
+}
+
+impl AutoplaceConfig {
+    /// Take the courtyard clearance from design rules.
+    pub fn with_rules(mut self, rules: &PcbDesignRules) -> Self {
+        self.courtyard_clearance = rules.min_courtyard_clearance;
+        self
+    }
+
+    /// Set the random seed.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Set the number of annealing moves.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Set the wirelength weight of a net.
+    pub fn with_net_weight(mut self, net: &str, weight: f64) -> Self {
+        self.net_weights.insert(net.to_string(), weight);
+        self
+    }
+}
+
+/// Outcome of an autoplacement run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoplaceReport {
+    /// Weighted wirelength before placement (mm)
+    pub initial_wirelength: f64,
+
+    /// Weighted wirelength after placement (mm)
+    pub wirelength: f64,
+
+    /// Wiring demand above capacity after placement (mm)
+    pub congestion: f64,
+
+    /// Accepted moves
+    pub accepted_moves: usize,
+
+    /// Components whose position or rotation changed
+    pub moved: Vec<String>,
+
+    /// Components still overlapping, off the board or in a keepout
+    pub violations: Vec<String>,
+}
+
+/// Simulated-annealing autoplacer.
+pub struct Autoplacer {
+    config: AutoplaceConfig,
+}
+
+impl Autoplacer {
+    /// Create an autoplacer.
+    pub fn new(config: AutoplaceConfig) -> Self {
+        Self { config }
+    }
+
+    /// Optimize the placement of the unlocked components of a layout.
+    pub fn place(&self, layout: &mut Layout) -> AutoplaceResult<AutoplaceReport> {
+        let mut annealer = Annealer::new(&self.config, layout)?;
+        let initial_wirelength = annealer.wirelength();
+        let accepted_moves = annealer.run();
+
+        let report = AutoplaceReport {
+            initial_wirelength,
+            wirelength: annealer.wirelength(),
+            congestion: annealer.congestion(),
+            accepted_moves,
+            moved: annealer.parts.iter()
+                .filter(|p| p.x != p.start.0 || p.y != p.start.1 || p.quarter != 0)
+                .map(|p| layout.components[p.index].reference.clone())
+                .collect(),
+            violations: (0..annealer.parts.len())
+                .filter(|&p| annealer.penalty(p) > 1e-9)
+                .map(|p| layout.components[annealer.parts[p].index].reference.clone())
+                .collect(),
+        };
+        let placements: Vec<(usize, f64, f64, usize)> = annealer.parts.iter()
+            .filter(|p| p.movable)
+            .map(|p| (p.index, p.x, p.y, p.quarter))
+            .collect();
+
+        for (index, x, y, quarter) in placements {
+            let component = &mut layout.components[index];
+            component.position.x = x;
+            component.position.y = y;
+            component.rotation = (component.rotation + 90.0 * quarter as f64).rem_euclid(360.0);
+        }
+        Ok(report)
+    }
+}
+
+/// A footprint being placed. Geometry is precomputed relative to the
+/// origin for each of the four quarter turns from its starting rotation.
+struct Part {
+    index: usize,
+    movable: bool,
+    layer: ComponentLayer,
+    start: (f64, f64),
+    x: f64,
+    y: f64,
+    quarter: usize,
+    courtyards: [BoundingBox; 4],
+    /// Net id and pad offsets per quarter turn
+    pins: Vec<(usize, [Point2D; 4])>,
+}
+
+struct Net {
+    weight: f64,
+    /// Part and pin index
+    pins: Vec<(usize, usize)>,
+}
+
+/// Proposed state of one part.
+type Move = (usize, f64, f64, usize);
+
+struct Annealer<'a> {
+    config: &'a AutoplaceConfig,
+    layout: &'a Layout,
+    board: Vec<Point2D>,
+    board_box: BoundingBox,
+    /// Footprint keepout rule areas per side (top, bottom)
+    keepouts: [Vec<usize>; 2],
+    parts: Vec<Part>,
+    nets: Vec<Net>,
+    net_boxes: Vec<BoundingBox>,
+    columns: usize,
+    rows: usize,
+    demand: Vec<f64>,
+    rng: SplitMix64,
+}
+
+impl<'a> Annealer<'a> {
+    fn new(config: &'a AutoplaceConfig, layout: &'a Layout) -> AutoplaceResult<Self> {
+        let outline = layout.outline.as_ref()
+            .ok_or_else(|| AutoplaceError::new("layout has no board outline"))?;
+        let board: Vec<Point2D> = outline.to_polygon(64).iter()
+            .map(|p| Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y)))
+            .collect();
+        let board_box = BoundingBox::from_points(&board)
+            .ok_or_else(|| AutoplaceError::new("board outline is empty"))?;
+
+        let copper = layout.copper_layer_names();
+        let keepouts_on = |side: Option<&String>| -> Vec<usize> {
+            let Some(side) = side else { return Vec::new() };
+            layout.rule_areas.iter().enumerate()
+                .filter(|(_, area)| area.forbids(KeepoutKind::Footprint, side))
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let keepouts = [keepouts_on(copper.first()), keepouts_on(copper.last())];
+
+        // Nets in name order so cost sums are evaluated deterministically
+        let mut net_ids: BTreeMap<&str, usize> = BTreeMap::new();
+        for component in &layout.components {
+            for pad in &component.pads {
+                if let Some(net) = pad.net.as_deref()
+                    && !config.skip_nets.iter().any(|n| n == net)
+                {
+                    net_ids.insert(net, 0);
+                }
+            }
+        }
+        for (id, value) in net_ids.values_mut().enumerate() {
+            *value = id;
+        }
+        let mut nets: Vec<Net> = net_ids.keys()
+            .map(|name| Net { weight: config.net_weights.get(*name).copied().unwrap_or(1.0), pins: Vec::new() })
+            .collect();
+
+        let mut parts = Vec::new();
+        for (index, component) in layout.components.iter().enumerate() {
+            let mut courtyards = Vec::new();
+            let mut offsets: Vec<Vec<Point2D>> = Vec::new();
+            for quarter in 0..4 {
+                let mut turned = component.clone();
+                turned.rotation = (component.rotation + 90.0 * quarter as f64).rem_euclid(360.0);
+                turned.position.x = 0.0;
+                turned.position.y = 0.0;
+                let Some(courtyard) = turned.courtyard(COURTYARD_MARGIN) else { break };
+                courtyards.push(courtyard);
+                offsets.push(turned.pads.iter().map(|pad| turned.pad_position(pad)).collect());
+            }
+            let Ok(courtyards) = <[BoundingBox; 4]>::try_from(courtyards) else { continue };
+
+            let part = parts.len();
+            let mut pins = Vec::new();
+            for (pad_index, pad) in component.pads.iter().enumerate() {
+                let Some(&net) = pad.net.as_deref().and_then(|n| net_ids.get(n)) else { continue };
+                nets[net].pins.push((part, pins.len()));
+                pins.push((net, [0, 1, 2, 3].map(|q| offsets[q][pad_index])));
+            }
+            parts.push(Part {
+                index,
+                movable: !component.locked,
+                layer: component.layer,
+                start: (component.position.x, component.position.y),
+                x: component.position.x,
+                y: component.position.y,
+                quarter: 0,
+                courtyards,
+                pins,
+            });
+        }
+
+        let bin = config.congestion_bin.max(0.1);
+        let columns = (board_box.width() / bin).ceil().max(1.0) as usize;
+        let rows = (board_box.height() / bin).ceil().max(1.0) as usize;
+        let mut annealer = Self {
+            config,
+            layout,
+            board,
+            board_box,
+            keepouts,
+            parts,
+            nets,
+            net_boxes: Vec::new(),
+            columns,
+            rows,
+            demand: vec![0.0; columns * rows],
+            rng: SplitMix64::new(config.seed),
+        };
+        annealer.net_boxes = (0..annealer.nets.len()).map(|n| annealer.net_box(n)).collect();
+        let mut demand = BTreeMap::new();
+        for n in 0..annealer.nets.len() {
+            annealer.spread(n, &annealer.net_boxes[n], 1.0, &mut demand);
+        }
+        for (bin, d) in demand {
+            annealer.demand[bin] += d;
+        }
+        Ok(annealer)
+    }
+
+    /// Anneal; returns the number of accepted moves.
+    fn run(&mut self) -> usize {
+        let movable: Vec<usize> = (0..self.parts.len()).filter(|&p| self.parts[p].movable).collect();
+        if movable.is_empty() {
+            return 0;
+        }
+        let config = self.config;
+        let start = config.start_temperature.max(1e-9);
+        let ratio = config.end_temperature.max(1e-12) / start;
+        let span = self.board_box.width().max(self.board_box.height());
+        let grid = config.grid.max(1e-3);
+
+        let mut accepted = 0;
+        for k in 0..config.iterations {
+            let progress = k as f64 / config.iterations as f64;
+            let temperature = start * ratio.powf(progress);
+            let range = (span * (1.0 - progress)).max(2.0 * grid);
+
+            let p = movable[(self.rng.next_u64() % movable.len() as u64) as usize];
+            let part = &self.parts[p];
+            let choice = self.rng.next_f64();
+            let moves: Vec<Move> = if choice < 0.15 && movable.len() > 1 {
+                let q = movable[(self.rng.next_u64() % movable.len() as u64) as usize];
+                let other = &self.parts[q];
+                if q == p || other.layer != part.layer {
+                    continue;
+                }
+                vec![(p, other.x, other.y, part.quarter), (q, part.x, part.y, other.quarter)]
+            } else if choice < 0.3 && config.allow_rotation {
+                let turn = 1 + (self.rng.next_u64() % 3) as usize;
+                vec![(p, part.x, part.y, (part.quarter + turn) % 4)]
+            } else {
+                let dx = (2.0 * self.rng.next_f64() - 1.0) * range;
+                let dy = (2.0 * self.rng.next_f64() - 1.0) * range;
+                let x = ((part.x + dx).clamp(self.board_box.min.x, self.board_box.max.x) / grid).round() * grid;
+                let y = ((part.y + dy).clamp(self.board_box.min.y, self.board_box.max.y) / grid).round() * grid;
+                vec![(p, x, y, part.quarter)]
+            };
+            if self.try_moves(&moves, temperature) {
+                accepted += 1;
+            }
+        }
+        accepted
+    }
+
+    /// Apply the moves if the Metropolis criterion accepts the cost change.
+    fn try_moves(&mut self, moves: &[Move], temperature: f64) -> bool {
+        let config = self.config;
+        let mut nets: Vec<usize> = moves.iter()
+            .flat_map(|&(p, ..)| self.parts[p].pins.iter().map(|pin| pin.0))
+            .collect();
+        nets.sort_unstable();
+        nets.dedup();
+
+        let old_penalty: f64 = moves.iter().map(|&(p, ..)| self.penalty(p)).sum();
+        let old_states: Vec<Move> = moves.iter()
+            .map(|&(p, ..)| (p, self.parts[p].x, self.parts[p].y, self.parts[p].quarter))
+            .collect();
+        self.apply(moves);
+        let new_penalty: f64 = moves.iter().map(|&(p, ..)| self.penalty(p)).sum();
+
+        let mut wirelength = 0.0;
+        let mut demand = BTreeMap::new();
+        let new_boxes: Vec<BoundingBox> = nets.iter().map(|&n| self.net_box(n)).collect();
+        for (&n, new_box) in nets.iter().zip(&new_boxes) {
+            let old_box = &self.net_boxes[n];
+            wirelength += self.nets[n].weight * (half_perimeter(new_box) - half_perimeter(old_box));
+            self.spread(n, old_box, -1.0, &mut demand);
+            self.spread(n, new_box, 1.0, &mut demand);
+        }
+        let capacity = self.capacity();
+        let congestion: f64 = demand.iter()
+            .map(|(&bin, &d)| {
+                let before = self.demand[bin];
+                (before + d - capacity).max(0.0) - (before - capacity).max(0.0)
+            })
+            .sum();
+
+        let delta = config.wirelength_weight * wirelength
+            + config.congestion_weight * congestion
+            + config.overlap_weight * (new_penalty - old_penalty);
+        if delta <= 0.0 || self.rng.next_f64() < (-delta / temperature).exp() {
+            for (n, new_box) in nets.into_iter().zip(new_boxes) {
+                self.net_boxes[n] = new_box;
+            }
+            for (bin, d) in demand {
+                self.demand[bin] += d;
+            }
+            true
+        } else {
+            self.apply(&old_states);
+            false
+        }
+    }
+
+    fn apply(&mut self, moves: &[Move]) {
+        for &(p, x, y, quarter) in moves {
+            let part = &mut self.parts[p];
+            part.x = x;
+            part.y = y;
+            part.quarter = quarter;
+        }
+    }
+
+    fn courtyard(&self, p: usize) -> BoundingBox {
+        let part = &self.parts[p];
+        let local = &part.courtyards[part.quarter];
+        BoundingBox::new(
+            Point2D::new(part.x + local.min.x, part.y + local.min.y),
+            Point2D::new(part.x + local.max.x, part.y + local.max.y),
+        )
+    }
+
+    fn pin_position(&self, p: usize, pin: usize) -> Point2D {
+        let part = &self.parts[p];
+        let offset = part.pins[pin].1[part.quarter];
+        Point2D::new(part.x + offset.x, part.y + offset.y)
+    }
+
+    fn net_box(&self, n: usize) -> BoundingBox {
+        let points: Vec<Point2D> = self.nets[n].pins.iter().map(|&(p, pin)| self.pin_position(p, pin)).collect();
+        BoundingBox::from_points(&points).unwrap_or_else(|| BoundingBox::new(Point2D::new(0.0, 0.0), Point2D::new(0.0, 0.0)))
+    }
+
+    /// Add a net's RUDY demand over `bounds`, scaled by `sign`, to `demand`.
+    fn spread(&self, n: usize, bounds: &BoundingBox, sign: f64, demand: &mut BTreeMap<usize, f64>) {
+        if self.nets[n].pins.len() < 2 {
+            return;
+        }
+        // Flat boxes are widened to a bin so their demand stays finite
+        let bin = self.config.congestion_bin.max(0.1);
+        let center = bounds.center();
+        let (w, h) = (bounds.width().max(bin), bounds.height().max(bin));
+        let spread = BoundingBox::new(
+            Point2D::new(center.x - w / 2.0, center.y - h / 2.0),
+            Point2D::new(center.x + w / 2.0, center.y + h / 2.0),
+        );
+        let density = sign * half_perimeter(bounds) / (w * h);
+
+        let origin = self.board_box.min;
+        let column_range = |lo: f64, hi: f64, count: usize| {
+            let first = ((lo - origin.x) / bin).floor().max(0.0) as usize;
+            let last = (((hi - origin.x) / bin).ceil().max(0.0) as usize).min(count);
+            first..last
+        };
+        let row_range = |lo: f64, hi: f64, count: usize| {
+            let first = ((lo - origin.y) / bin).floor().max(0.0) as usize;
+            let last = (((hi - origin.y) / bin).ceil().max(0.0) as usize).min(count);
+            first..last
+        };
+        for row in row_range(spread.min.y, spread.max.y, self.rows) {
+            for column in column_range(spread.min.x, spread.max.x, self.columns) {
+                let cell = BoundingBox::new(
+                    Point2D::new(origin.x + column as f64 * bin, origin.y + row as f64 * bin),
+                    Point2D::new(origin.x + (column + 1) as f64 * bin, origin.y + (row + 1) as f64 * bin),
+                );
+                let area = overlap_area(&cell, &spread);
+                if area > 0.0 {
+                    *demand.entry(row * self.columns + column).or_insert(0.0) += density * area;
+                }
+            }
+        }
+    }
+
+    /// Wiring capacity of one congestion bin (mm).
+    fn capacity(&self) -> f64 {
+        let bin = self.config.congestion_bin.max(0.1);
+        self.config.routing_capacity * bin * bin
+    }
+
+    /// Courtyard overlap with same-side parts (grown by the clearance),
+    /// plus area off the board or in a footprint keepout (mm²).
+    fn penalty(&self, p: usize) -> f64 {
+        let courtyard = self.courtyard(p);
+        let grown = courtyard.expanded(self.config.courtyard_clearance / 2.0);
+        let layer = self.parts[p].layer;
+        let area = courtyard.width() * courtyard.height();
+
+        let mut penalty: f64 = (0..self.parts.len())
+            .filter(|&q| q != p && self.parts[q].layer == layer)
+            .map(|q| overlap_area(&grown, &self.courtyard(q).expanded(self.config.courtyard_clearance / 2.0)))
+            .sum();
+
+        penalty += area - overlap_area(&courtyard, &self.board_box);
+        let corners = [
+            courtyard.min,
+            Point2D::new(courtyard.max.x, courtyard.min.y),
+            courtyard.max,
+            Point2D::new(courtyard.min.x, courtyard.max.y),
+        ];
+        let outside = corners.iter().filter(|c| !point_in_polygon(c, &self.board)).count();
+        penalty += area * outside as f64 / 4.0;
+
+        let side = match layer {
+            ComponentLayer::Top => 0,
+            ComponentLayer::Bottom => 1,
+        };
+        for &area_index in &self.keepouts[side] {
+            if self.layout.rule_areas[area_index].overlaps_box(&courtyard) {
+                penalty += area.max(1e-3);
+            }
+        }
+        penalty
+    }
+
+    fn wirelength(&self) -> f64 {
+        self.nets.iter().zip(&self.net_boxes).map(|(net, b)| net.weight * half_perimeter(b)).sum()
+    }
+
+    fn congestion(&self) -> f64 {
+        let capacity = self.capacity();
+        self.demand.iter().map(|d| (d - capacity).max(0.0)).sum()
+    }
+}
+
+fn half_perimeter(b: &BoundingBox) -> f64 {
+    b.width() + b.height()
+}
+
+fn overlap_area(a: &BoundingBox, b: &BoundingBox) -> f64 {
+    let w = a.max.x.min(b.max.x) - a.min.x.max(b.min.x);
+    let h = a.max.y.min(b.max.y) - a.min.y.max(b.min.y);
+    if w > 0.0 && h > 0.0 { w * h } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Keepouts, Pad, PlacedComponent, RuleArea};
+    use crate::units::LengthUnit;
+
+    /// MCU locked in the middle with four resistors scattered in the corners.
+    fn scattered_layout() -> Layout {
+        let mut layout = Layout::with_board_size(40.0, 30.0, LengthUnit::Mm);
+        let mut mcu = PlacedComponent::new("U1", "MCU", "QFN").at(20.0, 15.0);
+        for (i, (x, y)) in [(-2.0, 0.0), (2.0, 0.0), (0.0, -2.0), (0.0, 2.0)].iter().enumerate() {
+            mcu = mcu.with_pad(Pad::smd(format!("{}", i + 1), *x, *y, 0.6, 0.6).with_net(format!("N{}", i + 1)));
+        }
+        mcu.locked = true;
+        layout.components.push(mcu);
+        for (i, (x, y)) in [(3.0, 3.0), (37.0, 3.0), (3.0, 27.0), (37.0, 27.0)].iter().enumerate() {
+            layout.components.push(
+                PlacedComponent::new(format!("R{}", i + 1), "10k", "R_0603")
+                    .at(*x, *y)
+                    .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9).with_net(format!("N{}", i + 1)))
+                    .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9).with_net("GND")),
+            );
+        }
+        layout
+    }
+
+    fn assert_legal(layout: &Layout, clearance: f64) {
+        for (i, a) in layout.components.iter().enumerate() {
+            let ca = a.courtyard(COURTYARD_MARGIN).unwrap();
+            assert!(ca.min.x >= 0.0 && ca.max.x <= 40.0 && ca.min.y >= 0.0 && ca.max.y <= 30.0);
+            assert!(layout.placement_keepout(a, COURTYARD_MARGIN).is_none(), "{} in keepout", a.reference);
+            for b in &layout.components[i + 1..] {
+                let cb = b.courtyard(COURTYARD_MARGIN).unwrap();
+                assert!(overlap_area(&ca.expanded(clearance / 2.0), &cb.expanded(clearance / 2.0)) < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_autoplace_reduces_wirelength() {
+        let mut layout = scattered_layout();
+        let config = AutoplaceConfig::default().with_rules(&PcbDesignRules::default());
+        let report = Autoplacer::new(config).place(&mut layout).unwrap();
+
+        assert!(report.wirelength < report.initial_wirelength / 2.0);
+        assert!(report.violations.is_empty());
+        assert_eq!(report.moved.len(), 4);
+        let mcu = layout.component("U1").unwrap();
+        assert_eq!((mcu.position.x, mcu.position.y), (20.0, 15.0));
+        for reference in ["R1", "R2", "R3", "R4"] {
+            let r = layout.component(reference).unwrap();
+            assert!(r.position.to_point2d().distance(&Point2D::new(20.0, 15.0)) < 8.0);
+        }
+        assert_legal(&layout, 0.25);
+    }
+
+    #[test]
+    fn test_autoplace_reproducible() {
+        let run = |seed: u64| {
+            let mut layout = scattered_layout();
+            let config = AutoplaceConfig::default().with_seed(seed).with_iterations(5000);
+            Autoplacer::new(config).place(&mut layout).unwrap();
+            layout.components.iter().map(|c| (c.position.x, c.position.y, c.rotation)).collect::<Vec<_>>()
+        };
+        assert_eq!(run(7), run(7));
+    }
+
+    #[test]
+    fn test_autoplace_resolves_overlap_and_keepouts() {
+        let mut layout = scattered_layout();
+        for component in &mut layout.components[1..] {
+            component.position.x = 20.0;
+            component.position.y = 22.0;
+        }
+        // Footprint keepout over the left half of the board
+        let keepout = Keepouts { footprints: true, ..Default::default() };
+        let points = vec![Point2D::new(0.0, 0.0), Point2D::new(16.0, 0.0), Point2D::new(16.0, 30.0), Point2D::new(0.0, 30.0)];
+        layout.rule_areas.push(RuleArea::new("left", points).with_keepout(keepout));
+
+        let config = AutoplaceConfig { courtyard_clearance: 0.5, ..Default::default() };
+        let report = Autoplacer::new(config).place(&mut layout).unwrap();
+        assert!(report.violations.is_empty(), "{:?}", report.violations);
+        assert_legal(&layout, 0.5);
+        assert!(layout.components[1..].iter().all(|c| c.courtyard(COURTYARD_MARGIN).unwrap().min.x > 16.0));
+    }
+}
@@ -0,0 +1,1290 @@
+//! Batch Autorouter.
+//!
+//! Grid-based maze router that routes every open ratsnest connection of a
+//! layout.
+//! Connections are routed shortest-first with A*, honouring net classes,
+//! keepouts, per-layer direction preferences and via costs. Connections that
+//! cannot be routed trigger rip-up-and-reroute passes, and the final routes
+//! are written to the layout through `Router::commit_route`.
+//!
+//! All ordering decisions are driven by a seeded generator so the same layout
+//! and configuration always produce the same result.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D, Position};
+use crate::layout::{KeepoutKind, Layout, RuleArea};
+use crate::ratsnest::Ratsnest;
+use crate::rng::SplitMix64;
+use crate::routing::{Router, RoutingConfig, RoutingError, RoutingResult, RoutingSession};
+
+/// Preferred routing direction for a copper layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LayerDirection {
+    /// No preference
+    #[default]
+    Any,
+    /// Prefer horizontal segments
+    Horizontal,
+    /// Prefer vertical segments
+    Vertical,
+}
+
+/// Routing parameters for a group of nets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutorouteNetClass {
+    /// Net class name
+    pub name: String,
+
+    /// Nets belonging to this class
+    pub nets: Vec<String>,
+
+    /// Trace width (mm)
+    pub trace_width: f64,
+
+    /// Clearance to other nets (mm)
+    pub clearance: f64,
+
+    /// Via drill diameter (mm)
+    pub via_drill: f64,
+
+    /// Via pad diameter (mm)
+    pub via_pad: f64,
+}
+
+/// Autorouter configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutorouteConfig {
+    /// Base routing parameters (default width, clearance, via size)
+    pub routing: RoutingConfig,
+
+    /// Routing grid pitch (mm)
+    pub grid_pitch: f64,
+
+    /// Maximum number of rip-up-and-reroute passes
+    pub max_passes: usize,
+
+    /// Cost of a via, in grid steps
+    pub via_cost: f64,
+
+    /// Extra cost per step against a layer's preferred direction
+    pub wrong_direction_cost: f64,
+
+    /// Cost per step through another net's route during rip-up search
+    pub rip_up_cost: f64,
+
+    /// Seed for net ordering between passes
+    pub seed: u64,
+
+    /// Net classes
+    pub net_classes: Vec<AutorouteNetClass>,
+
+    /// Preferred direction per copper layer
+    pub layer_directions: HashMap<String, LayerDirection>,
+
+    /// Keepout areas honoured in addition to the layout's rule areas
+    pub keepouts: Vec<RuleArea>,
+
+    /// Nets left unrouted (e.g., planes poured separately)
+    pub skip_nets: Vec<String>,
+}
+
+impl Default for AutorouteConfig {
+    fn default() -> Self {
+        Self {
+            routing: RoutingConfig::default(),
+            grid_pitch: 0.25,
+            max_passes: 8,
+            via_cost: 20.0,
+            wrong_direction_cost: 2.0,
+            rip_up_cost: 40.0,
+            seed: 1,
+            net_classes: Vec::new(),
+            layer_directions: HashMap::new(),
+            keepouts: Vec::new(),
+            skip_nets: Vec::new(),
+        }
+    }
+}
+
+impl AutorouteConfig {
+    /// Assign alternating horizontal/vertical preferences to copper layers,
+    /// starting with horizontal on the first layer.
+    pub fn with_alternating_directions(mut self, copper_layers: &[String]) -> Self {
+        for (i, layer) in copper_layers.iter().enumerate() {
+            let direction = if i % 2 == 0 {
+                LayerDirection::Horizontal
+            } else {
+                LayerDirection::Vertical
+            };
+            self.layer_directions.insert(layer.clone(), direction);
+        }
+        self
+    }
+
+    /// Find the net class for a net.
+    pub fn net_class(&self, net: &str) -> Option<&AutorouteNetClass> {
+        self.net_classes.iter().find(|c| c.nets.iter().any(|n| n == net))
+    }
+
+    /// Trace width for a net on a layer: the class width solved for that
+    /// layer in `routing.class_widths`, the class width, or the default.
+    fn trace_width(&self, net: &str, layer: &str) -> f64 {
+        let class = self.net_class(net);
+        class
+            .and_then(|c| self.routing.class_widths.get(&c.name))
+            .and_then(|widths| widths.get(layer))
+            .copied()
+            .or(class.map(|c| c.trace_width))
+            .unwrap_or(self.routing.trace_width)
+    }
+
+    /// Widest trace of a net over the copper layers.
+    fn widest_trace(&self, net: &str, copper: &[String]) -> f64 {
+        copper.iter().map(|layer| self.trace_width(net, layer)).fold(0.0, f64::max)
+    }
+
+    /// Via (drill, pad) for a net.
+    fn via_size(&self, net: &str) -> (f64, f64) {
+        self.net_class(net)
+            .map(|c| (c.via_drill, c.via_pad))
+            .unwrap_or((self.routing.via_drill, self.routing.via_pad))
+    }
+
+    /// Largest clearance used by any net.
+    fn max_clearance(&self) -> f64 {
+        self.net_classes
+            .iter()
+            .map(|c| c.clearance)
+            .fold(self.routing.clearance, f64::max)
+    }
+
+    /// Largest trace width used by any net.
+    fn max_trace_width(&self) -> f64 {
+        self.net_classes
+            .iter()
+            .map(|c| c.trace_width)
+            .chain(self.routing.class_widths.values().flat_map(|widths| widths.values().copied()))
+            .fold(self.routing.trace_width, f64::max)
+    }
+
+    /// Largest via pad used by any net.
+    fn max_via_pad(&self) -> f64 {
+        self.net_classes
+            .iter()
+            .map(|c| c.via_pad)
+            .fold(self.routing.via_pad, f64::max)
+    }
+}
+
+/// A pad-to-pad connection the autorouter could not complete.
+#[derive(Debug, Clone)]
+pub struct FailedConnection {
+    /// Net name
+    pub net: String,
+    /// Start point
+    pub from: Point2D,
+    /// End point
+    pub to: Point2D,
+}
+
+/// Autorouter result summary.
+#[derive(Debug, Clone, Default)]
+pub struct AutorouteReport {
+    /// Connections that needed routing
+    pub connections: usize,
+    /// Connections routed
+    pub routed: usize,
+    /// Connections left unrouted
+    pub failed: Vec<FailedConnection>,
+    /// Passes run
+    pub passes: usize,
+    /// Total routed length (mm)
+    pub total_length: f64,
+    /// Vias added
+    pub vias_added: usize,
+}
+
+impl AutorouteReport {
+    /// Fraction of connections routed (1.0 when there was nothing to route).
+    pub fn completion(&self) -> f64 {
+        if self.connections == 0 {
+            1.0
+        } else {
+            self.routed as f64 / self.connections as f64
+        }
+    }
+}
+
+/// A connection endpoint on one or more copper layers.
+#[derive(Debug, Clone)]
+struct Terminal {
+    point: Point2D,
+    layers: Vec<usize>,
+}
+
+/// A connection to route.
+#[derive(Debug, Clone)]
+struct Connection {
+    net: usize,
+    from: Terminal,
+    to: Terminal,
+}
+
+/// One grid step of a routed path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GridNode {
+    layer: usize,
+    x: usize,
+    y: usize,
+}
+
+/// A routed connection held in the grid.
+#[derive(Debug, Clone)]
+struct GridRoute {
+    path: Vec<GridNode>,
+    marked: Vec<usize>,
+}
+
+/// Cell states in the fixed-obstacle map.
+const FREE: i32 = -1;
+const BLOCKED: i32 = -2;
+
+/// Batch autorouter over a whole layout.
+pub struct Autorouter<'a> {
+    layout: &'a mut Layout,
+    config: AutorouteConfig,
+}
+
+impl<'a> Autorouter<'a> {
+    /// Create a new autorouter.
+    pub fn new(layout: &'a mut Layout, config: AutorouteConfig) -> Self {
+        Self { layout, config }
+    }
+
+    /// Route all unrouted connections and commit the results to the layout.
+    /// Connections that cannot be routed, or whose route the layout rejects,
+    /// are listed as failed; the other routes are still committed.
+    pub fn route_all(&mut self) -> RoutingResult<AutorouteReport> {
+        let copper = self.layout.copper_layer_names();
+        if copper.is_empty() {
+            return Err(RoutingError::LayerNotFound("no copper layers".to_string()));
+        }
+
+        let net_names = self.net_names();
+        let connections = self.unrouted_connections(&net_names, &copper);
+        let mut report = AutorouteReport {
+            connections: connections.len(),
+            ..Default::default()
+        };
+        if connections.is_empty() {
+            return Ok(report);
+        }
+
+        let bounds = self.routing_bounds().ok_or_else(|| {
+            RoutingError::NoPath("layout has no outline or pads to bound routing".to_string())
+        })?;
+        let drill_pairs = self
+            .layout
+            .effective_drill_pairs(self.config.routing.via_drill, self.config.routing.via_pad);
+        let via_spans: Vec<(usize, usize)> = drill_pairs
+            .iter()
+            .filter_map(|pair| pair.span(&copper).ok())
+            .collect();
+        let via_pad = drill_pairs.iter().map(|pair| pair.pad).fold(self.config.max_via_pad(), f64::max);
+        let mut grid = RouteGrid::new(bounds, &copper, via_spans, &self.config);
+        grid.mark_fixed_copper(self.layout, &net_names, &copper, &self.config);
+        grid.mark_board_edge(self.layout, &self.config);
+        let areas = self.layout.rule_areas.iter().chain(&self.config.keepouts);
+        grid.mark_rule_areas(areas, &copper, self.config.max_trace_width() / 2.0, via_pad / 2.0);
+
+        let routes = self.route_connections(&mut grid, &connections, &net_names, &copper, &mut report);
+
+        for (conn, route) in connections.iter().zip(&routes) {
+            let net = &net_names[conn.net];
+            // A rejected route commits nothing, so the layout stays whole
+            let committed = route.as_ref().and_then(|route| self.commit(&grid, conn, route, net, &copper).ok());
+            match committed {
+                Some((length, vias)) => {
+                    report.routed += 1;
+                    report.total_length += length;
+                    report.vias_added += vias;
+                }
+                None => report.failed.push(FailedConnection {
+                    net: net.clone(),
+                    from: conn.from.point,
+                    to: conn.to.point,
+                }),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Run routing passes, returning a grid path per connection.
+    fn route_connections(
+        &self,
+        grid: &mut RouteGrid,
+        connections: &[Connection],
+        net_names: &[String],
+        copper: &[String],
+        report: &mut AutorouteReport,
+    ) -> Vec<Option<GridRoute>> {
+        let mut routes: Vec<Option<GridRoute>> = vec![None; connections.len()];
+        let mut rng = SplitMix64::new(self.config.seed);
+
+        // Shortest connections first
+        let mut order: Vec<usize> = (0..connections.len()).collect();
+        order.sort_by(|&a, &b| {
+            let la = connections[a].from.point.distance(&connections[a].to.point);
+            let lb = connections[b].from.point.distance(&connections[b].to.point);
+            la.total_cmp(&lb).then(a.cmp(&b))
+        });
+
+        for pass in 0..self.config.max_passes.max(1) {
+            report.passes = pass + 1;
+            let last_pass = pass + 1 == self.config.max_passes.max(1);
+
+            for &ci in &order {
+                if routes[ci].is_some() {
+                    continue;
+                }
+                let conn = &connections[ci];
+                let net = &net_names[conn.net];
+                // Cleared for the widest layer so any layer change fits
+                let width = self.config.widest_trace(net, copper);
+                let via_radius = self.via_extra_radius(net, width);
+
+                if let Some(path) = grid.search(conn, via_radius, None, &self.config) {
+                    let marked = grid.occupy(conn.net, &path, self.mark_radius(width), via_radius);
+                    routes[ci] = Some(GridRoute { path, marked });
+                    continue;
+                }
+
+                if last_pass {
+                    continue;
+                }
+
+                // Rip up the routes in the way and take their place
+                let rip_cost = (self.config.rip_up_cost * STEP as f64) as u64;
+                let Some(path) = grid.search(conn, via_radius, Some(rip_cost), &self.config) else {
+                    continue;
+                };
+                let conflicts = grid.conflict_cells(conn.net, &path, via_radius);
+                for route in routes.iter_mut() {
+                    let ripped = route
+                        .as_ref()
+                        .is_some_and(|r| r.marked.iter().any(|idx| conflicts.contains(idx)));
+                    if let Some(r) = route.take_if(|_| ripped) {
+                        grid.release(&r.marked);
+                    }
+                }
+                if let Some(path) = grid.search(conn, via_radius, None, &self.config) {
+                    let marked = grid.occupy(conn.net, &path, self.mark_radius(width), via_radius);
+                    routes[ci] = Some(GridRoute { path, marked });
+                }
+            }
+
+            let pending: Vec<usize> = order.iter().copied().filter(|&ci| routes[ci].is_none()).collect();
+            if pending.is_empty() {
+                break;
+            }
+
+            // Unrouted connections first, remaining order shuffled by seed
+            let mut rest: Vec<usize> = order.iter().copied().filter(|&ci| routes[ci].is_some()).collect();
+            rng.shuffle(&mut rest);
+            order = pending;
+            order.extend(rest);
+        }
+
+        routes
+    }
+
+    /// Commit a grid route through `Router::commit_route`.
+    fn commit(
+        &mut self,
+        grid: &RouteGrid,
+        conn: &Connection,
+        route: &GridRoute,
+        net: &str,
+        copper: &[String],
+    ) -> RoutingResult<(f64, usize)> {
+        let (via_drill, via_pad) = self.config.via_size(net);
+        let first = route.path[0];
+        let last = route.path[route.path.len() - 1];
+
+        let mut session = RoutingSession::new(
+            net.to_string(),
+            point_to_position(&conn.from.point),
+            copper[first.layer].clone(),
+            self.config.trace_width(net, &copper[first.layer]),
+        );
+
+        let mut points: Vec<(Point2D, usize)> = Vec::new();
+        points.push((conn.from.point, first.layer));
+        for node in &route.path {
+            points.push((grid.cell_center(node.x, node.y), node.layer));
+        }
+        points.push((conn.to.point, last.layer));
+
+        // Drop duplicate points and intermediate points of straight runs
+        let mut corners: Vec<(Point2D, usize)> = Vec::new();
+        for (point, layer) in points {
+            let duplicate = corners
+                .last()
+                .is_some_and(|&(prev, prev_layer)| prev_layer == layer && prev.distance(&point) < 1e-9);
+            if duplicate {
+                continue;
+            }
+            if corners.len() >= 2 {
+                let (a, la) = corners[corners.len() - 2];
+                let (b, lb) = corners[corners.len() - 1];
+                if la == layer && lb == layer && direction(&a, &b) == direction(&b, &point) {
+                    corners.pop();
+                }
+            }
+            corners.push((point, layer));
+        }
+
+        let mut vias = 0;
+        let mut current_layer = first.layer;
+        for &(point, layer) in &corners[1..] {
+            if layer != current_layer {
+                session.insert_via(copper[layer].clone());
+                session.set_width(self.config.trace_width(net, &copper[layer]));
+                current_layer = layer;
+                vias += 1;
+            } else {
+                session.add_segment(point_to_position(&point));
+            }
+        }
+
+        if session.segments.is_empty() {
+            return Ok((0.0, 0));
+        }
+
+        let length = session.total_length();
+        let config = RoutingConfig {
+            trace_width: self.config.trace_width(net, &copper[first.layer]),
+            via_drill,
+            via_pad,
+            ..self.config.routing.clone()
+        };
+        Router::new(self.layout, config).commit_route(session)?;
+        Ok((length, vias))
+    }
+
+    /// Inflation (mm) around a routed centerline that other nets must avoid.
+    fn mark_radius(&self, width: f64) -> f64 {
+        width / 2.0 + self.config.max_clearance() + self.config.max_trace_width() / 2.0
+    }
+
+    /// Extra inflation (mm) a via needs beyond the trace itself.
+    fn via_extra_radius(&self, net: &str, width: f64) -> f64 {
+        let (_, via_pad) = self.config.via_size(net);
+        ((via_pad - width) / 2.0).max(0.0)
+    }
+
+    /// Sorted, de-duplicated names of all nets with pads, minus skipped nets.
+    fn net_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .layout
+            .components
+            .iter()
+            .flat_map(|c| c.pads.iter().filter_map(|p| p.net.clone()))
+            .filter(|n| !n.is_empty() && !self.config.skip_nets.contains(n))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// Open ratsnest connections of the nets being routed.
+    fn unrouted_connections(&self, net_names: &[String], copper: &[String]) -> Vec<Connection> {
+        let layer_indices = |layers: &[String]| -> Vec<usize> {
+            layers
+                .iter()
+                .filter_map(|name| copper.iter().position(|l| l == name))
+                .collect()
+        };
+        Ratsnest::compute(self.layout)
+            .open_connections()
+            .into_iter()
+            .filter_map(|open| {
+                let net = net_names.iter().position(|n| n == &open.net)?;
+                let from = Terminal { point: open.from, layers: layer_indices(&open.from_layers) };
+                let to = Terminal { point: open.to, layers: layer_indices(&open.to_layers) };
+                if from.layers.is_empty() || to.layers.is_empty() {
+                    return None;
+                }
+                Some(Connection { net, from, to })
+            })
+            .collect()
+    }
+
+    /// Area available for routing.
+    fn routing_bounds(&self) -> Option<BoundingBox> {
+        let outline_bbox = self
+            .layout
+            .outline
+            .as_ref()
+            .and_then(|o| BoundingBox::from_points(&o.to_polygon(64)))
+            .filter(|b| b.width() > 0.0 && b.height() > 0.0);
+        if outline_bbox.is_some() {
+            return outline_bbox;
+        }
+        let pads: Vec<Point2D> = self
+            .layout
+            .components
+            .iter()
+            .flat_map(|c| c.pads.iter().map(move |p| c.pad_position(p)))
+            .collect();
+        BoundingBox::from_points(&pads).map(|b| b.expanded(5.0))
+    }
+}
+
+/// Grid step cost units.
+const STEP: u64 = 10;
+const DIAGONAL_STEP: u64 = 14;
+
+/// Occupancy grid shared by all connections.
+struct RouteGrid {
+    origin: Point2D,
+    pitch: f64,
+    nx: usize,
+    ny: usize,
+    layers: usize,
+    directions: Vec<LayerDirection>,
+    /// Copper layer ranges a single via may span
+    via_spans: Vec<(usize, usize)>,
+    /// Fixed copper owner per cell (net index, `FREE` or `BLOCKED`)
+    fixed: Vec<i32>,
+    /// Routed net per cell and its reference count
+    routed: Vec<i32>,
+    routed_count: Vec<u16>,
+    /// Cells where a via would enter a via keepout, per layer
+    via_blocked: Vec<bool>,
+    /// Search state reused by every search
+    scratch: SearchScratch,
+}
+
+/// Per-cell search costs and parents, reused across searches. An entry is
+/// valid only while its stamp matches the current generation, so starting
+/// a search does not touch every cell.
+#[derive(Debug, Default)]
+struct SearchScratch {
+    generation: u32,
+    stamp: Vec<u32>,
+    cost: Vec<u64>,
+    parent: Vec<usize>,
+}
+
+impl SearchScratch {
+    /// Start a search over `cells` cells with every entry unset.
+    fn reset(&mut self, cells: usize) {
+        if self.stamp.len() != cells {
+            self.stamp = vec![0; cells];
+            self.cost = vec![u64::MAX; cells];
+            self.parent = vec![usize::MAX; cells];
+            self.generation = 0;
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamp.fill(0);
+            self.generation = 1;
+        }
+    }
+
+    fn cost(&self, idx: usize) -> u64 {
+        if self.stamp[idx] == self.generation { self.cost[idx] } else { u64::MAX }
+    }
+
+    fn parent(&self, idx: usize) -> usize {
+        if self.stamp[idx] == self.generation { self.parent[idx] } else { usize::MAX }
+    }
+
+    fn set(&mut self, idx: usize, cost: u64, parent: usize) {
+        self.stamp[idx] = self.generation;
+        self.cost[idx] = cost;
+        self.parent[idx] = parent;
+    }
+}
+
+impl RouteGrid {
+    fn new(bounds: BoundingBox, copper: &[String], via_spans: Vec<(usize, usize)>, config: &AutorouteConfig) -> Self {
+        let pitch = config.grid_pitch.max(0.01);
+        let nx = (bounds.width() / pitch).ceil() as usize + 1;
+        let ny = (bounds.height() / pitch).ceil() as usize + 1;
+        let cells = nx * ny * copper.len();
+        Self {
+            origin: bounds.min,
+            pitch,
+            nx,
+            ny,
+            layers: copper.len(),
+            directions: copper
+                .iter()
+                .map(|l| config.layer_directions.get(l).copied().unwrap_or_default())
+                .collect(),
+            via_spans,
+            fixed: vec![FREE; cells],
+            routed: vec![FREE; cells],
+            routed_count: vec![0; cells],
+            via_blocked: vec![false; cells],
+            scratch: SearchScratch::default(),
+        }
+    }
+
+    /// Layers drilled by the via for a change between two layers: the
+    /// shortest allowed span covering both.
+    fn via_layers(&self, a: usize, b: usize) -> (usize, usize) {
+        let (lo, hi) = (a.min(b), a.max(b));
+        self.via_spans
+            .iter()
+            .filter(|&&(first, last)| first <= lo && hi <= last)
+            .min_by_key(|&&(first, last)| last - first)
+            .copied()
+            .unwrap_or((lo, hi))
+    }
+
+    fn index(&self, layer: usize, x: usize, y: usize) -> usize {
+        (layer * self.ny + y) * self.nx + x
+    }
+
+    fn cell_center(&self, x: usize, y: usize) -> Point2D {
+        Point2D::new(
+            self.origin.x + x as f64 * self.pitch,
+            self.origin.y + y as f64 * self.pitch,
+        )
+    }
+
+    fn nearest_cell(&self, p: &Point2D) -> (usize, usize) {
+        let x = ((p.x - self.origin.x) / self.pitch).round().clamp(0.0, (self.nx - 1) as f64);
+        let y = ((p.y - self.origin.y) / self.pitch).round().clamp(0.0, (self.ny - 1) as f64);
+        (x as usize, y as usize)
+    }
+
+    /// Cell index range covering a box around `center`.
+    fn cell_range(&self, center: &Point2D, radius: f64) -> (usize, usize, usize, usize) {
+        let (x0, y0) = self.nearest_cell(&Point2D::new(center.x - radius, center.y - radius));
+        let (x1, y1) = self.nearest_cell(&Point2D::new(center.x + radius, center.y + radius));
+        (x0, y0, x1, y1)
+    }
+
+    /// Mark cells for which `distance(cell) <= 0` as owned by `owner`.
+    ///
+    /// Cells claimed by two nets become blocked unless `force` is set, which
+    /// is used for the copper of pads themselves so fine-pitch pads stay
+    /// reachable.
+    fn mark_fixed<F>(&mut self, layer: usize, bbox: BoundingBox, owner: i32, force: bool, distance: F)
+    where
+        F: Fn(&Point2D) -> f64,
+    {
+        let (x0, y0) = self.nearest_cell(&bbox.min);
+        let (x1, y1) = self.nearest_cell(&bbox.max);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if distance(&self.cell_center(x, y)) <= 0.0 {
+                    let idx = self.index(layer, x, y);
+                    if self.fixed[idx] == FREE || owner == BLOCKED || force {
+                        self.fixed[idx] = owner;
+                    } else if self.fixed[idx] != owner {
+                        // Two nets claim the cell; nobody may route through it
+                        self.fixed[idx] = BLOCKED;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rasterize existing pads, traces and vias as obstacles.
+    fn mark_fixed_copper(
+        &mut self,
+        layout: &Layout,
+        net_names: &[String],
+        copper: &[String],
+        config: &AutorouteConfig,
+    ) {
+        let inflate = config.max_clearance() + config.max_trace_width() / 2.0 + self.pitch / 2.0;
+        let owner_of = |net: Option<&str>| -> i32 {
+            net.and_then(|n| net_names.iter().position(|name| name == n))
+                .map(|i| i as i32)
+                .unwrap_or(BLOCKED)
+        };
+        let layer_index = |name: &str| copper.iter().position(|l| l == name);
+
+        for component in &layout.components {
+            for pad in &component.pads {
+                let center = component.pad_position(pad);
+                let owner = owner_of(pad.net.as_deref());
+                let reach = (pad.size.0 / 2.0).hypot(pad.size.1 / 2.0) + inflate;
+                let bbox = BoundingBox::new(center, center).expanded(reach);
+                for layer in component.pad_copper_layers(pad, copper) {
+                    let Some(li) = layer_index(&layer) else { continue };
+                    self.mark_fixed(li, bbox, owner, false, |p| {
+                        component.pad_distance(pad, p) - inflate
+                    });
+                }
+                if pad.drill > 0.0 && pad.net.is_none() {
+                    // Unconnected holes block every layer
+                    for li in 0..self.layers {
+                        self.mark_fixed(li, bbox, BLOCKED, false, |p| {
+                            p.distance(&center) - pad.drill / 2.0 - inflate
+                        });
+                    }
+                }
+            }
+        }
+
+        // Pad copper always belongs to the pad's own net
+        for component in &layout.components {
+            for pad in component.pads.iter().filter(|p| p.net.is_some()) {
+                let center = component.pad_position(pad);
+                let owner = owner_of(pad.net.as_deref());
+                let bbox = BoundingBox::new(center, center)
+                    .expanded((pad.size.0 / 2.0).hypot(pad.size.1 / 2.0));
+                for layer in component.pad_copper_layers(pad, copper) {
+                    let Some(li) = layer_index(&layer) else { continue };
+                    self.mark_fixed(li, bbox, owner, true, |p| component.pad_distance(pad, p));
+                }
+            }
+        }
+
+        for trace in &layout.traces {
+            let Some(li) = layer_index(&trace.layer) else { continue };
+            let a = trace.start.to_point2d();
+            let b = trace.end.to_point2d();
+            let reach = trace.width / 2.0 + inflate;
+            let mut bbox = BoundingBox::new(a, a);
+            bbox.include(&b);
+            let owner = owner_of(Some(&trace.net));
+            self.mark_fixed(li, bbox.expanded(reach), owner, false, |p| {
+                p.distance_to_segment(&a, &b) - reach
+            });
+        }
+
+        for via in &layout.vias {
+            let center = via.position.to_point2d();
+            let reach = via.pad / 2.0 + inflate;
+            let bbox = BoundingBox::new(center, center).expanded(reach);
+            let owner = owner_of(Some(&via.net));
+            for li in 0..self.layers {
+                self.mark_fixed(li, bbox, owner, false, |p| p.distance(&center) - reach);
+            }
+        }
+    }
+
+    /// Block cells outside the board or too close to its edge.
+    fn mark_board_edge(&mut self, layout: &Layout, config: &AutorouteConfig) {
+        let Some(outline) = &layout.outline else { return };
+        let polygon = outline.to_polygon(64);
+        if polygon.len() < 3 {
+            return;
+        }
+        let margin = config.routing.clearance + config.max_trace_width() / 2.0;
+        for y in 0..self.ny {
+            for x in 0..self.nx {
+                let p = self.cell_center(x, y);
+                let inside = point_in_polygon(&p, &polygon)
+                    && !outline.cutouts.iter().any(|cutout| point_in_polygon(&p, cutout));
+                let edge = std::iter::once(&polygon)
+                    .chain(&outline.cutouts)
+                    .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+                    .map(|(a, b)| p.distance_to_segment(a, b))
+                    .fold(f64::INFINITY, f64::min);
+                if !inside || edge < margin {
+                    for li in 0..self.layers {
+                        let idx = self.index(li, x, y);
+                        self.fixed[idx] = BLOCKED;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Block the track and via keepouts of rule areas. Tracks keep
+    /// `track_radius` plus a cell pitch clear so diagonal steps between
+    /// cell centers stay outside; vias sit on cell centers and only need
+    /// their pad radius.
+    fn mark_rule_areas<'r>(&mut self, areas: impl IntoIterator<Item = &'r RuleArea>, copper: &[String], track_radius: f64, via_radius: f64) {
+        for area in areas {
+            let polygon = area.polygon_mm();
+            let Some(bbox) = BoundingBox::from_points(&polygon) else { continue };
+            let reach = track_radius + self.pitch;
+            for (li, layer) in copper.iter().enumerate() {
+                if area.forbids(KeepoutKind::Track, layer) {
+                    self.mark_fixed(li, bbox.expanded(reach), BLOCKED, false, |p| polygon_distance(p, &polygon) - reach);
+                }
+                if area.forbids(KeepoutKind::Via, layer) {
+                    let reach = bbox.expanded(via_radius);
+                    let (x0, y0) = self.nearest_cell(&reach.min);
+                    let (x1, y1) = self.nearest_cell(&reach.max);
+                    for y in y0..=y1 {
+                        for x in x0..=x1 {
+                            if polygon_distance(&self.cell_center(x, y), &polygon) <= via_radius {
+                                let idx = self.index(li, x, y);
+                                self.via_blocked[idx] = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `net` may occupy a cell, and whether that costs a rip-up.
+    fn passable(&self, idx: usize, net: usize) -> Option<bool> {
+        let fixed = self.fixed[idx];
+        if fixed == BLOCKED || (fixed != FREE && fixed != net as i32) {
+            return None;
+        }
+        let routed = self.routed[idx];
+        Some(routed != FREE && routed != net as i32)
+    }
+
+    /// Whether a via of `radius` spanning `layers` fits at (x, y) for `net`.
+    fn via_fits(&self, x: usize, y: usize, layers: (usize, usize), net: usize, radius: f64, allow_rip: bool) -> bool {
+        let center = self.cell_center(x, y);
+        let (x0, y0, x1, y1) = self.cell_range(&center, radius);
+        for layer in layers.0..=layers.1 {
+            if self.via_blocked[self.index(layer, x, y)] {
+                return false;
+            }
+            for cy in y0..=y1 {
+                for cx in x0..=x1 {
+                    if self.cell_center(cx, cy).distance(&center) > radius {
+                        continue;
+                    }
+                    match self.passable(self.index(layer, cx, cy), net) {
+                        None => return false,
+                        Some(true) if !allow_rip => return false,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// A* search for a connection. With `rip_cost` set, cells used by other
+    /// nets' routes are passable at that extra cost.
+    fn search(
+        &mut self,
+        conn: &Connection,
+        via_radius: f64,
+        rip_cost: Option<u64>,
+        config: &AutorouteConfig,
+    ) -> Option<Vec<GridNode>> {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.reset(self.layers * self.nx * self.ny);
+        let path = self.search_in(&mut scratch, conn, via_radius, rip_cost, config);
+        self.scratch = scratch;
+        path
+    }
+
+    fn search_in(
+        &self,
+        scratch: &mut SearchScratch,
+        conn: &Connection,
+        via_radius: f64,
+        rip_cost: Option<u64>,
+        config: &AutorouteConfig,
+    ) -> Option<Vec<GridNode>> {
+        let net = conn.net;
+        let (sx, sy) = self.nearest_cell(&conn.from.point);
+        let (tx, ty) = self.nearest_cell(&conn.to.point);
+        let is_target = |n: &GridNode| n.x == tx && n.y == ty && conn.to.layers.contains(&n.layer);
+        let heuristic = |x: usize, y: usize| -> u64 {
+            let dx = x.abs_diff(tx) as u64;
+            let dy = y.abs_diff(ty) as u64;
+            STEP * dx.max(dy) + (DIAGONAL_STEP - STEP) * dx.min(dy)
+        };
+        let via_cost = (config.via_cost * STEP as f64) as u64;
+        let wrong_dir = (config.wrong_direction_cost * STEP as f64) as u64;
+
+        let mut heap = BinaryHeap::new();
+        let mut counter = 0u64;
+
+        for &layer in &conn.from.layers {
+            let idx = self.index(layer, sx, sy);
+            if self.passable(idx, net).is_none() {
+                continue;
+            }
+            scratch.set(idx, 0, usize::MAX);
+            heap.push(Reverse((heuristic(sx, sy), counter, idx)));
+            counter += 1;
+        }
+
+        while let Some(Reverse((_, _, idx))) = heap.pop() {
+            let layer = idx / (self.nx * self.ny);
+            let rem = idx % (self.nx * self.ny);
+            let (x, y) = (rem % self.nx, rem / self.nx);
+            let node = GridNode { layer, x, y };
+            if is_target(&node) {
+                let mut path = vec![node];
+                let mut cur = idx;
+                while scratch.parent(cur) != usize::MAX {
+                    cur = scratch.parent(cur);
+                    let layer = cur / (self.nx * self.ny);
+                    let rem = cur % (self.nx * self.ny);
+                    path.push(GridNode { layer, x: rem % self.nx, y: rem / self.nx });
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let base = scratch.cost(idx);
+
+            let mut relax = |next: usize, step: u64, heap: &mut BinaryHeap<_>, nx: usize, ny: usize| {
+                let extra = match self.passable(next, net) {
+                    None => return,
+                    Some(true) => match rip_cost {
+                        Some(c) => c,
+                        None => return,
+                    },
+                    Some(false) => 0,
+                };
+                let new_cost = base + step + extra;
+                if new_cost < scratch.cost(next) {
+                    scratch.set(next, new_cost, idx);
+                    heap.push(Reverse((new_cost + heuristic(nx, ny), counter, next)));
+                    counter += 1;
+                }
+            };
+
+            // Planar moves, diagonals only when both orthogonal cells are open
+            for (dx, dy) in [(1i64, 0i64), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let nx = x as i64 + dx;
+                let ny = y as i64 + dy;
+                if nx < 0 || ny < 0 || nx >= self.nx as i64 || ny >= self.ny as i64 {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                let diagonal = dx != 0 && dy != 0;
+                if diagonal {
+                    let a = self.index(layer, nx, y);
+                    let b = self.index(layer, x, ny);
+                    if self.passable(a, net) != Some(false) || self.passable(b, net) != Some(false) {
+                        continue;
+                    }
+                }
+                let mut step = if diagonal { DIAGONAL_STEP } else { STEP };
+                step += match self.directions[layer] {
+                    LayerDirection::Horizontal if dy != 0 => wrong_dir,
+                    LayerDirection::Vertical if dx != 0 => wrong_dir,
+                    _ => 0,
+                };
+                relax(self.index(layer, nx, ny), step, &mut heap, nx, ny);
+            }
+
+            // Layer changes through any allowed via span containing this layer
+            for &(first, last) in &self.via_spans {
+                if layer < first || layer > last {
+                    continue;
+                }
+                if !self.via_fits(x, y, (first, last), net, via_radius, rip_cost.is_some()) {
+                    continue;
+                }
+                for other in (first..=last).filter(|&l| l != layer) {
+                    relax(self.index(other, x, y), via_cost, &mut heap, x, y);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Cells on a path (and its via footprints) held by other nets' routes.
+    fn conflict_cells(&self, net: usize, path: &[GridNode], via_radius: f64) -> HashSet<usize> {
+        let mut cells = HashSet::new();
+        let mut check = |idx: usize| {
+            let routed = self.routed[idx];
+            if routed != FREE && routed != net as i32 {
+                cells.insert(idx);
+            }
+        };
+        for (i, node) in path.iter().enumerate() {
+            check(self.index(node.layer, node.x, node.y));
+            if let Some(next) = path.get(i + 1).filter(|n| n.layer != node.layer) {
+                let center = self.cell_center(node.x, node.y);
+                let (x0, y0, x1, y1) = self.cell_range(&center, via_radius);
+                let (first, last) = self.via_layers(node.layer, next.layer);
+                for layer in first..=last {
+                    for cy in y0..=y1 {
+                        for cx in x0..=x1 {
+                            check(self.index(layer, cx, cy));
+                        }
+                    }
+                }
+            }
+        }
+        cells
+    }
+
+    /// Claim the cells around a path for `net`; returns the marked cells.
+    fn occupy(&mut self, net: usize, path: &[GridNode], radius: f64, via_radius: f64) -> Vec<usize> {
+        let mut marked = Vec::new();
+        let mut claim = |grid: &mut RouteGrid, layer: usize, center: &Point2D, r: f64| {
+            let (x0, y0, x1, y1) = grid.cell_range(center, r);
+            for cy in y0..=y1 {
+                for cx in x0..=x1 {
+                    if grid.cell_center(cx, cy).distance(center) > r {
+                        continue;
+                    }
+                    let idx = grid.index(layer, cx, cy);
+                    if grid.fixed[idx] == BLOCKED {
+                        continue;
+                    }
+                    grid.routed[idx] = net as i32;
+                    grid.routed_count[idx] = grid.routed_count[idx].saturating_add(1);
+                    marked.push(idx);
+                }
+            }
+        };
+        for (i, node) in path.iter().enumerate() {
+            let center = self.cell_center(node.x, node.y);
+            claim(self, node.layer, &center, radius);
+            if let Some(next) = path.get(i + 1).filter(|n| n.layer != node.layer) {
+                let (first, last) = self.via_layers(node.layer, next.layer);
+                for layer in first..=last {
+                    claim(self, layer, &center, radius + via_radius);
+                }
+            }
+        }
+        marked
+    }
+
+    /// Release cells claimed by `occupy`.
+    fn release(&mut self, marked: &[usize]) {
+        for &idx in marked {
+            self.routed_count[idx] = self.routed_count[idx].saturating_sub(1);
+            if self.routed_count[idx] == 0 {
+                self.routed[idx] = FREE;
+            }
+        }
+    }
+}
+
+/// Distance from a point to a polygon, negative inside it.
+fn polygon_distance(p: &Point2D, polygon: &[Point2D]) -> f64 {
+    if point_in_polygon(p, polygon) {
+        return -1.0;
+    }
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| p.distance_to_segment(a, b))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Unit direction of a step, quantized to compare collinear runs.
+fn direction(a: &Point2D, b: &Point2D) -> (i64, i64) {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let len = dx.hypot(dy);
+    if len < 1e-12 {
+        return (0, 0);
+    }
+    (((dx / len) * 1000.0).round() as i64, ((dy / len) * 1000.0).round() as i64)
+}
+
+fn point_to_position(p: &Point2D) -> Position {
+    Position::new(p.x, p.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Pad, PlacedComponent};
+    use crate::units::LengthUnit;
+
+    fn two_resistor_layout() -> Layout {
+        let mut layout = Layout::with_board_size(40.0, 30.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("R1", "10k", "R_0603")
+                .at(10.0, 15.0)
+                .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9).with_net("SIG"))
+                .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9).with_net("VCC")),
+        );
+        layout.components.push(
+            PlacedComponent::new("R2", "10k", "R_0603")
+                .at(30.0, 15.0)
+                .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9).with_net("SIG"))
+                .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9).with_net("GND")),
+        );
+        layout
+    }
+
+    #[test]
+    fn test_autoroute_simple_connection() {
+        let mut layout = two_resistor_layout();
+        let report = Autorouter::new(&mut layout, AutorouteConfig::default())
+            .route_all()
+            .unwrap();
+
+        assert_eq!(report.connections, 1);
+        assert_eq!(report.routed, 1);
+        assert!(report.failed.is_empty());
+        assert!(!layout.traces.is_empty());
+        assert!(layout.traces.iter().all(|t| t.net == "SIG"));
+    }
+
+    #[test]
+    fn test_autoroute_is_deterministic() {
+        let mut a = two_resistor_layout();
+        let mut b = two_resistor_layout();
+        let config = AutorouteConfig { seed: 42, ..Default::default() };
+        Autorouter::new(&mut a, config.clone()).route_all().unwrap();
+        Autorouter::new(&mut b, config).route_all().unwrap();
+
+        assert_eq!(a.traces.len(), b.traces.len());
+        for (ta, tb) in a.traces.iter().zip(&b.traces) {
+            assert_eq!(ta.start.x, tb.start.x);
+            assert_eq!(ta.end.y, tb.end.y);
+            assert_eq!(ta.layer, tb.layer);
+        }
+    }
+
+    #[test]
+    fn test_autoroute_uses_net_class_width() {
+        let mut layout = two_resistor_layout();
+        let config = AutorouteConfig {
+            net_classes: vec![AutorouteNetClass {
+                name: "Wide".to_string(),
+                nets: vec!["SIG".to_string()],
+                trace_width: 0.4,
+                clearance: 0.2,
+                via_drill: 0.3,
+                via_pad: 0.6,
+            }],
+            ..Default::default()
+        };
+        Autorouter::new(&mut layout, config).route_all().unwrap();
+        assert!(layout.traces.iter().all(|t| (t.width - 0.4).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_autoroute_uses_class_width_per_layer() {
+        use crate::impedance::LayerWidth;
+        use crate::layout::Keepouts;
+
+        let mut layout = two_resistor_layout();
+        let routing = RoutingConfig::default().with_class_widths("RF", &[
+            LayerWidth { layer: "F.Cu".to_string(), width: 0.3, impedance: 50.0 },
+            LayerWidth { layer: "B.Cu".to_string(), width: 0.5, impedance: 50.0 },
+        ]);
+        let config = AutorouteConfig {
+            routing,
+            net_classes: vec![AutorouteNetClass {
+                name: "RF".to_string(),
+                nets: vec!["SIG".to_string()],
+                trace_width: 0.4,
+                clearance: 0.2,
+                via_drill: 0.3,
+                via_pad: 0.6,
+            }],
+            // Force a detour over the bottom layer
+            keepouts: vec![RuleArea::new("keepout", vec![
+                Point2D::new(18.0, 0.0),
+                Point2D::new(22.0, 0.0),
+                Point2D::new(22.0, 30.0),
+                Point2D::new(18.0, 30.0),
+            ])
+            .with_layers(&["F.Cu"])
+            .with_keepout(Keepouts::all())],
+            ..Default::default()
+        };
+        Autorouter::new(&mut layout, config).route_all().unwrap();
+
+        assert!(layout.traces.iter().any(|t| t.layer == "B.Cu"));
+        for trace in &layout.traces {
+            let width = if trace.layer == "F.Cu" { 0.3 } else { 0.5 };
+            assert!((trace.width - width).abs() < 1e-9, "{} on {}", trace.width, trace.layer);
+        }
+    }
+
+    #[test]
+    fn test_autoroute_avoids_keepout_with_via() {
+        use crate::layout::Keepouts;
+
+        let mut layout = two_resistor_layout();
+        let config = AutorouteConfig {
+            keepouts: vec![RuleArea::new("keepout", vec![
+                Point2D::new(18.0, 0.0),
+                Point2D::new(22.0, 0.0),
+                Point2D::new(22.0, 30.0),
+                Point2D::new(18.0, 30.0),
+            ])
+            .with_layers(&["F.Cu"])
+            .with_keepout(Keepouts::all())],
+            ..Default::default()
+        };
+        let report = Autorouter::new(&mut layout, config).route_all().unwrap();
+
+        assert_eq!(report.routed, 1);
+        assert!(report.vias_added >= 2);
+        assert!(layout.traces.iter().any(|t| t.layer == "B.Cu"));
+        assert!(layout.traces.iter().filter(|t| t.layer == "F.Cu").all(|t| {
+            t.start.x.max(t.end.x) <= 18.0 || t.start.x.min(t.end.x) >= 22.0
+        }));
+    }
+
+    #[test]
+    fn test_autoroute_honours_rule_areas() {
+        use crate::layout::{Keepouts, RuleArea};
+
+        let band = |x0: f64, x1: f64| vec![
+            Point2D::new(x0, 0.0),
+            Point2D::new(x1, 0.0),
+            Point2D::new(x1, 30.0),
+            Point2D::new(x0, 30.0),
+        ];
+        let mut layout = two_resistor_layout();
+        layout.rule_areas.push(RuleArea::new("slot", band(18.0, 22.0))
+            .with_layers(&["F.Cu"])
+            .with_keepout(Keepouts { tracks: true, ..Default::default() }));
+        layout.rule_areas.push(RuleArea::new("no_vias", band(14.0, 26.0))
+            .with_keepout(Keepouts { vias: true, ..Default::default() }));
+        let report = Autorouter::new(&mut layout, AutorouteConfig::default()).route_all().unwrap();
+
+        assert_eq!(report.routed, 1);
+        assert!(report.vias_added >= 2);
+        assert!(layout.vias.iter().all(|v| v.position.x < 14.0 || v.position.x > 26.0));
+        assert!(layout.traces.iter().filter(|t| t.layer == "F.Cu").all(|t| {
+            t.start.x.max(t.end.x) < 18.0 || t.start.x.min(t.end.x) > 22.0
+        }));
+    }
+
+    #[test]
+    fn test_search_scratch_reuse() {
+        let mut scratch = SearchScratch::default();
+        scratch.reset(4);
+        scratch.set(1, 7, 0);
+        assert_eq!(scratch.cost(1), 7);
+        scratch.reset(4);
+        assert_eq!(scratch.cost(1), u64::MAX);
+        assert_eq!(scratch.parent(1), usize::MAX);
+
+        // A wrapped generation must not revive old entries
+        scratch.generation = u32::MAX;
+        scratch.set(2, 3, 1);
+        scratch.reset(4);
+        assert_eq!(scratch.cost(2), u64::MAX);
+    }
+
+    #[test]
+    fn test_autoroute_skips_already_routed() {
+        let mut layout = two_resistor_layout();
+        Autorouter::new(&mut layout, AutorouteConfig::default()).route_all().unwrap();
+        let traces = layout.traces.len();
+
+        let report = Autorouter::new(&mut layout, AutorouteConfig::default())
+            .route_all()
+            .unwrap();
+        assert_eq!(report.connections, 0);
+        assert_eq!(layout.traces.len(), traces);
+    }
+}
@@ -4,6 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::net::NetType;
 use crate::units::LengthUnit;
 
 /// A design constraint.
@@ -42,6 +43,15 @@ pub enum Constraint {
         impedance: f64,
         /// Tolerance (%)
         tolerance: f64,
+        /// Maximum length difference between the two nets
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_skew: Option<f64>,
+        /// Maximum routed length where the nets are not coupled
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_uncoupled: Option<f64>,
+        /// Unit of the length limits
+        #[serde(default)]
+        unit: LengthUnit,
     },
 
     /// Length matching constraint
@@ -67,6 +77,36 @@ pub enum Constraint {
         /// Unit
         unit: LengthUnit,
     },
+
+    /// Maximum via count per net
+    MaxVias {
+        /// Scope
+        scope: String,
+        /// Maximum number of vias
+        max: usize,
+    },
+
+    /// Maximum length of branches off a net's main route
+    StubLength {
+        /// Scope
+        scope: String,
+        /// Maximum stub length
+        max: f64,
+        /// Unit
+        unit: LengthUnit,
+    },
+
+    /// Parallel-run limit between nets, for crosstalk
+    ParallelRun {
+        /// Scope (applies when either net is in scope)
+        scope: String,
+        /// Edge-to-edge gap below which tracks count as parallel
+        gap: f64,
+        /// Maximum total parallel length per pair of nets
+        max_length: f64,
+        /// Unit
+        unit: LengthUnit,
+    },
 }
 
 impl Constraint {
@@ -101,7 +141,59 @@ impl Constraint {
             net_negative: net_negative.into(),
             impedance,
             tolerance,
+            max_skew: None,
+            max_uncoupled: None,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    /// Create a via count constraint.
+    pub fn max_vias(scope: impl Into<String>, max: usize) -> Self {
+        Self::MaxVias {
+            scope: scope.into(),
+            max,
+        }
+    }
+
+    /// Create a stub length constraint.
+    pub fn stub_length(scope: impl Into<String>, max: f64, unit: LengthUnit) -> Self {
+        Self::StubLength {
+            scope: scope.into(),
+            max,
+            unit,
+        }
+    }
+
+    /// Create a parallel-run (crosstalk) constraint.
+    pub fn parallel_run(scope: impl Into<String>, gap: f64, max_length: f64, unit: LengthUnit) -> Self {
+        Self::ParallelRun {
+            scope: scope.into(),
+            gap,
+            max_length,
+            unit,
+        }
+    }
+}
+
+/// Whether a constraint scope covers a net. Scopes are `all`, `net:<name>`,
+/// `class:<class>` or `type:<net type>` (e.g. `type:clock`).
+pub fn scope_matches(scope: &str, net: &str, class: Option<&str>, net_type: NetType) -> bool {
+    match scope.split_once(':') {
+        None => scope == "all" || scope == "*",
+        Some(("net", name)) => name == net,
+        Some(("class", name)) => class == Some(name),
+        Some(("type", name)) => {
+            let wanted = match name.to_ascii_lowercase().as_str() {
+                "signal" => NetType::Signal,
+                "power" => NetType::Power,
+                "ground" => NetType::Ground,
+                "clock" => NetType::Clock,
+                "differential" => NetType::Differential,
+                _ => return false,
+            };
+            wanted == net_type
         }
+        Some(_) => false,
     }
 }
 
@@ -115,4 +207,14 @@ mod tests {
         let json = serde_json::to_string(&constraint).unwrap();
         assert!(json.contains("clearance"));
     }
+
+    #[test]
+    fn test_scope_matches() {
+        assert!(scope_matches("all", "CLK", None, NetType::Clock));
+        assert!(scope_matches("net:CLK", "CLK", None, NetType::Clock));
+        assert!(scope_matches("class:ddr", "DQ0", Some("ddr"), NetType::Signal));
+        assert!(scope_matches("type:clock", "CLK", None, NetType::Clock));
+        assert!(!scope_matches("type:clock", "DQ0", Some("ddr"), NetType::Signal));
+        assert!(!scope_matches("net:CLK", "CLK2", None, NetType::Clock));
+    }
 }
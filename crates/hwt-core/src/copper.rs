@@ -0,0 +1,517 @@
+//! Copper Shape Geometry.
+//!
+//! Exact outlines of tracks, vias, pads and zones for clearance checks.
+//! Every shape is a core (a segment or a polygon) grown by a radius, so a
+//! track is a capsule, a via a disc and a round-rect pad a shrunken
+//! rectangle grown by its corner radius.
+
+use crate::geometry::{point_in_polygon, segment_distance, segment_polygon_distance, segments_intersect, BoundingBox, Point2D};
+use crate::layout::{Layout, Pad, PadShape, PlacedComponent, Trace, Via, Zone, ZoneKind};
+
+/// Corner radius of round-rect pads relative to their smaller side.
+pub const ROUND_RECT_RATIO: f64 = 0.25;
+
+/// Core of a copper shape before it is grown by its radius.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeCore {
+    /// Line segment (a point when both ends coincide)
+    Segment(Point2D, Point2D),
+    /// Closed polygon
+    Polygon(Vec<Point2D>),
+}
+
+/// A copper outline: a core grown by `radius`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CopperShape {
+    /// Shape core
+    pub core: ShapeCore,
+    /// Distance the core is grown by (mm)
+    pub radius: f64,
+}
+
+impl CopperShape {
+    /// Disc of radius `radius` around `center`.
+    pub fn circle(center: Point2D, radius: f64) -> Self {
+        Self::capsule(center, center, radius)
+    }
+
+    /// Segment from `a` to `b` with round ends.
+    pub fn capsule(a: Point2D, b: Point2D, radius: f64) -> Self {
+        Self {
+            core: ShapeCore::Segment(a, b),
+            radius,
+        }
+    }
+
+    /// Polygon with sharp corners.
+    pub fn polygon(points: Vec<Point2D>) -> Self {
+        Self {
+            core: ShapeCore::Polygon(points),
+            radius: 0.0,
+        }
+    }
+
+    /// Outline of a track (in mm).
+    pub fn from_trace(trace: &Trace) -> Self {
+        let to_mm = |v: f64| trace.unit.to_mm(v);
+        Self::capsule(
+            Point2D::new(to_mm(trace.start.x), to_mm(trace.start.y)),
+            Point2D::new(to_mm(trace.end.x), to_mm(trace.end.y)),
+            to_mm(trace.width) / 2.0,
+        )
+    }
+
+    /// Outline of a via pad (in mm).
+    pub fn from_via(via: &Via) -> Self {
+        let to_mm = |v: f64| via.unit.to_mm(v);
+        Self::circle(
+            Point2D::new(to_mm(via.position.x), to_mm(via.position.y)),
+            to_mm(via.pad) / 2.0,
+        )
+    }
+
+    /// Outline of a zone polygon (in mm).
+    pub fn from_zone(zone: &Zone) -> Self {
+        Self::polygon(
+            zone.points
+                .iter()
+                .map(|p| Point2D::new(zone.unit.to_mm(p.x), zone.unit.to_mm(p.y)))
+                .collect(),
+        )
+    }
+
+    /// Outline of a placed pad, following component rotation and side.
+    /// Trapezoid and custom pads use their bounding rectangle.
+    pub fn from_pad(component: &PlacedComponent, pad: &Pad) -> Self {
+        let center = component.pad_position(pad);
+        let (hw, hh) = (pad.size.0 / 2.0, pad.size.1 / 2.0);
+        let (sin, cos) = component.rotation.to_radians().sin_cos();
+        let place = |x: f64, y: f64| Point2D::new(center.x + x * cos - y * sin, center.y + x * sin + y * cos);
+        let rect = |hw: f64, hh: f64| vec![place(-hw, -hh), place(hw, -hh), place(hw, hh), place(-hw, hh)];
+
+        match pad.shape {
+            PadShape::Circle => Self::circle(center, hw.max(hh)),
+            PadShape::Oval => {
+                let r = hw.min(hh);
+                Self::capsule(place(-(hw - r), -(hh - r)), place(hw - r, hh - r), r)
+            }
+            PadShape::RoundRect => {
+                let r = hw.min(hh) * 2.0 * ROUND_RECT_RATIO;
+                Self {
+                    core: ShapeCore::Polygon(rect(hw - r, hh - r)),
+                    radius: r,
+                }
+            }
+            PadShape::Rect | PadShape::Trapezoid | PadShape::Custom => Self::polygon(rect(hw, hh)),
+        }
+    }
+
+    /// Edge-to-edge distance to another shape; zero or negative when the
+    /// shapes touch or overlap.
+    pub fn distance(&self, other: &CopperShape) -> f64 {
+        core_distance(&self.core, &other.core) - self.radius - other.radius
+    }
+
+    /// Bounding box of the grown shape.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let bbox = match &self.core {
+            ShapeCore::Segment(a, b) => BoundingBox::from_points(&[*a, *b]),
+            ShapeCore::Polygon(points) => BoundingBox::from_points(points),
+        };
+        bbox.map(|b| b.expanded(self.radius))
+    }
+
+    /// Distance from `point` to the farthest part of the shape.
+    pub fn max_distance_from(&self, point: &Point2D) -> f64 {
+        let farthest = match &self.core {
+            ShapeCore::Segment(a, b) => point.distance(a).max(point.distance(b)),
+            ShapeCore::Polygon(points) => points.iter().map(|p| point.distance(p)).fold(0.0, f64::max),
+        };
+        farthest + self.radius
+    }
+
+    /// Point on this shape's outline nearest to `other`, or a point inside
+    /// both shapes when they overlap. Used as the location of pair
+    /// violations, so it lies within the clearance of both items.
+    pub fn contact_point(&self, other: &CopperShape) -> Point2D {
+        let (p, q) = core_closest_points(&self.core, &other.core);
+        let d = p.distance(&q);
+        if d <= f64::EPSILON {
+            return p;
+        }
+        let t = self.radius.min(d) / d;
+        Point2D::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t)
+    }
+
+    /// A representative point for reporting.
+    pub fn anchor(&self) -> Point2D {
+        match &self.core {
+            ShapeCore::Segment(a, b) => Point2D::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0),
+            ShapeCore::Polygon(points) => {
+                BoundingBox::from_points(points).map(|b| b.center()).unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Distance between two shape cores (zero when they touch or overlap).
+fn core_distance(a: &ShapeCore, b: &ShapeCore) -> f64 {
+    match (a, b) {
+        (ShapeCore::Segment(a1, a2), ShapeCore::Segment(b1, b2)) => segment_distance(a1, a2, b1, b2),
+        (ShapeCore::Segment(s1, s2), ShapeCore::Polygon(poly))
+        | (ShapeCore::Polygon(poly), ShapeCore::Segment(s1, s2)) => segment_polygon_distance(s1, s2, poly),
+        (ShapeCore::Polygon(p), ShapeCore::Polygon(q)) => polygon_distance(p, q),
+    }
+}
+
+/// Closest points of two shape cores, one on each.
+fn core_closest_points(a: &ShapeCore, b: &ShapeCore) -> (Point2D, Point2D) {
+    let edges = |core: &ShapeCore| -> Vec<(Point2D, Point2D)> {
+        match core {
+            ShapeCore::Segment(s, e) => vec![(*s, *e)],
+            ShapeCore::Polygon(poly) => {
+                let n = poly.len();
+                (0..n).map(|i| (poly[i], poly[(i + 1) % n])).collect()
+            }
+        }
+    };
+    // A vertex of one core inside the other polygon is a common point
+    let inside = |core: &ShapeCore, other: &ShapeCore| -> Option<Point2D> {
+        let ShapeCore::Polygon(poly) = other else { return None };
+        let vertices = match core {
+            ShapeCore::Segment(s, e) => vec![*s, *e],
+            ShapeCore::Polygon(points) => points.clone(),
+        };
+        vertices.into_iter().find(|v| point_in_polygon(v, poly))
+    };
+    if let Some(p) = inside(a, b).or_else(|| inside(b, a)) {
+        return (p, p);
+    }
+
+    let mut best = (Point2D::default(), Point2D::default());
+    let mut best_distance = f64::INFINITY;
+    for (a1, a2) in edges(a) {
+        for (b1, b2) in edges(b) {
+            let (p, q) = segment_closest_points(&a1, &a2, &b1, &b2);
+            let d = p.distance(&q);
+            if d < best_distance {
+                best_distance = d;
+                best = (p, q);
+            }
+        }
+    }
+    best
+}
+
+/// Closest points of segments `ab` and `cd`.
+fn segment_closest_points(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> (Point2D, Point2D) {
+    if segments_intersect(a, b, c, d) {
+        // Intersection of the supporting lines, or a shared endpoint when collinear
+        let r = Point2D::new(b.x - a.x, b.y - a.y);
+        let s = Point2D::new(d.x - c.x, d.y - c.y);
+        let denom = r.x * s.y - r.y * s.x;
+        if denom.abs() > f64::EPSILON {
+            let t = ((c.x - a.x) * s.y - (c.y - a.y) * s.x) / denom;
+            let p = Point2D::new(a.x + r.x * t, a.y + r.y * t);
+            return (p, p);
+        }
+    }
+    [
+        (*a, project(a, c, d)),
+        (*b, project(b, c, d)),
+        (project(c, a, b), *c),
+        (project(d, a, b), *d),
+    ]
+    .into_iter()
+    .min_by(|(p1, q1), (p2, q2)| p1.distance(q1).total_cmp(&p2.distance(q2)))
+    .unwrap()
+}
+
+/// Point of segment `ab` nearest to `p`.
+fn project(p: &Point2D, a: &Point2D, b: &Point2D) -> Point2D {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 <= f64::EPSILON {
+        return *a;
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+    Point2D::new(a.x + dx * t, a.y + dy * t)
+}
+
+/// Distance between two polygons (zero when one contains or crosses the other).
+fn polygon_distance(p: &[Point2D], q: &[Point2D]) -> f64 {
+    if p.is_empty() || q.is_empty() {
+        return f64::INFINITY;
+    }
+    if point_in_polygon(&p[0], q) || point_in_polygon(&q[0], p) {
+        return 0.0;
+    }
+    let edges = |poly: &[Point2D]| {
+        let n = poly.len();
+        (0..n).map(move |i| (poly[i], poly[(i + 1) % n])).collect::<Vec<_>>()
+    };
+    let (pe, qe) = (edges(p), edges(q));
+    let mut min = f64::INFINITY;
+    for (a, b) in &pe {
+        for (c, d) in &qe {
+            if segments_intersect(a, b, c, d) {
+                return 0.0;
+            }
+            min = min.min(segment_distance(a, b, c, d));
+        }
+    }
+    min
+}
+
+/// Kind of copper item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CopperKind {
+    Track,
+    Via,
+    Pad,
+    /// Outline of an unfilled zone
+    Zone,
+    /// Filled zone copper
+    ZoneFill,
+    Teardrop,
+}
+
+impl CopperKind {
+    /// Lowercase name used in rule ids and messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CopperKind::Track => "track",
+            CopperKind::Via => "via",
+            CopperKind::Pad => "pad",
+            CopperKind::Zone => "zone",
+            CopperKind::ZoneFill => "zone fill",
+            CopperKind::Teardrop => "teardrop",
+        }
+    }
+}
+
+/// A piece of copper with its net and layers.
+#[derive(Debug, Clone)]
+pub struct CopperItem {
+    /// Item kind
+    pub kind: CopperKind,
+    /// Net name (empty when unconnected)
+    pub net: String,
+    /// Copper layers the item occupies
+    pub layers: Vec<String>,
+    /// Outline
+    pub shape: CopperShape,
+    /// Owning component and pad number, for pads
+    pub pad: Option<(usize, String)>,
+    /// Item-specific minimum clearance (zones)
+    pub clearance: Option<f64>,
+    /// Human-readable description
+    pub label: String,
+}
+
+impl CopperItem {
+    /// Copper item of a track.
+    pub fn from_trace(trace: &Trace) -> Self {
+        Self {
+            kind: CopperKind::Track,
+            net: trace.net.clone(),
+            layers: vec![trace.layer.clone()],
+            shape: CopperShape::from_trace(trace),
+            pad: None,
+            clearance: None,
+            label: format!("track {} on {}", trace.net, trace.layer),
+        }
+    }
+
+    /// Copper item of a via, given the board's copper layers.
+    pub fn from_via(via: &Via, copper_layers: &[String]) -> Self {
+        Self {
+            kind: CopperKind::Via,
+            net: via.net.clone(),
+            layers: via.copper_layers(copper_layers),
+            shape: CopperShape::from_via(via),
+            pad: None,
+            clearance: None,
+            label: format!("via {}", via.net),
+        }
+    }
+
+    /// Whether both items are on a common copper layer.
+    pub fn shares_layer(&self, other: &CopperItem) -> bool {
+        self.layers.iter().any(|l| other.layers.contains(l))
+    }
+
+    /// Whether both items are connected by net or are the same pad.
+    pub fn same_net(&self, other: &CopperItem) -> bool {
+        if !self.net.is_empty() && self.net == other.net {
+            return true;
+        }
+        self.pad.is_some() && self.pad == other.pad
+    }
+}
+
+/// Collect all copper items of a layout.
+pub fn collect_copper(layout: &Layout) -> Vec<CopperItem> {
+    collect_copper_where(layout, |_, _| true)
+}
+
+/// Collect the copper items for which `keep` accepts the net and outline.
+pub(crate) fn collect_copper_where(layout: &Layout, keep: impl Fn(&str, &CopperShape) -> bool) -> Vec<CopperItem> {
+    let copper = layout.copper_layer_names();
+    let mut items = Vec::new();
+
+    items.extend(layout.traces.iter()
+        .filter(|trace| keep(&trace.net, &CopperShape::from_trace(trace)))
+        .map(CopperItem::from_trace));
+    items.extend(layout.vias.iter()
+        .filter(|via| keep(&via.net, &CopperShape::from_via(via)))
+        .map(|via| CopperItem::from_via(via, &copper)));
+    for (ci, component) in layout.components.iter().enumerate() {
+        for pad in &component.pads {
+            let net = pad.net.clone().unwrap_or_default();
+            let shape = CopperShape::from_pad(component, pad);
+            if !keep(&net, &shape) {
+                continue;
+            }
+            items.push(CopperItem {
+                kind: CopperKind::Pad,
+                net,
+                layers: component.pad_copper_layers(pad, &copper),
+                shape,
+                pad: Some((ci, pad.number.clone())),
+                clearance: None,
+                label: format!("pad {}-{}", component.reference, pad.number),
+            });
+        }
+    }
+    for zone in &layout.zones {
+        if zone.is_filled() {
+            let to_mm = |p: &Point2D| Point2D::new(zone.unit.to_mm(p.x), zone.unit.to_mm(p.y));
+            let (kind, label) = match zone.kind {
+                ZoneKind::Copper => (CopperKind::ZoneFill, format!("zone {} on {}", zone.net, zone.layer)),
+                ZoneKind::Teardrop => (CopperKind::Teardrop, format!("teardrop {} on {}", zone.net, zone.layer)),
+            };
+            items.extend(zone.filled.iter()
+                .map(|polygon| CopperShape::polygon(polygon.iter().map(to_mm).collect()))
+                .filter(|shape| keep(&zone.net, shape))
+                .map(|shape| CopperItem {
+                    kind,
+                    net: zone.net.clone(),
+                    layers: vec![zone.layer.clone()],
+                    shape,
+                    pad: None,
+                    clearance: zone.clearance.map(|c| zone.unit.to_mm(c)),
+                    label: label.clone(),
+                }));
+            continue;
+        }
+        let shape = CopperShape::from_zone(zone);
+        if !keep(&zone.net, &shape) {
+            continue;
+        }
+        let kind = match zone.kind {
+            ZoneKind::Copper => CopperKind::Zone,
+            ZoneKind::Teardrop => CopperKind::Teardrop,
+        };
+        items.push(CopperItem {
+            kind,
+            net: zone.net.clone(),
+            layers: vec![zone.layer.clone()],
+            shape,
+            pad: None,
+            clearance: zone.clearance.map(|c| zone.unit.to_mm(c)),
+            label: format!("{} {} on {}", kind.name(), zone.net, zone.layer),
+        });
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::units::LengthUnit;
+
+    #[test]
+    fn test_crossing_tracks_touch() {
+        let a = CopperShape::capsule(Point2D::new(0.0, 0.0), Point2D::new(10.0, 10.0), 0.1);
+        let b = CopperShape::capsule(Point2D::new(0.0, 10.0), Point2D::new(10.0, 0.0), 0.1);
+        assert!(a.distance(&b) < 0.0);
+    }
+
+    #[test]
+    fn test_parallel_tracks() {
+        let a = CopperShape::capsule(Point2D::new(0.0, 0.0), Point2D::new(10.0, 0.0), 0.1);
+        let b = CopperShape::capsule(Point2D::new(0.0, 0.5), Point2D::new(10.0, 0.5), 0.1);
+        assert!((a.distance(&b) - 0.3).abs() < 1e-9);
+
+        // Offset along the track: midpoints are far apart, edges are not
+        let c = CopperShape::capsule(Point2D::new(9.0, 0.5), Point2D::new(30.0, 0.5), 0.1);
+        assert!((a.distance(&c) - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_contact_point() {
+        let a = CopperShape::capsule(Point2D::new(0.0, 0.0), Point2D::new(10.0, 0.0), 0.1);
+        let b = CopperShape::circle(Point2D::new(20.0, 0.0), 0.3);
+        let p = a.contact_point(&b);
+        assert!((p.x - 10.1).abs() < 1e-9 && p.y.abs() < 1e-9);
+
+        // Overlapping shapes meet at a common point
+        let pad = CopperShape::polygon(vec![
+            Point2D::new(4.0, -1.0),
+            Point2D::new(6.0, -1.0),
+            Point2D::new(6.0, 1.0),
+            Point2D::new(4.0, 1.0),
+        ]);
+        let p = a.contact_point(&pad);
+        assert!(a.distance(&CopperShape::circle(p, 0.0)) <= 1e-9);
+        assert!(pad.distance(&CopperShape::circle(p, 0.0)) <= 1e-9);
+    }
+
+    #[test]
+    fn test_rotated_pad_shape() {
+        let component = PlacedComponent::new("U1", "", "QFN").at(10.0, 10.0).rotated(90.0);
+        let pad = Pad::smd("1", 0.0, 0.0, 2.0, 0.5);
+        let shape = CopperShape::from_pad(&component, &pad);
+        let bbox = shape.bounding_box().unwrap();
+        assert!((bbox.width() - 0.5).abs() < 1e-9);
+        assert!((bbox.height() - 2.0).abs() < 1e-9);
+
+        let via = CopperShape::circle(Point2D::new(10.0, 11.5), 0.3);
+        assert!((shape.distance(&via) - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_rect_corner() {
+        let component = PlacedComponent::new("R1", "", "R").at(0.0, 0.0);
+        let pad = Pad::smd("1", 0.0, 0.0, 2.0, 2.0).with_shape(PadShape::RoundRect);
+        let shape = CopperShape::from_pad(&component, &pad);
+        // Corner point of the square is outside the rounded corner
+        let probe = CopperShape::circle(Point2D::new(1.0, 1.0), 0.0);
+        assert!(shape.distance(&probe) > 0.1);
+        let edge = CopperShape::circle(Point2D::new(1.0, 0.0), 0.0);
+        assert!(shape.distance(&edge).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_collect_copper() {
+        let mut layout = Layout::with_board_size(20.0, 20.0, LengthUnit::Mm);
+        layout.traces.push(Trace {
+            net: "A".to_string(),
+            layer: "B.Cu".to_string(),
+            start: Position::new(0.0, 0.0),
+            end: Position::new(1.0, 0.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        layout.components.push(
+            PlacedComponent::new("J1", "", "Conn")
+                .with_pad(Pad::thru_hole("1", 0.0, 0.0, 1.7, 1.0).with_net("A")),
+        );
+        let items = collect_copper(&layout);
+        assert_eq!(items.len(), 2);
+        assert!(items[0].shares_layer(&items[1]));
+        assert!(items[0].same_net(&items[1]));
+    }
+}
@@ -0,0 +1,455 @@
+//! Current Capacity.
+//!
+//! Per-net copper area, bottleneck width and temperature rise of traces and
+//! via groups carrying a current budget. The budget is read from the
+//! `current` property of `net::Net` (e.g., "2A", "500mA"), with an optional
+//! `temperature_rise` property (°C) overriding the allowed rise.
+//!
+//! Trace sizing uses the curve fit of the IPC-2152 baseline chart (1 oz
+//! copper, 1.6 mm board, still air), which IPC-2152 applies to internal and
+//! external layers alike:
+//!
+//! A = (117.555·ΔT^-0.913 + 1.15) · I^(0.84·ΔT^-0.018 + 1.159)
+//!
+//! with A the cross-section in mil², ΔT in °C and I in A. A via barrel is
+//! treated as a trace as wide as its plated circumference, and vias of a
+//! net within `via_group_distance` of each other share its current.
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{polygon_area, Point2D};
+use crate::layout::Layout;
+use crate::net::Net;
+use crate::ratsnest::UnionFind;
+use crate::stackup::{Stackup, COPPER_THICKNESS_PER_OZ};
+
+/// Net property holding the current budget.
+pub const CURRENT_PROPERTY: &str = "current";
+
+/// Net property overriding the allowed temperature rise (°C).
+pub const TEMPERATURE_RISE_PROPERTY: &str = "temperature_rise";
+
+/// Square millimeters per square mil.
+const MM2_PER_MIL2: f64 = 0.0254 * 0.0254;
+
+/// Temperature rise range searched when solving for ΔT (°C).
+const RISE_RANGE: (f64, f64) = (1e-3, 1000.0);
+
+/// Current capacity settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrentConfig {
+    /// Allowed temperature rise above ambient (°C)
+    pub temperature_rise: f64,
+
+    /// Copper thickness of layers missing from the stackup (mm)
+    pub copper_thickness: f64,
+
+    /// Via barrel plating thickness (mm)
+    pub via_plating: f64,
+
+    /// Vias of a net closer than this share its current (mm)
+    pub via_group_distance: f64,
+}
+
+impl Default for CurrentConfig {
+    fn default() -> Self {
+        Self {
+            temperature_rise: 10.0,
+            copper_thickness: COPPER_THICKNESS_PER_OZ,
+            via_plating: 0.025,
+            via_group_distance: 2.0,
+        }
+    }
+}
+
+/// Cross-section (mm²) carrying `current` (A) at a temperature rise of
+/// `rise` (°C), from the IPC-2152 baseline chart fit.
+pub fn required_area(current: f64, rise: f64) -> f64 {
+    if current <= 0.0 {
+        return 0.0;
+    }
+    let rise = rise.max(RISE_RANGE.0);
+    let mil2 = (117.555 * rise.powf(-0.913) + 1.15) * current.powf(0.84 * rise.powf(-0.018) + 1.159);
+    mil2 * MM2_PER_MIL2
+}
+
+/// Trace width (mm) of copper `thickness` (mm) carrying `current` (A) at a
+/// temperature rise of `rise` (°C).
+pub fn required_width(current: f64, rise: f64, thickness: f64) -> f64 {
+    required_area(current, rise) / thickness
+}
+
+/// Temperature rise (°C) of a cross-section `area` (mm²) carrying
+/// `current` (A). Saturates at the ends of the fitted range.
+pub fn temperature_rise(current: f64, area: f64) -> f64 {
+    if current <= 0.0 {
+        return 0.0;
+    }
+    // The required area falls as the allowed rise grows
+    let (mut lo, mut hi) = RISE_RANGE;
+    if required_area(current, lo) <= area {
+        return lo;
+    }
+    if required_area(current, hi) >= area {
+        return hi;
+    }
+    for _ in 0..100 {
+        let mid = (lo * hi).sqrt();
+        if required_area(current, mid) > area {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    hi
+}
+
+/// Current (A) a cross-section `area` (mm²) carries at a temperature rise
+/// of `rise` (°C).
+pub fn current_capacity(area: f64, rise: f64) -> f64 {
+    if area <= 0.0 {
+        return 0.0;
+    }
+    let (mut lo, mut hi) = (0.0, 1.0);
+    while required_area(hi, rise) < area {
+        hi *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if required_area(mid, rise) < area {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+/// Parse a current such as "2", "2A", "1.5 A" or "500mA" into amperes.
+pub fn parse_current(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, scale) = if let Some(n) = value.strip_suffix("mA") {
+        (n, 1e-3)
+    } else if let Some(n) = value.strip_suffix('A') {
+        (n, 1.0)
+    } else {
+        (value, 1.0)
+    };
+    number.trim().parse::<f64>().ok().filter(|c| c.is_finite() && *c >= 0.0).map(|c| c * scale)
+}
+
+/// Capacity of one trace segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentCapacity {
+    /// Index into `Layout::traces`
+    pub trace: usize,
+
+    /// Copper layer
+    pub layer: String,
+
+    /// Width (mm)
+    pub width: f64,
+
+    /// Copper thickness (mm)
+    pub thickness: f64,
+
+    /// Width needed for the net current at the allowed rise (mm)
+    pub required_width: f64,
+
+    /// Current carried at the allowed rise (A)
+    pub capacity: f64,
+
+    /// Estimated temperature rise at the net current (°C)
+    pub temperature_rise: f64,
+}
+
+/// Capacity of vias of a net sharing its current.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ViaGroupCapacity {
+    /// Indices into `Layout::vias`
+    pub vias: Vec<usize>,
+
+    /// Center of the group (mm)
+    pub position: Point2D,
+
+    /// Current one via carries at the allowed rise (A)
+    pub capacity_per_via: f64,
+
+    /// Current the group carries (A)
+    pub capacity: f64,
+
+    /// Vias needed for the net current
+    pub required_count: usize,
+
+    /// Estimated temperature rise at the net current (°C)
+    pub temperature_rise: f64,
+}
+
+/// Current analysis of one net.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetCurrentAnalysis {
+    /// Net name
+    pub net: String,
+
+    /// Current budget (A)
+    pub current: f64,
+
+    /// Allowed temperature rise (°C)
+    pub allowed_rise: f64,
+
+    /// Copper area of traces and zone fills (mm²)
+    pub copper_area: f64,
+
+    /// Width of the segment with the smallest cross-section (mm)
+    pub bottleneck_width: Option<f64>,
+
+    /// Highest estimated temperature rise over segments and vias (°C)
+    pub temperature_rise: f64,
+
+    /// Trace segments
+    pub segments: Vec<SegmentCapacity>,
+
+    /// Via groups
+    pub via_groups: Vec<ViaGroupCapacity>,
+}
+
+impl NetCurrentAnalysis {
+    /// Segments narrower than the required width.
+    pub fn undersized_segments(&self) -> impl Iterator<Item = &SegmentCapacity> {
+        self.segments.iter().filter(|s| s.capacity < self.current)
+    }
+
+    /// Via groups with too few vias.
+    pub fn undersized_vias(&self) -> impl Iterator<Item = &ViaGroupCapacity> {
+        self.via_groups.iter().filter(|g| g.capacity < self.current)
+    }
+
+    /// Whether every segment and via group carries the current.
+    pub fn passes(&self) -> bool {
+        self.undersized_segments().next().is_none() && self.undersized_vias().next().is_none()
+    }
+}
+
+/// Current capacity analyzer.
+pub struct CurrentAnalyzer<'a> {
+    layout: &'a Layout,
+    stackup: Option<&'a Stackup>,
+    config: CurrentConfig,
+}
+
+impl<'a> CurrentAnalyzer<'a> {
+    /// Create an analyzer.
+    pub fn new(layout: &'a Layout, config: CurrentConfig) -> Self {
+        Self { layout, stackup: None, config }
+    }
+
+    /// Take copper thicknesses from a stackup.
+    pub fn with_stackup(mut self, stackup: &'a Stackup) -> Self {
+        self.stackup = Some(stackup);
+        self
+    }
+
+    /// Analyze every net with a current budget, in the given order.
+    pub fn analyze(&self, nets: &[Net]) -> Vec<NetCurrentAnalysis> {
+        nets.iter()
+            .filter_map(|net| {
+                let current = parse_current(net.properties.get(CURRENT_PROPERTY)?)?;
+                let rise = net.properties.get(TEMPERATURE_RISE_PROPERTY)
+                    .and_then(|r| r.trim().parse::<f64>().ok())
+                    .filter(|r| *r > 0.0)
+                    .unwrap_or(self.config.temperature_rise);
+                Some(self.analyze_net(&net.name, current, rise))
+            })
+            .collect()
+    }
+
+    /// Analyze one net carrying `current` (A) at an allowed rise of `rise`
+    /// (°C).
+    pub fn analyze_net(&self, net: &str, current: f64, rise: f64) -> NetCurrentAnalysis {
+        let mut copper_area = 0.0;
+        let mut segments = Vec::new();
+        for (index, trace) in self.layout.traces.iter().enumerate().filter(|(_, t)| t.net == net) {
+            let width = trace.unit.to_mm(trace.width);
+            let thickness = self.layout.copper_thickness(&trace.layer, self.stackup).unwrap_or(self.config.copper_thickness);
+            copper_area += trace.start.to_mm().distance(&trace.end.to_mm()) * width;
+            segments.push(SegmentCapacity {
+                trace: index,
+                layer: trace.layer.clone(),
+                width,
+                thickness,
+                required_width: required_width(current, rise, thickness),
+                capacity: current_capacity(width * thickness, rise),
+                temperature_rise: temperature_rise(current, width * thickness),
+            });
+        }
+        for zone in self.layout.zones.iter().filter(|z| z.net == net) {
+            let scale = zone.unit.to_mm(1.0);
+            copper_area += zone.filled.iter().map(|ring| polygon_area(ring).abs()).sum::<f64>() * scale * scale;
+        }
+
+        let via_groups = self.via_groups(net, current, rise);
+        let bottleneck_width = segments.iter()
+            .min_by(|a, b| (a.width * a.thickness).total_cmp(&(b.width * b.thickness)))
+            .map(|s| s.width);
+        let temperature_rise = segments.iter().map(|s| s.temperature_rise)
+            .chain(via_groups.iter().map(|g| g.temperature_rise))
+            .fold(0.0, f64::max);
+
+        NetCurrentAnalysis {
+            net: net.to_string(),
+            current,
+            allowed_rise: rise,
+            copper_area,
+            bottleneck_width,
+            temperature_rise,
+            segments,
+            via_groups,
+        }
+    }
+
+    /// Group the net's vias by proximity; each group carries the full
+    /// current in parallel.
+    fn via_groups(&self, net: &str, current: f64, rise: f64) -> Vec<ViaGroupCapacity> {
+        let vias: Vec<usize> = (0..self.layout.vias.len()).filter(|&i| self.layout.vias[i].net == net).collect();
+        let positions: Vec<Point2D> = vias.iter().map(|&i| self.layout.vias[i].position.to_mm()).collect();
+        let mut groups = UnionFind::new(vias.len());
+        for a in 0..vias.len() {
+            for b in (a + 1)..vias.len() {
+                if positions[a].distance(&positions[b]) <= self.config.via_group_distance {
+                    groups.union(a, b);
+                }
+            }
+        }
+
+        let mut members: Vec<Vec<usize>> = Vec::new();
+        let mut root_group: Vec<Option<usize>> = vec![None; vias.len()];
+        for a in 0..vias.len() {
+            let root = groups.find(a);
+            let group = *root_group[root].get_or_insert_with(|| {
+                members.push(Vec::new());
+                members.len() - 1
+            });
+            members[group].push(a);
+        }
+
+        let plating = self.config.via_plating;
+        members.into_iter()
+            .map(|group| {
+                // The weakest via sets the per-via capacity and rise
+                let area = group.iter()
+                    .map(|&a| {
+                        let via = &self.layout.vias[vias[a]];
+                        std::f64::consts::PI * (via.unit.to_mm(via.drill) + plating) * plating
+                    })
+                    .fold(f64::INFINITY, f64::min);
+                let count = group.len();
+                let capacity_per_via = current_capacity(area, rise);
+                let n = count as f64;
+                let position = Point2D::new(
+                    group.iter().map(|&a| positions[a].x).sum::<f64>() / n,
+                    group.iter().map(|&a| positions[a].y).sum::<f64>() / n,
+                );
+                ViaGroupCapacity {
+                    vias: group.iter().map(|&a| vias[a]).collect(),
+                    position,
+                    capacity_per_via,
+                    capacity: capacity_per_via * n,
+                    required_count: if capacity_per_via > 0.0 { (current / capacity_per_via).ceil() as usize } else { usize::MAX },
+                    temperature_rise: temperature_rise(current / n, area),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{Trace, Via, ViaType};
+    use crate::stackup::StackupLayer;
+    use crate::units::LengthUnit;
+
+    fn trace(net: &str, layer: &str, x0: f64, x1: f64, width: f64) -> Trace {
+        Trace {
+            net: net.to_string(),
+            layer: layer.to_string(),
+            start: Position::new(x0, 10.0),
+            end: Position::new(x1, 10.0),
+            width,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    fn via(net: &str, x: f64) -> Via {
+        Via {
+            net: net.to_string(),
+            position: Position::new(x, 10.0),
+            via_type: ViaType::Through,
+            drill: 0.3,
+            pad: 0.6,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn test_ipc2152_fit() {
+        // 1 A at 10 °C needs about 11 mil of 1 oz copper on the baseline chart
+        let width = required_width(1.0, 10.0, 0.035) / 0.0254;
+        assert!((10.0..13.0).contains(&width), "{}", width);
+        assert!(required_width(3.0, 10.0, 0.035) > required_width(3.0, 20.0, 0.035));
+        assert!(required_width(3.0, 10.0, 0.07) < required_width(3.0, 10.0, 0.035));
+
+        let area = required_area(2.0, 10.0);
+        assert!((temperature_rise(2.0, area) - 10.0).abs() < 1e-6);
+        assert!((current_capacity(area, 10.0) - 2.0).abs() < 1e-6);
+
+        assert_eq!(parse_current("500mA"), Some(0.5));
+        assert_eq!(parse_current(" 2.5 A"), Some(2.5));
+        assert_eq!(parse_current("3"), Some(3.0));
+        assert_eq!(parse_current("fast"), None);
+    }
+
+    #[test]
+    fn test_net_bottleneck_and_vias() {
+        let mut layout = Layout::with_board_size(50.0, 20.0, LengthUnit::Mm);
+        layout.traces.push(trace("VBUS", "F.Cu", 0.0, 20.0, 2.0));
+        layout.traces.push(trace("VBUS", "F.Cu", 20.0, 25.0, 0.3));
+        layout.traces.push(trace("VBUS", "B.Cu", 25.0, 45.0, 1.0));
+        layout.traces.push(trace("SIG", "F.Cu", 0.0, 20.0, 0.15));
+        layout.vias.push(via("VBUS", 25.0));
+        layout.vias.push(via("VBUS", 25.8));
+        layout.vias.push(via("VBUS", 40.0));
+
+        let stackup = Stackup::new("test")
+            .with_layer(StackupLayer::copper("F.Cu", 1.0))
+            .with_layer(StackupLayer::copper("B.Cu", 2.0));
+        let mut vbus = Net::new("VBUS");
+        vbus.properties.insert(CURRENT_PROPERTY.to_string(), "2A".to_string());
+        let nets = vec![vbus, Net::new("SIG")];
+
+        let analyses = CurrentAnalyzer::new(&layout, CurrentConfig::default())
+            .with_stackup(&stackup)
+            .analyze(&nets);
+        assert_eq!(analyses.len(), 1);
+        let vbus = &analyses[0];
+        assert!((vbus.copper_area - (40.0 + 1.5 + 20.0)).abs() < 1e-9);
+        assert_eq!(vbus.bottleneck_width, Some(0.3));
+
+        // Only the 0.3 mm neck is too narrow; 1 mm of 2 oz copper carries 2 A
+        let undersized: Vec<usize> = vbus.undersized_segments().map(|s| s.trace).collect();
+        assert_eq!(undersized, vec![1]);
+        assert!(vbus.temperature_rise > 10.0);
+        assert!(vbus.segments[2].thickness > vbus.segments[0].thickness);
+
+        // The two vias at the transition share the current, the lone one does not
+        assert_eq!(vbus.via_groups.len(), 2);
+        assert_eq!(vbus.via_groups[0].vias, vec![0, 1]);
+        let lone = &vbus.via_groups[1];
+        assert!(lone.capacity < 2.0 && lone.required_count > 1);
+        assert!(vbus.undersized_vias().any(|g| g.vias == vec![2]));
+        assert!(!vbus.passes());
+    }
+}
@@ -0,0 +1,411 @@
+//! DRC report export.
+//!
+//! Writes a `DrcReport` as JSON, JUnit XML (one test case per rule, so CI
+//! shows failures per rule), SARIF 2.1.0 for code scanning tools, or a
+//! standalone HTML page with an SVG thumbnail of each violation.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::drc::{DrcReport, DrcRule, DrcSeverity, DrcViolation};
+use crate::layout::Layout;
+use crate::svg_export::SvgGenerator;
+
+/// Side of the area shown in a violation thumbnail (mm).
+const THUMBNAIL_SIZE: f64 = 6.0;
+
+/// Thumbnail scale (pixels per mm).
+const THUMBNAIL_SCALE: f64 = 30.0;
+
+/// Report file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Plain JSON
+    Json,
+    /// JUnit XML
+    Junit,
+    /// SARIF 2.1.0
+    Sarif,
+    /// Standalone HTML page
+    Html,
+}
+
+impl ReportFormat {
+    /// Parse a format name (`json`, `junit`, `sarif`, `html`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "junit" | "xml" => Some(Self::Junit),
+            "sarif" => Some(Self::Sarif),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Infer the format from a file extension; `.sarif.json` is SARIF.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        if name.ends_with(".sarif.json") {
+            return Some(Self::Sarif);
+        }
+        Self::from_name(&path.extension()?.to_string_lossy())
+    }
+}
+
+/// Exports a DRC report in the supported formats.
+pub struct DrcExporter<'a> {
+    report: &'a DrcReport,
+    layout: Option<&'a Layout>,
+    rules: Vec<DrcRule>,
+    source: Option<String>,
+}
+
+impl<'a> DrcExporter<'a> {
+    /// Create an exporter for a report.
+    pub fn new(report: &'a DrcReport) -> Self {
+        Self {
+            report,
+            layout: None,
+            rules: Vec::new(),
+            source: None,
+        }
+    }
+
+    /// Layout the report was run on, for HTML thumbnails.
+    pub fn with_layout(mut self, layout: &'a Layout) -> Self {
+        self.layout = Some(layout);
+        self
+    }
+
+    /// Rules that were checked, so passing rules are listed too.
+    pub fn with_rules(mut self, rules: Vec<DrcRule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Design file the report refers to, for SARIF locations.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Number of error-severity violations.
+    pub fn error_count(&self) -> usize {
+        self.report.violations.iter().filter(is_error).count()
+    }
+
+    /// Export in the given format.
+    pub fn export(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Junit => self.to_junit(),
+            ReportFormat::Sarif => self.to_sarif(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    /// Export to a file.
+    pub fn write(&self, path: &Path, format: ReportFormat) -> std::io::Result<()> {
+        std::fs::write(path, self.export(format))
+    }
+
+    /// Report as JSON.
+    pub fn to_json(&self) -> String {
+        let violations: Vec<Value> = self.report.violations.iter()
+            .map(|v| {
+                json!({
+                    "rule": v.rule,
+                    "severity": level(v),
+                    "message": v.message,
+                    "location": { "x": v.location.x, "y": v.location.y },
+                    "actual": v.actual_value,
+                    "required": v.required_value,
+                    "unit": v.unit,
+                    "fix": v.fix_suggestion,
+                })
+            })
+            .collect();
+        let value = json!({
+            "name": self.report.name,
+            "domain": self.report.domain,
+            "summary": {
+                "errors": self.error_count(),
+                "warnings": self.report.violations.len() - self.error_count(),
+                "total": self.report.violations.len(),
+            },
+            "violations": violations,
+        });
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    }
+
+    /// Report as JUnit XML with one test case per rule. Errors fail the test
+    /// case; other violations are listed in its output.
+    pub fn to_junit(&self) -> String {
+        let rules = self.rule_ids();
+        let failing = rules.iter()
+            .filter(|id| self.violations(id).any(|v| is_error(&v)))
+            .count();
+
+        let mut xml = String::new();
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(xml, r#"<testsuites name="DRC" tests="{}" failures="{}">"#, rules.len(), failing).unwrap();
+        writeln!(xml, r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+            escape_xml(&self.report.name), rules.len(), failing).unwrap();
+        for id in &rules {
+            let category = self.rule(id).map_or(self.report.domain.as_str(), |r| r.category.as_str());
+            let (errors, others): (Vec<&DrcViolation>, Vec<&DrcViolation>) = self.violations(id).partition(is_error);
+            if errors.is_empty() && others.is_empty() {
+                writeln!(xml, r#"    <testcase classname="drc.{}" name="{}"/>"#, escape_xml(category), escape_xml(id)).unwrap();
+                continue;
+            }
+            writeln!(xml, r#"    <testcase classname="drc.{}" name="{}">"#, escape_xml(category), escape_xml(id)).unwrap();
+            if !errors.is_empty() {
+                writeln!(xml, r#"      <failure type="error" message="{} violation(s)">{}</failure>"#,
+                    errors.len(), escape_xml(&violation_lines(&errors))).unwrap();
+            }
+            if !others.is_empty() {
+                writeln!(xml, "      <system-out>{}</system-out>", escape_xml(&violation_lines(&others))).unwrap();
+            }
+            writeln!(xml, "    </testcase>").unwrap();
+        }
+        writeln!(xml, "  </testsuite>").unwrap();
+        writeln!(xml, "</testsuites>").unwrap();
+        xml
+    }
+
+    /// Report as a SARIF 2.1.0 log. Board coordinates (mm) are given in
+    /// each result's properties, since SARIF regions address text.
+    pub fn to_sarif(&self) -> String {
+        let rules: Vec<Value> = self.rule_ids().iter()
+            .map(|id| match self.rule(id) {
+                Some(rule) => json!({
+                    "id": rule.id,
+                    "name": rule.name,
+                    "shortDescription": { "text": rule.description },
+                    "defaultConfiguration": { "level": severity_level(&rule.default_severity) },
+                }),
+                None => json!({ "id": id }),
+            })
+            .collect();
+        let results: Vec<Value> = self.report.violations.iter()
+            .map(|v| {
+                let mut result = json!({
+                    "ruleId": v.rule,
+                    "level": level(v),
+                    "message": { "text": v.message },
+                    "properties": { "x": v.location.x, "y": v.location.y, "unit": "mm" },
+                });
+                if let Some(source) = &self.source {
+                    result["locations"] = json!([{
+                        "physicalLocation": { "artifactLocation": { "uri": source } },
+                    }]);
+                }
+                result
+            })
+            .collect();
+        let value = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "hwt",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        });
+        serde_json::to_string_pretty(&value).unwrap_or_default()
+    }
+
+    /// Report as a standalone HTML page. With a layout, each violation gets
+    /// an inline SVG thumbnail of the board around its location.
+    pub fn to_html(&self) -> String {
+        let generator = SvgGenerator::new().with_scale(THUMBNAIL_SCALE);
+        let errors = self.error_count();
+        let title = escape_xml(&self.report.name);
+
+        let mut html = String::new();
+        writeln!(html, "<!DOCTYPE html>").unwrap();
+        writeln!(html, r#"<html lang="en">"#).unwrap();
+        writeln!(html, "<head>").unwrap();
+        writeln!(html, r#"  <meta charset="utf-8">"#).unwrap();
+        writeln!(html, "  <title>DRC Report - {}</title>", title).unwrap();
+        writeln!(html, "  <style>").unwrap();
+        writeln!(html, "    body {{ font-family: sans-serif; margin: 2em; }}").unwrap();
+        writeln!(html, "    table {{ border-collapse: collapse; }}").unwrap();
+        writeln!(html, "    th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}").unwrap();
+        writeln!(html, "    .error {{ color: #c00000; }}").unwrap();
+        writeln!(html, "    .warning {{ color: #a06000; }}").unwrap();
+        writeln!(html, "    svg {{ width: 120px; height: 120px; }}").unwrap();
+        writeln!(html, "  </style>").unwrap();
+        writeln!(html, "</head>").unwrap();
+        writeln!(html, "<body>").unwrap();
+        writeln!(html, "  <h1>{}</h1>", title).unwrap();
+        writeln!(html, "  <p>{} violation(s): {} error(s), {} warning(s)</p>",
+            self.report.violations.len(), errors, self.report.violations.len() - errors).unwrap();
+
+        if !self.report.violations.is_empty() {
+            writeln!(html, "  <table>").unwrap();
+            writeln!(html, "    <tr><th>#</th><th>Severity</th><th>Rule</th><th>Message</th><th>Location (mm)</th><th>View</th></tr>").unwrap();
+            for (i, v) in self.report.violations.iter().enumerate() {
+                let thumbnail = self.layout
+                    .map(|layout| generator.generate_layout_detail(layout, &v.location, THUMBNAIL_SIZE))
+                    .map(|svg| strip_xml_declaration(&svg).trim_end().to_string())
+                    .unwrap_or_default();
+                let mut message = escape_xml(&v.message);
+                if let Some(fix) = &v.fix_suggestion {
+                    write!(message, "<br><small>{}</small>", escape_xml(fix)).unwrap();
+                }
+                writeln!(html, r#"    <tr><td>{}</td><td class="{}">{}</td><td>{}</td><td>{}</td><td>{:.3}, {:.3}</td><td>{}</td></tr>"#,
+                    i + 1, level(v), level(v), escape_xml(&v.rule), message, v.location.x, v.location.y, thumbnail).unwrap();
+            }
+            writeln!(html, "  </table>").unwrap();
+        }
+
+        writeln!(html, "</body>").unwrap();
+        writeln!(html, "</html>").unwrap();
+        html
+    }
+
+    /// Checked rules followed by any other rules with violations.
+    fn rule_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.rules.iter().map(|r| r.id.clone()).collect();
+        for v in &self.report.violations {
+            if !ids.contains(&v.rule) {
+                ids.push(v.rule.clone());
+            }
+        }
+        ids
+    }
+
+    fn rule(&self, id: &str) -> Option<&DrcRule> {
+        self.rules.iter().find(|r| r.id == id)
+    }
+
+    fn violations<'s>(&'s self, rule: &'s str) -> impl Iterator<Item = &'s DrcViolation> {
+        self.report.violations.iter().filter(move |v| v.rule == rule)
+    }
+}
+
+fn is_error(violation: &&DrcViolation) -> bool {
+    matches!(violation.severity, DrcSeverity::Error)
+}
+
+/// SARIF level name, also used for JSON and HTML.
+fn severity_level(severity: &DrcSeverity) -> &'static str {
+    match severity {
+        DrcSeverity::Error => "error",
+        DrcSeverity::Warning => "warning",
+        _ => "note",
+    }
+}
+
+fn level(violation: &DrcViolation) -> &'static str {
+    severity_level(&violation.severity)
+}
+
+/// One line per violation with its location.
+fn violation_lines(violations: &[&DrcViolation]) -> String {
+    violations.iter()
+        .map(|v| format!("{} at ({:.3}, {:.3})", v.message, v.location.x, v.location.y))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn strip_xml_declaration(svg: &str) -> &str {
+    match svg.strip_prefix("<?xml") {
+        Some(rest) => rest.split_once('\n').map_or("", |(_, body)| body),
+        None => svg,
+    }
+}
+
+/// Escape XML special characters.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point2D;
+
+    fn sample_report() -> DrcReport {
+        let mut report = DrcReport::new("PCB Layout", "pcb");
+        report.violations.push(
+            DrcViolation::new("clearance.track_to_track", "Clearance 0.100mm < 0.200mm", Point2D::new(10.0, 5.0))
+                .with_severity(DrcSeverity::Error)
+                .with_values(0.1, 0.2, "mm"),
+        );
+        report.violations.push(
+            DrcViolation::new("silk.width", "Stroke <thin>", Point2D::new(3.0, 4.0))
+                .with_severity(DrcSeverity::Warning),
+        );
+        report
+    }
+
+    #[test]
+    fn test_report_format_from_path() {
+        assert_eq!(ReportFormat::from_path(Path::new("out/drc.json")), Some(ReportFormat::Json));
+        assert_eq!(ReportFormat::from_path(Path::new("drc.sarif.json")), Some(ReportFormat::Sarif));
+        assert_eq!(ReportFormat::from_path(Path::new("drc.xml")), Some(ReportFormat::Junit));
+        assert_eq!(ReportFormat::from_path(Path::new("drc.html")), Some(ReportFormat::Html));
+        assert_eq!(ReportFormat::from_path(Path::new("drc.txt")), None);
+    }
+
+    #[test]
+    fn test_export_json_and_sarif() {
+        let report = sample_report();
+        let exporter = DrcExporter::new(&report).with_source("board.kicad_pcb");
+        assert_eq!(exporter.error_count(), 1);
+
+        let json: Value = serde_json::from_str(&exporter.to_json()).unwrap();
+        assert_eq!(json["summary"]["errors"], 1);
+        assert_eq!(json["violations"][0]["required"], 0.2);
+
+        let sarif: Value = serde_json::from_str(&exporter.to_sarif()).unwrap();
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[1]["level"], "warning");
+        assert_eq!(results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "board.kicad_pcb");
+    }
+
+    #[test]
+    fn test_export_junit_per_rule() {
+        let report = sample_report();
+        let rules = vec![DrcRule {
+            id: "width.track".to_string(),
+            name: "Track Width".to_string(),
+            description: "Minimum track width".to_string(),
+            category: "Size".to_string(),
+            default_severity: DrcSeverity::Error,
+            can_disable: false,
+        }];
+        let xml = DrcExporter::new(&report).with_rules(rules).to_junit();
+
+        assert!(xml.contains(r#"<testsuites name="DRC" tests="3" failures="1">"#));
+        assert!(xml.contains(r#"<testcase classname="drc.Size" name="width.track"/>"#));
+        assert!(xml.contains(r#"<failure type="error" message="1 violation(s)">"#));
+        assert!(xml.contains("Stroke &lt;thin&gt;"));
+    }
+
+    #[test]
+    fn test_export_html_thumbnails() {
+        let report = sample_report();
+        let layout = Layout::new();
+        let html = DrcExporter::new(&report).with_layout(&layout).to_html();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        assert!(!html.contains("<?xml"));
+        assert!(html.contains("1 error(s), 1 warning(s)"));
+    }
+}
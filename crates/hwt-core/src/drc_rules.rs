@@ -0,0 +1,796 @@
+//! Custom DRC Rules.
+//!
+//! User-defined design rules with scoped conditions, stored in the project
+//! file. A rule sets the minimum of one constraint wherever its condition
+//! matches; when several rules match, the highest priority wins and later
+//! rules win ties.
+//!
+//! Condition grammar:
+//!
+//! ```text
+//! condition := and ("||" and)*
+//! and       := unary ("&&" unary)*
+//! unary     := "!" unary | "(" condition ")" | predicate
+//! predicate := item "." property ("==" | "!=") STRING
+//!            | "layer" ("==" | "!=") STRING
+//!            | item ".inside_courtyard(" STRING ")"
+//!            | item ".inside_area(" NUMBER "," NUMBER "," NUMBER "," NUMBER ")"
+//!            | item ".inside_area(" STRING ")"
+//!            | "true"
+//! item      := "A" | "B"
+//! property  := "net" | "netclass" | "type" | "component"
+//! ```
+//!
+//! Strings are quoted with `'` or `"` and may use `*` wildcards. The layer
+//! patterns `outer` and `inner` match outer and inner copper layers.
+//! Clearance rules apply to a pair of items and match in either order.
+//! `inside_area` with a string matches the layout's rule areas by name;
+//! the rules of those areas are added after the project rules.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::copper::{CopperItem, CopperKind};
+use crate::geometry::{polygon_intersects_box, BoundingBox, Point2D};
+use crate::layout::Layout;
+
+/// Courtyard margin around component pads (mm).
+pub const COURTYARD_MARGIN: f64 = 0.25;
+
+/// Constraint a custom rule sets a minimum for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleConstraint {
+    /// Copper-to-copper clearance
+    Clearance,
+    /// Track width
+    TrackWidth,
+    /// Via pad diameter
+    ViaDiameter,
+    /// Via drill diameter
+    ViaDrill,
+    /// Copper-to-board-edge clearance
+    EdgeClearance,
+}
+
+/// A user-defined design rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomRule {
+    /// Rule name shown in violations
+    pub name: String,
+
+    /// Condition selecting where the rule applies
+    #[serde(default = "default_condition")]
+    pub condition: String,
+
+    /// Constrained quantity
+    pub constraint: RuleConstraint,
+
+    /// Minimum value (mm)
+    pub min: f64,
+
+    /// Priority; higher wins when several rules match
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_condition() -> String {
+    "true".to_string()
+}
+
+impl CustomRule {
+    /// Create a new rule.
+    pub fn new(name: &str, condition: &str, constraint: RuleConstraint, min: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            condition: condition.to_string(),
+            constraint,
+            min,
+            priority: 0,
+        }
+    }
+
+    /// Set the priority.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+/// Rule compilation error.
+#[derive(Debug, Clone)]
+pub struct RuleError {
+    /// Rule name
+    pub rule: String,
+    /// Error description
+    pub message: String,
+    /// Character offset in the condition
+    pub position: usize,
+}
+
+impl std::fmt::Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rule '{}': {} at column {}", self.rule, self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+pub type RuleResult<T> = Result<T, RuleError>;
+
+/// An item a condition is evaluated against.
+#[derive(Debug, Clone)]
+pub struct RuleSubject<'a> {
+    /// Item kind
+    pub kind: CopperKind,
+    /// Net name
+    pub net: &'a str,
+    /// Owning component reference, for pads
+    pub component: Option<&'a str>,
+    /// Item extent
+    pub bounds: Option<BoundingBox>,
+}
+
+impl<'a> RuleSubject<'a> {
+    /// Subject for a collected copper item.
+    pub fn from_copper(item: &'a CopperItem, layout: &'a Layout) -> Self {
+        Self {
+            kind: item.kind,
+            net: &item.net,
+            component: item
+                .pad
+                .as_ref()
+                .and_then(|(ci, _)| layout.components.get(*ci))
+                .map(|c| c.reference.as_str()),
+            bounds: item.shape.bounding_box(),
+        }
+    }
+}
+
+/// Design data conditions refer to.
+pub struct RuleContext<'a> {
+    net_classes: &'a HashMap<String, String>,
+    copper: Vec<String>,
+    courtyards: HashMap<&'a str, BoundingBox>,
+    /// Named rule areas with their outlines in mm
+    areas: Vec<(&'a str, Vec<Point2D>)>,
+}
+
+impl<'a> RuleContext<'a> {
+    /// Create a context for a layout and a net-to-class map.
+    pub fn new(layout: &'a Layout, net_classes: &'a HashMap<String, String>) -> Self {
+        Self {
+            net_classes,
+            copper: layout.copper_layer_names(),
+            courtyards: layout
+                .components
+                .iter()
+                .filter_map(|c| Some((c.reference.as_str(), c.courtyard(COURTYARD_MARGIN)?)))
+                .collect(),
+            areas: layout
+                .rule_areas
+                .iter()
+                .filter(|a| !a.name.is_empty())
+                .map(|a| (a.name.as_str(), a.polygon_mm()))
+                .collect(),
+        }
+    }
+}
+
+/// A compiled set of custom rules.
+#[derive(Debug, Clone, Default)]
+pub struct CustomRuleSet {
+    /// Rules with parsed conditions, in ascending priority
+    rules: Vec<(CustomRule, Expr)>,
+}
+
+impl CustomRuleSet {
+    /// Parse the conditions of a list of rules.
+    pub fn compile(rules: &[CustomRule]) -> RuleResult<Self> {
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let expr = Parser::new(&rule.condition)
+                .and_then(|mut p| p.parse())
+                .map_err(|(message, position)| RuleError {
+                    rule: rule.name.clone(),
+                    message,
+                    position,
+                })?;
+            compiled.push((rule.clone(), expr));
+        }
+        // Stable sort keeps file order within a priority
+        compiled.sort_by_key(|(rule, _)| rule.priority);
+        Ok(Self { rules: compiled })
+    }
+
+    /// Rules of a layout's rule areas.
+    pub fn from_areas(layout: &Layout) -> Self {
+        Self::compile(&area_rules(layout)).expect("rule area conditions are valid")
+    }
+
+    /// Rules of both sets; rules of `other` win ties.
+    pub fn merged(&self, other: &CustomRuleSet) -> Self {
+        let mut rules: Vec<(CustomRule, Expr)> = self.rules.iter().chain(&other.rules).cloned().collect();
+        rules.sort_by_key(|(rule, _)| rule.priority);
+        Self { rules }
+    }
+
+    /// Number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Whether there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Largest minimum of any rule for a constraint.
+    pub fn max_min(&self, constraint: RuleConstraint) -> f64 {
+        self.rules
+            .iter()
+            .filter(|(rule, _)| rule.constraint == constraint)
+            .map(|(rule, _)| rule.min)
+            .fold(0.0, f64::max)
+    }
+
+    /// The winning rule for a constraint on `layer`, if any matches.
+    pub fn find(
+        &self,
+        constraint: RuleConstraint,
+        ctx: &RuleContext,
+        a: &RuleSubject,
+        b: Option<&RuleSubject>,
+        layer: &str,
+    ) -> Option<&CustomRule> {
+        self.rules
+            .iter()
+            .rev()
+            .filter(|(rule, _)| rule.constraint == constraint)
+            .find(|(_, expr)| {
+                let scope = Scope { ctx, a, b, layer };
+                let swapped = b.map(|b| Scope { ctx, a: b, b: Some(a), layer });
+                expr.eval(&scope) || swapped.is_some_and(|s| expr.eval(&s))
+            })
+            .map(|(rule, _)| rule)
+    }
+
+    /// The strictest winning rule over several layers.
+    pub fn find_on_layers(
+        &self,
+        constraint: RuleConstraint,
+        ctx: &RuleContext,
+        a: &RuleSubject,
+        b: Option<&RuleSubject>,
+        layers: &[String],
+    ) -> Option<&CustomRule> {
+        layers
+            .iter()
+            .filter_map(|layer| self.find(constraint, ctx, a, b, layer))
+            .max_by(|x, y| x.min.total_cmp(&y.min))
+    }
+}
+
+/// Custom rules for the rules of a layout's named rule areas, limited to
+/// items inside the area and on its layers.
+pub fn area_rules(layout: &Layout) -> Vec<CustomRule> {
+    let quote = |text: &str| {
+        let q = if text.contains('\'') { '"' } else { '\'' };
+        format!("{q}{text}{q}")
+    };
+    let mut rules = Vec::new();
+    for area in layout.rule_areas.iter().filter(|a| !a.name.is_empty()) {
+        if area.name.contains('\'') && area.name.contains('"') {
+            continue;
+        }
+        for rule in &area.rules {
+            let mut condition = format!("A.inside_area({})", quote(&area.name));
+            if let Some(class) = &rule.net_class {
+                condition.push_str(&format!(" && A.netclass == {}", quote(class)));
+            }
+            if !area.layers.is_empty() {
+                let layers: Vec<String> = area.layers.iter().map(|l| format!("layer == {}", quote(l))).collect();
+                condition.push_str(&format!(" && ({})", layers.join(" || ")));
+            }
+            rules.push(CustomRule::new(&area.name, &condition, rule.constraint, rule.min));
+        }
+    }
+    rules
+}
+
+/// Evaluation scope for one condition check.
+struct Scope<'s, 'a> {
+    ctx: &'s RuleContext<'a>,
+    a: &'s RuleSubject<'s>,
+    b: Option<&'s RuleSubject<'s>>,
+    layer: &'s str,
+}
+
+/// Item a predicate refers to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Item {
+    A,
+    B,
+}
+
+/// Item property a predicate compares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Property {
+    Net,
+    NetClass,
+    Type,
+    Component,
+}
+
+/// Parsed condition.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    True,
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Property { item: Item, property: Property, pattern: String, negate: bool },
+    Layer { pattern: String, negate: bool },
+    InsideCourtyard { item: Item, reference: String },
+    InsideArea { item: Item, area: BoundingBox },
+    InsideRuleArea { item: Item, name: String },
+}
+
+impl Expr {
+    fn eval(&self, scope: &Scope) -> bool {
+        let subject = |item: &Item| match item {
+            Item::A => Some(scope.a),
+            Item::B => scope.b,
+        };
+        match self {
+            Expr::True => true,
+            Expr::Not(e) => !e.eval(scope),
+            Expr::And(l, r) => l.eval(scope) && r.eval(scope),
+            Expr::Or(l, r) => l.eval(scope) || r.eval(scope),
+            Expr::Property { item, property, pattern, negate } => {
+                let Some(s) = subject(item) else { return false };
+                let value = match property {
+                    Property::Net => Some(s.net),
+                    Property::NetClass => scope.ctx.net_classes.get(s.net).map(String::as_str),
+                    Property::Type => Some(s.kind.name()),
+                    Property::Component => s.component,
+                };
+                value.is_some_and(|v| glob_match(pattern, v)) != *negate
+            }
+            Expr::Layer { pattern, negate } => {
+                let copper = &scope.ctx.copper;
+                let index = copper.iter().position(|l| l == scope.layer);
+                let outer = index.is_some_and(|i| i == 0 || i + 1 == copper.len());
+                let matched = match pattern.as_str() {
+                    "outer" => outer,
+                    "inner" => index.is_some() && !outer,
+                    _ => glob_match(pattern, scope.layer),
+                };
+                matched != *negate
+            }
+            Expr::InsideCourtyard { item, reference } => {
+                let Some(bounds) = subject(item).and_then(|s| s.bounds.as_ref()) else { return false };
+                scope
+                    .ctx
+                    .courtyards
+                    .iter()
+                    .any(|(r, court)| glob_match(reference, r) && court.intersects(bounds))
+            }
+            Expr::InsideArea { item, area } => subject(item)
+                .and_then(|s| s.bounds.as_ref())
+                .is_some_and(|bounds| area.intersects(bounds)),
+            Expr::InsideRuleArea { item, name } => {
+                let Some(bounds) = subject(item).and_then(|s| s.bounds.as_ref()) else { return false };
+                scope
+                    .ctx
+                    .areas
+                    .iter()
+                    .any(|(area, outline)| glob_match(name, area) && polygon_intersects_box(outline, bounds))
+            }
+        }
+    }
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Condition token.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    LParen,
+    RParen,
+    Comma,
+    Dot,
+    Eq,
+    Ne,
+    And,
+    Or,
+    Not,
+}
+
+/// Recursive-descent condition parser.
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    end: usize,
+}
+
+type ParseResult<T> = Result<T, (String, usize)>;
+
+impl Parser {
+    fn new(input: &str) -> ParseResult<Self> {
+        let chars: Vec<(usize, char)> = input.char_indices().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let (at, c) = chars[i];
+            let two = chars.get(i + 1).map(|&(_, n)| n);
+            match c {
+                ' ' | '\t' | '\n' | '\r' => {}
+                '(' => tokens.push((Token::LParen, at)),
+                ')' => tokens.push((Token::RParen, at)),
+                ',' => tokens.push((Token::Comma, at)),
+                '.' => tokens.push((Token::Dot, at)),
+                '=' if two == Some('=') => {
+                    tokens.push((Token::Eq, at));
+                    i += 1;
+                }
+                '!' if two == Some('=') => {
+                    tokens.push((Token::Ne, at));
+                    i += 1;
+                }
+                '!' => tokens.push((Token::Not, at)),
+                '&' if two == Some('&') => {
+                    tokens.push((Token::And, at));
+                    i += 1;
+                }
+                '|' if two == Some('|') => {
+                    tokens.push((Token::Or, at));
+                    i += 1;
+                }
+                '\'' | '"' => {
+                    let mut value = String::new();
+                    i += 1;
+                    while i < chars.len() && chars[i].1 != c {
+                        value.push(chars[i].1);
+                        i += 1;
+                    }
+                    if i >= chars.len() {
+                        return Err(("Unterminated string".to_string(), at));
+                    }
+                    tokens.push((Token::Str(value), at));
+                }
+                c if c.is_ascii_digit() || c == '-' => {
+                    let start = i;
+                    i += 1;
+                    while i < chars.len() && (chars[i].1.is_ascii_digit() || chars[i].1 == '.') {
+                        i += 1;
+                    }
+                    let text: String = chars[start..i].iter().map(|&(_, c)| c).collect();
+                    let value = text.parse().map_err(|_| (format!("Invalid number '{}'", text), at))?;
+                    tokens.push((Token::Num(value), at));
+                    continue;
+                }
+                c if c.is_alphabetic() || c == '_' => {
+                    let start = i;
+                    while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                        i += 1;
+                    }
+                    tokens.push((Token::Ident(chars[start..i].iter().map(|&(_, c)| c).collect()), at));
+                    continue;
+                }
+                _ => return Err((format!("Unexpected character '{}'", c), at)),
+            }
+            i += 1;
+        }
+        Ok(Self { tokens, pos: 0, end: input.len() })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map(|&(_, at)| at).unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> ParseResult<Token> {
+        let token = self.peek().cloned().ok_or_else(|| ("Unexpected end of condition".to_string(), self.end))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> ParseResult<()> {
+        let at = self.offset();
+        let token = self.next()?;
+        if token == expected {
+            Ok(())
+        } else {
+            Err((format!("Expected {:?}, found {:?}", expected, token), at))
+        }
+    }
+
+    fn parse(&mut self) -> ParseResult<Expr> {
+        let expr = self.parse_or()?;
+        if self.pos < self.tokens.len() {
+            return Err(("Unexpected trailing input".to_string(), self.offset()));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> ParseResult<Expr> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ParseResult<Expr> {
+        let mut left = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult<Expr> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.parse_unary()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            _ => self.parse_predicate(),
+        }
+    }
+
+    fn parse_string(&mut self) -> ParseResult<String> {
+        let at = self.offset();
+        match self.next()? {
+            Token::Str(s) => Ok(s),
+            other => Err((format!("Expected a quoted string, found {:?}", other), at)),
+        }
+    }
+
+    fn parse_number(&mut self) -> ParseResult<f64> {
+        let at = self.offset();
+        match self.next()? {
+            Token::Num(n) => Ok(n),
+            other => Err((format!("Expected a number, found {:?}", other), at)),
+        }
+    }
+
+    fn parse_negation(&mut self) -> ParseResult<bool> {
+        let at = self.offset();
+        match self.next()? {
+            Token::Eq => Ok(false),
+            Token::Ne => Ok(true),
+            other => Err((format!("Expected == or !=, found {:?}", other), at)),
+        }
+    }
+
+    fn parse_predicate(&mut self) -> ParseResult<Expr> {
+        let at = self.offset();
+        let name = match self.next()? {
+            Token::Ident(name) => name,
+            other => return Err((format!("Expected a condition, found {:?}", other), at)),
+        };
+        let item = match name.as_str() {
+            "true" => return Ok(Expr::True),
+            "layer" => {
+                let negate = self.parse_negation()?;
+                return Ok(Expr::Layer { pattern: self.parse_string()?, negate });
+            }
+            "A" => Item::A,
+            "B" => Item::B,
+            _ => return Err((format!("Unknown name '{}'", name), at)),
+        };
+
+        self.expect(Token::Dot)?;
+        let at = self.offset();
+        let member = match self.next()? {
+            Token::Ident(member) => member,
+            other => return Err((format!("Expected a property, found {:?}", other), at)),
+        };
+        let property = match member.as_str() {
+            "net" => Property::Net,
+            "netclass" => Property::NetClass,
+            "type" => Property::Type,
+            "component" => Property::Component,
+            "inside_courtyard" => {
+                self.expect(Token::LParen)?;
+                let reference = self.parse_string()?;
+                self.expect(Token::RParen)?;
+                return Ok(Expr::InsideCourtyard { item, reference });
+            }
+            "inside_area" => {
+                self.expect(Token::LParen)?;
+                if let Some(Token::Str(_)) = self.peek() {
+                    let name = self.parse_string()?;
+                    self.expect(Token::RParen)?;
+                    return Ok(Expr::InsideRuleArea { item, name });
+                }
+                let mut values = [0.0; 4];
+                for (i, value) in values.iter_mut().enumerate() {
+                    if i > 0 {
+                        self.expect(Token::Comma)?;
+                    }
+                    *value = self.parse_number()?;
+                }
+                self.expect(Token::RParen)?;
+                let [x1, y1, x2, y2] = values;
+                let area = BoundingBox::new(
+                    Point2D::new(x1.min(x2), y1.min(y2)),
+                    Point2D::new(x1.max(x2), y1.max(y2)),
+                );
+                return Ok(Expr::InsideArea { item, area });
+            }
+            _ => return Err((format!("Unknown property '{}'", member), at)),
+        };
+        let negate = self.parse_negation()?;
+        Ok(Expr::Property { item, property, pattern: self.parse_string()?, negate })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Pad, PlacedComponent};
+    use crate::units::LengthUnit;
+
+    fn subject(kind: CopperKind, net: &str) -> RuleSubject<'_> {
+        RuleSubject {
+            kind,
+            net,
+            component: None,
+            bounds: BoundingBox::from_points(&[Point2D::new(10.0, 10.0), Point2D::new(11.0, 10.0)]),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = CustomRuleSet::compile(&[CustomRule::new("bad", "A.net == ", RuleConstraint::Clearance, 1.0)])
+            .unwrap_err();
+        assert_eq!(err.rule, "bad");
+        assert!(CustomRuleSet::compile(&[CustomRule::new("x", "A.colour == 'red'", RuleConstraint::Clearance, 1.0)]).is_err());
+        assert!(CustomRuleSet::compile(&[CustomRule::new("x", "(A.net == 'X'", RuleConstraint::Clearance, 1.0)]).is_err());
+        assert!(CustomRuleSet::compile(&[CustomRule::new("x", "A.net == 'X' B", RuleConstraint::Clearance, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_netclass_clearance_on_outer_layers() {
+        let layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        let classes = HashMap::from([("HV_IN".to_string(), "HV".to_string())]);
+        let ctx = RuleContext::new(&layout, &classes);
+        let rules = CustomRuleSet::compile(&[CustomRule::new(
+            "HV clearance",
+            "A.netclass == 'HV' && layer == 'outer'",
+            RuleConstraint::Clearance,
+            2.0,
+        )])
+        .unwrap();
+
+        let hv = subject(CopperKind::Track, "HV_IN");
+        let gnd = subject(CopperKind::Pad, "GND");
+        // Symmetric: matches with the HV item in either position
+        assert!(rules.find(RuleConstraint::Clearance, &ctx, &gnd, Some(&hv), "F.Cu").is_some());
+        assert!(rules.find(RuleConstraint::Clearance, &ctx, &gnd, Some(&gnd), "F.Cu").is_none());
+        assert!(rules.find(RuleConstraint::TrackWidth, &ctx, &hv, None, "F.Cu").is_none());
+        assert_eq!(rules.max_min(RuleConstraint::Clearance), 2.0);
+    }
+
+    #[test]
+    fn test_priority_and_order() {
+        let layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        let classes = HashMap::new();
+        let ctx = RuleContext::new(&layout, &classes);
+        let rules = CustomRuleSet::compile(&[
+            CustomRule::new("power", "A.net == 'V*' || A.net == 'GND'", RuleConstraint::TrackWidth, 0.5)
+                .with_priority(10),
+            CustomRule::new("default", "true", RuleConstraint::TrackWidth, 0.2),
+            CustomRule::new("late default", "!(A.type != 'track')", RuleConstraint::TrackWidth, 0.25),
+        ])
+        .unwrap();
+
+        let vcc = subject(CopperKind::Track, "VCC_3V3");
+        let sig = subject(CopperKind::Track, "SDA");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &vcc, None, "F.Cu").unwrap().name, "power");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &sig, None, "B.Cu").unwrap().name, "late default");
+    }
+
+    #[test]
+    fn test_inside_courtyard_and_area() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("U5", "MCU", "QFN-32")
+                .at(10.0, 10.0)
+                .with_pad(Pad::smd("1", -2.0, 0.0, 0.3, 0.8))
+                .with_pad(Pad::smd("2", 2.0, 0.0, 0.3, 0.8)),
+        );
+        let classes = HashMap::new();
+        let ctx = RuleContext::new(&layout, &classes);
+        let rules = CustomRuleSet::compile(&[
+            CustomRule::new("fanout", "A.type == 'track' && A.inside_courtyard('U5')", RuleConstraint::TrackWidth, 0.1),
+            CustomRule::new("corner", "A.inside_area(40, 40, 50, 50)", RuleConstraint::TrackWidth, 0.3),
+        ])
+        .unwrap();
+
+        let inside = subject(CopperKind::Track, "SIG");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &inside, None, "F.Cu").unwrap().name, "fanout");
+
+        let mut outside = subject(CopperKind::Track, "SIG");
+        outside.bounds = BoundingBox::from_points(&[Point2D::new(45.0, 45.0)]);
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &outside, None, "F.Cu").unwrap().name, "corner");
+    }
+
+    #[test]
+    fn test_rule_area_rules() {
+        use crate::layout::RuleArea;
+
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        let square = vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(20.0, 0.0),
+            Point2D::new(20.0, 20.0),
+            Point2D::new(0.0, 20.0),
+        ];
+        layout.rule_areas.push(
+            RuleArea::new("BGA", square)
+                .with_layers(&["F.Cu"])
+                .with_rule(Some("DDR"), RuleConstraint::TrackWidth, 0.09),
+        );
+        let classes = HashMap::from([("DQ0".to_string(), "DDR".to_string())]);
+        let ctx = RuleContext::new(&layout, &classes);
+        let project = CustomRuleSet::compile(&[CustomRule::new("default", "true", RuleConstraint::TrackWidth, 0.15)])
+            .unwrap();
+        let rules = project.merged(&CustomRuleSet::from_areas(&layout));
+        assert_eq!(rules.len(), 2);
+
+        let dq = subject(CopperKind::Track, "DQ0");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &dq, None, "F.Cu").unwrap().name, "BGA");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &dq, None, "B.Cu").unwrap().name, "default");
+        let other = subject(CopperKind::Track, "SIG");
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &other, None, "F.Cu").unwrap().name, "default");
+        let mut outside = subject(CopperKind::Track, "DQ0");
+        outside.bounds = BoundingBox::from_points(&[Point2D::new(30.0, 30.0)]);
+        assert_eq!(rules.find(RuleConstraint::TrackWidth, &ctx, &outside, None, "F.Cu").unwrap().name, "default");
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("USB_*", "USB_DP"));
+        assert!(glob_match("*_N", "CLK_N"));
+        assert!(glob_match("D*_*", "DDR_DQ0"));
+        assert!(!glob_match("USB_*", "GND"));
+        assert!(glob_match("GND", "GND"));
+    }
+}
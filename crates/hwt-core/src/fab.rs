@@ -0,0 +1,626 @@
+//! Manufacturer Capability Profiles.
+//!
+//! Fabrication limits of a PCB manufacturer, loaded from TOML files in the
+//! project's `fab/` directory or the user's `hwt/fab/` configuration
+//! directory, with built-in profiles as a fallback. A profile can be turned
+//! into design rules or checked directly against a layout.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::drc::{DrcReport, DrcSeverity, DrcViolation};
+use crate::geometry::Point2D;
+use crate::layout::{Layout, PadType, Via, ViaType};
+use crate::pcb_drc::{PcbDesignRules, PcbDrcChecker};
+
+/// Built-in profile files.
+const BUILTIN_PROFILES: &[&str] = &[
+    include_str!("../fab/jlcpcb.toml"),
+    include_str!("../fab/osh_park.toml"),
+];
+
+/// Board thickness assumed when the layout stack does not define one (mm).
+pub const DEFAULT_BOARD_THICKNESS: f64 = 1.6;
+
+/// Fab profile error.
+#[derive(Debug, Clone)]
+pub struct FabError {
+    /// Error description
+    pub message: String,
+}
+
+impl FabError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for FabError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fab profile error: {}", self.message)
+    }
+}
+
+impl std::error::Error for FabError {}
+
+pub type FabResult<T> = Result<T, FabError>;
+
+/// Track and space limits for one copper weight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopperCapability {
+    /// Finished copper weight (oz/ft²)
+    pub weight_oz: f64,
+
+    /// Minimum track width (mm)
+    pub min_track: f64,
+
+    /// Minimum copper spacing (mm)
+    pub min_space: f64,
+}
+
+/// Fabrication capabilities of a manufacturer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FabProfile {
+    /// Profile name
+    pub name: String,
+
+    /// Description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Track and space limits per copper weight
+    pub copper: Vec<CopperCapability>,
+
+    /// Minimum finished drill diameter (mm)
+    pub min_drill: f64,
+
+    /// Maximum finished drill diameter (mm)
+    pub max_drill: f64,
+
+    /// Maximum board thickness to drill diameter ratio
+    pub max_aspect_ratio: f64,
+
+    /// Minimum annular ring of plated through-hole pads (mm)
+    pub min_annular_ring_pth: f64,
+
+    /// Minimum annular ring of vias (mm)
+    pub min_annular_ring_via: f64,
+
+    /// Minimum hole-to-hole clearance (mm)
+    pub min_hole_clearance: f64,
+
+    /// Minimum via-to-via copper clearance (mm)
+    pub min_via_clearance: f64,
+
+    /// Minimum copper-to-edge clearance (mm)
+    pub min_edge_clearance: f64,
+
+    /// Minimum solder mask sliver (mm)
+    pub min_mask_sliver: f64,
+
+    /// Minimum silkscreen line width (mm)
+    pub min_silk_width: f64,
+
+    /// Minimum silkscreen text height (mm)
+    pub min_silk_text_height: f64,
+
+    /// Supported via types
+    #[serde(default = "default_via_types")]
+    pub via_types: Vec<ViaType>,
+
+    /// Available board thicknesses (mm)
+    pub board_thicknesses: Vec<f64>,
+
+    /// Maximum copper layer count
+    pub max_layers: usize,
+}
+
+fn default_via_types() -> Vec<ViaType> {
+    vec![ViaType::Through]
+}
+
+impl FabProfile {
+    /// Parse a profile from TOML.
+    pub fn from_toml(toml_str: &str) -> FabResult<Self> {
+        let profile: Self = toml::from_str(toml_str).map_err(|e| FabError::new(e.to_string()))?;
+        if profile.copper.is_empty() {
+            return Err(FabError::new(format!("Profile '{}' defines no copper weights", profile.name)));
+        }
+        Ok(profile)
+    }
+
+    /// Serialize to TOML.
+    pub fn to_toml(&self) -> FabResult<String> {
+        toml::to_string_pretty(self).map_err(|e| FabError::new(e.to_string()))
+    }
+
+    /// Load a profile from a TOML file.
+    pub fn load(path: impl AsRef<Path>) -> FabResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| FabError::new(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::from_toml(&content).map_err(|e| FabError::new(format!("{}: {}", path.display(), e.message)))
+    }
+
+    /// Built-in profiles.
+    pub fn builtin() -> Vec<Self> {
+        BUILTIN_PROFILES
+            .iter()
+            .map(|toml| Self::from_toml(toml).expect("built-in fab profile is valid"))
+            .collect()
+    }
+
+    /// Limits for a copper weight: the lightest listed weight that is at
+    /// least as heavy, or `None` when the weight is not offered.
+    pub fn copper_capability(&self, weight_oz: f64) -> Option<&CopperCapability> {
+        self.copper
+            .iter()
+            .filter(|c| c.weight_oz >= weight_oz - 1e-9)
+            .min_by(|a, b| a.weight_oz.total_cmp(&b.weight_oz))
+    }
+
+    /// Design rules enforcing this profile at a copper weight. Fails when
+    /// the profile does not offer the weight.
+    pub fn design_rules(&self, weight_oz: f64) -> FabResult<PcbDesignRules> {
+        let copper = self.copper_capability(weight_oz).ok_or_else(|| {
+            FabError::new(format!("Profile '{}' does not offer {}oz copper", self.name, weight_oz))
+        })?;
+        Ok(PcbDesignRules {
+            min_track_clearance: copper.min_space,
+            min_track_to_pad_clearance: copper.min_space,
+            min_track_to_via_clearance: copper.min_space,
+            min_via_clearance: self.min_via_clearance.max(copper.min_space),
+            min_track_width: copper.min_track,
+            min_via_diameter: self.min_drill + 2.0 * self.min_annular_ring_via,
+            min_via_drill: self.min_drill,
+            min_annular_ring: self.min_annular_ring_via,
+            min_hole_clearance: self.min_hole_clearance,
+            min_edge_clearance: self.min_edge_clearance,
+            min_silk_width: self.min_silk_width,
+            min_silk_text_height: self.min_silk_text_height,
+            min_mask_sliver: self.min_mask_sliver,
+            ..PcbDesignRules::default()
+        })
+    }
+}
+
+/// Directories searched for profiles, in increasing precedence: the user
+/// configuration directory, then the project's `fab/` directory.
+pub fn profile_dirs(project_dir: Option<&Path>) -> Vec<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    let mut dirs: Vec<PathBuf> = config.into_iter().map(|c| c.join("hwt").join("fab")).collect();
+    if let Some(project_dir) = project_dir {
+        dirs.push(project_dir.join("fab"));
+    }
+    dirs
+}
+
+/// Load the built-in profiles and every `*.toml` profile in `dirs`. A later
+/// profile replaces an earlier one of the same name.
+pub fn load_profiles(dirs: &[PathBuf]) -> FabResult<Vec<FabProfile>> {
+    let mut profiles = FabProfile::builtin();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let profile = FabProfile::load(&path)?;
+            profiles.retain(|p| p.name != profile.name);
+            profiles.push(profile);
+        }
+    }
+    Ok(profiles)
+}
+
+/// Find a profile by name in the search directories of a project.
+pub fn find_profile(name: &str, project_dir: Option<&Path>) -> FabResult<FabProfile> {
+    load_profiles(&profile_dirs(project_dir))?
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| FabError::new(format!("Unknown fab profile '{}'", name)))
+}
+
+/// Checks a layout against a fab profile.
+pub struct FabChecker<'a> {
+    layout: &'a Layout,
+    profile: &'a FabProfile,
+    copper_weight: f64,
+    board_thickness: Option<f64>,
+}
+
+impl<'a> FabChecker<'a> {
+    /// Create a checker for 1oz copper and the layout's stack thickness.
+    pub fn new(layout: &'a Layout, profile: &'a FabProfile) -> Self {
+        Self {
+            layout,
+            profile,
+            copper_weight: 1.0,
+            board_thickness: None,
+        }
+    }
+
+    /// Set the copper weight (oz/ft²).
+    pub fn with_copper_weight(mut self, weight_oz: f64) -> Self {
+        self.copper_weight = weight_oz;
+        self
+    }
+
+    /// Set the board thickness (mm).
+    pub fn with_board_thickness(mut self, thickness: f64) -> Self {
+        self.board_thickness = Some(thickness);
+        self
+    }
+
+    /// Board thickness: the configured value, the sum of the layer stack,
+    /// or the default.
+    pub fn board_thickness(&self) -> f64 {
+        self.board_thickness.unwrap_or_else(|| {
+            let stack: f64 = self.layout.layers.iter().filter_map(|l| l.thickness).sum();
+            if stack > 0.0 { stack } else { DEFAULT_BOARD_THICKNESS }
+        })
+    }
+
+    /// Depth a via is drilled through. Through vias span the board; blind,
+    /// buried and micro vias span their start and end layers, or else the
+    /// first drill pair of their type. The span is measured over the layer
+    /// stack when it gives every layer a thickness, and otherwise taken as
+    /// its share of the board thickness by copper layer count.
+    pub fn via_depth(&self, via: &Via) -> f64 {
+        let thickness = self.board_thickness();
+        if via.via_type == ViaType::Through {
+            return thickness;
+        }
+        let span = match (&via.start_layer, &via.end_layer) {
+            (Some(start), Some(end)) => (start.as_str(), end.as_str()),
+            _ => match self.layout.drill_pairs.iter().find(|p| p.via_type == via.via_type) {
+                Some(pair) => (pair.start_layer.as_str(), pair.end_layer.as_str()),
+                None => return thickness,
+            },
+        };
+
+        let layers = &self.layout.layers;
+        let position = |name: &str| layers.iter().position(|l| l.name == name);
+        if let (Some(a), Some(b)) = (position(span.0), position(span.1)) {
+            let stack = &layers[a.min(b)..=a.max(b)];
+            if stack.iter().all(|l| l.thickness.is_some()) {
+                return stack.iter().filter_map(|l| l.thickness).sum();
+            }
+        }
+        let copper = self.layout.copper_layer_names();
+        let index = |name: &str| copper.iter().position(|l| l == name);
+        match (index(span.0), index(span.1)) {
+            (Some(a), Some(b)) if copper.len() > 1 => thickness * a.abs_diff(b) as f64 / (copper.len() - 1) as f64,
+            _ => thickness,
+        }
+    }
+
+    /// Report every board feature the profile cannot manufacture.
+    pub fn check(&self) -> DrcReport {
+        let mut report = DrcReport::new("Fab Capability", "pcb");
+        let origin = Point2D::new(0.0, 0.0);
+
+        self.check_board(&mut report, origin);
+        self.check_copper(&mut report);
+        self.check_holes(&mut report);
+        report
+    }
+
+    /// Board-level limits: copper weight, thickness and layer count.
+    fn check_board(&self, report: &mut DrcReport, origin: Point2D) {
+        let profile = self.profile;
+        if profile.copper_capability(self.copper_weight).is_none() {
+            let max = profile.copper.iter().map(|c| c.weight_oz).fold(0.0, f64::max);
+            report.violations.push(
+                DrcViolation::new(
+                    "fab.copper_weight",
+                    format!("{}oz copper exceeds the heaviest weight {} offers ({}oz)",
+                        self.copper_weight, profile.name, max),
+                    origin,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(self.copper_weight, max, "oz"),
+            );
+        }
+
+        let thickness = self.board_thickness();
+        if !profile.board_thicknesses.iter().any(|t| (t - thickness).abs() < 0.01) {
+            let options: Vec<String> = profile.board_thicknesses.iter().map(|t| format!("{}", t)).collect();
+            report.violations.push(
+                DrcViolation::new(
+                    "fab.board_thickness",
+                    format!("Board thickness {:.2}mm is not offered by {} (options: {} mm)",
+                        thickness, profile.name, options.join(", ")),
+                    origin,
+                )
+                .with_severity(DrcSeverity::Error),
+            );
+        }
+
+        let layers = self.layout.copper_layer_names().len();
+        if layers > profile.max_layers {
+            report.violations.push(
+                DrcViolation::new(
+                    "fab.layer_count",
+                    format!("{} copper layers exceed the {} layer maximum of {}",
+                        layers, profile.max_layers, profile.name),
+                    origin,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(layers as f64, profile.max_layers as f64, "layers"),
+            );
+        }
+    }
+
+    /// Track widths, copper spacing, edge clearance, mask slivers and
+    /// silkscreen sizes at the chosen copper weight. Skipped when the weight
+    /// is not offered, which `check_board` reports.
+    fn check_copper(&self, report: &mut DrcReport) {
+        let Ok(rules) = self.profile.design_rules(self.copper_weight) else { return };
+        let copper = PcbDrcChecker::new(self.layout, rules).check_all();
+        for mut violation in copper.violations {
+            let fab_rule = match violation.rule.as_str() {
+                "width.track" => "fab.track_width",
+                "clearance.edge" => "fab.edge_clearance",
+                "clearance.track_to_track" | "clearance.track_to_via" | "clearance.track_to_pad"
+                | "clearance.via_to_via" | "clearance.via_to_pad" | "clearance.pad_to_pad"
+                | "clearance.teardrop" | "clearance.zone" => "fab.clearance",
+                "mask.sliver" => "fab.mask_sliver",
+                "silk.width" => "fab.silk_width",
+                "silk.text_height" => "fab.silk_text_height",
+                _ => continue,
+            };
+            violation.rule = fab_rule.to_string();
+            violation.message = format!("{} ({} at {}oz)", violation.message, self.profile.name, self.copper_weight);
+            report.violations.push(violation);
+        }
+    }
+
+    /// Drill sizes, aspect ratio, annular rings, via types and hole spacing.
+    fn check_holes(&self, report: &mut DrcReport) {
+        let profile = self.profile;
+        let thickness = self.board_thickness();
+        let mut holes: Vec<(Point2D, f64, String)> = Vec::new();
+
+        for via in &self.layout.vias {
+            let center = Point2D::new(via.unit.to_mm(via.position.x), via.unit.to_mm(via.position.y));
+            let drill = via.unit.to_mm(via.drill);
+            let label = format!("via on {}", via.net);
+
+            if !profile.via_types.contains(&via.via_type) {
+                report.violations.push(
+                    DrcViolation::new(
+                        "fab.via_type",
+                        format!("{:?} {} is not supported by {}", via.via_type, label, profile.name),
+                        center,
+                    )
+                    .with_severity(DrcSeverity::Error)
+                    .with_fix("Use a supported via type or another manufacturer"),
+                );
+            }
+            let ring = (via.unit.to_mm(via.pad) - drill) / 2.0;
+            self.check_ring(report, "fab.annular_ring_via", &label, center, ring, profile.min_annular_ring_via);
+            self.check_drill(report, &label, center, drill, self.via_depth(via));
+            holes.push((center, drill, label));
+        }
+
+        for component in &self.layout.components {
+            for pad in &component.pads {
+                if pad.drill <= 0.0 {
+                    continue;
+                }
+                let center = component.pad_position(pad);
+                let label = format!("{} pad {}", component.reference, pad.number);
+                if pad.pad_type == PadType::ThruHole {
+                    let ring = (pad.size.0.min(pad.size.1) - pad.drill) / 2.0;
+                    self.check_ring(report, "fab.annular_ring_pth", &label, center, ring, profile.min_annular_ring_pth);
+                }
+                self.check_drill(report, &label, center, pad.drill, thickness);
+                holes.push((center, pad.drill, label));
+            }
+        }
+
+        for i in 0..holes.len() {
+            for j in (i + 1)..holes.len() {
+                let (a, b) = (&holes[i], &holes[j]);
+                let gap = a.0.distance(&b.0) - (a.1 + b.1) / 2.0;
+                if gap < profile.min_hole_clearance {
+                    report.violations.push(
+                        DrcViolation::new(
+                            "fab.hole_clearance",
+                            format!("Hole clearance {:.3}mm between {} and {} is below {} minimum {:.3}mm",
+                                gap.max(0.0), a.2, b.2, profile.name, profile.min_hole_clearance),
+                            a.0,
+                        )
+                        .with_severity(DrcSeverity::Error)
+                        .with_values(gap.max(0.0), profile.min_hole_clearance, "mm"),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Check a drill against the size range and the aspect ratio over `depth`.
+    fn check_drill(&self, report: &mut DrcReport, label: &str, at: Point2D, drill: f64, depth: f64) {
+        let profile = self.profile;
+        if drill < profile.min_drill || drill > profile.max_drill {
+            let limit = if drill < profile.min_drill { profile.min_drill } else { profile.max_drill };
+            report.violations.push(
+                DrcViolation::new(
+                    "fab.drill_size",
+                    format!("Drill {:.3}mm of {} is outside the {} range {:.3}-{:.3}mm",
+                        drill, label, profile.name, profile.min_drill, profile.max_drill),
+                    at,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(drill, limit, "mm"),
+            );
+        }
+        if drill > 0.0 && depth / drill > profile.max_aspect_ratio {
+            report.violations.push(
+                DrcViolation::new(
+                    "fab.aspect_ratio",
+                    format!("Aspect ratio {:.1}:1 of {} ({:.2}mm board, {:.3}mm drill) exceeds {} maximum {:.1}:1",
+                        depth / drill, label, depth, drill, profile.name, profile.max_aspect_ratio),
+                    at,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(depth / drill, profile.max_aspect_ratio, ":1")
+                .with_fix(format!("Use a drill of at least {:.3}mm", depth / profile.max_aspect_ratio)),
+            );
+        }
+    }
+
+    /// Check an annular ring against a minimum.
+    fn check_ring(&self, report: &mut DrcReport, rule: &str, label: &str, at: Point2D, ring: f64, min: f64) {
+        if ring < min {
+            report.violations.push(
+                DrcViolation::new(
+                    rule,
+                    format!("Annular ring {:.3}mm of {} is below {} minimum {:.3}mm",
+                        ring, label, self.profile.name, min),
+                    at,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(ring, min, "mm"),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{Graphic, Layer, LayerType, Pad, PlacedComponent};
+    use crate::units::LengthUnit;
+
+    fn via(x: f64, drill: f64, pad: f64, via_type: ViaType) -> Via {
+        Via {
+            net: "SIG".to_string(),
+            position: Position::new(x, 10.0),
+            via_type,
+            drill,
+            pad,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = FabProfile::builtin();
+        assert_eq!(profiles.len(), 2);
+        let jlc = profiles.iter().find(|p| p.name == "jlcpcb").unwrap();
+        assert_eq!(jlc.copper_capability(1.0).unwrap().min_track, 0.127);
+        assert_eq!(jlc.copper_capability(1.5).unwrap().weight_oz, 2.0);
+        assert!(jlc.copper_capability(3.0).is_none());
+        assert_eq!(jlc.design_rules(2.0).unwrap().min_track_width, 0.2);
+        assert!(jlc.design_rules(3.0).is_err());
+
+        let roundtrip = FabProfile::from_toml(&jlc.to_toml().unwrap()).unwrap();
+        assert_eq!(roundtrip.board_thicknesses, jlc.board_thicknesses);
+    }
+
+    #[test]
+    fn test_project_profile_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        let fab_dir = dir.path().join("fab");
+        std::fs::create_dir(&fab_dir).unwrap();
+        let custom = BUILTIN_PROFILES[0].replace("max_layers = 32", "max_layers = 2");
+        std::fs::write(fab_dir.join("jlc.toml"), custom).unwrap();
+        std::fs::write(fab_dir.join("notes.txt"), "ignored").unwrap();
+
+        let profiles = load_profiles(&[fab_dir]).unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles.iter().find(|p| p.name == "jlcpcb").unwrap().max_layers, 2);
+    }
+
+    #[test]
+    fn test_invalid_profile() {
+        assert!(FabProfile::from_toml("name = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_fab_check() {
+        let profile = FabProfile::builtin().into_iter().find(|p| p.name == "osh_park").unwrap();
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.vias.push(via(10.0, 0.3, 0.6, ViaType::Through));
+        layout.vias.push(via(20.0, 0.2, 0.6, ViaType::Blind));
+        layout.vias.push(via(20.5, 0.3, 0.6, ViaType::Through));
+        layout.components.push(
+            PlacedComponent::new("J1", "Header", "PinHeader")
+                .at(35.0, 35.0)
+                .with_pad(Pad::thru_hole("1", 0.0, 0.0, 1.1, 1.0)),
+        );
+
+        let report = FabChecker::new(&layout, &profile).with_copper_weight(2.0).check();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("fab.via_type"), 1);
+        assert_eq!(count("fab.drill_size"), 1);
+        // The blind via names no span, so it is drilled through the board:
+        // 1.6mm over 0.2mm is 8:1
+        assert_eq!(count("fab.aspect_ratio"), 1);
+        assert_eq!(count("fab.annular_ring_pth"), 1);
+        assert_eq!(count("fab.hole_clearance"), 1);
+        assert_eq!(count("fab.board_thickness"), 0);
+
+        let thick = FabChecker::new(&layout, &profile).with_board_thickness(2.4).check();
+        assert!(thick.violations.iter().any(|v| v.rule == "fab.board_thickness"));
+        // 2.4mm over a 0.3mm drill is 8:1, beyond OSH Park's 6.3:1
+        assert!(thick.violations.iter().any(|v| v.rule == "fab.aspect_ratio"));
+    }
+
+    #[test]
+    fn test_fab_check_silkscreen() {
+        let profile = FabProfile::builtin().into_iter().find(|p| p.name == "jlcpcb").unwrap();
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.graphics.push(Graphic::line("F.SilkS", Point2D::new(5.0, 5.0), Point2D::new(15.0, 5.0), 0.1));
+        layout.graphics.push(Graphic::text("F.SilkS", "REV A", Point2D::new(5.0, 20.0), 0.8, 0.15));
+
+        let report = FabChecker::new(&layout, &profile).check();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("fab.silk_width"), 1);
+        assert_eq!(count("fab.silk_text_height"), 1);
+    }
+
+    #[test]
+    fn test_fab_check_blind_via_depth() {
+        let profile = FabProfile::builtin().into_iter().find(|p| p.name == "osh_park").unwrap();
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.layers.retain(|l| l.layer_type != LayerType::Copper);
+        for (i, name) in ["F.Cu", "In1.Cu", "In2.Cu", "B.Cu"].into_iter().enumerate() {
+            layout.layers.insert(i, Layer::new(name, LayerType::Copper));
+        }
+        let mut blind = via(10.0, 0.08, 0.4, ViaType::Blind);
+        blind.start_layer = Some("F.Cu".to_string());
+        blind.end_layer = Some("In1.Cu".to_string());
+        layout.vias.push(blind);
+        let mut buried = via(20.0, 0.1, 0.4, ViaType::Buried);
+        buried.start_layer = Some("In1.Cu".to_string());
+        buried.end_layer = Some("In2.Cu".to_string());
+        layout.vias.push(buried);
+
+        let checker = FabChecker::new(&layout, &profile);
+        assert!((checker.via_depth(&layout.vias[0]) - 1.6 / 3.0).abs() < 1e-9);
+        // 0.53mm over 0.08mm is 6.7:1; over 0.1mm it is 5.3:1
+        let report = checker.check();
+        let aspect: Vec<&DrcViolation> = report.violations.iter().filter(|v| v.rule == "fab.aspect_ratio").collect();
+        assert_eq!(aspect.len(), 1);
+        assert_eq!(aspect[0].location, Point2D::new(10.0, 10.0));
+
+        for layer in &mut layout.layers {
+            layer.thickness = Some(0.2);
+        }
+        let checker = FabChecker::new(&layout, &profile);
+        assert!((checker.via_depth(&layout.vias[1]) - 0.4).abs() < 1e-9);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! Basic geometric types used throughout Hardware Tool.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::units::LengthUnit;
@@ -23,6 +25,112 @@ impl Point2D {
     pub fn distance(&self, other: &Point2D) -> f64 {
         ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
     }
+
+    /// Distance to the line segment from `a` to `b`.
+    pub fn distance_to_segment(&self, a: &Point2D, b: &Point2D) -> f64 {
+        let dx = b.x - a.x;
+        let dy = b.y - a.y;
+        let len_sq = dx * dx + dy * dy;
+        if len_sq == 0.0 {
+            return self.distance(a);
+        }
+        let t = (((self.x - a.x) * dx + (self.y - a.y) * dy) / len_sq).clamp(0.0, 1.0);
+        self.distance(&Point2D::new(a.x + t * dx, a.y + t * dy))
+    }
+}
+
+/// Signed polygon area (shoelace formula), positive for counter-clockwise
+/// points.
+pub fn polygon_area(polygon: &[Point2D]) -> f64 {
+    let n = polygon.len();
+    if n < 3 {
+        return 0.0;
+    }
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&polygon[i], &polygon[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Check if a point is inside a polygon (even-odd rule).
+pub fn point_in_polygon(point: &Point2D, polygon: &[Point2D]) -> bool {
+    let mut inside = false;
+    let n = polygon.len();
+    if n < 3 {
+        return false;
+    }
+    let mut j = n - 1;
+    for i in 0..n {
+        let pi = &polygon[i];
+        let pj = &polygon[j];
+        if (pi.y > point.y) != (pj.y > point.y)
+            && point.x < (pj.x - pi.x) * (point.y - pi.y) / (pj.y - pi.y) + pi.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Check if two line segments intersect or touch.
+pub fn segments_intersect(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> bool {
+    let cross = |o: &Point2D, p: &Point2D, q: &Point2D| (p.x - o.x) * (q.y - o.y) - (p.y - o.y) * (q.x - o.x);
+    let d1 = cross(c, d, a);
+    let d2 = cross(c, d, b);
+    let d3 = cross(a, b, c);
+    let d4 = cross(a, b, d);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    // Collinear or touching cases
+    (d1 == 0.0 && a.distance_to_segment(c, d) == 0.0)
+        || (d2 == 0.0 && b.distance_to_segment(c, d) == 0.0)
+        || (d3 == 0.0 && c.distance_to_segment(a, b) == 0.0)
+        || (d4 == 0.0 && d.distance_to_segment(a, b) == 0.0)
+}
+
+/// Minimum distance between segments `a`-`b` and `c`-`d`.
+pub fn segment_distance(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> f64 {
+    if segments_intersect(a, b, c, d) {
+        return 0.0;
+    }
+    a.distance_to_segment(c, d)
+        .min(b.distance_to_segment(c, d))
+        .min(c.distance_to_segment(a, b))
+        .min(d.distance_to_segment(a, b))
+}
+
+/// Minimum distance from segment `a`-`b` to a polygon (zero when inside or crossing).
+pub fn segment_polygon_distance(a: &Point2D, b: &Point2D, polygon: &[Point2D]) -> f64 {
+    if point_in_polygon(a, polygon) || point_in_polygon(b, polygon) {
+        return 0.0;
+    }
+    let n = polygon.len();
+    (0..n)
+        .map(|i| segment_distance(a, b, &polygon[i], &polygon[(i + 1) % n]))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Check if a polygon and a box overlap (touching counts).
+pub fn polygon_intersects_box(polygon: &[Point2D], bounds: &BoundingBox) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    let corners = [
+        bounds.min,
+        Point2D::new(bounds.max.x, bounds.min.y),
+        bounds.max,
+        Point2D::new(bounds.min.x, bounds.max.y),
+    ];
+    if corners.iter().any(|c| point_in_polygon(c, polygon)) || polygon.iter().any(|p| bounds.contains(p)) {
+        return true;
+    }
+    let n = polygon.len();
+    (0..n).any(|i| (0..4).any(|j| segments_intersect(&polygon[i], &polygon[(i + 1) % n], &corners[j], &corners[(j + 1) % 4])))
 }
 
 /// 3D point.
@@ -83,6 +191,11 @@ impl Position {
         Point2D::new(self.x, self.y)
     }
 
+    /// Convert to a Point2D in millimetres.
+    pub fn to_mm(&self) -> Point2D {
+        Point2D::new(self.unit.to_mm(self.x), self.unit.to_mm(self.y))
+    }
+
     /// Convert to Point3D.
     pub fn to_point3d(&self) -> Point3D {
         Point3D::new(self.x, self.y, self.z.unwrap_or(0.0))
@@ -127,6 +240,215 @@ impl BoundingBox {
             && point.y >= self.min.y
             && point.y <= self.max.y
     }
+
+    /// Smallest bounding box containing all points.
+    pub fn from_points(points: &[Point2D]) -> Option<Self> {
+        let first = points.first()?;
+        let mut bbox = Self::new(*first, *first);
+        for p in &points[1..] {
+            bbox.include(p);
+        }
+        Some(bbox)
+    }
+
+    /// Grow the bounding box to include a point.
+    pub fn include(&mut self, point: &Point2D) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    /// Bounding box grown by `margin` on every side.
+    pub fn expanded(&self, margin: f64) -> Self {
+        Self::new(
+            Point2D::new(self.min.x - margin, self.min.y - margin),
+            Point2D::new(self.max.x + margin, self.max.y + margin),
+        )
+    }
+
+    /// Check if two bounding boxes overlap.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+}
+
+/// Cells an item may span before it is kept off the grid.
+const MAX_ITEM_CELLS: i64 = 64;
+
+/// Smallest grid cell (mm).
+const MIN_CELL: f64 = 0.01;
+
+/// Uniform grid over item bounds for broad-phase neighbour queries.
+///
+/// Items spanning many cells, such as large zone fills, are kept in a
+/// separate list that every query tests.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SpatialGrid {
+    cell: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    large: Vec<usize>,
+    bounds: Vec<Option<BoundingBox>>,
+    /// Occupied cell range (min x, min y, max x, max y)
+    span: Option<(i64, i64, i64, i64)>,
+}
+
+impl SpatialGrid {
+    /// Index items by their bounds. Items without bounds are never
+    /// returned by queries.
+    pub(crate) fn new(bounds: Vec<Option<BoundingBox>>) -> Self {
+        let mut extents: Vec<f64> = bounds.iter().flatten().map(|b| b.width().max(b.height())).collect();
+        extents.sort_by(f64::total_cmp);
+        let typical = extents.get(extents.len() / 2).copied().unwrap_or(0.0);
+        let spread = bounds.iter().flatten().fold(None::<BoundingBox>, |acc, b| match acc {
+            Some(mut acc) => {
+                acc.include(&b.min);
+                acc.include(&b.max);
+                Some(acc)
+            }
+            None => Some(*b),
+        });
+        // About one typical item per cell, and no more cells than items
+        let sparse = spread.map_or(0.0, |s| s.width().max(s.height()) / (extents.len() as f64).sqrt());
+        let mut grid = Self {
+            cell: (2.0 * typical).max(sparse).max(MIN_CELL),
+            ..Self::default()
+        };
+        for b in bounds {
+            grid.insert(b);
+        }
+        grid
+    }
+
+    /// Add an item and return its index.
+    pub(crate) fn insert(&mut self, bounds: Option<BoundingBox>) -> usize {
+        if self.cell <= 0.0 {
+            self.cell = 1.0;
+        }
+        let index = self.bounds.len();
+        if let Some(b) = &bounds {
+            let (x0, y0, x1, y1) = self.cell_range(b);
+            if (x1 - x0 + 1) * (y1 - y0 + 1) > MAX_ITEM_CELLS {
+                self.large.push(index);
+            } else {
+                for x in x0..=x1 {
+                    for y in y0..=y1 {
+                        self.cells.entry((x, y)).or_default().push(index);
+                    }
+                }
+                self.span = Some(match self.span {
+                    Some((sx0, sy0, sx1, sy1)) => (sx0.min(x0), sy0.min(y0), sx1.max(x1), sy1.max(y1)),
+                    None => (x0, y0, x1, y1),
+                });
+            }
+        }
+        self.bounds.push(bounds);
+        index
+    }
+
+    /// Remove an item; its index is not reused.
+    pub(crate) fn remove(&mut self, index: usize) {
+        let Some(b) = self.bounds.get_mut(index).and_then(Option::take) else { return };
+        let (x0, y0, x1, y1) = self.cell_range(&b);
+        if (x1 - x0 + 1) * (y1 - y0 + 1) > MAX_ITEM_CELLS {
+            self.large.retain(|&i| i != index);
+            return;
+        }
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                if let Some(items) = self.cells.get_mut(&(x, y)) {
+                    items.retain(|&i| i != index);
+                }
+            }
+        }
+    }
+
+    /// Bounds of an item, `None` when it has none or was removed.
+    pub(crate) fn bounds(&self, index: usize) -> Option<&BoundingBox> {
+        self.bounds.get(index).and_then(Option::as_ref)
+    }
+
+    /// Items whose bounds intersect `region`, in index order.
+    pub(crate) fn query(&self, region: &BoundingBox) -> Vec<usize> {
+        let hit = |i: usize| self.bounds(i).is_some_and(|b| b.intersects(region));
+        let mut found: Vec<usize> = self.large.iter().copied().filter(|&i| hit(i)).collect();
+        if let Some((sx0, sy0, sx1, sy1)) = self.span {
+            let (x0, y0, x1, y1) = self.cell_range(region);
+            let (x0, y0, x1, y1) = (x0.max(sx0), y0.max(sy0), x1.min(sx1), y1.min(sy1));
+            if x0 <= x1 && y0 <= y1 {
+                if (x1 - x0 + 1) * (y1 - y0 + 1) > self.cells.len() as i64 {
+                    found.extend(self.cells.values().flatten().copied().filter(|&i| hit(i)));
+                } else {
+                    for x in x0..=x1 {
+                        for y in y0..=y1 {
+                            if let Some(items) = self.cells.get(&(x, y)) {
+                                found.extend(items.iter().copied().filter(|&i| hit(i)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Item nearest to `point` as measured by `distance`, searching no
+    /// farther than `within`. `distance` returns `None` to skip an item
+    /// and must not be less than the distance to the item's bounds.
+    pub(crate) fn nearest(
+        &self,
+        point: &Point2D,
+        within: f64,
+        distance: impl Fn(usize) -> Option<f64>,
+    ) -> Option<(usize, f64)> {
+        let mut best: Option<(usize, f64)> = None;
+        let consider = |i: usize, best: &mut Option<(usize, f64)>| {
+            if let Some(d) = distance(i)
+                && d <= within
+                && best.is_none_or(|(bi, bd)| (d, i) < (bd, bi))
+            {
+                *best = Some((i, d));
+            }
+        };
+        for &i in &self.large {
+            consider(i, &mut best);
+        }
+        let Some((sx0, sy0, sx1, sy1)) = self.span else { return best };
+        let (cx, cy) = self.cell_of(point);
+        let rings = (cx - sx0).max(sx1 - cx).max(cy - sy0).max(sy1 - cy).max(0);
+        for r in 0..=rings {
+            for x in (cx - r)..=(cx + r) {
+                // Whole columns at the ring's sides, top and bottom cells between
+                let step = if x == cx - r || x == cx + r { 1 } else { 2 * r as usize };
+                for y in ((cy - r)..=(cy + r)).step_by(step) {
+                    if let Some(items) = self.cells.get(&(x, y)) {
+                        items.iter().for_each(|&i| consider(i, &mut best));
+                    }
+                }
+            }
+            // Cells beyond this ring are at least r cells away
+            let reach = r as f64 * self.cell;
+            if reach > within || best.is_some_and(|(_, d)| d <= reach) {
+                break;
+            }
+        }
+        best
+    }
+
+    fn cell_of(&self, point: &Point2D) -> (i64, i64) {
+        ((point.x / self.cell).floor() as i64, (point.y / self.cell).floor() as i64)
+    }
+
+    fn cell_range(&self, b: &BoundingBox) -> (i64, i64, i64, i64) {
+        let (x0, y0) = self.cell_of(&b.min);
+        let (x1, y1) = self.cell_of(&b.max);
+        (x0, y0, x1, y1)
+    }
 }
 
 #[cfg(test)]
@@ -148,4 +470,69 @@ mod tests {
         assert!(bbox.contains(&Point2D::new(5.0, 10.0)));
         assert!(!bbox.contains(&Point2D::new(15.0, 10.0)));
     }
+
+    #[test]
+    fn test_distance_to_segment() {
+        let p = Point2D::new(5.0, 3.0);
+        let a = Point2D::new(0.0, 0.0);
+        let b = Point2D::new(10.0, 0.0);
+        assert!((p.distance_to_segment(&a, &b) - 3.0).abs() < 1e-10);
+        assert!((Point2D::new(13.0, 4.0).distance_to_segment(&a, &b) - 5.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_segment_polygon_distance() {
+        let square = vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 0.0),
+            Point2D::new(10.0, 10.0),
+            Point2D::new(0.0, 10.0),
+        ];
+        let d = segment_polygon_distance(&Point2D::new(12.0, -5.0), &Point2D::new(12.0, 15.0), &square);
+        assert!((d - 2.0).abs() < 1e-9);
+        // Crossing the polygon without an endpoint inside
+        let d = segment_polygon_distance(&Point2D::new(-5.0, 5.0), &Point2D::new(15.0, 5.0), &square);
+        assert_eq!(d, 0.0);
+        assert!(segments_intersect(
+            &Point2D::new(0.0, 0.0),
+            &Point2D::new(4.0, 4.0),
+            &Point2D::new(0.0, 4.0),
+            &Point2D::new(4.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn test_spatial_grid() {
+        let boxes = vec![
+            BoundingBox::from_points(&[Point2D::new(0.0, 0.0), Point2D::new(1.0, 1.0)]),
+            BoundingBox::from_points(&[Point2D::new(5.0, 5.0), Point2D::new(6.0, 6.0)]),
+            None,
+            // Spans many cells and is kept off the grid
+            BoundingBox::from_points(&[Point2D::new(-50.0, -50.0), Point2D::new(50.0, 50.0)]),
+        ];
+        let mut grid = SpatialGrid::new(boxes);
+        let region = BoundingBox::new(Point2D::new(0.5, 0.5), Point2D::new(2.0, 2.0));
+        assert_eq!(grid.query(&region), vec![0, 3]);
+
+        let moved = grid.insert(BoundingBox::from_points(&[Point2D::new(1.5, 1.5)]));
+        assert_eq!(grid.query(&region), vec![0, 3, moved]);
+
+        let point = Point2D::new(4.0, 4.0);
+        let centre = |i: usize| grid.bounds(i).map(|b| b.center().distance(&point));
+        let nearest = grid.nearest(&point, f64::INFINITY, |i| (i != 3).then(|| centre(i)).flatten());
+        assert_eq!(nearest.map(|(i, _)| i), Some(1));
+        assert_eq!(grid.nearest(&point, 1.0, |i| (i != 3).then(|| centre(i)).flatten()), None);
+    }
+
+    #[test]
+    fn test_point_in_polygon() {
+        let square = vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 0.0),
+            Point2D::new(10.0, 10.0),
+            Point2D::new(0.0, 10.0),
+        ];
+        assert!(point_in_polygon(&Point2D::new(5.0, 5.0), &square));
+        assert!(!point_in_polygon(&Point2D::new(15.0, 5.0), &square));
+    }
 }
@@ -0,0 +1,801 @@
+//! High-speed design rules.
+//!
+//! Checks driven by `Constraint`s and by clock and differential nets:
+//! via count and stub length per net, intra-pair skew and uncoupled length
+//! of differential pairs, parallel-run crosstalk between nets, and tracks
+//! that leave the reference plane (the fill of ground and power zones) on
+//! an adjacent layer.
+//!
+//! Routed length is the sum of track lengths; via barrels are not counted.
+//! Differential nets without a `DifferentialPair` constraint are paired by
+//! name suffix (`_P`/`_N`, `+`/`-`) and checked against default limits.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+
+use crate::constraint::{scope_matches, Constraint};
+use crate::copper::CopperShape;
+use crate::drc::{DrcSeverity, DrcViolation};
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D, SpatialGrid};
+use crate::layout::{Layout, Trace, ZoneKind};
+use crate::net::{Net, NetType};
+
+/// Default maximum intra-pair skew (mm).
+pub const DEFAULT_MAX_SKEW: f64 = 0.127;
+
+/// Default maximum uncoupled length of a pair (mm).
+pub const DEFAULT_MAX_UNCOUPLED: f64 = 6.35;
+
+/// Pair tracks count as coupled up to this centre distance, in track widths.
+const COUPLING_WIDTHS: f64 = 3.0;
+
+/// Largest sine of the angle between tracks counted as parallel.
+const PARALLEL_SIN: f64 = 0.02;
+
+/// Distance at which copper counts as connected (mm).
+const TOUCH: f64 = 1e-3;
+
+/// Name suffixes of the positive and negative nets of a pair.
+const PAIR_SUFFIXES: [(&str, &str); 3] = [("_P", "_N"), ("_DP", "_DN"), ("+", "-")];
+
+/// A differential pair with its routing limits (mm).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffPair {
+    /// Positive net
+    pub positive: String,
+    /// Negative net
+    pub negative: String,
+    /// Maximum length difference
+    pub max_skew: f64,
+    /// Maximum uncoupled length
+    pub max_uncoupled: f64,
+}
+
+/// A track in mm.
+struct Segment<'a> {
+    net: &'a str,
+    layer: &'a str,
+    start: Point2D,
+    end: Point2D,
+    width: f64,
+}
+
+impl<'a> Segment<'a> {
+    fn from_trace(trace: &'a Trace) -> Self {
+        let to_mm = |x: f64| trace.unit.to_mm(x);
+        Self {
+            net: &trace.net,
+            layer: &trace.layer,
+            start: Point2D::new(to_mm(trace.start.x), to_mm(trace.start.y)),
+            end: Point2D::new(to_mm(trace.end.x), to_mm(trace.end.y)),
+            width: to_mm(trace.width),
+        }
+    }
+
+    fn length(&self) -> f64 {
+        self.start.distance(&self.end)
+    }
+
+    /// Bounds of the centre line grown by `margin`.
+    fn bounds(&self, margin: f64) -> Option<BoundingBox> {
+        BoundingBox::from_points(&[self.start, self.end]).map(|b| b.expanded(margin))
+    }
+
+    fn at(&self, t: f64) -> Point2D {
+        let len = self.length().max(f64::EPSILON);
+        Point2D::new(
+            self.start.x + (self.end.x - self.start.x) * t / len,
+            self.start.y + (self.end.y - self.start.y) * t / len,
+        )
+    }
+
+    /// Interval of this segment, as distances from its start, that runs
+    /// parallel to `other` within `max_distance` (centre to centre).
+    fn parallel_overlap(&self, other: &Segment, max_distance: f64) -> Option<(f64, f64)> {
+        let len = self.length();
+        if len <= f64::EPSILON || other.length() <= f64::EPSILON {
+            return None;
+        }
+        let (ux, uy) = ((self.end.x - self.start.x) / len, (self.end.y - self.start.y) / len);
+        let (ox, oy) = ((other.end.x - other.start.x) / other.length(), (other.end.y - other.start.y) / other.length());
+        if (ux * oy - uy * ox).abs() > PARALLEL_SIN {
+            return None;
+        }
+        let project = |p: &Point2D| ((p.x - self.start.x) * ux + (p.y - self.start.y) * uy, (p.y - self.start.y) * ux - (p.x - self.start.x) * uy);
+        let (t0, d0) = project(&other.start);
+        let (t1, d1) = project(&other.end);
+        if d0.abs().max(d1.abs()) > max_distance {
+            return None;
+        }
+        let (lo, hi) = (t0.min(t1).max(0.0), t0.max(t1).min(len));
+        (hi - lo > f64::EPSILON).then_some((lo, hi))
+    }
+}
+
+/// High-speed rule checker.
+pub struct HighSpeedChecker<'a> {
+    layout: &'a Layout,
+    nets: HashMap<&'a str, &'a Net>,
+    constraints: &'a [Constraint],
+    segments: Vec<Segment<'a>>,
+    /// Nets the checks are limited to; `None` checks all nets
+    only: Option<&'a HashSet<String>>,
+}
+
+impl<'a> HighSpeedChecker<'a> {
+    /// Create a checker for a layout with its nets and constraints.
+    pub fn new(layout: &'a Layout, nets: &'a [Net], constraints: &'a [Constraint]) -> Self {
+        Self {
+            layout,
+            nets: nets.iter().map(|n| (n.name.as_str(), n)).collect(),
+            constraints,
+            segments: layout.traces.iter().map(Segment::from_trace).collect(),
+            only: None,
+        }
+    }
+
+    /// Limit the checks to violations involving any of `nets`.
+    pub fn for_nets(mut self, nets: &'a HashSet<String>) -> Self {
+        self.only = Some(nets);
+        self
+    }
+
+    /// Run all high-speed checks.
+    pub fn check(&self) -> Vec<DrcViolation> {
+        self.check_by_net().into_iter().map(|(_, violation)| violation).collect()
+    }
+
+    /// Run all high-speed checks, returning each violation with the nets
+    /// it involves.
+    pub fn check_by_net(&self) -> Vec<(Vec<String>, DrcViolation)> {
+        let mut violations = Vec::new();
+        self.check_via_counts(&mut violations);
+        self.check_stubs(&mut violations);
+        self.check_diff_pairs(&mut violations);
+        self.check_crosstalk(&mut violations);
+        self.check_reference_planes(&mut violations);
+        violations
+    }
+
+    /// Differential pairs from constraints, then differential nets paired
+    /// by name.
+    pub fn diff_pairs(&self) -> Vec<DiffPair> {
+        let mut pairs: Vec<DiffPair> = self.constraints.iter()
+            .filter_map(|c| match c {
+                Constraint::DifferentialPair { net_positive, net_negative, max_skew, max_uncoupled, unit, .. } => Some(DiffPair {
+                    positive: net_positive.clone(),
+                    negative: net_negative.clone(),
+                    max_skew: max_skew.map_or(DEFAULT_MAX_SKEW, |v| unit.to_mm(v)),
+                    max_uncoupled: max_uncoupled.map_or(DEFAULT_MAX_UNCOUPLED, |v| unit.to_mm(v)),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let mut differential: Vec<&str> = self.nets.values()
+            .filter(|n| n.net_type == NetType::Differential)
+            .map(|n| n.name.as_str())
+            .collect();
+        differential.sort();
+        for name in &differential {
+            for (p, n) in PAIR_SUFFIXES {
+                let Some(base) = name.strip_suffix(p) else { continue };
+                let negative = format!("{}{}", base, n);
+                let paired = pairs.iter().any(|pair| pair.positive == *name || pair.negative == *name);
+                if !paired && differential.contains(&negative.as_str()) {
+                    pairs.push(DiffPair {
+                        positive: name.to_string(),
+                        negative,
+                        max_skew: DEFAULT_MAX_SKEW,
+                        max_uncoupled: DEFAULT_MAX_UNCOUPLED,
+                    });
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Routed track length of a net (mm).
+    pub fn net_length(&self, net: &str) -> f64 {
+        self.segments.iter().filter(|s| s.net == net).map(|s| s.length()).sum()
+    }
+
+    /// Whether a constraint scope covers a net.
+    fn in_scope(&self, scope: &str, net: &str) -> bool {
+        let info = self.nets.get(net);
+        scope_matches(scope, net, info.and_then(|n| n.class.as_deref()),
+            info.map_or(NetType::Signal, |n| n.net_type))
+    }
+
+    /// Whether a net's violations are checked.
+    fn selected(&self, net: &str) -> bool {
+        self.only.is_none_or(|only| only.contains(net))
+    }
+
+    /// Routed nets in name order.
+    fn routed_nets(&self) -> Vec<&'a str> {
+        let mut nets: Vec<&str> = self.segments.iter().map(|s| s.net)
+            .chain(self.layout.vias.iter().map(|v| v.net.as_str()))
+            .filter(|net| self.selected(net))
+            .collect();
+        nets.sort();
+        nets.dedup();
+        nets
+    }
+
+    /// Tightest limit any matching constraint sets for a net.
+    fn limit<T: PartialOrd>(&self, net: &str, limit: impl Fn(&Constraint) -> Option<(&str, T)>) -> Option<T> {
+        self.constraints.iter()
+            .filter_map(&limit)
+            .filter(|(scope, _)| self.in_scope(scope, net))
+            .map(|(_, value)| value)
+            .reduce(|a, b| if b < a { b } else { a })
+    }
+
+    /// Check the number of vias on each constrained net.
+    fn check_via_counts(&self, violations: &mut Vec<(Vec<String>, DrcViolation)>) {
+        for net in self.routed_nets() {
+            let Some(max) = self.limit(net, |c| match c {
+                Constraint::MaxVias { scope, max } => Some((scope.as_str(), *max)),
+                _ => None,
+            }) else { continue };
+            let vias: Vec<_> = self.layout.vias.iter().filter(|v| v.net == net).collect();
+            if vias.len() > max {
+                let via = vias[max];
+                violations.push((vec![net.to_string()],
+                    DrcViolation::new(
+                        "hs.via_count",
+                        format!("Net {} has {} vias (maximum {})", net, vias.len(), max),
+                        Point2D::new(via.unit.to_mm(via.position.x), via.unit.to_mm(via.position.y)),
+                    )
+                    .with_severity(DrcSeverity::Error)
+                    .with_values(vias.len() as f64, max as f64, "vias")
+                    .with_fix("Route the net with fewer layer changes")
+                ));
+            }
+        }
+    }
+
+    /// Check branches off the main route of each constrained net. The main
+    /// route is the longest path between two of the net's pads; everything
+    /// else attached to it is a stub.
+    fn check_stubs(&self, violations: &mut Vec<(Vec<String>, DrcViolation)>) {
+        for net in self.routed_nets() {
+            let Some(max) = self.limit(net, |c| match c {
+                Constraint::StubLength { scope, max, unit } => Some((scope.as_str(), unit.to_mm(*max))),
+                _ => None,
+            }) else { continue };
+            for (length, location) in self.stubs(net) {
+                if length > max {
+                    violations.push((vec![net.to_string()],
+                        DrcViolation::new(
+                            "hs.stub_length",
+                            format!("Stub of {:.3}mm on net {} exceeds maximum {:.3}mm", length, net, max),
+                            location,
+                        )
+                        .with_severity(DrcSeverity::Error)
+                        .with_values(length, max, "mm")
+                        .with_fix("Shorten the branch or route the net as a daisy chain")
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Stubs of a net as (length, attachment point).
+    fn stubs(&self, net: &str) -> Vec<(f64, Point2D)> {
+        // Nodes: pads, vias and free track ends
+        let mut points: Vec<Point2D> = Vec::new();
+        let mut shapes: Vec<Option<CopperShape>> = Vec::new();
+        let mut terminal: Vec<bool> = Vec::new();
+        for component in &self.layout.components {
+            for pad in component.pads.iter().filter(|p| p.net.as_deref() == Some(net)) {
+                points.push(component.pad_position(pad));
+                shapes.push(Some(CopperShape::from_pad(component, pad)));
+                terminal.push(true);
+            }
+        }
+        for via in self.layout.vias.iter().filter(|v| v.net == net) {
+            let shape = CopperShape::from_via(via);
+            points.push(shape.anchor());
+            shapes.push(Some(shape));
+            terminal.push(false);
+        }
+        let mut node_at = |point: Point2D, points: &mut Vec<Point2D>, shapes: &mut Vec<Option<CopperShape>>| -> usize {
+            let probe = CopperShape::circle(point, 0.0);
+            let found = shapes.iter().position(|s| s.as_ref().is_some_and(|s| s.distance(&probe) <= TOUCH))
+                .or_else(|| points.iter().zip(shapes.iter()).position(|(p, s)| s.is_none() && p.distance(&point) <= TOUCH));
+            found.unwrap_or_else(|| {
+                points.push(point);
+                shapes.push(None);
+                terminal.push(false);
+                points.len() - 1
+            })
+        };
+        let mut edges: Vec<Vec<(usize, f64)>> = Vec::new();
+        let mut links = Vec::new();
+        for segment in self.segments.iter().filter(|s| s.net == net) {
+            let a = node_at(segment.start, &mut points, &mut shapes);
+            let b = node_at(segment.end, &mut points, &mut shapes);
+            if a != b {
+                links.push((a, b, segment.length()));
+            }
+        }
+        edges.resize(points.len(), Vec::new());
+        for (a, b, length) in links {
+            edges[a].push((b, length));
+            edges[b].push((a, length));
+        }
+
+        // Main route: the longest shortest path between two pads
+        let terminals: Vec<usize> = (0..points.len()).filter(|&i| terminal[i] && !edges[i].is_empty()).collect();
+        let mut trunk: Option<(f64, Vec<usize>)> = None;
+        for &from in &terminals {
+            let (dist, prev) = shortest_paths(&edges, &[from]);
+            for &to in terminals.iter().filter(|&&t| t != from) {
+                if dist[to].is_finite() && trunk.as_ref().is_none_or(|(d, _)| dist[to] > *d) {
+                    let mut path = vec![to];
+                    while let Some(p) = prev[*path.last().unwrap()] {
+                        path.push(p);
+                    }
+                    trunk = Some((dist[to], path));
+                }
+            }
+        }
+        let Some((_, trunk)) = trunk else { return Vec::new() };
+
+        // Depth of everything hanging off each trunk node
+        let (dist, prev) = shortest_paths(&edges, &trunk);
+        let mut depth: BTreeMap<usize, f64> = BTreeMap::new();
+        for (node, &d) in dist.iter().enumerate() {
+            if !d.is_finite() || d <= TOUCH {
+                continue;
+            }
+            let mut root = node;
+            while let Some(p) = prev[root] {
+                root = p;
+            }
+            let entry = depth.entry(root).or_insert(0.0);
+            *entry = entry.max(d);
+        }
+        depth.into_iter().map(|(root, length)| (length, points[root])).collect()
+    }
+
+    /// Check intra-pair skew and uncoupled length of differential pairs.
+    fn check_diff_pairs(&self, violations: &mut Vec<(Vec<String>, DrcViolation)>) {
+        for pair in self.diff_pairs() {
+            if !self.selected(&pair.positive) && !self.selected(&pair.negative) {
+                continue;
+            }
+            let (lp, ln) = (self.net_length(&pair.positive), self.net_length(&pair.negative));
+            if lp <= 0.0 || ln <= 0.0 {
+                continue;
+            }
+            let skew = (lp - ln).abs();
+            if skew > pair.max_skew {
+                let shorter = if lp < ln { &pair.positive } else { &pair.negative };
+                let location = self.segments.iter().find(|s| s.net == shorter).map_or_else(Point2D::default, |s| s.end);
+                violations.push((vec![pair.positive.clone(), pair.negative.clone()],
+                    DrcViolation::new(
+                        "hs.skew",
+                        format!("Pair {}/{} skew {:.3}mm exceeds maximum {:.3}mm ({:.3}mm vs {:.3}mm)",
+                            pair.positive, pair.negative, skew, pair.max_skew, lp, ln),
+                        location,
+                    )
+                    .with_severity(DrcSeverity::Error)
+                    .with_values(skew, pair.max_skew, "mm")
+                    .with_fix(format!("Add length tuning to {}", shorter))
+                ));
+            }
+
+            let (up, worst_p) = self.uncoupled(&pair.positive, &pair.negative);
+            let (un, worst_n) = self.uncoupled(&pair.negative, &pair.positive);
+            let (uncoupled, location) = if up >= un { (up, worst_p) } else { (un, worst_n) };
+            if uncoupled > pair.max_uncoupled {
+                violations.push((vec![pair.positive.clone(), pair.negative.clone()],
+                    DrcViolation::new(
+                        "hs.uncoupled_length",
+                        format!("Pair {}/{} runs uncoupled for {:.3}mm (maximum {:.3}mm)",
+                            pair.positive, pair.negative, uncoupled, pair.max_uncoupled),
+                        location,
+                    )
+                    .with_severity(DrcSeverity::Warning)
+                    .with_values(uncoupled, pair.max_uncoupled, "mm")
+                    .with_fix("Route the pair together")
+                ));
+            }
+        }
+    }
+
+    /// Length of `net` not running alongside `partner`, with the midpoint
+    /// of its longest uncoupled piece.
+    fn uncoupled(&self, net: &str, partner: &str) -> (f64, Point2D) {
+        let mut total = 0.0;
+        let mut worst = (0.0, Point2D::default());
+        for segment in self.segments.iter().filter(|s| s.net == net) {
+            let mut coupled: Vec<(f64, f64)> = self.segments.iter()
+                .filter(|o| o.net == partner && o.layer == segment.layer)
+                .filter_map(|o| segment.parallel_overlap(o, COUPLING_WIDTHS * segment.width.max(o.width)))
+                .collect();
+            for (lo, hi) in gaps(&mut coupled, segment.length()) {
+                total += hi - lo;
+                if hi - lo > worst.0 {
+                    worst = (hi - lo, segment.at((lo + hi) / 2.0));
+                }
+            }
+        }
+        (total, worst.1)
+    }
+
+    /// Check parallel-run length between nets against `ParallelRun`
+    /// constraints. Partners of a differential pair are exempt.
+    fn check_crosstalk(&self, violations: &mut Vec<(Vec<String>, DrcViolation)>) {
+        let pairs = self.diff_pairs();
+        let partners = |a: &str, b: &str| pairs.iter()
+            .any(|p| (p.positive == a && p.negative == b) || (p.positive == b && p.negative == a));
+
+        for constraint in self.constraints {
+            let Constraint::ParallelRun { scope, gap, max_length, unit } = constraint else { continue };
+            let (gap, max_length) = (unit.to_mm(*gap), unit.to_mm(*max_length));
+            let mut runs: BTreeMap<(&str, &str), (f64, f64, Point2D)> = BTreeMap::new();
+            // Each side grown by half the coupling distance, so segments
+            // close enough to couple have meeting bounds
+            let grid = SpatialGrid::new(self.segments.iter().map(|s| s.bounds((gap + s.width) / 2.0)).collect());
+            for (i, a) in self.segments.iter().enumerate() {
+                let Some(bounds) = grid.bounds(i) else { continue };
+                for b in grid.query(bounds).into_iter().filter(|&j| j > i).map(|j| &self.segments[j]) {
+                    if a.net == b.net || a.layer != b.layer || partners(a.net, b.net) {
+                        continue;
+                    }
+                    if !self.selected(a.net) && !self.selected(b.net) {
+                        continue;
+                    }
+                    if !self.in_scope(scope, a.net) && !self.in_scope(scope, b.net) {
+                        continue;
+                    }
+                    let Some((lo, hi)) = a.parallel_overlap(b, gap + (a.width + b.width) / 2.0) else { continue };
+                    let key = if a.net < b.net { (a.net, b.net) } else { (b.net, a.net) };
+                    let run = runs.entry(key).or_insert((0.0, 0.0, Point2D::default()));
+                    run.0 += hi - lo;
+                    if hi - lo > run.1 {
+                        run.1 = hi - lo;
+                        run.2 = a.at((lo + hi) / 2.0);
+                    }
+                }
+            }
+            for ((a, b), (length, _, location)) in runs {
+                if length > max_length {
+                    violations.push((vec![a.to_string(), b.to_string()],
+                        DrcViolation::new(
+                            "hs.crosstalk",
+                            format!("Nets {} and {} run in parallel for {:.3}mm within {:.3}mm (maximum {:.3}mm)",
+                                a, b, length, gap, max_length),
+                            location,
+                        )
+                        .with_severity(DrcSeverity::Warning)
+                        .with_values(length, max_length, "mm")
+                        .with_fix("Increase the spacing or break up the parallel run")
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Check that clock and differential tracks stay over a copper zone on
+    /// an adjacent layer. Adjacent layers without zones are not treated as
+    /// reference planes.
+    fn check_reference_planes(&self, violations: &mut Vec<(Vec<String>, DrcViolation)>) {
+        let copper = self.layout.copper_layer_names();
+        let pairs = self.diff_pairs();
+        let high_speed = |net: &str| {
+            self.nets.get(net).is_some_and(|n| matches!(n.net_type, NetType::Clock | NetType::Differential))
+                || pairs.iter().any(|p| p.positive == net || p.negative == net)
+        };
+        let plane_net = |net: &str| {
+            self.nets.get(net).is_some_and(|n| matches!(n.net_type, NetType::Ground | NetType::Power))
+        };
+        // Filled copper of ground and power zones; fill pieces do not overlap
+        let planes: HashMap<&str, Vec<Vec<Point2D>>> = copper.iter()
+            .map(|layer| {
+                let zones = self.layout.zones.iter()
+                    .filter(|z| z.kind == ZoneKind::Copper && z.layer == *layer && plane_net(&z.net))
+                    .flat_map(|z| z.filled.iter().map(|ring| {
+                        ring.iter().map(|p| Point2D::new(z.unit.to_mm(p.x), z.unit.to_mm(p.y))).collect()
+                    }))
+                    .collect();
+                (layer.as_str(), zones)
+            })
+            .collect();
+
+        for segment in self.segments.iter().filter(|s| high_speed(s.net) && self.selected(s.net)) {
+            let Some(index) = copper.iter().position(|l| l == segment.layer) else { continue };
+            let adjacent: Vec<&str> = [index.checked_sub(1), Some(index + 1)].into_iter()
+                .flatten()
+                .filter_map(|i| copper.get(i))
+                .map(String::as_str)
+                .filter(|l| planes.get(l).is_some_and(|z| !z.is_empty()))
+                .collect();
+            // Worst gap over the best-covered reference layer
+            let mut best: Option<(f64, &str, Point2D)> = None;
+            for layer in adjacent {
+                let gaps = uncovered(segment, &planes[layer]);
+                let missing: f64 = gaps.iter().map(|(lo, hi)| hi - lo).sum();
+                if best.as_ref().is_none_or(|(m, ..)| missing < *m) {
+                    let location = gaps.first().map_or(segment.start, |(lo, hi)| segment.at((lo + hi) / 2.0));
+                    best = Some((missing, layer, location));
+                }
+            }
+            if let Some((missing, layer, location)) = best
+                && missing > TOUCH
+            {
+                violations.push((vec![segment.net.to_string()],
+                    DrcViolation::new(
+                        "hs.reference_plane",
+                        format!("Track of net {} on {} crosses a gap in the reference plane on {} ({:.3}mm unreferenced)",
+                            segment.net, segment.layer, layer, missing),
+                        location,
+                    )
+                    .with_severity(DrcSeverity::Warning)
+                    .with_values(missing, 0.0, "mm")
+                    .with_fix("Route over a continuous plane or add stitching capacitors at the split")
+                ));
+            }
+        }
+    }
+}
+
+/// Dijkstra from `sources` over an adjacency list; returns distances and
+/// predecessors.
+fn shortest_paths(edges: &[Vec<(usize, f64)>], sources: &[usize]) -> (Vec<f64>, Vec<Option<usize>>) {
+    let n = edges.len();
+    let mut dist = vec![f64::INFINITY; n];
+    let mut prev = vec![None; n];
+    let mut queue = BinaryHeap::new();
+    for &s in sources {
+        dist[s] = 0.0;
+        queue.push(Visit(0.0, s));
+    }
+    while let Some(Visit(d, u)) = queue.pop() {
+        // Stale entry for a node already reached more cheaply
+        if d > dist[u] {
+            continue;
+        }
+        for &(v, w) in &edges[u] {
+            if d + w < dist[v] {
+                dist[v] = d + w;
+                prev[v] = Some(u);
+                queue.push(Visit(dist[v], v));
+            }
+        }
+    }
+    (dist, prev)
+}
+
+/// Queue entry of `shortest_paths`: a node and its distance, ordered so
+/// the heap pops the nearest node first, lowest index on ties.
+struct Visit(f64, usize);
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+/// Parts of `0..length` not covered by any interval.
+fn gaps(intervals: &mut [(f64, f64)], length: f64) -> Vec<(f64, f64)> {
+    intervals.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut gaps = Vec::new();
+    let mut covered = 0.0;
+    for &(lo, hi) in intervals.iter() {
+        if lo > covered + TOUCH {
+            gaps.push((covered, lo));
+        }
+        covered = f64::max(covered, hi);
+    }
+    if length > covered + TOUCH {
+        gaps.push((covered, length));
+    }
+    gaps
+}
+
+/// Parts of a segment, as distances from its start, outside all polygons.
+fn uncovered(segment: &Segment, polygons: &[Vec<Point2D>]) -> Vec<(f64, f64)> {
+    let length = segment.length();
+    let mut cuts = vec![0.0, length];
+    for polygon in polygons {
+        let n = polygon.len();
+        for i in 0..n {
+            let (c, d) = (&polygon[i], &polygon[(i + 1) % n]);
+            if let Some(t) = crossing(&segment.start, &segment.end, c, d) {
+                cuts.push(t * length);
+            }
+        }
+    }
+    cuts.sort_by(f64::total_cmp);
+    cuts.windows(2)
+        .map(|w| (w[0], w[1]))
+        .filter(|(lo, hi)| hi - lo > f64::EPSILON)
+        .filter(|(lo, hi)| {
+            let mid = segment.at((lo + hi) / 2.0);
+            !polygons.iter().any(|p| point_in_polygon(&mid, p))
+        })
+        .collect()
+}
+
+/// Parameter along `ab` where it crosses `cd`, if they cross.
+fn crossing(a: &Point2D, b: &Point2D, c: &Point2D, d: &Point2D) -> Option<f64> {
+    let (rx, ry) = (b.x - a.x, b.y - a.y);
+    let (sx, sy) = (d.x - c.x, d.y - c.y);
+    let denom = rx * sy - ry * sx;
+    if denom.abs() <= f64::EPSILON {
+        return None;
+    }
+    let t = ((c.x - a.x) * sy - (c.y - a.y) * sx) / denom;
+    let u = ((c.x - a.x) * ry - (c.y - a.y) * rx) / denom;
+    ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{Pad, PlacedComponent, Via, ViaType, Zone};
+    use crate::units::LengthUnit;
+
+    fn track(net: &str, layer: &str, x1: f64, y1: f64, x2: f64, y2: f64) -> Trace {
+        Trace {
+            net: net.to_string(),
+            layer: layer.to_string(),
+            start: Position::new(x1, y1),
+            end: Position::new(x2, y2),
+            width: 0.15,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    fn square(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Point2D> {
+        vec![Point2D::new(x0, y0), Point2D::new(x1, y0), Point2D::new(x1, y1), Point2D::new(x0, y1)]
+    }
+
+    /// Board-sized zone on B.Cu holding the given fill.
+    fn filled_zone(net: &str, filled: Vec<Vec<Point2D>>) -> Zone {
+        let mut zone = Zone::new(net, "B.Cu", square(0.0, 0.0, 50.0, 50.0));
+        zone.filled = filled;
+        zone
+    }
+
+    fn count(violations: &[DrcViolation], rule: &str) -> usize {
+        violations.iter().filter(|v| v.rule == rule).count()
+    }
+
+    #[test]
+    fn test_diff_pair_skew_and_coupling() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(track("USB_P", "F.Cu", 5.0, 10.0, 30.0, 10.0));
+        layout.traces.push(track("USB_N", "F.Cu", 5.0, 10.3, 20.0, 10.3));
+        // Negative leg detours away from its partner
+        layout.traces.push(track("USB_N", "F.Cu", 20.0, 10.3, 20.0, 15.0));
+        layout.traces.push(track("USB_N", "F.Cu", 20.0, 15.0, 30.0, 15.0));
+        let nets = vec![
+            Net::new("USB_P").with_type(NetType::Differential),
+            Net::new("USB_N").with_type(NetType::Differential),
+        ];
+
+        let checker = HighSpeedChecker::new(&layout, &nets, &[]);
+        assert_eq!(checker.diff_pairs().len(), 1);
+        let violations = checker.check();
+        assert_eq!(count(&violations, "hs.skew"), 1);
+        assert_eq!(count(&violations, "hs.uncoupled_length"), 1);
+        let uncoupled = violations.iter().find(|v| v.rule == "hs.uncoupled_length").unwrap();
+        assert!((uncoupled.actual_value.unwrap() - 14.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_via_count_and_stub_length() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        for (reference, x) in [("U1", 5.0), ("U2", 35.0)] {
+            layout.components.push(
+                PlacedComponent::new(reference, "", "")
+                    .at(x, 10.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 0.5, 0.5).with_net("CLK")),
+            );
+        }
+        layout.traces.push(track("CLK", "F.Cu", 5.0, 10.0, 20.0, 10.0));
+        layout.traces.push(track("CLK", "F.Cu", 20.0, 10.0, 35.0, 10.0));
+        // 4mm branch from the middle of the route
+        layout.traces.push(track("CLK", "F.Cu", 20.0, 10.0, 20.0, 14.0));
+        for x in [10.0, 25.0] {
+            layout.vias.push(Via {
+                net: "CLK".to_string(),
+                position: Position::new(x, 10.0),
+                via_type: ViaType::Through,
+                drill: 0.3,
+                pad: 0.6,
+                start_layer: None,
+                end_layer: None,
+                unit: LengthUnit::Mm,
+            });
+        }
+        let nets = vec![Net::new("CLK").with_type(NetType::Clock)];
+        let constraints = vec![
+            Constraint::max_vias("type:clock", 1),
+            Constraint::stub_length("net:CLK", 2.0, LengthUnit::Mm),
+        ];
+
+        let violations = HighSpeedChecker::new(&layout, &nets, &constraints).check();
+        assert_eq!(count(&violations, "hs.via_count"), 1);
+        let stub = violations.iter().find(|v| v.rule == "hs.stub_length").expect("stub violation");
+        assert!((stub.actual_value.unwrap() - 4.0).abs() < 1e-6);
+        assert_eq!(stub.location, Point2D::new(20.0, 10.0));
+    }
+
+    #[test]
+    fn test_crosstalk_and_reference_plane() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(track("CLK", "F.Cu", 5.0, 10.0, 45.0, 10.0));
+        layout.traces.push(track("DATA", "F.Cu", 5.0, 10.4, 25.0, 10.4));
+        // Ground plane on the next layer, split at x = 30..32
+        for (x0, x1) in [(0.0, 30.0), (32.0, 50.0)] {
+            layout.zones.push(filled_zone("GND", vec![square(x0, 0.0, x1, 50.0)]));
+        }
+        let nets = vec![
+            Net::new("CLK").with_type(NetType::Clock),
+            Net::new("DATA"),
+            Net::new("GND").with_type(NetType::Ground),
+        ];
+        let constraints = vec![Constraint::parallel_run("net:CLK", 0.3, 10.0, LengthUnit::Mm)];
+
+        let violations = HighSpeedChecker::new(&layout, &nets, &constraints).check();
+        let crosstalk = violations.iter().find(|v| v.rule == "hs.crosstalk").expect("crosstalk violation");
+        assert!((crosstalk.actual_value.unwrap() - 20.0).abs() < 1e-6);
+        let split = violations.iter().find(|v| v.rule == "hs.reference_plane").expect("split violation");
+        assert_eq!(split.location, Point2D::new(31.0, 10.0));
+        // DATA is not a high-speed net
+        assert_eq!(count(&violations, "hs.reference_plane"), 1);
+    }
+
+    #[test]
+    fn test_reference_plane_follows_fill() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(track("CLK", "F.Cu", 5.0, 10.0, 45.0, 10.0));
+        // The ground zone outline covers the board but its fill has a slot
+        // at x = 20..22 under the clock track
+        layout.zones.push(filled_zone("GND", vec![
+            square(0.0, 0.0, 50.0, 8.0),
+            square(0.0, 8.0, 20.0, 12.0),
+            square(22.0, 8.0, 50.0, 12.0),
+            square(0.0, 12.0, 50.0, 50.0),
+        ]));
+        // A signal pour bridging the slot is no reference
+        layout.zones.push(filled_zone("SIG", vec![square(19.0, 9.0, 23.0, 11.0)]));
+        let mut nets = vec![
+            Net::new("CLK").with_type(NetType::Clock),
+            Net::new("GND").with_type(NetType::Ground),
+            Net::new("SIG"),
+        ];
+
+        let violations = HighSpeedChecker::new(&layout, &nets, &[]).check();
+        let slot = violations.iter().find(|v| v.rule == "hs.reference_plane").expect("slot violation");
+        assert!((slot.actual_value.unwrap() - 2.0).abs() < 1e-6);
+        assert_eq!(slot.location, Point2D::new(21.0, 10.0));
+
+        // Once the pour is a power plane it covers the slot
+        nets[2] = Net::new("SIG").with_type(NetType::Power);
+        let violations = HighSpeedChecker::new(&layout, &nets, &[]).check();
+        assert_eq!(count(&violations, "hs.reference_plane"), 0);
+    }
+}
@@ -0,0 +1,476 @@
+//! Controlled Impedance.
+//!
+//! Closed-form characteristic impedance of microstrip, embedded microstrip,
+//! stripline and coplanar waveguide traces, with edge-coupled differential
+//! impedance for each. Geometry comes from the stackup and the calculators
+//! solve both ways: trace geometry to impedance, and a target impedance to
+//! trace width or pair gap. All lengths are in millimeters.
+//!
+//! Microstrip uses Hammerstad-Jensen with the thickness correction,
+//! stripline Wheeler's finite-thickness formula (asymmetric lines combine
+//! two symmetric ones), coplanar waveguide the zero-thickness conformal
+//! mapping solution, and differential pairs the IPC-2141 coupling factors.
+
+use std::collections::HashMap;
+use std::f64::consts::{E, PI};
+
+use crate::constraint::Constraint;
+use crate::stackup::{Stackup, StackupLayerKind};
+
+/// Frequency used for Dk when none is given (GHz).
+pub const DEFAULT_FREQUENCY_GHZ: f64 = 1.0;
+
+/// Narrowest and widest trace or gap the solver considers (mm).
+const SOLVE_RANGE: (f64, f64) = (0.01, 50.0);
+
+/// Impedance error.
+#[derive(Debug, Clone)]
+pub struct ImpedanceError {
+    /// Error description
+    pub message: String,
+}
+
+impl ImpedanceError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for ImpedanceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Impedance error: {}", self.message)
+    }
+}
+
+impl std::error::Error for ImpedanceError {}
+
+pub type ImpedanceResult<T> = Result<T, ImpedanceError>;
+
+/// Cross-section of a trace and its reference planes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineStructure {
+    /// Surface trace over one plane, air above
+    Microstrip {
+        /// Dielectric height to the plane
+        height: f64,
+        /// Substrate Dk
+        dk: f64,
+    },
+    /// Trace over one plane under a dielectric cover (e.g., solder mask)
+    EmbeddedMicrostrip {
+        /// Dielectric height to the plane
+        height: f64,
+        /// Cover thickness above the trace
+        cover: f64,
+        /// Substrate Dk
+        dk: f64,
+        /// Cover Dk
+        cover_dk: f64,
+    },
+    /// Trace between two planes; symmetric when `above == below`
+    Stripline {
+        /// Dielectric height from the trace to the upper plane
+        above: f64,
+        /// Dielectric height from the trace to the lower plane
+        below: f64,
+        /// Dielectric Dk
+        dk: f64,
+    },
+    /// Surface trace with coplanar ground on both sides
+    CoplanarWaveguide {
+        /// Dielectric height to the plane below
+        height: f64,
+        /// Substrate Dk
+        dk: f64,
+        /// Gap from the trace to the coplanar ground
+        ground_gap: f64,
+        /// Whether a ground plane lies below the trace
+        grounded: bool,
+    },
+}
+
+/// A trace cross-section with its copper thickness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransmissionLine {
+    /// Trace structure
+    pub structure: LineStructure,
+
+    /// Copper thickness
+    pub thickness: f64,
+}
+
+impl TransmissionLine {
+    /// Create a transmission line.
+    pub fn new(structure: LineStructure, thickness: f64) -> Self {
+        Self { structure, thickness }
+    }
+
+    /// Structure of a trace on a stackup copper layer, with the nearest
+    /// copper layers above and below as reference planes. Outer layers
+    /// under solder mask are embedded microstrip.
+    pub fn from_stackup(stackup: &Stackup, layer: &str, frequency_ghz: f64) -> ImpedanceResult<Self> {
+        let copper = stackup
+            .layer(layer)
+            .filter(|l| l.kind == StackupLayerKind::Copper)
+            .ok_or_else(|| ImpedanceError::new(format!("{} is not a copper layer of {}", layer, stackup.name)))?;
+
+        let span = |other: &str| {
+            stackup.dielectric_between(layer, other, frequency_ghz).ok_or_else(|| {
+                ImpedanceError::new(format!("No dielectric data between {} and {}", layer, other))
+            })
+        };
+        let structure = match stackup.adjacent_copper(layer) {
+            (Some(up), Some(down)) => {
+                let (above, below) = (span(&up.name)?, span(&down.name)?);
+                let height = above.height + below.height;
+                // Thickness-weighted, as the field splits between both sides
+                let dk = (above.dk * above.height + below.dk * below.height) / height;
+                LineStructure::Stripline { above: above.height, below: below.height, dk }
+            }
+            (plane, None) | (None, plane) => {
+                let plane = plane.ok_or_else(|| ImpedanceError::new(format!("{} has no reference plane", layer)))?;
+                let substrate = span(&plane.name)?;
+                let position = stackup.layers.iter().position(|l| l.name == layer).unwrap_or(0);
+                // The cover, if any, is on the side away from the plane
+                let outside = if is_above(stackup, &plane.name, layer) {
+                    stackup.layers.get(position + 1)
+                } else {
+                    position.checked_sub(1).and_then(|i| stackup.layers.get(i))
+                };
+                match outside.filter(|l| l.kind == StackupLayerKind::SolderMask) {
+                    Some(mask) => LineStructure::EmbeddedMicrostrip {
+                        height: substrate.height,
+                        cover: mask.thickness,
+                        dk: substrate.dk,
+                        cover_dk: mask.dk(frequency_ghz).unwrap_or(substrate.dk),
+                    },
+                    None => LineStructure::Microstrip { height: substrate.height, dk: substrate.dk },
+                }
+            }
+        };
+        Ok(Self::new(structure, copper.thickness))
+    }
+
+    /// Grounded coplanar waveguide version of a surface trace, or `None`
+    /// for stripline.
+    pub fn coplanar(&self, ground_gap: f64) -> Option<Self> {
+        let (height, dk) = match self.structure {
+            LineStructure::Microstrip { height, dk }
+            | LineStructure::EmbeddedMicrostrip { height, dk, .. }
+            | LineStructure::CoplanarWaveguide { height, dk, .. } => (height, dk),
+            LineStructure::Stripline { .. } => return None,
+        };
+        let structure = LineStructure::CoplanarWaveguide { height, dk, ground_gap, grounded: true };
+        Some(Self::new(structure, self.thickness))
+    }
+
+    /// Characteristic impedance of a trace (ohms).
+    pub fn z0(&self, width: f64) -> f64 {
+        self.solve(width).0
+    }
+
+    /// Effective dielectric constant seen by a trace.
+    pub fn effective_dk(&self, width: f64) -> f64 {
+        self.solve(width).1
+    }
+
+    /// Differential impedance of an edge-coupled pair (ohms).
+    pub fn zdiff(&self, width: f64, gap: f64) -> f64 {
+        let z0 = self.z0(width);
+        match self.structure {
+            LineStructure::Stripline { above, below, .. } => {
+                let b = above + below + self.thickness;
+                2.0 * z0 * (1.0 - 0.347 * (-2.9 * gap / b).exp())
+            }
+            LineStructure::Microstrip { height, .. }
+            | LineStructure::EmbeddedMicrostrip { height, .. }
+            | LineStructure::CoplanarWaveguide { height, .. } => {
+                2.0 * z0 * (1.0 - 0.48 * (-0.96 * gap / height).exp())
+            }
+        }
+    }
+
+    /// Trace width for a characteristic impedance, or `None` when the
+    /// target is out of reach.
+    pub fn width_for_z0(&self, target: f64) -> Option<f64> {
+        bisect(|w| self.z0(w), target, true)
+    }
+
+    /// Trace width for a differential impedance at a fixed gap.
+    pub fn width_for_zdiff(&self, target: f64, gap: f64) -> Option<f64> {
+        bisect(|w| self.zdiff(w, gap), target, true)
+    }
+
+    /// Pair gap for a differential impedance at a fixed trace width.
+    pub fn gap_for_zdiff(&self, target: f64, width: f64) -> Option<f64> {
+        bisect(|s| self.zdiff(width, s), target, false)
+    }
+
+    /// Impedance and effective Dk.
+    fn solve(&self, width: f64) -> (f64, f64) {
+        let t = self.thickness;
+        match self.structure {
+            LineStructure::Microstrip { height, dk } => microstrip(width, height, t, dk),
+            LineStructure::EmbeddedMicrostrip { height, cover, dk, cover_dk } => {
+                let (z0, eeff) = microstrip(width, height, t, dk);
+                let buried = 1.0 - (-2.0 * cover / height).exp();
+                let covered = eeff * (1.0 - buried) + (dk + cover_dk) / 2.0 * buried;
+                (z0 * (eeff / covered).sqrt(), covered)
+            }
+            LineStructure::Stripline { above, below, dk } => {
+                let z1 = stripline(width, 2.0 * above + t, t, dk);
+                let z2 = stripline(width, 2.0 * below + t, t, dk);
+                (2.0 * z1 * z2 / (z1 + z2), dk)
+            }
+            LineStructure::CoplanarWaveguide { height, dk, ground_gap, grounded } => {
+                coplanar(width, ground_gap, height, dk, grounded)
+            }
+        }
+    }
+}
+
+/// Whether layer `a` lies above layer `b` in the stackup.
+fn is_above(stackup: &Stackup, a: &str, b: &str) -> bool {
+    let index = |name: &str| stackup.layers.iter().position(|l| l.name == name);
+    index(a) < index(b)
+}
+
+/// Microstrip impedance and effective Dk (Hammerstad-Jensen).
+fn microstrip(w: f64, h: f64, t: f64, er: f64) -> (f64, f64) {
+    let u = w / h;
+    let (du1, dur) = if t > 0.0 {
+        let tn = t / h;
+        let coth = 1.0 / (6.517 * u).sqrt().tanh();
+        let du1 = tn / PI * (1.0 + 4.0 * E / (tn * coth * coth)).ln();
+        (du1, 0.5 * (1.0 + 1.0 / (er - 1.0).sqrt().cosh()) * du1)
+    } else {
+        (0.0, 0.0)
+    };
+    let (u1, ur) = (u + du1, u + dur);
+
+    let z01 = |x: f64| {
+        let f = 6.0 + (2.0 * PI - 6.0) * (-(30.666 / x).powf(0.7528)).exp();
+        60.0 * (f / x + (1.0 + 4.0 / (x * x)).sqrt()).ln()
+    };
+    let eeff = |x: f64| {
+        let a = 1.0
+            + ((x.powi(4) + (x / 52.0).powi(2)) / (x.powi(4) + 0.432)).ln() / 49.0
+            + (1.0 + (x / 18.1).powi(3)).ln() / 18.7;
+        let b = 0.564 * ((er - 0.9) / (er + 3.0)).powf(0.053);
+        (er + 1.0) / 2.0 + (er - 1.0) / 2.0 * (1.0 + 10.0 / x).powf(-a * b)
+    };
+
+    let z0 = z01(ur) / eeff(ur).sqrt();
+    let effective = eeff(ur) * (z01(u1) / z01(ur)).powi(2);
+    (z0, effective)
+}
+
+/// Symmetric stripline impedance for plane spacing `b` (Wheeler).
+fn stripline(w: f64, b: f64, t: f64, er: f64) -> f64 {
+    let dw = if t > 0.0 {
+        let x = t / b;
+        let m = 2.0 / (1.0 + 2.0 * x / (3.0 * (1.0 - x)));
+        x / (PI * (1.0 - x))
+            * (1.0 - 0.5 * ((x / (2.0 - x)).powi(2) + (0.0796 * x / (w / b + 1.1 * x)).powf(m)).ln())
+    } else {
+        0.0
+    };
+    let wp = w / (b - t) + dw;
+    let k = 8.0 / (PI * wp);
+    30.0 / er.sqrt() * (1.0 + 4.0 / (PI * wp) * (k + (k * k + 6.27).sqrt())).ln()
+}
+
+/// Coplanar waveguide impedance and effective Dk (conformal mapping).
+fn coplanar(w: f64, s: f64, h: f64, er: f64, grounded: bool) -> (f64, f64) {
+    let k = w / (w + 2.0 * s);
+    let ratio = |k: f64| elliptic_k(k) / elliptic_k((1.0 - k * k).sqrt());
+    if grounded {
+        let k3 = (PI * w / (4.0 * h)).tanh() / (PI * (w + 2.0 * s) / (4.0 * h)).tanh();
+        let (q1, q3) = (ratio(k), ratio(k3));
+        let eeff = (1.0 + er * q3 / q1) / (1.0 + q3 / q1);
+        (60.0 * PI / eeff.sqrt() / (q1 + q3), eeff)
+    } else {
+        let k1 = (PI * w / (4.0 * h)).sinh() / (PI * (w + 2.0 * s) / (4.0 * h)).sinh();
+        let eeff = 1.0 + (er - 1.0) / 2.0 * ratio(k1) / ratio(k);
+        (30.0 * PI / eeff.sqrt() / ratio(k), eeff)
+    }
+}
+
+/// Complete elliptic integral of the first kind, by the arithmetic-geometric
+/// mean.
+fn elliptic_k(k: f64) -> f64 {
+    let (mut a, mut b) = (1.0, (1.0 - k * k).sqrt());
+    for _ in 0..64 {
+        if (a - b).abs() < 1e-15 {
+            break;
+        }
+        (a, b) = ((a + b) / 2.0, (a * b).sqrt());
+    }
+    PI / (2.0 * a)
+}
+
+/// Solve `f(x) = target` over the solver range, for `f` decreasing or
+/// increasing in `x`.
+fn bisect(f: impl Fn(f64) -> f64, target: f64, decreasing: bool) -> Option<f64> {
+    let (mut lo, mut hi) = SOLVE_RANGE;
+    let (f_lo, f_hi) = (f(lo), f(hi));
+    let (min, max) = (f_lo.min(f_hi), f_lo.max(f_hi));
+    if !(min..=max).contains(&target) {
+        return None;
+    }
+    for _ in 0..100 {
+        let mid = (lo * hi).sqrt();
+        if (f(mid) > target) == decreasing {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some((lo * hi).sqrt())
+}
+
+/// Impedance target for a net class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImpedanceTarget {
+    /// Single-ended characteristic impedance (ohms)
+    Single(f64),
+    /// Differential impedance (ohms) at a fixed pair gap (mm)
+    Differential {
+        /// Target impedance
+        impedance: f64,
+        /// Edge-to-edge gap
+        gap: f64,
+    },
+}
+
+impl ImpedanceTarget {
+    /// Target of a differential pair constraint, routed at `gap`.
+    pub fn from_constraint(constraint: &Constraint, gap: f64) -> Option<Self> {
+        match constraint {
+            Constraint::DifferentialPair { impedance, .. } => {
+                Some(ImpedanceTarget::Differential { impedance: *impedance, gap })
+            }
+            _ => None,
+        }
+    }
+
+    /// Trace width meeting the target on each stackup copper layer with a
+    /// reference plane. Layers where the target is out of reach are left
+    /// out.
+    pub fn layer_widths(&self, stackup: &Stackup, frequency_ghz: f64) -> Vec<LayerWidth> {
+        stackup
+            .copper_layer_names()
+            .into_iter()
+            .filter_map(|layer| {
+                let line = TransmissionLine::from_stackup(stackup, &layer, frequency_ghz).ok()?;
+                let (width, impedance) = match *self {
+                    ImpedanceTarget::Single(z0) => {
+                        let width = line.width_for_z0(z0)?;
+                        (width, line.z0(width))
+                    }
+                    ImpedanceTarget::Differential { impedance, gap } => {
+                        let width = line.width_for_zdiff(impedance, gap)?;
+                        (width, line.zdiff(width, gap))
+                    }
+                };
+                Some(LayerWidth { layer, width, impedance })
+            })
+            .collect()
+    }
+}
+
+/// Trace width solved for one layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerWidth {
+    /// Copper layer name
+    pub layer: String,
+
+    /// Trace width (mm)
+    pub width: f64,
+
+    /// Impedance at that width (ohms)
+    pub impedance: f64,
+}
+
+/// Layer to width map of solved widths, for routing.
+pub fn width_map(widths: &[LayerWidth]) -> HashMap<String, f64> {
+    widths.iter().map(|w| (w.layer.clone(), w.width)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tolerance: f64) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn test_single_ended_structures() {
+        // Reference values from common field-solver results
+        let microstrip = TransmissionLine::new(LineStructure::Microstrip { height: 0.2, dk: 4.2 }, 0.035);
+        let z0 = microstrip.z0(0.35);
+        assert!(close(z0, 50.0, 2.5), "microstrip {}", z0);
+        assert!(microstrip.effective_dk(0.35) > 2.6 && microstrip.effective_dk(0.35) < 4.2);
+
+        let covered = TransmissionLine::new(
+            LineStructure::EmbeddedMicrostrip { height: 0.2, cover: 0.02, dk: 4.2, cover_dk: 3.8 },
+            0.035,
+        );
+        assert!(covered.z0(0.35) < z0 && covered.z0(0.35) > z0 - 4.0);
+
+        let stripline = TransmissionLine::new(LineStructure::Stripline { above: 0.2, below: 0.2, dk: 4.2 }, 0.0175);
+        let z0 = stripline.z0(0.15);
+        assert!(close(z0, 50.0, 3.0), "stripline {}", z0);
+
+        let offset = TransmissionLine::new(LineStructure::Stripline { above: 0.2, below: 0.4, dk: 4.2 }, 0.0175);
+        assert!(offset.z0(0.15) > z0);
+
+        let cpwg = microstrip.coplanar(0.15).unwrap();
+        assert!(cpwg.z0(0.35) < microstrip.z0(0.35));
+        let cpw = TransmissionLine::new(
+            LineStructure::CoplanarWaveguide { height: 1.5, dk: 4.5, ground_gap: 0.2, grounded: false },
+            0.0,
+        );
+        // Thick substrate: half the field in air
+        assert!(close(cpw.effective_dk(1.0), 2.75, 0.15), "cpw {}", cpw.effective_dk(1.0));
+        assert!(close(cpw.z0(1.0), 57.0, 2.0), "cpw {}", cpw.z0(1.0));
+        assert!(stripline.coplanar(0.2).is_none());
+    }
+
+    #[test]
+    fn test_solve_for_geometry() {
+        let line = TransmissionLine::new(LineStructure::Microstrip { height: 0.2, dk: 4.2 }, 0.035);
+        let width = line.width_for_z0(50.0).unwrap();
+        assert!(close(line.z0(width), 50.0, 1e-6));
+        assert!(line.width_for_z0(1000.0).is_none());
+
+        let zdiff = line.zdiff(0.2, 0.2);
+        assert!(zdiff < 2.0 * line.z0(0.2));
+        let width = line.width_for_zdiff(90.0, 0.15).unwrap();
+        assert!(close(line.zdiff(width, 0.15), 90.0, 1e-6));
+        let gap = line.gap_for_zdiff(100.0, 0.15).unwrap();
+        assert!(close(line.zdiff(0.15, gap), 100.0, 1e-6));
+    }
+
+    #[test]
+    fn test_stackup_layer_widths() {
+        let stackup = Stackup::preset("4-layer").unwrap();
+        let outer = TransmissionLine::from_stackup(&stackup, "F.Cu", DEFAULT_FREQUENCY_GHZ).unwrap();
+        assert!(matches!(outer.structure, LineStructure::EmbeddedMicrostrip { .. }));
+        let bottom = TransmissionLine::from_stackup(&stackup, "B.Cu", DEFAULT_FREQUENCY_GHZ).unwrap();
+        assert_eq!(bottom, outer);
+        let inner = TransmissionLine::from_stackup(&stackup, "In1.Cu", DEFAULT_FREQUENCY_GHZ).unwrap();
+        assert!(matches!(inner.structure, LineStructure::Stripline { .. }));
+        assert!(TransmissionLine::from_stackup(&stackup, "Core 1", DEFAULT_FREQUENCY_GHZ).is_err());
+
+        let widths = ImpedanceTarget::Single(50.0).layer_widths(&stackup, DEFAULT_FREQUENCY_GHZ);
+        assert_eq!(widths.len(), 4);
+        assert!(widths.iter().all(|w| close(w.impedance, 50.0, 1e-6)));
+
+        let constraint = Constraint::differential_pair("USB_P", "USB_N", 90.0, 10.0);
+        let target = ImpedanceTarget::from_constraint(&constraint, 0.15).unwrap();
+        let widths = width_map(&target.layer_widths(&stackup, DEFAULT_FREQUENCY_GHZ));
+        assert!(widths["F.Cu"] > 0.1 && widths["F.Cu"] < 0.4, "{:?}", widths);
+    }
+}
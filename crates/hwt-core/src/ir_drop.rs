@@ -0,0 +1,809 @@
+//! DC IR-Drop Analysis.
+//!
+//! Voltage drop and current density of power nets on the copper of a
+//! layout. Zone fills and pads are rasterized into square cells of
+//! `mesh_size`, traces become chains of resistors that merge into the cells
+//! they cross, and vias and plated pads connect the layers through their
+//! barrels. Sources hold component pins at a voltage, sinks draw a current
+//! from pins, and the resulting conductance network is solved with
+//! Jacobi-preconditioned conjugate gradients.
+//!
+//! Results are reported per sink pin and per mesh node, as CSV or JSON, and
+//! `SvgGenerator::generate_heat_map` overlays them on the layout.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D};
+use crate::layout::{Layout, Pad};
+use crate::net::{Net, NetType};
+use crate::ratsnest::UnionFind;
+use crate::stackup::{Stackup, COPPER_THICKNESS_PER_OZ};
+
+/// Resistivity of copper at 20 °C (Ω·mm).
+pub const COPPER_RESISTIVITY: f64 = 1.72e-5;
+
+/// Coordinates closer than this share a trace or via node (mm).
+const NODE_TOLERANCE: f64 = 1e-4;
+
+/// IR-drop error.
+#[derive(Debug, Clone)]
+pub struct IrDropError {
+    /// Error description
+    pub message: String,
+}
+
+impl IrDropError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for IrDropError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IR drop error: {}", self.message)
+    }
+}
+
+impl std::error::Error for IrDropError {}
+
+/// Result type for IR-drop analysis.
+pub type IrDropResult<T> = Result<T, IrDropError>;
+
+/// IR-drop settings (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrDropConfig {
+    /// Cell size of the zone and pad mesh
+    pub mesh_size: f64,
+
+    /// Copper resistivity (Ω·mm)
+    pub resistivity: f64,
+
+    /// Copper thickness of layers missing from the stackup
+    pub copper_thickness: f64,
+
+    /// Via and plated hole barrel plating thickness
+    pub via_plating: f64,
+
+    /// Board thickness, spread evenly between copper layers without a
+    /// stackup
+    pub board_thickness: f64,
+
+    /// Relative residual at which the solver stops
+    pub tolerance: f64,
+}
+
+impl Default for IrDropConfig {
+    fn default() -> Self {
+        Self {
+            mesh_size: 0.25,
+            resistivity: COPPER_RESISTIVITY,
+            copper_thickness: COPPER_THICKNESS_PER_OZ,
+            via_plating: 0.025,
+            board_thickness: 1.6,
+            tolerance: 1e-10,
+        }
+    }
+}
+
+/// A component pin held at a voltage or drawing a current.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinLoad {
+    /// Component reference
+    pub reference: String,
+
+    /// Pad number
+    pub pin: String,
+
+    /// Voltage for sources (V), current drawn for sinks (A)
+    pub value: f64,
+}
+
+/// Quantity shown on a heat map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IrDropQuantity {
+    /// Node voltage (V)
+    Voltage,
+    /// Drop below the source voltage (V)
+    Drop,
+    /// Current density (A/mm²)
+    CurrentDensity,
+}
+
+impl IrDropQuantity {
+    /// Display name with unit.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Voltage => "Voltage (V)",
+            Self::Drop => "Drop (V)",
+            Self::CurrentDensity => "Current density (A/mm²)",
+        }
+    }
+}
+
+/// Solution at one mesh node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeshNode {
+    /// Copper layer
+    pub layer: String,
+
+    /// Position (mm); the center of mesh cells
+    pub position: Point2D,
+
+    /// Whether the node is a mesh cell rather than a trace or via point
+    pub cell: bool,
+
+    /// Voltage (V)
+    pub voltage: f64,
+
+    /// Highest current density through the node's connections (A/mm²)
+    pub current_density: f64,
+}
+
+/// Voltage at a sink pin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinVoltage {
+    /// Component reference
+    pub reference: String,
+
+    /// Pad number
+    pub pin: String,
+
+    /// Current drawn (A)
+    pub current: f64,
+
+    /// Voltage (V)
+    pub voltage: f64,
+
+    /// Drop below the source voltage (V)
+    pub drop: f64,
+}
+
+/// IR-drop solution of one net.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrDropReport {
+    /// Net name
+    pub net: String,
+
+    /// Mesh cell size (mm)
+    pub cell_size: f64,
+
+    /// Highest source voltage (V)
+    pub source_voltage: f64,
+
+    /// Lowest node voltage (V)
+    pub min_voltage: f64,
+
+    /// Largest drop below the source voltage (V)
+    pub max_drop: f64,
+
+    /// Total sink current (A)
+    pub total_current: f64,
+
+    /// Power dissipated in the copper (W)
+    pub power_loss: f64,
+
+    /// Highest current density (A/mm²)
+    pub max_current_density: f64,
+
+    /// Sink pins
+    pub sinks: Vec<PinVoltage>,
+
+    /// Mesh nodes connected to a source
+    pub nodes: Vec<MeshNode>,
+}
+
+impl IrDropReport {
+    /// Value of a quantity at a node.
+    pub fn value(&self, node: &MeshNode, quantity: IrDropQuantity) -> f64 {
+        match quantity {
+            IrDropQuantity::Voltage => node.voltage,
+            IrDropQuantity::Drop => self.source_voltage - node.voltage,
+            IrDropQuantity::CurrentDensity => node.current_density,
+        }
+    }
+
+    /// Mesh nodes as CSV.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("layer,x,y,voltage,drop,current_density\n");
+        for node in &self.nodes {
+            writeln!(csv, "{},{:.4},{:.4},{:.6},{:.6},{:.4}",
+                node.layer, node.position.x, node.position.y, node.voltage,
+                self.source_voltage - node.voltage, node.current_density).unwrap();
+        }
+        csv
+    }
+
+    /// Report as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// DC IR-drop solver.
+pub struct IrDropSolver<'a> {
+    layout: &'a Layout,
+    stackup: Option<&'a Stackup>,
+    config: IrDropConfig,
+    sources: Vec<PinLoad>,
+    sinks: Vec<PinLoad>,
+}
+
+impl<'a> IrDropSolver<'a> {
+    /// Create a solver.
+    pub fn new(layout: &'a Layout, config: IrDropConfig) -> Self {
+        Self { layout, stackup: None, config, sources: Vec::new(), sinks: Vec::new() }
+    }
+
+    /// Take copper thicknesses and layer depths from a stackup.
+    pub fn with_stackup(mut self, stackup: &'a Stackup) -> Self {
+        self.stackup = Some(stackup);
+        self
+    }
+
+    /// Hold a pin at `voltage` (V).
+    pub fn with_source(mut self, reference: &str, pin: &str, voltage: f64) -> Self {
+        self.sources.push(PinLoad { reference: reference.to_string(), pin: pin.to_string(), value: voltage });
+        self
+    }
+
+    /// Draw `current` (A) from a pin.
+    pub fn with_sink(mut self, reference: &str, pin: &str, current: f64) -> Self {
+        self.sinks.push(PinLoad { reference: reference.to_string(), pin: pin.to_string(), value: current });
+        self
+    }
+
+    /// Solve every power net with a source pin, in the given order.
+    pub fn solve_power_nets(&self, nets: &[Net]) -> IrDropResult<Vec<IrDropReport>> {
+        let mut reports = Vec::new();
+        for net in nets.iter().filter(|n| n.net_type == NetType::Power) {
+            if !self.pins_on(&self.sources, &net.name)?.is_empty() {
+                reports.push(self.solve(&net.name)?);
+            }
+        }
+        Ok(reports)
+    }
+
+    /// Solve one net.
+    pub fn solve(&self, net: &str) -> IrDropResult<IrDropReport> {
+        let sources = self.pins_on(&self.sources, net)?;
+        if sources.is_empty() {
+            return Err(IrDropError::new(format!("net {} has no voltage source", net)));
+        }
+        let sinks = self.pins_on(&self.sinks, net)?;
+
+        let mut mesh = self.mesh(net)?;
+        let source_nodes: Vec<(usize, f64)> = sources.iter()
+            .map(|(load, pad)| (mesh.pin_node(pad), load.value))
+            .collect();
+        let sink_nodes: Vec<(usize, f64)> = sinks.iter()
+            .map(|(load, pad)| (mesh.pin_node(pad), load.value))
+            .collect();
+
+        let n = mesh.nodes.len();
+        let mut fixed: Vec<Option<f64>> = vec![None; n];
+        for &(node, voltage) in &source_nodes {
+            fixed[node] = Some(voltage);
+        }
+        let mut injected = vec![0.0; n];
+        for &(node, current) in &sink_nodes {
+            injected[node] -= current;
+        }
+
+        // Only copper connected to a source has a defined voltage
+        let mut components = UnionFind::new(n);
+        for edge in &mesh.edges {
+            components.union(edge.a, edge.b);
+        }
+        let mut sourced = vec![false; n];
+        for &(node, _) in &source_nodes {
+            let root = components.find(node);
+            sourced[root] = true;
+        }
+        let active: Vec<bool> = (0..n).map(|i| sourced[components.find(i)]).collect();
+        for ((load, _), &(node, _)) in sinks.iter().zip(&sink_nodes) {
+            if !active[node] {
+                return Err(IrDropError::new(format!("sink {}.{} is not connected to a source of {}", load.reference, load.pin, net)));
+            }
+        }
+
+        let voltages = self.solve_network(&mesh, &fixed, &injected, &active)?;
+
+        // Edge currents and node current densities
+        let mut density = vec![0.0f64; n];
+        let mut power_loss = 0.0;
+        for edge in &mesh.edges {
+            if !active[edge.a] {
+                continue;
+            }
+            let dv = voltages[edge.a] - voltages[edge.b];
+            let current = edge.conductance * dv;
+            power_loss += current * dv;
+            let j = current.abs() / edge.area;
+            density[edge.a] = density[edge.a].max(j);
+            density[edge.b] = density[edge.b].max(j);
+        }
+
+        let source_voltage = source_nodes.iter().map(|s| s.1).fold(f64::NEG_INFINITY, f64::max);
+        let layers = std::mem::take(&mut mesh.layers);
+        let nodes: Vec<MeshNode> = mesh.nodes.iter().enumerate()
+            .filter(|(i, _)| active[*i])
+            .map(|(i, node)| MeshNode {
+                layer: layers[node.layer].clone(),
+                position: node.position,
+                cell: node.cell,
+                voltage: voltages[i],
+                current_density: density[i],
+            })
+            .collect();
+        let min_voltage = nodes.iter().map(|n| n.voltage).fold(f64::INFINITY, f64::min);
+
+        Ok(IrDropReport {
+            net: net.to_string(),
+            cell_size: mesh.size,
+            source_voltage,
+            min_voltage,
+            max_drop: source_voltage - min_voltage,
+            total_current: sinks.iter().map(|(load, _)| load.value).sum(),
+            power_loss,
+            max_current_density: density.iter().copied().fold(0.0, f64::max),
+            sinks: sinks.iter().zip(&sink_nodes)
+                .map(|((load, _), &(node, current))| PinVoltage {
+                    reference: load.reference.clone(),
+                    pin: load.pin.clone(),
+                    current,
+                    voltage: voltages[node],
+                    drop: source_voltage - voltages[node],
+                })
+                .collect(),
+            nodes,
+        })
+    }
+
+    /// Pins of `loads` on a net, with their pad copper.
+    fn pins_on<'l>(&self, loads: &'l [PinLoad], net: &str) -> IrDropResult<Vec<(&'l PinLoad, PadCopper)>> {
+        let mut pins = Vec::new();
+        for load in loads {
+            let component = self.layout.component(&load.reference)
+                .ok_or_else(|| IrDropError::new(format!("component {} not found", load.reference)))?;
+            let pad = component.pads.iter().find(|p| p.number == load.pin)
+                .ok_or_else(|| IrDropError::new(format!("pin {}.{} not found", load.reference, load.pin)))?;
+            if pad.net.as_deref() == Some(net) {
+                pins.push((load, PadCopper::new(pad, component.pad_position(pad), component.rotation, &self.copper_layers())));
+            }
+        }
+        Ok(pins)
+    }
+
+    fn copper_layers(&self) -> Vec<String> {
+        self.layout.copper_layer_names()
+    }
+
+    /// Depth of the middle of each copper layer (mm).
+    fn depths(&self, layers: &[String]) -> Vec<f64> {
+        let step = self.config.board_thickness / (layers.len().max(2) - 1) as f64;
+        layers.iter().enumerate()
+            .map(|(i, layer)| {
+                self.stackup
+                    .and_then(|s| s.layer_depth(layer))
+                    .map(|(top, bottom)| (top + bottom) / 2.0)
+                    .unwrap_or(i as f64 * step)
+            })
+            .collect()
+    }
+
+    /// Build the conductance network of a net's copper.
+    fn mesh(&self, net: &str) -> IrDropResult<Mesh> {
+        let layers = self.copper_layers();
+        let thickness: Vec<f64> = layers.iter()
+            .map(|l| self.layout.copper_thickness(l, self.stackup).unwrap_or(self.config.copper_thickness))
+            .collect();
+        let depths = self.depths(&layers);
+        let rho = self.config.resistivity;
+        let size = self.config.mesh_size.max(1e-3);
+
+        let zones: Vec<(usize, Vec<Vec<Point2D>>)> = self.layout.zones.iter()
+            .filter(|z| z.net == net)
+            .filter_map(|z| {
+                let layer = layers.iter().position(|l| *l == z.layer)?;
+                let rings = z.filled.iter()
+                    .map(|ring| ring.iter().map(|p| Point2D::new(z.unit.to_mm(p.x), z.unit.to_mm(p.y))).collect())
+                    .collect();
+                Some((layer, rings))
+            })
+            .collect();
+        let pads: Vec<PadCopper> = self.layout.components.iter()
+            .flat_map(|c| c.pads.iter().map(move |pad| (c, pad)))
+            .filter(|(_, pad)| pad.net.as_deref() == Some(net))
+            .map(|(c, pad)| PadCopper::new(pad, c.pad_position(pad), c.rotation, &layers))
+            .collect();
+
+        let mut points: Vec<Point2D> = zones.iter().flat_map(|(_, rings)| rings.iter().flatten().copied()).collect();
+        points.extend(pads.iter().flat_map(|p| [p.bounds.min, p.bounds.max]));
+        let origin = BoundingBox::from_points(&points).map_or(Point2D::new(0.0, 0.0), |b| b.min);
+        let mut mesh = Mesh {
+            layers: layers.clone(),
+            origin,
+            size,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            cells: HashMap::new(),
+            points: HashMap::new(),
+        };
+
+        // Zone fills and pads as cells
+        for (layer, rings) in &zones {
+            for ring in rings {
+                let Some(bounds) = BoundingBox::from_points(ring) else { continue };
+                mesh.cover(*layer, &bounds, |p| point_in_polygon(p, ring));
+            }
+        }
+        for pad in &pads {
+            for &layer in &pad.layers {
+                mesh.cover(layer, &pad.bounds, |p| pad.contains(p));
+                mesh.add_cell(layer, mesh.cell_of(&pad.center));
+            }
+        }
+        for a in 0..mesh.nodes.len() {
+            let node = &mesh.nodes[a];
+            let (layer, (i, j)) = (node.layer, mesh.cell_of(&node.position));
+            for neighbour in [(i + 1, j), (i, j + 1)] {
+                if let Some(&b) = mesh.cells.get(&(layer, neighbour.0, neighbour.1)) {
+                    let t = thickness[layer];
+                    mesh.connect(a, b, t / rho, size * t);
+                }
+            }
+        }
+
+        // Traces as resistor chains
+        for trace in self.layout.traces.iter().filter(|t| t.net == net) {
+            let Some(layer) = layers.iter().position(|l| *l == trace.layer) else { continue };
+            let (start, end) = (trace.start.to_mm(), trace.end.to_mm());
+            let width = trace.unit.to_mm(trace.width);
+            let length = start.distance(&end);
+            let steps = (length / size).ceil().max(1.0) as usize;
+            let step = length / steps as f64;
+            let area = width * thickness[layer];
+            let mut previous = mesh.node_at(layer, start);
+            for k in 1..=steps {
+                let f = k as f64 / steps as f64;
+                let p = Point2D::new(start.x + (end.x - start.x) * f, start.y + (end.y - start.y) * f);
+                let node = mesh.node_at(layer, p);
+                if node != previous && step > 0.0 {
+                    mesh.connect(previous, node, area / (rho * step), area);
+                }
+                previous = node;
+            }
+        }
+
+        // Via and plated hole barrels between the layers they span
+        let plating = self.config.via_plating;
+        let mut barrels: Vec<(Point2D, f64, Vec<usize>)> = self.layout.vias.iter()
+            .filter(|v| v.net == net)
+            .map(|v| {
+                let spanned = v.copper_layers(&layers);
+                let indices = spanned.iter().filter_map(|l| layers.iter().position(|x| x == l)).collect();
+                (v.position.to_mm(), v.unit.to_mm(v.drill), indices)
+            })
+            .collect();
+        barrels.extend(pads.iter().filter(|p| p.drill > 0.0).map(|p| (p.center, p.drill, p.layers.clone())));
+        for (position, drill, spanned) in barrels {
+            let area = std::f64::consts::PI * (drill + plating) * plating;
+            for pair in spanned.windows(2) {
+                let length = (depths[pair[1]] - depths[pair[0]]).abs().max(1e-3);
+                let a = mesh.node_at(pair[0], position);
+                let b = mesh.node_at(pair[1], position);
+                mesh.connect(a, b, area / (rho * length), area);
+            }
+        }
+
+        if mesh.nodes.is_empty() {
+            return Err(IrDropError::new(format!("net {} has no copper", net)));
+        }
+        Ok(mesh)
+    }
+
+    /// Node voltages of the active nodes, with `fixed` voltages at sources
+    /// and `injected` currents elsewhere.
+    fn solve_network(&self, mesh: &Mesh, fixed: &[Option<f64>], injected: &[f64], active: &[bool]) -> IrDropResult<Vec<f64>> {
+        let n = mesh.nodes.len();
+        let mut neighbours: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+        for edge in &mesh.edges {
+            neighbours[edge.a].push((edge.b, edge.conductance));
+            neighbours[edge.b].push((edge.a, edge.conductance));
+        }
+
+        let mut voltages: Vec<f64> = fixed.iter().map(|v| v.unwrap_or(0.0)).collect();
+        let free: Vec<usize> = (0..n).filter(|&i| active[i] && fixed[i].is_none()).collect();
+        if free.is_empty() {
+            return Ok(voltages);
+        }
+        let mut slot = vec![usize::MAX; n];
+        for (k, &i) in free.iter().enumerate() {
+            slot[i] = k;
+        }
+        let diagonal: Vec<f64> = free.iter().map(|&i| neighbours[i].iter().map(|e| e.1).sum()).collect();
+        let rhs: Vec<f64> = free.iter()
+            .map(|&i| injected[i] + neighbours[i].iter().filter_map(|&(j, g)| fixed[j].map(|v| g * v)).sum::<f64>())
+            .collect();
+        let multiply = |x: &[f64], y: &mut [f64]| {
+            for (k, &i) in free.iter().enumerate() {
+                let coupled: f64 = neighbours[i].iter()
+                    .filter(|(j, _)| slot[*j] != usize::MAX)
+                    .map(|&(j, g)| g * x[slot[j]])
+                    .sum();
+                y[k] = diagonal[k] * x[k] - coupled;
+            }
+        };
+
+        let size = free.len();
+        let mut x = vec![0.0; size];
+        let mut r = rhs.clone();
+        let mut z: Vec<f64> = r.iter().zip(&diagonal).map(|(r, d)| r / d).collect();
+        let mut p = z.clone();
+        let mut ap = vec![0.0; size];
+        let mut rz: f64 = r.iter().zip(&z).map(|(a, b)| a * b).sum();
+        let norm = rhs.iter().map(|v| v * v).sum::<f64>().sqrt().max(1e-30);
+        let mut converged = false;
+        for _ in 0..(20 * size + 100) {
+            if r.iter().map(|v| v * v).sum::<f64>().sqrt() <= self.config.tolerance * norm {
+                converged = true;
+                break;
+            }
+            multiply(&p, &mut ap);
+            let alpha = rz / p.iter().zip(&ap).map(|(a, b)| a * b).sum::<f64>();
+            for k in 0..size {
+                x[k] += alpha * p[k];
+                r[k] -= alpha * ap[k];
+                z[k] = r[k] / diagonal[k];
+            }
+            let next: f64 = r.iter().zip(&z).map(|(a, b)| a * b).sum();
+            let beta = next / rz;
+            rz = next;
+            for k in 0..size {
+                p[k] = z[k] + beta * p[k];
+            }
+        }
+        if !converged {
+            return Err(IrDropError::new("solver did not converge"));
+        }
+        for (k, &i) in free.iter().enumerate() {
+            voltages[i] = x[k];
+        }
+        Ok(voltages)
+    }
+}
+
+/// Pad copper in board coordinates.
+struct PadCopper {
+    center: Point2D,
+    half: (f64, f64),
+    /// Rotation of the pad (degrees)
+    rotation: f64,
+    bounds: BoundingBox,
+    drill: f64,
+    /// Copper layer indices, top to bottom
+    layers: Vec<usize>,
+}
+
+impl PadCopper {
+    fn new(pad: &Pad, center: Point2D, rotation: f64, copper: &[String]) -> Self {
+        let layers = (0..copper.len())
+            .filter(|&i| pad.layers.iter().any(|l| *l == copper[i] || l == "*.Cu"))
+            .collect();
+        let half = (pad.size.0 / 2.0, pad.size.1 / 2.0);
+        let reach = half.0.hypot(half.1);
+        Self {
+            center,
+            half,
+            rotation,
+            bounds: BoundingBox::new(
+                Point2D::new(center.x - reach, center.y - reach),
+                Point2D::new(center.x + reach, center.y + reach),
+            ),
+            drill: pad.drill,
+            layers,
+        }
+    }
+
+    fn contains(&self, p: &Point2D) -> bool {
+        let (sin, cos) = (-self.rotation).to_radians().sin_cos();
+        let (dx, dy) = (p.x - self.center.x, p.y - self.center.y);
+        let (u, v) = (dx * cos - dy * sin, dx * sin + dy * cos);
+        u.abs() <= self.half.0 && v.abs() <= self.half.1
+    }
+}
+
+struct MeshPoint {
+    layer: usize,
+    position: Point2D,
+    cell: bool,
+}
+
+struct Edge {
+    a: usize,
+    b: usize,
+    /// Conductance (S)
+    conductance: f64,
+    /// Conducting cross-section (mm²)
+    area: f64,
+}
+
+struct Mesh {
+    layers: Vec<String>,
+    origin: Point2D,
+    size: f64,
+    nodes: Vec<MeshPoint>,
+    edges: Vec<Edge>,
+    /// Cell nodes by layer and grid index
+    cells: HashMap<(usize, i64, i64), usize>,
+    /// Trace and via nodes by layer and rounded position
+    points: HashMap<(usize, i64, i64), usize>,
+}
+
+impl Mesh {
+    fn cell_of(&self, p: &Point2D) -> (i64, i64) {
+        (((p.x - self.origin.x) / self.size).floor() as i64, ((p.y - self.origin.y) / self.size).floor() as i64)
+    }
+
+    fn add_cell(&mut self, layer: usize, (i, j): (i64, i64)) -> usize {
+        if let Some(&node) = self.cells.get(&(layer, i, j)) {
+            return node;
+        }
+        let position = Point2D::new(
+            self.origin.x + (i as f64 + 0.5) * self.size,
+            self.origin.y + (j as f64 + 0.5) * self.size,
+        );
+        self.nodes.push(MeshPoint { layer, position, cell: true });
+        self.cells.insert((layer, i, j), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Add the cells of `bounds` whose centers satisfy `inside`.
+    fn cover(&mut self, layer: usize, bounds: &BoundingBox, inside: impl Fn(&Point2D) -> bool) {
+        let (i0, j0) = self.cell_of(&bounds.min);
+        let (i1, j1) = self.cell_of(&bounds.max);
+        for j in j0..=j1 {
+            for i in i0..=i1 {
+                let center = Point2D::new(
+                    self.origin.x + (i as f64 + 0.5) * self.size,
+                    self.origin.y + (j as f64 + 0.5) * self.size,
+                );
+                if inside(&center) {
+                    self.add_cell(layer, (i, j));
+                }
+            }
+        }
+    }
+
+    /// The cell under a point, or a shared point node where there is none.
+    fn node_at(&mut self, layer: usize, p: Point2D) -> usize {
+        let (i, j) = self.cell_of(&p);
+        if let Some(&node) = self.cells.get(&(layer, i, j)) {
+            return node;
+        }
+        let key = (layer, (p.x / NODE_TOLERANCE).round() as i64, (p.y / NODE_TOLERANCE).round() as i64);
+        if let Some(&node) = self.points.get(&key) {
+            return node;
+        }
+        self.nodes.push(MeshPoint { layer, position: p, cell: false });
+        self.points.insert(key, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Node of a pin: its center cell on the topmost layer of the pad.
+    fn pin_node(&mut self, pad: &PadCopper) -> usize {
+        let layer = pad.layers.first().copied().unwrap_or(0);
+        self.node_at(layer, pad.center)
+    }
+
+    fn connect(&mut self, a: usize, b: usize, conductance: f64, area: f64) {
+        if a != b && conductance.is_finite() && conductance > 0.0 {
+            self.edges.push(Edge { a, b, conductance, area });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{PlacedComponent, Trace, Via, ViaType, Zone};
+    use crate::units::LengthUnit;
+
+    fn part(reference: &str, x: f64, y: f64, net: &str) -> PlacedComponent {
+        PlacedComponent::new(reference, "", "").at(x, y).with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net(net))
+    }
+
+    fn trace(layer: &str, x0: f64, x1: f64, width: f64) -> Trace {
+        Trace {
+            net: "VCC".to_string(),
+            layer: layer.to_string(),
+            start: Position::new(x0, 10.0),
+            end: Position::new(x1, 10.0),
+            width,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn test_trace_drop_matches_resistance() {
+        let mut layout = Layout::with_board_size(40.0, 20.0, LengthUnit::Mm);
+        layout.components.push(part("U1", 5.0, 10.0, "VCC"));
+        layout.components.push(part("U2", 25.0, 10.0, "VCC"));
+        layout.traces.push(trace("F.Cu", 5.0, 25.0, 0.5));
+
+        let report = IrDropSolver::new(&layout, IrDropConfig::default())
+            .with_source("U1", "1", 3.3)
+            .with_sink("U2", "1", 2.0)
+            .solve("VCC")
+            .unwrap();
+
+        // 19 mm of bare 0.5 mm trace between the pad edges dominates
+        let resistance = COPPER_RESISTIVITY * 19.0 / (0.5 * COPPER_THICKNESS_PER_OZ);
+        let drop = report.sinks[0].drop;
+        assert!(drop > 2.0 * resistance && drop < 2.0 * resistance * 1.1, "{} vs {}", drop, 2.0 * resistance);
+        assert!((report.power_loss - 2.0 * drop).abs() < 1e-9);
+        assert!((report.max_current_density - 2.0 / (0.5 * COPPER_THICKNESS_PER_OZ)).abs() < 1e-6);
+        assert!(report.to_csv().lines().count() > 40);
+    }
+
+    #[test]
+    fn test_plane_and_via_drop() {
+        let mut layout = Layout::with_board_size(40.0, 20.0, LengthUnit::Mm);
+        layout.components.push(part("U1", 5.0, 10.0, "VCC"));
+        layout.components.push(part("U2", 35.0, 10.0, "VCC"));
+        let mut plane = Zone::new("VCC", "B.Cu", vec![]);
+        plane.filled = vec![vec![Point2D::new(8.0, 5.0), Point2D::new(32.0, 5.0), Point2D::new(32.0, 15.0), Point2D::new(8.0, 15.0)]];
+        layout.zones.push(plane);
+        layout.traces.push(trace("F.Cu", 5.0, 10.0, 1.0));
+        layout.traces.push(trace("F.Cu", 30.0, 35.0, 1.0));
+        for x in [10.0, 30.0] {
+            layout.vias.push(Via {
+                net: "VCC".to_string(),
+                position: Position::new(x, 10.0),
+                via_type: ViaType::Through,
+                drill: 0.3,
+                pad: 0.6,
+                start_layer: None,
+                end_layer: None,
+                unit: LengthUnit::Mm,
+            });
+        }
+        let vcc = Net::new("VCC").with_type(NetType::Power);
+
+        let reports = IrDropSolver::new(&layout, IrDropConfig::default())
+            .with_source("U1", "1", 5.0)
+            .with_sink("U2", "1", 1.0)
+            .solve_power_nets(&[vcc, Net::new("SIG")])
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+        let report = &reports[0];
+
+        // Two vias plus 2 squares of plane plus two 1 mm traces, within the
+        // spreading resistance around the vias
+        let via = COPPER_RESISTIVITY * 1.6 / (std::f64::consts::PI * 0.325 * 0.025);
+        let sheet = COPPER_RESISTIVITY / COPPER_THICKNESS_PER_OZ;
+        let traces = 2.0 * COPPER_RESISTIVITY * 4.5 / COPPER_THICKNESS_PER_OZ;
+        let lower = 2.0 * via + 2.0 * sheet + traces;
+        assert!(report.max_drop > lower && report.max_drop < 3.0 * lower, "{} vs {}", report.max_drop, lower);
+        assert!(report.nodes.iter().any(|n| n.layer == "B.Cu" && n.cell));
+        assert!((report.source_voltage - report.sinks[0].voltage - report.sinks[0].drop).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_disconnected_sink() {
+        let mut layout = Layout::with_board_size(40.0, 20.0, LengthUnit::Mm);
+        layout.components.push(part("U1", 5.0, 10.0, "VCC"));
+        layout.components.push(part("U2", 25.0, 10.0, "VCC"));
+        layout.traces.push(trace("F.Cu", 5.0, 15.0, 0.5));
+
+        let solver = IrDropSolver::new(&layout, IrDropConfig::default()).with_sink("U2", "1", 1.0);
+        assert!(solver.solve("VCC").unwrap_err().message.contains("no voltage source"));
+        let error = solver.with_source("U1", "1", 3.3).solve("VCC").unwrap_err();
+        assert!(error.message.contains("U2.1"));
+    }
+}
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::component::{Component, Pin, PinType};
 use crate::geometry::{Point2D, Position};
-use crate::layout::{Layout, Layer, LayerType, PlacedComponent, ComponentLayer, Trace, Via, ViaType, Zone, ZoneFillType, Pad, PadShape, PadType};
+use crate::layout::{Layout, Layer, LayerType, PlacedComponent, ComponentLayer, Graphic, GraphicShape, Trace, Via, ViaType, Zone, ZoneFillType, ZoneKind, IslandRemoval, Keepouts, RuleArea, PadConnection, Pad, PadShape, PadType};
 use crate::units::LengthUnit;
 use crate::schematic::{
     Bus, BusSegment, Junction, LabelType, NetLabel, NoConnect, PlacedSymbol, PowerSymbol,
@@ -734,6 +734,11 @@ impl KicadSymbolLibImporter {
     }
 }
 
+/// Whether a list has a bare `hide` atom (pre-7.0 visibility flag).
+fn items_contain_hide(expr: &SExpr) -> bool {
+    expr.as_list().is_some_and(|items| items.iter().any(|e| e.as_atom() == Some("hide")))
+}
+
 /// KiCAD PCB importer.
 pub struct KicadPcbImporter;
 
@@ -797,11 +802,16 @@ impl KicadPcbImporter {
 
         // Parse zones
         for zone_expr in expr.find_all("zone") {
-            if let Ok(zone) = Self::parse_zone(zone_expr) {
+            if let Some(area) = Self::parse_rule_area(zone_expr) {
+                layout.rule_areas.push(area);
+            } else if let Ok(zone) = Self::parse_zone(zone_expr) {
                 layout.zones.push(zone);
             }
         }
 
+        // Parse board drawings on silkscreen layers
+        layout.graphics = Self::parse_graphics(&expr, "gr_");
+
         Ok(layout)
     }
 
@@ -911,6 +921,15 @@ impl KicadPcbImporter {
             }
         }
 
+        // Footprint drawings are relative to the footprint origin; text
+        // angles in the file are absolute
+        let mut graphics = Self::parse_graphics(expr, "fp_");
+        for graphic in &mut graphics {
+            if let GraphicShape::Text { rotation: text_rotation, .. } = &mut graphic.shape {
+                *text_rotation -= rotation;
+            }
+        }
+
         Ok(PlacedComponent {
             id: Uuid::new_v4(),
             reference,
@@ -926,6 +945,7 @@ impl KicadPcbImporter {
             layer: component_layer,
             pads,
             locked: false,
+            graphics,
         })
     }
 
@@ -977,6 +997,14 @@ impl KicadPcbImporter {
             .and_then(|e| e.get_atom(2))
             .map(|s| s.to_string());
 
+        // Parse copper and technical layers
+        let layers = expr.find("layers")
+            .and_then(|e| e.as_list())
+            .map(|list| list.iter().skip(1).filter_map(|l| l.as_atom()).map(|l| l.to_string()).collect())
+            .unwrap_or_default();
+
+        let margin = |tag: &str| expr.find(tag).and_then(|e| e.get_f64(1));
+
         Ok(Pad {
             number,
             name: None,
@@ -986,10 +1014,84 @@ impl KicadPcbImporter {
             size: (width, height),
             drill,
             net,
-            layers: Vec::new(),
+            layers,
+            mask_margin: margin("solder_mask_margin"),
+            paste_margin: margin("solder_paste_margin"),
+            paste_margin_ratio: margin("solder_paste_margin_ratio"),
         })
     }
 
+    /// Parse silkscreen lines, circles, polygons and texts with the given
+    /// tag prefix (`gr_` on the board, `fp_` in footprints).
+    fn parse_graphics(expr: &SExpr, prefix: &str) -> Vec<Graphic> {
+        let mut graphics = Vec::new();
+        let Some(items) = expr.as_list() else { return graphics };
+
+        for item in items {
+            let Some(tag) = item.tag().and_then(|t| t.strip_prefix(prefix)) else { continue };
+            let Some(layer) = item.find("layer").and_then(|e| e.get_atom(1)) else { continue };
+            if !layer.ends_with(".SilkS") {
+                continue;
+            }
+            let point = |name: &str| item.find(name)
+                .map(|e| Point2D::new(e.get_f64(1).unwrap_or(0.0), e.get_f64(2).unwrap_or(0.0)));
+            let width = item.find("stroke")
+                .and_then(|s| s.find("width"))
+                .or_else(|| item.find("width"))
+                .and_then(|e| e.get_f64(1))
+                .unwrap_or(0.0);
+
+            let (shape, width) = match tag {
+                "line" => match (point("start"), point("end")) {
+                    (Some(start), Some(end)) => (GraphicShape::Line { start, end }, width),
+                    _ => continue,
+                },
+                "circle" => match (point("center"), point("end")) {
+                    (Some(center), Some(end)) => (GraphicShape::Circle { center, radius: center.distance(&end) }, width),
+                    _ => continue,
+                },
+                "poly" => {
+                    let points: Vec<Point2D> = item.find("pts")
+                        .map(|pts| pts.find_all("xy").iter()
+                            .map(|xy| Point2D::new(xy.get_f64(1).unwrap_or(0.0), xy.get_f64(2).unwrap_or(0.0)))
+                            .collect())
+                        .unwrap_or_default();
+                    let filled = item.find("fill").and_then(|f| f.get_atom(1)).is_some_and(|f| f == "solid" || f == "yes");
+                    (GraphicShape::Polygon { points }, if filled { 0.0 } else { width })
+                }
+                "text" => {
+                    // fp_text carries a kind before the text; gr_text does not
+                    let text = if prefix == "fp_" { item.get_atom(2) } else { item.get_atom(1) };
+                    let Some(text) = text else { continue };
+                    let effects = item.find("effects");
+                    let hidden = items_contain_hide(item)
+                        || item.find("hide").is_some()
+                        || effects.is_some_and(|e| items_contain_hide(e) || e.find("hide").is_some());
+                    if hidden {
+                        continue;
+                    }
+                    let at = item.find("at");
+                    let font = effects.and_then(|e| e.find("font"));
+                    let height = font.and_then(|f| f.find("size")).and_then(|e| e.get_f64(1)).unwrap_or(1.0);
+                    let thickness = font.and_then(|f| f.find("thickness")).and_then(|e| e.get_f64(1)).unwrap_or(0.15);
+                    let shape = GraphicShape::Text {
+                        text: text.to_string(),
+                        position: Point2D::new(
+                            at.and_then(|e| e.get_f64(1)).unwrap_or(0.0),
+                            at.and_then(|e| e.get_f64(2)).unwrap_or(0.0),
+                        ),
+                        height,
+                        rotation: at.and_then(|e| e.get_f64(3)).unwrap_or(0.0),
+                    };
+                    (shape, thickness)
+                }
+                _ => continue,
+            };
+            graphics.push(Graphic { layer: layer.to_string(), shape, width });
+        }
+        graphics
+    }
+
     /// Parse a trace segment.
     fn parse_segment(expr: &SExpr) -> KicadResult<Trace> {
         let start = if let Some(start_expr) = expr.find("start") {
@@ -1106,6 +1208,55 @@ impl KicadPcbImporter {
         })
     }
 
+    /// Parse the polygon points of a zone outline.
+    fn parse_zone_outline(expr: &SExpr) -> Vec<Point2D> {
+        let mut points = Vec::new();
+        if let Some(polygon_expr) = expr.find("polygon") {
+            if let Some(pts_expr) = polygon_expr.find("pts") {
+                for xy_expr in pts_expr.find_all("xy") {
+                    let x = xy_expr.get_f64(1).unwrap_or(0.0);
+                    let y = xy_expr.get_f64(2).unwrap_or(0.0);
+                    points.push(Point2D::new(x, y));
+                }
+            }
+        }
+        points
+    }
+
+    /// Parse a rule area, a zone with a (keepout ...) section. Returns
+    /// `None` for ordinary zones.
+    fn parse_rule_area(expr: &SExpr) -> Option<RuleArea> {
+        let keepout_expr = expr.find("keepout")?;
+        let not_allowed = |tag: &str| keepout_expr.find(tag).and_then(|e| e.get_atom(1)) == Some("not_allowed");
+        let keepout = Keepouts {
+            tracks: not_allowed("tracks"),
+            vias: not_allowed("vias"),
+            pads: not_allowed("pads"),
+            copper_pour: not_allowed("copperpour"),
+            footprints: not_allowed("footprints"),
+        };
+
+        // (layers "F.Cu" "B.Cu") or (layer "F.Cu"); "*.Cu" covers every
+        // copper layer, which an empty list means here
+        let layer_expr = expr.find("layers").or_else(|| expr.find("layer"));
+        let mut layers = Vec::new();
+        for layer in (1..).map_while(|i| layer_expr.and_then(|e| e.get_atom(i))) {
+            match layer {
+                "*.Cu" => {
+                    layers.clear();
+                    break;
+                }
+                "F&B.Cu" => layers.extend(["F.Cu".to_string(), "B.Cu".to_string()]),
+                _ => layers.push(layer.to_string()),
+            }
+        }
+
+        let name = expr.find("name").and_then(|e| e.get_atom(1)).unwrap_or("");
+        let mut area = RuleArea::new(name, Self::parse_zone_outline(expr)).with_keepout(keepout);
+        area.layers = layers;
+        Some(area)
+    }
+
     /// Parse a copper zone.
     fn parse_zone(expr: &SExpr) -> KicadResult<Zone> {
         let net = expr.find("net_name")
@@ -1118,35 +1269,54 @@ impl KicadPcbImporter {
             .unwrap_or("F.Cu")
             .to_string();
 
-        // Parse polygon points
-        let mut points = Vec::new();
-        if let Some(polygon_expr) = expr.find("polygon") {
-            if let Some(pts_expr) = polygon_expr.find("pts") {
-                for xy_expr in pts_expr.find_all("xy") {
-                    let x = xy_expr.get_f64(1).unwrap_or(0.0);
-                    let y = xy_expr.get_f64(2).unwrap_or(0.0);
-                    points.push(Point2D::new(x, y));
-                }
-            }
-        }
+        let points = Self::parse_zone_outline(expr);
 
         // Parse fill settings
-        let fill_type = if let Some(fill_expr) = expr.find("fill") {
-            if fill_expr.get_atom(1) == Some("yes") {
-                ZoneFillType::Solid
-            } else {
-                ZoneFillType::None
-            }
-        } else {
-            ZoneFillType::Solid
+        let fill_expr = expr.find("fill");
+        let fill_type = match fill_expr {
+            Some(fill) if fill.get_atom(1) != Some("yes") => ZoneFillType::None,
+            Some(fill) if fill.find("mode").and_then(|m| m.get_atom(1)) == Some("hatch") => ZoneFillType::Hatched,
+            _ => ZoneFillType::Solid,
         };
+        let fill_f64 = |tag: &str| fill_expr.and_then(|f| f.find(tag)).and_then(|e| e.get_f64(1));
 
+        // KiCad nests the clearance in (connect_pads [yes|no|thru_hole_only] ...)
+        let connect_pads = expr.find("connect_pads");
         let clearance = expr.find("clearance")
+            .or_else(|| connect_pads.and_then(|c| c.find("clearance")))
             .and_then(|e| e.get_f64(1));
+        let pad_connection = match connect_pads.and_then(|c| c.get_atom(1)) {
+            Some("yes") => PadConnection::Solid,
+            Some("no") => PadConnection::None,
+            _ => PadConnection::Thermal,
+        };
+
+        let island_removal = match fill_f64("island_removal_mode").map(|mode| mode as u32) {
+            Some(1) => IslandRemoval::Never,
+            Some(2) => IslandRemoval::BelowArea(fill_f64("island_area_min").unwrap_or(0.0)),
+            _ => IslandRemoval::Always,
+        };
+
+        // Fill cached by KiCad
+        let filled: Vec<Vec<Point2D>> = expr.find_all("filled_polygon").into_iter()
+            .filter_map(|polygon| polygon.find("pts"))
+            .map(|pts| {
+                pts.find_all("xy").into_iter()
+                    .map(|xy| Point2D::new(xy.get_f64(1).unwrap_or(0.0), xy.get_f64(2).unwrap_or(0.0)))
+                    .collect()
+            })
+            .collect();
 
         let min_thickness = expr.find("min_thickness")
             .and_then(|e| e.get_f64(1));
 
+        // KiCad marks generated teardrops with (attr (teardrop ...))
+        let kind = if expr.find("attr").and_then(|a| a.find("teardrop")).is_some() {
+            ZoneKind::Teardrop
+        } else {
+            ZoneKind::Copper
+        };
+
         Ok(Zone {
             net,
             layer,
@@ -1154,6 +1324,16 @@ impl KicadPcbImporter {
             fill_type,
             clearance,
             min_width: min_thickness,
+            kind,
+            priority: expr.find("priority").and_then(|e| e.get_f64(1)).map_or(0, |p| p as u32),
+            pad_connection,
+            thermal_gap: fill_f64("thermal_gap"),
+            thermal_spoke_width: fill_f64("thermal_bridge_width"),
+            thermal_spokes: None,
+            hatch_width: fill_f64("hatch_thickness"),
+            hatch_gap: fill_f64("hatch_gap"),
+            island_removal,
+            filled,
             unit: LengthUnit::Mm,
         })
     }
@@ -1797,8 +1977,9 @@ mod tests {
   (zone
     (net_name "GND")
     (layer "F.Cu")
-    (fill yes)
-    (clearance 0.3)
+    (priority 2)
+    (connect_pads yes (clearance 0.3))
+    (fill yes (mode hatch) (thermal_gap 0.4) (thermal_bridge_width 0.6) (island_removal_mode 2) (island_area_min 10))
     (min_thickness 0.2)
     (polygon
       (pts
@@ -1808,6 +1989,14 @@ mod tests {
         (xy 0 80)
       )
     )
+    (filled_polygon
+      (layer "F.Cu")
+      (pts
+        (xy 1 1)
+        (xy 99 1)
+        (xy 99 79)
+      )
+    )
   )
 )
 "#;
@@ -1818,6 +2007,97 @@ mod tests {
         assert_eq!(layout.zones[0].net, "GND");
         assert_eq!(layout.zones[0].layer, "F.Cu");
         assert_eq!(layout.zones[0].points.len(), 4);
+        let zone = &layout.zones[0];
+        assert_eq!(zone.clearance, Some(0.3));
+        assert_eq!(zone.priority, 2);
+        assert_eq!(zone.pad_connection, PadConnection::Solid);
+        assert_eq!(zone.fill_type, ZoneFillType::Hatched);
+        assert_eq!(zone.thermal_spoke_width, Some(0.6));
+        assert_eq!(zone.island_removal, IslandRemoval::BelowArea(10.0));
+        assert_eq!(zone.filled, vec![vec![Point2D::new(1.0, 1.0), Point2D::new(99.0, 1.0), Point2D::new(99.0, 79.0)]]);
+    }
+
+    #[test]
+    fn test_import_pcb_rule_area() {
+        let content = r#"
+(kicad_pcb
+  (version 20230121)
+  (generator "pcbnew")
+  
+  (zone
+    (net 0)
+    (net_name "")
+    (layers "F&B.Cu")
+    (name "antenna")
+    (keepout (tracks not_allowed) (vias not_allowed) (pads allowed) (copperpour not_allowed) (footprints allowed))
+    (polygon
+      (pts
+        (xy 10 10)
+        (xy 20 10)
+        (xy 20 20)
+      )
+    )
+  )
+  (zone
+    (layers "*.Cu")
+    (keepout (tracks allowed) (vias allowed) (pads allowed) (copperpour allowed) (footprints not_allowed))
+    (polygon (pts (xy 0 0) (xy 5 0) (xy 5 5)))
+  )
+)
+"#;
+
+        let layout = KicadPcbImporter::import_from_string(content).unwrap();
+        
+        assert!(layout.zones.is_empty());
+        assert_eq!(layout.rule_areas.len(), 2);
+        let area = &layout.rule_areas[0];
+        assert_eq!(area.name, "antenna");
+        assert_eq!(area.layers, vec!["F.Cu", "B.Cu"]);
+        assert_eq!(area.points.len(), 3);
+        assert!(area.keepout.tracks && area.keepout.vias && area.keepout.copper_pour);
+        assert!(!area.keepout.pads && !area.keepout.footprints);
+        assert!(layout.rule_areas[1].layers.is_empty());
+        assert!(layout.rule_areas[1].keepout.footprints);
+    }
+
+    #[test]
+    fn test_import_pcb_silkscreen_and_margins() {
+        let content = r#"
+(kicad_pcb
+  (version 20230121)
+  (generator "pcbnew")
+  (footprint "Resistor_SMD:R_0603"
+    (layer "F.Cu")
+    (at 100 50 90)
+    (fp_text reference "R1" (at 0 -1.5 90) (layer "F.SilkS")
+      (effects (font (size 0.8 0.8) (thickness 0.12))))
+    (fp_text value "10k" (at 0 1.5 90) (layer "F.Fab") hide)
+    (fp_line (start -1 -0.6) (end 1 -0.6) (stroke (width 0.12) (type solid)) (layer "F.SilkS"))
+    (fp_line (start -1 -0.8) (end 1 -0.8) (stroke (width 0.05) (type solid)) (layer "F.CrtYd"))
+    (pad "1" smd roundrect (at -0.8 0) (size 0.8 0.9) (layers "F.Cu" "F.Paste" "F.Mask")
+      (solder_mask_margin 0.03) (solder_paste_margin -0.05) (net 1 "VCC"))
+  )
+  (gr_text "REV A" (at 110 60) (layer "B.SilkS")
+    (effects (font (size 1 1) (thickness 0.15)) (justify mirror)))
+  (gr_circle (center 120 60) (end 121 60) (stroke (width 0.2) (type solid)) (layer "F.SilkS"))
+)
+"#;
+
+        let layout = KicadPcbImporter::import_from_string(content).unwrap();
+        let component = &layout.components[0];
+        assert_eq!(component.graphics.len(), 2);
+        assert!(matches!(component.graphics[0].shape, GraphicShape::Text { rotation, height, .. }
+            if rotation == 0.0 && height == 0.8));
+        assert_eq!(component.graphics[1].width, 0.12);
+
+        let pad = &component.pads[0];
+        assert_eq!(pad.layers, vec!["F.Cu", "F.Paste", "F.Mask"]);
+        assert_eq!(pad.mask_margin, Some(0.03));
+        assert_eq!(pad.paste_margin, Some(-0.05));
+
+        assert_eq!(layout.graphics.len(), 2);
+        assert_eq!(layout.graphics[0].layer, "B.SilkS");
+        assert!(matches!(layout.graphics[1].shape, GraphicShape::Circle { radius, .. } if radius == 1.0));
     }
 
     #[test]
@@ -3,8 +3,11 @@
 //! Physical layout information for PCB, IC, and other domains.
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-use crate::geometry::{Point2D, Position};
+use crate::drc_rules::RuleConstraint;
+use crate::geometry::{polygon_intersects_box, BoundingBox, Point2D, Position};
+use crate::stackup::Stackup;
 use crate::units::LengthUnit;
 
 /// Layout data for a design.
@@ -18,6 +21,10 @@ pub struct Layout {
     #[serde(default)]
     pub layers: Vec<Layer>,
 
+    /// Placed components (footprints)
+    #[serde(default)]
+    pub components: Vec<PlacedComponent>,
+
     /// Traces/routes
     #[serde(default)]
     pub traces: Vec<Trace>,
@@ -29,6 +36,101 @@ pub struct Layout {
     /// Copper zones/fills
     #[serde(default)]
     pub zones: Vec<Zone>,
+
+    /// Allowed via drill spans (only through vias when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drill_pairs: Vec<DrillPair>,
+
+    /// Board-level drawings on non-copper layers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub graphics: Vec<Graphic>,
+
+    /// Keepout and rule areas
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rule_areas: Vec<RuleArea>,
+}
+
+impl Layout {
+    /// Create an empty layout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a layout with a rectangular board outline and the default
+    /// two-layer PCB layer stack.
+    pub fn with_board_size(width: f64, height: f64, unit: LengthUnit) -> Self {
+        Self {
+            outline: Some(Outline {
+                outline_type: OutlineType::Rectangle,
+                points: Vec::new(),
+                width: Some(width),
+                height: Some(height),
+                cutouts: Vec::new(),
+                unit,
+            }),
+            layers: Self::default_pcb_layers(),
+            ..Self::default()
+        }
+    }
+
+    /// Default two-layer PCB layer stack.
+    pub fn default_pcb_layers() -> Vec<Layer> {
+        vec![
+            Layer::new("F.Cu", LayerType::Copper),
+            Layer::new("B.Cu", LayerType::Copper),
+            Layer::new("F.SilkS", LayerType::Silkscreen),
+            Layer::new("B.SilkS", LayerType::Silkscreen),
+            Layer::new("F.Mask", LayerType::SolderMask),
+            Layer::new("B.Mask", LayerType::SolderMask),
+            Layer::new("F.Paste", LayerType::Paste),
+            Layer::new("B.Paste", LayerType::Paste),
+            Layer::new("F.CrtYd", LayerType::Courtyard),
+            Layer::new("B.CrtYd", LayerType::Courtyard),
+            Layer::new("Edge.Cuts", LayerType::Fabrication),
+        ]
+    }
+
+    /// Names of the copper layers, in stack order (top to bottom).
+    pub fn copper_layer_names(&self) -> Vec<String> {
+        self.layers
+            .iter()
+            .filter(|l| l.layer_type == LayerType::Copper)
+            .map(|l| l.name.clone())
+            .collect()
+    }
+
+    /// Copper thickness of a layer (mm): from `stackup` when it has the
+    /// layer as copper, else from the layout layer.
+    pub fn copper_thickness(&self, layer: &str, stackup: Option<&Stackup>) -> Option<f64> {
+        stackup
+            .and_then(|s| s.copper_thickness(layer))
+            .or_else(|| self.layers.iter().find(|l| l.name == layer).and_then(|l| l.thickness))
+    }
+
+    /// Drill pairs in effect: the defined pairs, or a single through span
+    /// with the given via size when none are defined.
+    pub fn effective_drill_pairs(&self, via_drill: f64, via_pad: f64) -> Vec<DrillPair> {
+        DrillPair::effective(&self.drill_pairs, &self.copper_layer_names(), via_drill, via_pad)
+    }
+
+    /// First rule area forbidding footprints that a component's courtyard
+    /// overlaps, on the component's side of the board.
+    pub fn placement_keepout(&self, component: &PlacedComponent, margin: f64) -> Option<&RuleArea> {
+        let copper = self.copper_layer_names();
+        let side = match component.layer {
+            ComponentLayer::Top => copper.first(),
+            ComponentLayer::Bottom => copper.last(),
+        }?;
+        let courtyard = component.courtyard(margin)?;
+        self.rule_areas
+            .iter()
+            .find(|area| area.forbids(KeepoutKind::Footprint, side) && area.overlaps_box(&courtyard))
+    }
+
+    /// Find a placed component by reference designator.
+    pub fn component(&self, reference: &str) -> Option<&PlacedComponent> {
+        self.components.iter().find(|c| c.reference == reference)
+    }
 }
 
 /// Board/die outline.
@@ -50,11 +152,71 @@ pub struct Outline {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub height: Option<f64>,
 
+    /// Interior cutouts and slots as closed polygons
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cutouts: Vec<Vec<Point2D>>,
+
     /// Unit
     #[serde(default)]
     pub unit: LengthUnit,
 }
 
+impl Outline {
+    /// Outline as a closed polygon in layout units.
+    ///
+    /// Rectangles are anchored at the origin; circles use `width` as the
+    /// diameter, are centred on the first point (or on the origin-anchored
+    /// bounding square when no point is given) and are approximated with
+    /// `circle_segments` edges.
+    pub fn to_polygon(&self, circle_segments: usize) -> Vec<Point2D> {
+        match self.outline_type {
+            OutlineType::Rectangle => {
+                let w = self.width.unwrap_or(0.0);
+                let h = self.height.unwrap_or(0.0);
+                vec![
+                    Point2D::new(0.0, 0.0),
+                    Point2D::new(w, 0.0),
+                    Point2D::new(w, h),
+                    Point2D::new(0.0, h),
+                ]
+            }
+            OutlineType::Polygon => self.points.clone(),
+            OutlineType::Circle => {
+                let radius = self.width.unwrap_or(0.0) / 2.0;
+                let center = self
+                    .points
+                    .first()
+                    .copied()
+                    .unwrap_or(Point2D::new(radius, radius));
+                let n = circle_segments.max(8);
+                (0..n)
+                    .map(|i| {
+                        let a = std::f64::consts::TAU * i as f64 / n as f64;
+                        Point2D::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Add a milled slot of `width` between two points as a cutout.
+    pub fn add_slot(&mut self, start: Point2D, end: Point2D, width: f64) {
+        let r = width / 2.0;
+        let angle = (end.y - start.y).atan2(end.x - start.x);
+        let arc = |center: Point2D, from: f64| {
+            (0..=8).map(move |i| {
+                let a = from + std::f64::consts::PI * i as f64 / 8.0;
+                Point2D::new(center.x + r * a.cos(), center.y + r * a.sin())
+            })
+        };
+        let quarter_turn = std::f64::consts::FRAC_PI_2;
+        let points = arc(end, angle - quarter_turn)
+            .chain(arc(start, angle + quarter_turn))
+            .collect();
+        self.cutouts.push(points);
+    }
+}
+
 /// Outline type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -90,6 +252,19 @@ pub struct Layer {
     pub visible: bool,
 }
 
+impl Layer {
+    /// Create a new visible layer.
+    pub fn new(name: &str, layer_type: LayerType) -> Self {
+        Self {
+            name: name.to_string(),
+            layer_type,
+            thickness: None,
+            material: None,
+            visible: true,
+        }
+    }
+}
+
 fn default_true() -> bool {
     true
 }
@@ -115,6 +290,514 @@ pub enum LayerType {
     Fabrication,
 }
 
+/// A component placed on the board.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacedComponent {
+    /// Unique identifier
+    pub id: Uuid,
+
+    /// Reference designator (e.g., "R1", "U1")
+    pub reference: String,
+
+    /// Component value (e.g., "10k", "100nF")
+    #[serde(default)]
+    pub value: String,
+
+    /// Footprint library reference
+    #[serde(default)]
+    pub footprint: String,
+
+    /// Footprint origin position
+    pub position: Position,
+
+    /// Rotation in degrees (counter-clockwise)
+    #[serde(default)]
+    pub rotation: f64,
+
+    /// Board side
+    #[serde(default)]
+    pub layer: ComponentLayer,
+
+    /// Footprint pads
+    #[serde(default)]
+    pub pads: Vec<Pad>,
+
+    /// Locked against moves
+    #[serde(default)]
+    pub locked: bool,
+
+    /// Footprint drawings, relative to the component origin
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub graphics: Vec<Graphic>,
+}
+
+impl PlacedComponent {
+    /// Create a new component at the origin on the top side.
+    pub fn new(
+        reference: impl Into<String>,
+        value: impl Into<String>,
+        footprint: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            reference: reference.into(),
+            value: value.into(),
+            footprint: footprint.into(),
+            position: Position::new(0.0, 0.0),
+            rotation: 0.0,
+            layer: ComponentLayer::Top,
+            pads: Vec::new(),
+            locked: false,
+            graphics: Vec::new(),
+        }
+    }
+
+    /// Set the position.
+    pub fn at(mut self, x: f64, y: f64) -> Self {
+        self.position = Position::new(x, y);
+        self
+    }
+
+    /// Set the rotation in degrees.
+    pub fn rotated(mut self, rotation: f64) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Place on the bottom side.
+    pub fn on_bottom(mut self) -> Self {
+        self.layer = ComponentLayer::Bottom;
+        self
+    }
+
+    /// Add a pad.
+    pub fn with_pad(mut self, pad: Pad) -> Self {
+        self.pads.push(pad);
+        self
+    }
+
+    /// Add a footprint drawing.
+    pub fn with_graphic(mut self, graphic: Graphic) -> Self {
+        self.graphics.push(graphic);
+        self
+    }
+
+    /// Absolute board position of a pad, applying component rotation and
+    /// bottom-side mirroring.
+    pub fn pad_position(&self, pad: &Pad) -> Point2D {
+        self.to_board(&pad.position)
+    }
+
+    /// Board position of a point given relative to the component origin.
+    pub fn to_board(&self, local: &Point2D) -> Point2D {
+        let local_x = match self.layer {
+            ComponentLayer::Top => local.x,
+            ComponentLayer::Bottom => -local.x,
+        };
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Point2D::new(
+            self.position.x + local_x * cos - local.y * sin,
+            self.position.y + local_x * sin + local.y * cos,
+        )
+    }
+
+    /// Footprint drawings in board coordinates.
+    pub fn board_graphics(&self) -> Vec<Graphic> {
+        self.graphics
+            .iter()
+            .map(|g| {
+                let mut graphic = g.clone();
+                graphic.shape = match &g.shape {
+                    GraphicShape::Line { start, end } => GraphicShape::Line {
+                        start: self.to_board(start),
+                        end: self.to_board(end),
+                    },
+                    GraphicShape::Circle { center, radius } => GraphicShape::Circle {
+                        center: self.to_board(center),
+                        radius: *radius,
+                    },
+                    GraphicShape::Polygon { points } => GraphicShape::Polygon {
+                        points: points.iter().map(|p| self.to_board(p)).collect(),
+                    },
+                    GraphicShape::Text { text, position, height, rotation } => GraphicShape::Text {
+                        text: text.clone(),
+                        position: self.to_board(position),
+                        height: *height,
+                        rotation: rotation + self.rotation,
+                    },
+                };
+                graphic
+            })
+            .collect()
+    }
+
+    /// Board sides where a pad has an opening on the technical layer with
+    /// the given suffix (`"Mask"` or `"Paste"`).
+    ///
+    /// Uses the pad's explicit layer list when present; otherwise through-hole
+    /// pads open the mask on both sides, SMD pads open mask and paste on the
+    /// component side and edge connector pads open the mask only.
+    pub fn pad_openings(&self, pad: &Pad, suffix: &str) -> Vec<ComponentLayer> {
+        if !pad.layers.is_empty() {
+            let mut sides = Vec::new();
+            for (prefix, side) in [("F.", ComponentLayer::Top), ("B.", ComponentLayer::Bottom)] {
+                let on_side = pad.layers.iter().any(|l| {
+                    l.strip_suffix(suffix)
+                        .is_some_and(|p| p == prefix || p == "*.")
+                });
+                if on_side {
+                    sides.push(side);
+                }
+            }
+            return sides;
+        }
+
+        let paste = suffix == "Paste";
+        match pad.pad_type {
+            PadType::ThruHole | PadType::Npth if !paste => vec![ComponentLayer::Top, ComponentLayer::Bottom],
+            PadType::Smd => vec![self.layer],
+            PadType::Connect if !paste => vec![self.layer],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Courtyard: the bounding box of the footprint's drawing on the
+    /// courtyard layer of its side (`F.CrtYd` or `B.CrtYd`) when it has
+    /// one, otherwise of all pads grown by `margin`.
+    pub fn courtyard(&self, margin: f64) -> Option<BoundingBox> {
+        let layer = match self.layer {
+            ComponentLayer::Top => "F.CrtYd",
+            ComponentLayer::Bottom => "B.CrtYd",
+        };
+        let drawn: Vec<Point2D> = self.graphics.iter()
+            .filter(|g| g.layer == layer)
+            .flat_map(|g| match &g.shape {
+                GraphicShape::Line { start, end } => vec![self.to_board(start), self.to_board(end)],
+                GraphicShape::Circle { center, radius } => {
+                    let c = self.to_board(center);
+                    vec![Point2D::new(c.x - radius, c.y - radius), Point2D::new(c.x + radius, c.y + radius)]
+                }
+                GraphicShape::Polygon { points } => points.iter().map(|p| self.to_board(p)).collect(),
+                GraphicShape::Text { .. } => Vec::new(),
+            })
+            .collect();
+        if !drawn.is_empty() {
+            return BoundingBox::from_points(&drawn);
+        }
+
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let mut corners = Vec::new();
+        for pad in &self.pads {
+            let center = self.pad_position(pad);
+            let (hw, hh) = (pad.size.0 / 2.0, pad.size.1 / 2.0);
+            for (x, y) in [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)] {
+                corners.push(Point2D::new(center.x + x * cos - y * sin, center.y + x * sin + y * cos));
+            }
+        }
+        BoundingBox::from_points(&corners).map(|b| b.expanded(margin))
+    }
+
+    /// Signed distance from a point to a pad's copper outline (negative
+    /// inside the pad).
+    ///
+    /// Circles are exact; ovals and round-rects are treated as stadiums and
+    /// rectangles respectively, other shapes as their bounding rectangle.
+    pub fn pad_distance(&self, pad: &Pad, point: &Point2D) -> f64 {
+        let center = self.pad_position(pad);
+        let (hw, hh) = (pad.size.0 / 2.0, pad.size.1 / 2.0);
+        if pad.shape == PadShape::Circle {
+            return point.distance(&center) - hw.max(hh);
+        }
+
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let dx = point.x - center.x;
+        let dy = point.y - center.y;
+        let lx = (dx * cos + dy * sin).abs();
+        let ly = (-dx * sin + dy * cos).abs();
+
+        if pad.shape == PadShape::Oval {
+            // Stadium: distance to the inner segment minus the cap radius
+            let r = hw.min(hh);
+            let qx = (lx - (hw - r)).max(0.0);
+            let qy = (ly - (hh - r)).max(0.0);
+            return qx.hypot(qy) - r;
+        }
+
+        let ox = lx - hw;
+        let oy = ly - hh;
+        if ox <= 0.0 && oy <= 0.0 {
+            ox.max(oy)
+        } else {
+            ox.max(0.0).hypot(oy.max(0.0))
+        }
+    }
+
+    /// Copper layers a pad is present on.
+    ///
+    /// Uses the pad's explicit layer list when present; otherwise SMD pads sit
+    /// on the outer layer of the component side and plated through-hole pads
+    /// on every copper layer in `copper_layers`.
+    pub fn pad_copper_layers(&self, pad: &Pad, copper_layers: &[String]) -> Vec<String> {
+        if !pad.layers.is_empty() {
+            if pad.layers.iter().any(|l| l == "*.Cu") {
+                return copper_layers.to_vec();
+            }
+            return pad
+                .layers
+                .iter()
+                .filter(|l| copper_layers.contains(l))
+                .cloned()
+                .collect();
+        }
+
+        match pad.pad_type {
+            PadType::ThruHole => copper_layers.to_vec(),
+            PadType::Npth => Vec::new(),
+            PadType::Smd | PadType::Connect => {
+                let side = match self.layer {
+                    ComponentLayer::Top => copper_layers.first(),
+                    ComponentLayer::Bottom => copper_layers.last(),
+                };
+                side.cloned().into_iter().collect()
+            }
+        }
+    }
+}
+
+/// Board side a component is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentLayer {
+    /// Top (front) side
+    #[default]
+    Top,
+    /// Bottom (back) side
+    Bottom,
+}
+
+/// A footprint pad.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pad {
+    /// Pad number (e.g., "1", "A3")
+    pub number: String,
+
+    /// Pad name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Pad type
+    #[serde(default)]
+    pub pad_type: PadType,
+
+    /// Pad shape
+    #[serde(default)]
+    pub shape: PadShape,
+
+    /// Position relative to the component origin
+    pub position: Point2D,
+
+    /// Size (width, height)
+    pub size: (f64, f64),
+
+    /// Drill diameter (0 for SMD)
+    #[serde(default)]
+    pub drill: f64,
+
+    /// Net name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net: Option<String>,
+
+    /// Layers the pad is on (empty means derived from the pad type)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<String>,
+
+    /// Solder mask expansion beyond the pad (board default when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mask_margin: Option<f64>,
+
+    /// Solder paste expansion beyond the pad, usually negative
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_margin: Option<f64>,
+
+    /// Solder paste expansion relative to the smaller pad side
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paste_margin_ratio: Option<f64>,
+}
+
+impl Pad {
+    /// Create a new SMD pad.
+    pub fn smd(number: impl Into<String>, x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            number: number.into(),
+            name: None,
+            pad_type: PadType::Smd,
+            shape: PadShape::Rect,
+            position: Point2D::new(x, y),
+            size: (width, height),
+            drill: 0.0,
+            net: None,
+            layers: Vec::new(),
+            mask_margin: None,
+            paste_margin: None,
+            paste_margin_ratio: None,
+        }
+    }
+
+    /// Create a new plated through-hole pad.
+    pub fn thru_hole(number: impl Into<String>, x: f64, y: f64, diameter: f64, drill: f64) -> Self {
+        Self {
+            number: number.into(),
+            name: None,
+            pad_type: PadType::ThruHole,
+            shape: PadShape::Circle,
+            position: Point2D::new(x, y),
+            size: (diameter, diameter),
+            drill,
+            net: None,
+            layers: Vec::new(),
+            mask_margin: None,
+            paste_margin: None,
+            paste_margin_ratio: None,
+        }
+    }
+
+    /// Set the net.
+    pub fn with_net(mut self, net: impl Into<String>) -> Self {
+        self.net = Some(net.into());
+        self
+    }
+
+    /// Set the shape.
+    pub fn with_shape(mut self, shape: PadShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Set the solder mask expansion.
+    pub fn with_mask_margin(mut self, margin: f64) -> Self {
+        self.mask_margin = Some(margin);
+        self
+    }
+
+    /// Set the solder paste expansion.
+    pub fn with_paste_margin(mut self, margin: f64) -> Self {
+        self.paste_margin = Some(margin);
+        self
+    }
+
+    /// Size of the paste aperture: the pad grown on each side by the paste
+    /// margin plus the ratio times the smaller pad side.
+    pub fn paste_size(&self) -> (f64, f64) {
+        let grow = self.paste_margin.unwrap_or(0.0)
+            + self.paste_margin_ratio.unwrap_or(0.0) * self.size.0.min(self.size.1);
+        ((self.size.0 + 2.0 * grow).max(0.0), (self.size.1 + 2.0 * grow).max(0.0))
+    }
+}
+
+/// Pad type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PadType {
+    /// Plated through-hole
+    ThruHole,
+    /// Surface mount
+    #[default]
+    Smd,
+    /// Non-plated through-hole
+    Npth,
+    /// Edge connector / connect pad
+    Connect,
+}
+
+/// Pad shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PadShape {
+    /// Circle
+    Circle,
+    /// Rectangle
+    #[default]
+    Rect,
+    /// Oval (stadium)
+    Oval,
+    /// Rounded rectangle
+    RoundRect,
+    /// Trapezoid
+    Trapezoid,
+    /// Custom shape
+    Custom,
+}
+
+/// A drawing on a non-copper layer such as silkscreen (in mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Graphic {
+    /// Layer name
+    pub layer: String,
+
+    /// Shape
+    pub shape: GraphicShape,
+
+    /// Stroke width (0 for a filled polygon)
+    #[serde(default)]
+    pub width: f64,
+}
+
+impl Graphic {
+    /// Create a line.
+    pub fn line(layer: &str, start: Point2D, end: Point2D, width: f64) -> Self {
+        Self {
+            layer: layer.to_string(),
+            shape: GraphicShape::Line { start, end },
+            width,
+        }
+    }
+
+    /// Create a text, centered on `position`, drawn with stroke `width`.
+    pub fn text(layer: &str, text: &str, position: Point2D, height: f64, width: f64) -> Self {
+        Self {
+            layer: layer.to_string(),
+            shape: GraphicShape::Text {
+                text: text.to_string(),
+                position,
+                height,
+                rotation: 0.0,
+            },
+            width,
+        }
+    }
+
+    /// Board side of the layer, if it is a front or back layer.
+    pub fn side(&self) -> Option<ComponentLayer> {
+        if self.layer.starts_with("F.") {
+            Some(ComponentLayer::Top)
+        } else if self.layer.starts_with("B.") {
+            Some(ComponentLayer::Bottom)
+        } else {
+            None
+        }
+    }
+}
+
+/// Shape of a drawing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GraphicShape {
+    /// Straight line
+    Line { start: Point2D, end: Point2D },
+    /// Circle outline
+    Circle { center: Point2D, radius: f64 },
+    /// Closed polygon
+    Polygon { points: Vec<Point2D> },
+    /// Stroke-font text centered on `position`
+    Text {
+        text: String,
+        position: Point2D,
+        height: f64,
+        #[serde(default)]
+        rotation: f64,
+    },
+}
+
 /// A trace/route segment.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trace {
@@ -170,6 +853,20 @@ pub struct Via {
     pub unit: LengthUnit,
 }
 
+impl Via {
+    /// Copper layers the via connects, from `start_layer` to `end_layer`
+    /// inclusive, or every layer in `copper_layers` when the span is unset.
+    pub fn copper_layers(&self, copper_layers: &[String]) -> Vec<String> {
+        let index = |name: &Option<String>| {
+            name.as_ref().and_then(|n| copper_layers.iter().position(|l| l == n))
+        };
+        match (index(&self.start_layer), index(&self.end_layer)) {
+            (Some(a), Some(b)) => copper_layers[a.min(b)..=a.max(b)].to_vec(),
+            _ => copper_layers.to_vec(),
+        }
+    }
+}
+
 /// Via type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -185,6 +882,83 @@ pub enum ViaType {
     Micro,
 }
 
+/// An allowed via drill span between two copper layers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrillPair {
+    /// First copper layer of the span
+    pub start_layer: String,
+
+    /// Last copper layer of the span
+    pub end_layer: String,
+
+    /// Via type drilled for this span
+    pub via_type: ViaType,
+
+    /// Drill diameter (mm)
+    pub drill: f64,
+
+    /// Pad diameter (mm)
+    pub pad: f64,
+}
+
+impl DrillPair {
+    /// Create a new drill pair.
+    pub fn new(start_layer: &str, end_layer: &str, via_type: ViaType, drill: f64, pad: f64) -> Self {
+        Self {
+            start_layer: start_layer.to_string(),
+            end_layer: end_layer.to_string(),
+            via_type,
+            drill,
+            pad,
+        }
+    }
+
+    /// Drill pairs in effect for a copper stack: `pairs`, or a single
+    /// through span with the given via size when none are defined.
+    pub(crate) fn effective(pairs: &[DrillPair], copper: &[String], via_drill: f64, via_pad: f64) -> Vec<DrillPair> {
+        if !pairs.is_empty() {
+            return pairs.to_vec();
+        }
+        match (copper.first(), copper.last()) {
+            (Some(first), Some(last)) if copper.len() > 1 => {
+                vec![DrillPair::new(first, last, ViaType::Through, via_drill, via_pad)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Copper layer index range `(first, last)` of the span, checking that
+    /// the layers exist and the via type matches the span.
+    pub fn span(&self, copper_layers: &[String]) -> Result<(usize, usize), String> {
+        let index = |name: &str| {
+            copper_layers
+                .iter()
+                .position(|l| l == name)
+                .ok_or_else(|| format!("Drill pair layer {} is not a copper layer", name))
+        };
+        let (a, b) = (index(&self.start_layer)?, index(&self.end_layer)?);
+        let (first, last) = (a.min(b), a.max(b));
+        if first == last {
+            return Err(format!("Drill pair {}-{} spans a single layer", self.start_layer, self.end_layer));
+        }
+
+        let outer = copper_layers.len() - 1;
+        let valid = match self.via_type {
+            ViaType::Through => first == 0 && last == outer,
+            ViaType::Blind => (first == 0) != (last == outer),
+            ViaType::Buried => first != 0 && last != outer,
+            ViaType::Micro => last - first == 1,
+        };
+        if !valid {
+            return Err(format!(
+                "Drill pair {}-{} cannot be a {:?} via",
+                self.start_layer, self.end_layer, self.via_type
+            ));
+        }
+        Ok((first, last))
+    }
+}
+
 /// A copper zone/fill.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Zone {
@@ -209,11 +983,105 @@ pub struct Zone {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub min_width: Option<f64>,
 
+    /// What created the zone
+    #[serde(default)]
+    pub kind: ZoneKind,
+
+    /// Fill priority; zones of other nets with a higher priority are
+    /// cut out of this one
+    #[serde(default)]
+    pub priority: u32,
+
+    /// How the fill connects to pads of the zone's net
+    #[serde(default)]
+    pub pad_connection: PadConnection,
+
+    /// Gap between pads and the fill around thermal reliefs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thermal_gap: Option<f64>,
+
+    /// Width of thermal relief spokes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thermal_spoke_width: Option<f64>,
+
+    /// Number of thermal relief spokes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thermal_spokes: Option<usize>,
+
+    /// Hatch line width (hatched fill)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hatch_width: Option<f64>,
+
+    /// Gap between hatch lines (hatched fill)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hatch_gap: Option<f64>,
+
+    /// Removal of fill islands not connected to the zone's net
+    #[serde(default)]
+    pub island_removal: IslandRemoval,
+
+    /// Filled copper polygons from the last fill; empty when unfilled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filled: Vec<Vec<Point2D>>,
+
     /// Unit
     #[serde(default)]
     pub unit: LengthUnit,
 }
 
+impl Zone {
+    /// Create a solid copper zone in mm.
+    pub fn new(net: &str, layer: &str, points: Vec<Point2D>) -> Self {
+        Self {
+            net: net.to_string(),
+            layer: layer.to_string(),
+            points,
+            fill_type: ZoneFillType::Solid,
+            clearance: None,
+            min_width: None,
+            kind: ZoneKind::Copper,
+            priority: 0,
+            pad_connection: PadConnection::Thermal,
+            thermal_gap: None,
+            thermal_spoke_width: None,
+            thermal_spokes: None,
+            hatch_width: None,
+            hatch_gap: None,
+            island_removal: IslandRemoval::Always,
+            filled: Vec::new(),
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    /// Set the fill priority.
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set how the fill connects to pads of the zone's net.
+    pub fn with_pad_connection(mut self, pad_connection: PadConnection) -> Self {
+        self.pad_connection = pad_connection;
+        self
+    }
+
+    /// Whether the zone holds a fill.
+    pub fn is_filled(&self) -> bool {
+        !self.filled.is_empty()
+    }
+}
+
+/// Origin of a zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneKind {
+    /// User-drawn copper zone
+    #[default]
+    Copper,
+    /// Generated teardrop, replaced when teardrops are regenerated
+    Teardrop,
+}
+
 /// Zone fill type.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -227,6 +1095,199 @@ pub enum ZoneFillType {
     None,
 }
 
+/// Connection between a zone fill and pads of its net.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PadConnection {
+    /// Spokes across a gap around the pad
+    #[default]
+    Thermal,
+    /// Fill flooded over the pad
+    Solid,
+    /// Pad kept clear like other-net copper
+    None,
+}
+
+/// Removal of fill islands not connected to the zone's net.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IslandRemoval {
+    /// Remove every isolated island
+    #[default]
+    Always,
+    /// Keep isolated islands
+    Never,
+    /// Remove isolated islands smaller than this area (square zone units)
+    BelowArea(f64),
+}
+
+/// Item kinds a keepout can forbid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeepoutKind {
+    /// Tracks
+    Track,
+    /// Vias
+    Via,
+    /// Footprint pads
+    Pad,
+    /// Zone fill
+    CopperPour,
+    /// Footprint courtyards
+    Footprint,
+}
+
+impl KeepoutKind {
+    /// Lowercase name used in rule ids and messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeepoutKind::Track => "track",
+            KeepoutKind::Via => "via",
+            KeepoutKind::Pad => "pad",
+            KeepoutKind::CopperPour => "copper_pour",
+            KeepoutKind::Footprint => "footprint",
+        }
+    }
+}
+
+/// Items forbidden inside a rule area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Keepouts {
+    /// No tracks
+    #[serde(default)]
+    pub tracks: bool,
+
+    /// No vias
+    #[serde(default)]
+    pub vias: bool,
+
+    /// No pads
+    #[serde(default)]
+    pub pads: bool,
+
+    /// No zone fill
+    #[serde(default)]
+    pub copper_pour: bool,
+
+    /// No footprints
+    #[serde(default)]
+    pub footprints: bool,
+}
+
+impl Keepouts {
+    /// Forbid everything.
+    pub fn all() -> Self {
+        Self { tracks: true, vias: true, pads: true, copper_pour: true, footprints: true }
+    }
+
+    /// Whether an item kind is forbidden.
+    pub fn forbids(&self, kind: KeepoutKind) -> bool {
+        match kind {
+            KeepoutKind::Track => self.tracks,
+            KeepoutKind::Via => self.vias,
+            KeepoutKind::Pad => self.pads,
+            KeepoutKind::CopperPour => self.copper_pour,
+            KeepoutKind::Footprint => self.footprints,
+        }
+    }
+}
+
+/// A design rule for items inside a rule area.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaRule {
+    /// Net class the rule applies to (`*` wildcards allowed; all nets when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_class: Option<String>,
+
+    /// Constrained quantity
+    pub constraint: RuleConstraint,
+
+    /// Minimum value (mm)
+    pub min: f64,
+}
+
+/// A keepout or rule area: a region where items are forbidden or where
+/// extra design rules apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleArea {
+    /// Area name, referenced by `inside_area('name')` rule conditions
+    #[serde(default)]
+    pub name: String,
+
+    /// Outline points
+    pub points: Vec<Point2D>,
+
+    /// Copper layers the area applies to (empty means all)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<String>,
+
+    /// Items forbidden inside the area
+    #[serde(default)]
+    pub keepout: Keepouts,
+
+    /// Rules for items inside the area
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<AreaRule>,
+
+    /// Unit
+    #[serde(default)]
+    pub unit: LengthUnit,
+}
+
+impl RuleArea {
+    /// Create an area on all copper layers, in mm, forbidding nothing.
+    pub fn new(name: &str, points: Vec<Point2D>) -> Self {
+        Self {
+            name: name.to_string(),
+            points,
+            layers: Vec::new(),
+            keepout: Keepouts::default(),
+            rules: Vec::new(),
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    /// Restrict the area to some copper layers.
+    pub fn with_layers(mut self, layers: &[&str]) -> Self {
+        self.layers = layers.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// Set the forbidden items.
+    pub fn with_keepout(mut self, keepout: Keepouts) -> Self {
+        self.keepout = keepout;
+        self
+    }
+
+    /// Add a rule for items inside the area.
+    pub fn with_rule(mut self, net_class: Option<&str>, constraint: RuleConstraint, min: f64) -> Self {
+        self.rules.push(AreaRule { net_class: net_class.map(str::to_string), constraint, min });
+        self
+    }
+
+    /// Whether the area applies to a copper layer.
+    pub fn on_layer(&self, layer: &str) -> bool {
+        self.layers.is_empty() || self.layers.iter().any(|l| l == layer)
+    }
+
+    /// Whether the area forbids an item kind on a layer.
+    pub fn forbids(&self, kind: KeepoutKind, layer: &str) -> bool {
+        self.keepout.forbids(kind) && self.on_layer(layer)
+    }
+
+    /// Outline in mm.
+    pub fn polygon_mm(&self) -> Vec<Point2D> {
+        self.points
+            .iter()
+            .map(|p| Point2D::new(self.unit.to_mm(p.x), self.unit.to_mm(p.y)))
+            .collect()
+    }
+
+    /// Whether the outline overlaps a box in mm.
+    pub fn overlaps_box(&self, bounds: &BoundingBox) -> bool {
+        polygon_intersects_box(&self.polygon_mm(), bounds)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,4 +1298,83 @@ mod tests {
         assert!(layout.layers.is_empty());
         assert!(layout.traces.is_empty());
     }
+
+    #[test]
+    fn test_layout_with_board_size() {
+        let layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        let outline = layout.outline.as_ref().unwrap();
+        assert_eq!(outline.width, Some(100.0));
+        assert_eq!(layout.copper_layer_names(), vec!["F.Cu", "B.Cu"]);
+    }
+
+    #[test]
+    fn test_drill_pair_span() {
+        let copper: Vec<String> = ["F.Cu", "In1.Cu", "In2.Cu", "B.Cu"].iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(DrillPair::new("B.Cu", "In2.Cu", ViaType::Micro, 0.1, 0.25).span(&copper), Ok((2, 3)));
+        assert_eq!(DrillPair::new("F.Cu", "In2.Cu", ViaType::Blind, 0.2, 0.45).span(&copper), Ok((0, 2)));
+        assert!(DrillPair::new("F.Cu", "In2.Cu", ViaType::Micro, 0.1, 0.25).span(&copper).is_err());
+        assert!(DrillPair::new("F.Cu", "B.Cu", ViaType::Buried, 0.2, 0.45).span(&copper).is_err());
+        assert!(DrillPair::new("F.Cu", "In5.Cu", ViaType::Blind, 0.2, 0.45).span(&copper).is_err());
+
+        let layout = Layout::with_board_size(10.0, 10.0, LengthUnit::Mm);
+        let pairs = layout.effective_drill_pairs(0.3, 0.6);
+        assert_eq!(pairs, vec![DrillPair::new("F.Cu", "B.Cu", ViaType::Through, 0.3, 0.6)]);
+    }
+
+    #[test]
+    fn test_placement_keepout() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        let square = vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 0.0),
+            Point2D::new(10.0, 10.0),
+            Point2D::new(0.0, 10.0),
+        ];
+        let keepout = Keepouts { footprints: true, ..Default::default() };
+        layout.rule_areas.push(RuleArea::new("antenna", square).with_layers(&["F.Cu"]).with_keepout(keepout));
+
+        let inside = PlacedComponent::new("U1", "MCU", "QFN").at(11.0, 5.0).with_pad(Pad::smd("1", 0.0, 0.0, 2.0, 2.0));
+        assert_eq!(layout.placement_keepout(&inside, 0.25).unwrap().name, "antenna");
+        assert!(layout.placement_keepout(&inside.clone().on_bottom(), 0.25).is_none());
+
+        let outside = PlacedComponent::new("U2", "MCU", "QFN").at(20.0, 5.0).with_pad(Pad::smd("1", 0.0, 0.0, 2.0, 2.0));
+        assert!(layout.placement_keepout(&outside, 0.25).is_none());
+    }
+
+    #[test]
+    fn test_pad_position_rotation_and_mirror() {
+        let pad = Pad::smd("1", 1.0, 0.0, 0.5, 0.5);
+        let top = PlacedComponent::new("R1", "10k", "R_0603").at(10.0, 10.0).rotated(90.0);
+        let p = top.pad_position(&pad);
+        assert!((p.x - 10.0).abs() < 1e-9);
+        assert!((p.y - 11.0).abs() < 1e-9);
+
+        let bottom = PlacedComponent::new("R2", "10k", "R_0603").at(10.0, 10.0).on_bottom();
+        let p = bottom.pad_position(&pad);
+        assert!((p.x - 9.0).abs() < 1e-9);
+
+        let copper = vec!["F.Cu".to_string(), "B.Cu".to_string()];
+        assert_eq!(bottom.pad_copper_layers(&pad, &copper), vec!["B.Cu"]);
+    }
+
+    #[test]
+    fn test_courtyard_from_drawing() {
+        let outline = [(-3.0, -1.0), (4.0, -1.0), (4.0, 1.0), (-3.0, 1.0)];
+        let mut component = PlacedComponent::new("J1", "USB", "USB_C")
+            .at(10.0, 10.0)
+            .rotated(90.0)
+            .with_pad(Pad::smd("1", 0.0, 0.0, 0.5, 0.5));
+        for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+            component = component.with_graphic(Graphic::line("F.CrtYd", Point2D::new(a.0, a.1), Point2D::new(b.0, b.1), 0.05));
+        }
+        let courtyard = component.courtyard(0.25).unwrap();
+        assert!((courtyard.min.x - 9.0).abs() < 1e-9 && (courtyard.max.x - 11.0).abs() < 1e-9);
+        assert!((courtyard.min.y - 7.0).abs() < 1e-9 && (courtyard.max.y - 14.0).abs() < 1e-9);
+
+        // The drawing on the other side does not count; pads grown by the margin do
+        component.layer = ComponentLayer::Bottom;
+        let courtyard = component.courtyard(0.25).unwrap();
+        assert!((courtyard.width() - 1.0).abs() < 1e-9);
+    }
 }
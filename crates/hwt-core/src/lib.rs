@@ -5,18 +5,24 @@
 //! This crate provides the universal data structures for representing
 //! hardware designs across all domains: PCB, IC, Quantum, MEMS, RF, and Packaging.
 
+pub mod autoroute;
 pub mod bom;
 pub mod circuit;
 pub mod pnp;
 pub mod command;
 pub mod component;
 pub mod constraint;
+pub mod copper;
 pub mod domain;
 pub mod drc;
+pub mod drc_export;
+pub mod drc_rules;
 pub mod erc;
+pub mod fab;
 pub mod pcb_drc;
 pub mod geometry;
 pub mod gerber;
+pub mod high_speed;
 pub mod io;
 pub mod kicad;
 pub mod layout;
@@ -25,8 +31,12 @@ pub mod net;
 pub mod netclass;
 pub mod programmatic;
 pub mod project;
+pub mod ratsnest;
+pub mod rng;
 pub mod routing;
+pub mod specctra;
 pub mod spice;
+pub mod teardrop;
 pub mod pdf_export;
 pub mod svg_export;
 pub mod png_export;
@@ -35,6 +45,15 @@ pub mod eagle;
 pub mod schematic;
 pub mod sync;
 pub mod units;
+pub mod zone_fill;
+pub mod stackup;
+pub mod impedance;
+pub mod stitching;
+pub mod panel;
+pub mod placement;
+pub mod autoplace;
+pub mod current;
+pub mod ir_drop;
 
 pub use circuit::CircuitJson;
 pub use component::Component;
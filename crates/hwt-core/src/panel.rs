@@ -0,0 +1,809 @@
+//! Panelization.
+//!
+//! Replicates one or more layouts in a grid to build a manufacturing
+//! panel. Boards are separated by milled slots held by breakaway tabs with
+//! mouse-bite holes, or butted against V-score lines, and the panel gets
+//! rails with fiducials and tooling holes. References, nets and rule area
+//! names are renamed per instance so the panel is an ordinary `Layout` the
+//! fabrication and assembly exporters can consume.
+//!
+//! Cells are filled row by row, cycling through the boards, and each
+//! board is centered in its cell. Panel coordinates are in mm with the
+//! origin at the top-left panel corner.
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::{polygon_area, BoundingBox, Point2D, Position};
+use crate::layout::{
+    Graphic, GraphicShape, Layer, LayerType, Layout, Outline, OutlineType, Pad, PadShape, PadType,
+    PlacedComponent,
+};
+use crate::units::LengthUnit;
+
+/// Layer holding V-score lines.
+pub const VSCORE_LAYER: &str = "Cmts.User";
+
+/// Slot pieces shorter than this are dropped (mm).
+const EPSILON: f64 = 1e-6;
+
+/// Panel error.
+#[derive(Debug, Clone)]
+pub struct PanelError {
+    /// Error description
+    pub message: String,
+}
+
+impl PanelError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for PanelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Panel error: {}", self.message)
+    }
+}
+
+impl std::error::Error for PanelError {}
+
+/// Result type for panelization.
+pub type PanelResult<T> = Result<T, PanelError>;
+
+/// How boards are separated from each other and from the rails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Separation {
+    /// Milled slots with breakaway tabs perforated by mouse-bite holes
+    #[default]
+    MouseBites,
+    /// Boards butted together and scored along straight lines
+    VScore,
+}
+
+/// Panel sides carrying a rail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Rails {
+    /// No rails
+    None,
+    /// Rails along the top and bottom edges
+    #[default]
+    TopBottom,
+    /// Rails along the left and right edges
+    LeftRight,
+    /// Rails on all four edges
+    All,
+}
+
+impl Rails {
+    /// Whether the top and bottom edges carry rails.
+    fn horizontal(self) -> bool {
+        matches!(self, Rails::TopBottom | Rails::All)
+    }
+
+    /// Whether the left and right edges carry rails.
+    fn vertical(self) -> bool {
+        matches!(self, Rails::LeftRight | Rails::All)
+    }
+}
+
+/// Panel settings (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanelConfig {
+    /// Grid rows
+    pub rows: usize,
+
+    /// Grid columns
+    pub columns: usize,
+
+    /// Board separation
+    pub separation: Separation,
+
+    /// Gap between boards and to the rails (mouse-bite panels only)
+    pub spacing: f64,
+
+    /// Width of the milled slots
+    pub mill_width: f64,
+
+    /// Rail sides
+    pub rails: Rails,
+
+    /// Rail width
+    pub rail_width: f64,
+
+    /// Breakaway tab width
+    pub tab_width: f64,
+
+    /// Target distance between tabs along a board edge
+    pub tab_spacing: f64,
+
+    /// Mouse-bite hole diameter
+    pub mouse_bite_drill: f64,
+
+    /// Distance between mouse-bite holes
+    pub mouse_bite_pitch: f64,
+
+    /// Add three fiducials on the rails
+    pub fiducials: bool,
+
+    /// Fiducial copper diameter
+    pub fiducial_diameter: f64,
+
+    /// Solder mask clearance around fiducials
+    pub fiducial_mask_margin: f64,
+
+    /// Add four tooling holes on the rails
+    pub tooling_holes: bool,
+
+    /// Tooling hole diameter
+    pub tooling_hole_drill: f64,
+
+    /// Distance of tooling holes from the panel ends along the rails;
+    /// fiducials sit at twice this distance
+    pub hole_inset: f64,
+
+    /// Instance name pattern for references, nets and rule areas, with
+    /// `{name}` for the original name and `{n}` for the 1-based instance
+    pub rename_format: String,
+}
+
+impl Default for PanelConfig {
+    fn default() -> Self {
+        Self {
+            rows: 1,
+            columns: 1,
+            separation: Separation::MouseBites,
+            spacing: 2.0,
+            mill_width: 2.0,
+            rails: Rails::TopBottom,
+            rail_width: 5.0,
+            tab_width: 3.0,
+            tab_spacing: 50.0,
+            mouse_bite_drill: 0.5,
+            mouse_bite_pitch: 0.8,
+            fiducials: true,
+            fiducial_diameter: 1.0,
+            fiducial_mask_margin: 1.0,
+            tooling_holes: true,
+            tooling_hole_drill: 3.0,
+            hole_inset: 5.0,
+            rename_format: "{name}_{n}".to_string(),
+        }
+    }
+}
+
+impl PanelConfig {
+    /// Set the grid size.
+    pub fn with_grid(mut self, rows: usize, columns: usize) -> Self {
+        self.rows = rows;
+        self.columns = columns;
+        self
+    }
+
+    /// Set the board separation.
+    pub fn with_separation(mut self, separation: Separation) -> Self {
+        self.separation = separation;
+        self
+    }
+
+    /// Set the rail sides and width.
+    pub fn with_rails(mut self, rails: Rails, width: f64) -> Self {
+        self.rails = rails;
+        self.rail_width = width;
+        self
+    }
+
+    /// Set the gap between boards.
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Instance name of `name` in instance `n` (1-based).
+    fn rename(&self, name: &str, n: usize) -> String {
+        if name.is_empty() {
+            return String::new();
+        }
+        self.rename_format.replace("{name}", name).replace("{n}", &n.to_string())
+    }
+}
+
+/// Rotation about the origin followed by a translation, in mm.
+#[derive(Debug, Clone, Copy)]
+struct Transform {
+    /// Rotation in degrees (counter-clockwise)
+    angle: f64,
+    sin: f64,
+    cos: f64,
+    offset: Point2D,
+}
+
+impl Transform {
+    fn new(angle: f64, offset: Point2D) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Self { angle, sin, cos, offset }
+    }
+
+    /// Transform a point given in mm.
+    fn point(&self, p: &Point2D) -> Point2D {
+        Point2D::new(
+            self.offset.x + p.x * self.cos - p.y * self.sin,
+            self.offset.y + p.x * self.sin + p.y * self.cos,
+        )
+    }
+
+    /// Transform a point given in `unit`, keeping the unit.
+    fn point_in(&self, p: &Point2D, unit: LengthUnit) -> Point2D {
+        let q = self.point(&Point2D::new(unit.to_mm(p.x), unit.to_mm(p.y)));
+        Point2D::new(unit.from_mm(q.x), unit.from_mm(q.y))
+    }
+
+    /// Transform a position, keeping its unit.
+    fn position(&self, p: &Position) -> Position {
+        let q = self.point_in(&p.to_point2d(), p.unit);
+        Position { x: q.x, y: q.y, ..p.clone() }
+    }
+
+    /// Transform a polygon given in `unit`, keeping the unit.
+    fn polygon_in(&self, points: &[Point2D], unit: LengthUnit) -> Vec<Point2D> {
+        points.iter().map(|p| self.point_in(p, unit)).collect()
+    }
+}
+
+/// A board of the panel.
+struct PanelBoard<'a> {
+    layout: &'a Layout,
+    rotation: f64,
+    /// Outline after rotation (mm)
+    outline: Vec<Point2D>,
+    bounds: BoundingBox,
+}
+
+/// A breakaway tab on a board edge.
+struct Tab {
+    center: Point2D,
+    /// Unit vector along the edge
+    along: Point2D,
+    /// Unit vector pointing away from the board
+    normal: Point2D,
+    /// Distance the tab spans away from the board
+    reach: f64,
+}
+
+/// Panel builder.
+pub struct Panelizer<'a> {
+    config: PanelConfig,
+    boards: Vec<(&'a Layout, f64)>,
+}
+
+impl<'a> Panelizer<'a> {
+    /// Create a panelizer with no boards.
+    pub fn new(config: PanelConfig) -> Self {
+        Self { config, boards: Vec::new() }
+    }
+
+    /// Add a board, rotated by `rotation` degrees counter-clockwise. Cells
+    /// cycle through the boards in the order they were added.
+    pub fn with_board(mut self, layout: &'a Layout, rotation: f64) -> Self {
+        self.boards.push((layout, rotation));
+        self
+    }
+
+    /// Build the panel.
+    pub fn panelize(&self) -> PanelResult<Layout> {
+        let config = &self.config;
+        if config.rows == 0 || config.columns == 0 {
+            return Err(PanelError::new("the panel grid needs at least one row and one column"));
+        }
+        let boards = self.prepare_boards()?;
+
+        let mouse_bites = config.separation == Separation::MouseBites;
+        let spacing = if mouse_bites { config.spacing.max(config.mill_width) } else { 0.0 };
+        let board_at = |row: usize, column: usize| &boards[(row * config.columns + column) % boards.len()];
+        let widths: Vec<f64> = (0..config.columns)
+            .map(|c| (0..config.rows).map(|r| board_at(r, c).bounds.width()).fold(0.0, f64::max))
+            .collect();
+        let heights: Vec<f64> = (0..config.rows)
+            .map(|r| (0..config.columns).map(|c| board_at(r, c).bounds.height()).fold(0.0, f64::max))
+            .collect();
+
+        // Cell origins, with a rail and a gap (or just the gap) around the grid
+        let rail = |present: bool| if present { config.rail_width } else { 0.0 };
+        let margin_x = rail(config.rails.vertical()) + spacing;
+        let margin_y = rail(config.rails.horizontal()) + spacing;
+        let starts = |sizes: &[f64], margin: f64| -> Vec<f64> {
+            sizes.iter()
+                .scan(margin, |next, size| {
+                    let start = *next;
+                    *next += size + spacing;
+                    Some(start)
+                })
+                .collect()
+        };
+        let xs = starts(&widths, margin_x);
+        let ys = starts(&heights, margin_y);
+        if !mouse_bites {
+            // Scores run along the cell edges, which must be the board edges
+            for (row, column) in (0..config.rows).flat_map(|r| (0..config.columns).map(move |c| (r, c))) {
+                let bounds = &board_at(row, column).bounds;
+                if (bounds.width() - widths[column]).abs() > EPSILON || (bounds.height() - heights[row]).abs() > EPSILON {
+                    return Err(PanelError::new(format!(
+                        "V-scored boards must share a width per column and a height per row (row {}, column {})",
+                        row + 1, column + 1,
+                    )));
+                }
+            }
+        }
+        let width = xs[config.columns - 1] + widths[config.columns - 1] + margin_x;
+        let height = ys[config.rows - 1] + heights[config.rows - 1] + margin_y;
+
+        let mut panel = Layout {
+            outline: Some(Outline {
+                outline_type: OutlineType::Polygon,
+                points: vec![
+                    Point2D::new(0.0, 0.0),
+                    Point2D::new(width, 0.0),
+                    Point2D::new(width, height),
+                    Point2D::new(0.0, height),
+                ],
+                width: None,
+                height: None,
+                cutouts: Vec::new(),
+                unit: LengthUnit::Mm,
+            }),
+            layers: boards[0].layout.layers.clone(),
+            drill_pairs: boards[0].layout.drill_pairs.clone(),
+            ..Layout::default()
+        };
+
+        let mut outlines = Vec::new();
+        for row in 0..config.rows {
+            for column in 0..config.columns {
+                let board = board_at(row, column);
+                let offset = Point2D::new(
+                    xs[column] + (widths[column] - board.bounds.width()) / 2.0 - board.bounds.min.x,
+                    ys[row] + (heights[row] - board.bounds.height()) / 2.0 - board.bounds.min.y,
+                );
+                let transform = Transform::new(board.rotation, offset);
+                self.add_instance(&mut panel, board, &transform, row * config.columns + column + 1);
+                outlines.push(board.outline.iter().map(|p| Transform::new(0.0, offset).point(p)).collect::<Vec<_>>());
+            }
+        }
+
+        let frame = BoundingBox::new(Point2D::new(0.0, 0.0), Point2D::new(width, height));
+        if mouse_bites {
+            self.add_breakaways(&mut panel, &outlines, spacing, &frame);
+        } else {
+            add_vscores(&mut panel, &xs, &widths, &ys, &heights, &frame);
+        }
+        self.add_rail_features(&mut panel, &frame);
+        Ok(panel)
+    }
+
+    /// Rotated outlines and bounds of the boards, checking that they can
+    /// share a panel.
+    fn prepare_boards(&self) -> PanelResult<Vec<PanelBoard<'a>>> {
+        let Some((first, _)) = self.boards.first() else {
+            return Err(PanelError::new("no boards to panelize"));
+        };
+        let copper = first.copper_layer_names();
+        let mut boards = Vec::new();
+        for (i, &(layout, rotation)) in self.boards.iter().enumerate() {
+            if layout.copper_layer_names() != copper {
+                return Err(PanelError::new(format!("board {} has a different copper stack than board 1", i + 1)));
+            }
+            let outline = layout.outline.as_ref()
+                .ok_or_else(|| PanelError::new(format!("board {} has no outline", i + 1)))?;
+            let rotate = Transform::new(rotation, Point2D::new(0.0, 0.0));
+            let outline: Vec<Point2D> = outline.to_polygon(64).iter()
+                .map(|p| rotate.point(&Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y))))
+                .collect();
+            let bounds = BoundingBox::from_points(&outline)
+                .filter(|b| b.width() > 0.0 && b.height() > 0.0)
+                .ok_or_else(|| PanelError::new(format!("board {} has an empty outline", i + 1)))?;
+            if self.config.separation == Separation::VScore {
+                if (rotation / 90.0 - (rotation / 90.0).round()).abs() > EPSILON {
+                    return Err(PanelError::new(format!("board {} is rotated by {} degrees; V-scored boards turn in 90 degree steps", i + 1, rotation)));
+                }
+                if (polygon_area(&outline).abs() - bounds.width() * bounds.height()).abs() > EPSILON {
+                    return Err(PanelError::new(format!("board {} is not rectangular and cannot be V-scored", i + 1)));
+                }
+            }
+            boards.push(PanelBoard { layout, rotation, outline, bounds });
+        }
+        Ok(boards)
+    }
+
+    /// Copy a board into the panel as instance `n`.
+    fn add_instance(&self, panel: &mut Layout, board: &PanelBoard, transform: &Transform, n: usize) {
+        let layout = board.layout;
+        let rename = |name: &str| self.config.rename(name, n);
+
+        for component in &layout.components {
+            let mut component = component.clone();
+            component.id = uuid::Uuid::new_v4();
+            component.reference = rename(&component.reference);
+            component.position = transform.position(&component.position);
+            component.rotation = (component.rotation + transform.angle).rem_euclid(360.0);
+            for pad in &mut component.pads {
+                pad.net = pad.net.as_deref().map(rename);
+            }
+            panel.components.push(component);
+        }
+        for trace in &layout.traces {
+            let mut trace = trace.clone();
+            trace.net = rename(&trace.net);
+            trace.start = transform.position(&trace.start);
+            trace.end = transform.position(&trace.end);
+            panel.traces.push(trace);
+        }
+        for via in &layout.vias {
+            let mut via = via.clone();
+            via.net = rename(&via.net);
+            via.position = transform.position(&via.position);
+            panel.vias.push(via);
+        }
+        for zone in &layout.zones {
+            let mut zone = zone.clone();
+            zone.net = rename(&zone.net);
+            zone.points = transform.polygon_in(&zone.points, zone.unit);
+            zone.filled = zone.filled.iter().map(|ring| transform.polygon_in(ring, zone.unit)).collect();
+            panel.zones.push(zone);
+        }
+        for area in &layout.rule_areas {
+            let mut area = area.clone();
+            area.name = rename(&area.name);
+            area.points = transform.polygon_in(&area.points, area.unit);
+            panel.rule_areas.push(area);
+        }
+        for graphic in &layout.graphics {
+            let mut graphic = graphic.clone();
+            graphic.shape = match &graphic.shape {
+                GraphicShape::Line { start, end } => GraphicShape::Line {
+                    start: transform.point(start),
+                    end: transform.point(end),
+                },
+                GraphicShape::Circle { center, radius } => GraphicShape::Circle {
+                    center: transform.point(center),
+                    radius: *radius,
+                },
+                GraphicShape::Polygon { points } => GraphicShape::Polygon {
+                    points: points.iter().map(|p| transform.point(p)).collect(),
+                },
+                GraphicShape::Text { text, position, height, rotation } => GraphicShape::Text {
+                    text: text.clone(),
+                    position: transform.point(position),
+                    height: *height,
+                    rotation: rotation + transform.angle,
+                },
+            };
+            panel.graphics.push(graphic);
+        }
+        if let (Some(outline), Some(panel_outline)) = (&layout.outline, &mut panel.outline) {
+            for cutout in &outline.cutouts {
+                let cutout: Vec<Point2D> = cutout.iter()
+                    .map(|p| transform.point(&Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y))))
+                    .collect();
+                panel_outline.cutouts.push(cutout);
+            }
+        }
+    }
+
+    /// Mill a slot around every board, leaving breakaway tabs perforated by
+    /// mouse-bite holes on the board side.
+    ///
+    /// A tab spans the gap to whatever lies beyond, so it also interrupts
+    /// the slot of a neighbouring board milled in the same gap.
+    fn add_breakaways(&self, panel: &mut Layout, outlines: &[Vec<Point2D>], spacing: f64, frame: &BoundingBox) {
+        let config = &self.config;
+        let r = config.mill_width / 2.0;
+        let reach = spacing.max(config.mill_width);
+        let inside_frame = |p: &Point2D| {
+            p.x > frame.min.x && p.x < frame.max.x && p.y > frame.min.y && p.y < frame.max.y
+        };
+
+        let mut slots = Vec::new();
+        let mut tabs = Vec::new();
+        for outline in outlines {
+            // Outward normals depend on the winding
+            let outward = if polygon_area(outline) > 0.0 { 1.0 } else { -1.0 };
+            for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+                let length = a.distance(b);
+                if length < EPSILON {
+                    continue;
+                }
+                let along = Point2D::new((b.x - a.x) / length, (b.y - a.y) / length);
+                let normal = Point2D::new(along.y * outward, -along.x * outward);
+                // Extended by the mill radius so slots overlap at corners
+                slots.push((
+                    Point2D::new(a.x + (normal.x - along.x) * r, a.y + (normal.y - along.y) * r),
+                    Point2D::new(b.x + (normal.x + along.x) * r, b.y + (normal.y + along.y) * r),
+                ));
+
+                if length < 2.0 * config.tab_width {
+                    continue;
+                }
+                let count = (length / config.tab_spacing).ceil().max(1.0) as usize;
+                for i in 0..count {
+                    let t = length * (i as f64 + 0.5) / count as f64;
+                    let center = Point2D::new(a.x + along.x * t, a.y + along.y * t);
+                    let beyond = Point2D::new(center.x + normal.x * (reach + r), center.y + normal.y * (reach + r));
+                    if inside_frame(&beyond) {
+                        tabs.push(Tab { center, along, normal, reach });
+                    }
+                }
+            }
+        }
+
+        let outline = panel.outline.as_mut().expect("panel outline");
+        for (a, b) in slots {
+            for (start, end) in uncovered_pieces(a, b, &tabs, config.tab_width / 2.0 + r, r) {
+                outline.add_slot(start, end, config.mill_width);
+            }
+        }
+
+        let holes = (config.tab_width / config.mouse_bite_pitch.max(EPSILON)).floor() as usize + 1;
+        let first = -((holes - 1) as f64) * config.mouse_bite_pitch / 2.0;
+        for (i, tab) in tabs.iter().enumerate() {
+            let mut component = PlacedComponent::new(format!("MB{}", i + 1), "", "MouseBites")
+                .at(tab.center.x, tab.center.y)
+                .rotated(tab.along.y.atan2(tab.along.x).to_degrees());
+            for h in 0..holes {
+                let x = first + h as f64 * config.mouse_bite_pitch;
+                component.pads.push(npth((h + 1).to_string(), x, 0.0, config.mouse_bite_drill));
+            }
+            panel.components.push(component);
+        }
+    }
+
+    /// Add fiducials and tooling holes to the rails.
+    fn add_rail_features(&self, panel: &mut Layout, frame: &BoundingBox) {
+        let config = &self.config;
+        if config.rails == Rails::None {
+            return;
+        }
+        let (w, h) = (frame.width(), frame.height());
+        let (rail, inset) = (config.rail_width / 2.0, config.hole_inset);
+        // Holes near each rail end; three fiducials, asymmetric so the panel
+        // orientation is unambiguous
+        let (holes, fiducials) = if config.rails.horizontal() {
+            (
+                vec![(inset, rail), (w - inset, rail), (inset, h - rail), (w - inset, h - rail)],
+                vec![(2.0 * inset, rail), (w - 2.0 * inset, rail), (2.0 * inset, h - rail)],
+            )
+        } else {
+            (
+                vec![(rail, inset), (rail, h - inset), (w - rail, inset), (w - rail, h - inset)],
+                vec![(rail, 2.0 * inset), (rail, h - 2.0 * inset), (w - rail, 2.0 * inset)],
+            )
+        };
+
+        if config.tooling_holes {
+            for (i, (x, y)) in holes.into_iter().enumerate() {
+                panel.components.push(
+                    PlacedComponent::new(format!("TH{}", i + 1), "", "ToolingHole")
+                        .at(x, y)
+                        .with_pad(npth("1", 0.0, 0.0, config.tooling_hole_drill)),
+                );
+            }
+        }
+        if config.fiducials {
+            let d = config.fiducial_diameter;
+            for (i, (x, y)) in fiducials.into_iter().enumerate() {
+                panel.components.push(
+                    PlacedComponent::new(format!("FID{}", i + 1), "", "Fiducial")
+                        .at(x, y)
+                        .with_pad(Pad::smd("1", 0.0, 0.0, d, d)
+                            .with_shape(PadShape::Circle)
+                            .with_mask_margin(config.fiducial_mask_margin)),
+                );
+            }
+        }
+    }
+}
+
+/// Draw V-score lines across the panel along every cell edge that is not
+/// a panel edge. Boards fill their cells exactly, so these are the board
+/// edges.
+fn add_vscores(panel: &mut Layout, xs: &[f64], widths: &[f64], ys: &[f64], heights: &[f64], frame: &BoundingBox) {
+    let cuts = |starts: &[f64], sizes: &[f64], end: f64| -> Vec<f64> {
+        let mut cuts: Vec<f64> = starts.iter().zip(sizes)
+            .flat_map(|(start, size)| [*start, start + size])
+            .filter(|c| *c > EPSILON && *c < end - EPSILON)
+            .collect();
+        cuts.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+        cuts
+    };
+    for x in cuts(xs, widths, frame.max.x) {
+        panel.graphics.push(Graphic::line(VSCORE_LAYER, Point2D::new(x, frame.min.y), Point2D::new(x, frame.max.y), 0.1));
+    }
+    for y in cuts(ys, heights, frame.max.y) {
+        panel.graphics.push(Graphic::line(VSCORE_LAYER, Point2D::new(frame.min.x, y), Point2D::new(frame.max.x, y), 0.1));
+    }
+    if !panel.layers.iter().any(|l| l.name == VSCORE_LAYER) {
+        panel.layers.push(Layer::new(VSCORE_LAYER, LayerType::Fabrication));
+    }
+}
+
+/// Non-plated hole pad.
+fn npth(number: impl Into<String>, x: f64, y: f64, drill: f64) -> Pad {
+    let mut pad = Pad::thru_hole(number, x, y, drill, drill);
+    pad.pad_type = PadType::Npth;
+    pad
+}
+
+/// Pieces of the slot centerline `a`-`b` clear of every tab. Each tab is
+/// grown by `half_width` along the edge and by `grow` across it so the
+/// rounded slot ends stay out of the tab.
+fn uncovered_pieces(a: Point2D, b: Point2D, tabs: &[Tab], half_width: f64, grow: f64) -> Vec<(Point2D, Point2D)> {
+    let mut covered: Vec<(f64, f64)> = tabs.iter()
+        .filter_map(|tab| {
+            // Clip the segment against the tab box in tab coordinates
+            let local = |p: &Point2D| {
+                let (dx, dy) = (p.x - tab.center.x, p.y - tab.center.y);
+                (dx * tab.along.x + dy * tab.along.y, dx * tab.normal.x + dy * tab.normal.y)
+            };
+            let (u0, v0) = local(&a);
+            let (u1, v1) = local(&b);
+            let (mut t0, mut t1) = (0.0f64, 1.0f64);
+            for (p0, p1, lo, hi) in [(u0, u1, -half_width, half_width), (v0, v1, -grow, tab.reach + grow)] {
+                let d = p1 - p0;
+                if d.abs() < EPSILON {
+                    if p0 < lo || p0 > hi {
+                        return None;
+                    }
+                    continue;
+                }
+                let (ta, tb) = ((lo - p0) / d, (hi - p0) / d);
+                t0 = t0.max(ta.min(tb));
+                t1 = t1.min(ta.max(tb));
+            }
+            (t0 < t1).then_some((t0, t1))
+        })
+        .collect();
+    covered.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let at = |t: f64| Point2D::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+    let length = a.distance(&b);
+    let mut pieces = Vec::new();
+    let mut start = 0.0;
+    for (t0, t1) in covered {
+        if (t0 - start) * length > EPSILON {
+            pieces.push((at(start), at(t0)));
+        }
+        start = start.max(t1);
+    }
+    if (1.0 - start) * length > EPSILON {
+        pieces.push((at(start), at(1.0)));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::point_in_polygon;
+    use crate::layout::Trace;
+
+    fn board(width: f64, height: f64) -> Layout {
+        let mut layout = Layout::with_board_size(width, height, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("R1", "10k", "R_0603")
+                .at(5.0, 5.0)
+                .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9).with_net("SIG"))
+                .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9).with_net("GND")),
+        );
+        layout.traces.push(Trace {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(4.2, 5.0),
+            end: Position::new(2.0, 5.0),
+            width: 0.25,
+            unit: LengthUnit::Mm,
+        });
+        layout
+    }
+
+    #[test]
+    fn test_panel_grid_renames_instances() {
+        let layout = board(20.0, 10.0);
+        let config = PanelConfig::default().with_grid(2, 3);
+        let panel = Panelizer::new(config).with_board(&layout, 0.0).panelize().unwrap();
+
+        // 3 x 20mm boards and 4 gaps wide; 2 x 10mm boards, 3 gaps and two rails high
+        let outline = panel.outline.as_ref().unwrap();
+        let bounds = BoundingBox::from_points(&outline.points).unwrap();
+        assert!((bounds.width() - 68.0).abs() < 1e-9);
+        assert!((bounds.height() - 36.0).abs() < 1e-9);
+
+        let resistors: Vec<&PlacedComponent> = panel.components.iter().filter(|c| c.footprint == "R_0603").collect();
+        assert_eq!(resistors.len(), 6);
+        assert!(panel.component("R1_1").is_some() && panel.component("R1_6").is_some());
+        assert_eq!(panel.traces.iter().filter(|t| t.net == "SIG_4").count(), 1);
+        assert_eq!(panel.component("R1_2").unwrap().pads[1].net.as_deref(), Some("GND_2"));
+        // Second instance sits one board and one gap to the right
+        let (first, second) = (panel.component("R1_1").unwrap(), panel.component("R1_2").unwrap());
+        assert!((second.position.x - first.position.x - 22.0).abs() < 1e-9);
+
+        assert_eq!(panel.components.iter().filter(|c| c.footprint == "Fiducial").count(), 3);
+        assert_eq!(panel.components.iter().filter(|c| c.footprint == "ToolingHole").count(), 4);
+    }
+
+    #[test]
+    fn test_panel_tabs_hold_boards() {
+        let layout = board(20.0, 10.0);
+        let config = PanelConfig::default().with_grid(2, 2);
+        let panel = Panelizer::new(config).with_board(&layout, 0.0).panelize().unwrap();
+        let cutouts = &panel.outline.as_ref().unwrap().cutouts;
+        let bites: Vec<&PlacedComponent> = panel.components.iter().filter(|c| c.footprint == "MouseBites").collect();
+        assert!(!cutouts.is_empty());
+        // Each board has a tab on every edge facing a neighbour or a rail,
+        // none on the open left and right panel edges
+        assert_eq!(bites.len(), 12);
+
+        for bite in bites {
+            assert!(bite.pads.iter().all(|p| p.pad_type == PadType::Npth));
+            // Neither side of the tab is milled away
+            for side in [-0.5, 0.5, 1.5] {
+                let p = bite.to_board(&Point2D::new(0.0, side));
+                assert!(!cutouts.iter().any(|c| point_in_polygon(&p, c)), "{} cut at {:?}", bite.reference, p);
+            }
+        }
+
+        // The slots still separate the boards away from the tabs
+        let first = panel.component("R1_1").unwrap().position.to_point2d();
+        let between = Point2D::new(first.x + 16.0, first.y - 4.0);
+        assert!(cutouts.iter().any(|c| point_in_polygon(&between, c)));
+    }
+
+    #[test]
+    fn test_panel_vscore_mixed_rotated() {
+        let a = board(20.0, 10.0);
+        let b = board(10.0, 20.0);
+        let config = PanelConfig::default()
+            .with_grid(1, 2)
+            .with_separation(Separation::VScore);
+        let panel = Panelizer::new(config)
+            .with_board(&a, 0.0)
+            .with_board(&b, 90.0)
+            .panelize()
+            .unwrap();
+
+        // Both boards are 20 x 10 once the second is rotated
+        let outline = panel.outline.as_ref().unwrap();
+        let bounds = BoundingBox::from_points(&outline.points).unwrap();
+        assert!((bounds.width() - 40.0).abs() < 1e-9);
+        assert!((bounds.height() - 20.0).abs() < 1e-9);
+        assert!(outline.cutouts.is_empty());
+
+        // Scores between the rails and the boards and between the boards
+        let scores: Vec<&Graphic> = panel.graphics.iter().filter(|g| g.layer == VSCORE_LAYER).collect();
+        assert_eq!(scores.len(), 3);
+        assert!(panel.layers.iter().any(|l| l.name == VSCORE_LAYER));
+
+        let rotated = panel.component("R1_2").unwrap();
+        assert_eq!(rotated.rotation, 90.0);
+        assert!(rotated.position.x > 20.0);
+    }
+
+    #[test]
+    fn test_panel_vscore_rejects_misfit_boards() {
+        let vscore = || PanelConfig::default().with_grid(1, 2).with_separation(Separation::VScore);
+        let a = board(20.0, 10.0);
+        let b = board(20.0, 12.0);
+
+        // A shorter board would leave the row scores off its edges
+        let error = Panelizer::new(vscore()).with_board(&a, 0.0).with_board(&b, 0.0).panelize().unwrap_err();
+        assert!(error.message.contains("height per row"));
+        // Stacked in a column the heights may differ
+        let config = vscore().with_grid(2, 1);
+        assert!(Panelizer::new(config).with_board(&a, 0.0).with_board(&b, 0.0).panelize().is_ok());
+
+        let error = Panelizer::new(vscore()).with_board(&a, 45.0).panelize().unwrap_err();
+        assert!(error.message.contains("90 degree steps"));
+        // Mouse bites cope with both
+        let config = PanelConfig::default().with_grid(1, 2);
+        assert!(Panelizer::new(config).with_board(&a, 45.0).with_board(&b, 0.0).panelize().is_ok());
+    }
+}
@@ -3,14 +3,32 @@
 //! Implements DRC rules for PCB layouts including clearance, width, and via checks.
 
 use crate::drc::{DrcConfig, DrcReport, DrcRule, DrcSeverity, DrcViolation};
-use crate::geometry::{Point2D, Position};
-use crate::layout::{Layout, Trace, Via};
+use crate::copper::{collect_copper, collect_copper_where, CopperItem, CopperKind, CopperShape, ShapeCore};
+use crate::constraint::Constraint;
+use crate::drc_rules::{CustomRuleSet, RuleConstraint, RuleContext, RuleSubject, COURTYARD_MARGIN};
+use crate::fab::FabProfile;
+use crate::geometry::{point_in_polygon, polygon_area, segments_intersect, BoundingBox, Point2D, Position, SpatialGrid};
+use crate::high_speed::HighSpeedChecker;
+use crate::layout::{
+    ComponentLayer, Graphic, GraphicShape, KeepoutKind, Layout, Outline, OutlineType, PlacedComponent, RuleArea, Trace,
+    Via, Zone,
+};
+use crate::net::Net;
+use crate::ratsnest::{Ratsnest, UnionFind};
 use crate::units::LengthUnit;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 /// PCB design rules configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PcbDesignRules {
     /// Minimum track-to-track clearance (mm)
     pub min_track_clearance: f64,
@@ -51,6 +69,21 @@ pub struct PcbDesignRules {
     /// Check silkscreen over pads
     pub check_silk_over_pads: bool,
     
+    /// Minimum silkscreen-to-edge clearance (mm)
+    pub min_silk_to_edge: f64,
+    
+    /// Default solder mask expansion around pads (mm)
+    pub mask_expansion: f64,
+    
+    /// Minimum solder mask web between openings (mm)
+    pub min_mask_sliver: f64,
+    
+    /// Minimum paste aperture to pad area ratio
+    pub min_paste_ratio: f64,
+    
+    /// Maximum paste aperture to pad area ratio
+    pub max_paste_ratio: f64,
+    
     /// Minimum courtyard clearance (mm)
     pub min_courtyard_clearance: f64,
 }
@@ -71,50 +104,35 @@ impl Default for PcbDesignRules {
             min_silk_width: 0.15,
             min_silk_text_height: 0.8,
             check_silk_over_pads: true,
+            min_silk_to_edge: 0.2,
+            mask_expansion: 0.05,
+            min_mask_sliver: 0.1,
+            min_paste_ratio: 0.5,
+            max_paste_ratio: 1.0,
             min_courtyard_clearance: 0.25,
         }
     }
 }
 
 impl PcbDesignRules {
-    /// Create rules for JLCPCB manufacturing.
+    /// Create rules for JLCPCB manufacturing with 1oz copper, from the
+    /// built-in `jlcpcb` fab profile.
     pub fn jlcpcb() -> Self {
-        Self {
-            min_track_clearance: 0.127,      // 5mil
-            min_track_to_pad_clearance: 0.127,
-            min_track_to_via_clearance: 0.127,
-            min_via_clearance: 0.254,
-            min_track_width: 0.127,          // 5mil
-            min_via_diameter: 0.45,
-            min_via_drill: 0.2,
-            min_annular_ring: 0.125,
-            min_hole_clearance: 0.5,
-            min_edge_clearance: 0.3,
-            min_silk_width: 0.15,
-            min_silk_text_height: 1.0,
-            check_silk_over_pads: true,
-            min_courtyard_clearance: 0.25,
-        }
+        Self::builtin_fab("jlcpcb")
     }
     
-    /// Create rules for OSH Park manufacturing.
+    /// Create rules for OSH Park manufacturing with 1oz copper, from the
+    /// built-in `osh_park` fab profile.
     pub fn osh_park() -> Self {
-        Self {
-            min_track_clearance: 0.152,      // 6mil
-            min_track_to_pad_clearance: 0.152,
-            min_track_to_via_clearance: 0.152,
-            min_via_clearance: 0.254,
-            min_track_width: 0.152,          // 6mil
-            min_via_diameter: 0.5,
-            min_via_drill: 0.254,            // 10mil
-            min_annular_ring: 0.127,
-            min_hole_clearance: 0.381,
-            min_edge_clearance: 0.381,
-            min_silk_width: 0.152,
-            min_silk_text_height: 0.8,
-            check_silk_over_pads: true,
-            min_courtyard_clearance: 0.25,
-        }
+        Self::builtin_fab("osh_park")
+    }
+    
+    fn builtin_fab(name: &str) -> Self {
+        FabProfile::builtin()
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.design_rules(1.0).ok())
+            .expect("built-in fab profile offers 1oz copper")
     }
 }
 
@@ -122,236 +140,905 @@ impl PcbDesignRules {
 pub struct PcbDrcChecker<'a> {
     layout: &'a Layout,
     rules: PcbDesignRules,
+    custom_rules: Cow<'a, CustomRuleSet>,
+    /// Custom rules merged with the layout's rule area rules, built on
+    /// first use
+    effective_rules: OnceLock<Cow<'a, CustomRuleSet>>,
+    net_classes: Cow<'a, HashMap<String, String>>,
+    /// Nets and constraints for the high-speed rules
+    nets: Cow<'a, [Net]>,
+    constraints: Cow<'a, [Constraint]>,
+    /// Regions to recheck; `None` checks the whole board
+    scope: Option<Vec<BoundingBox>>,
+    /// Worker threads for `check_all`
+    threads: usize,
 }
 
 impl<'a> PcbDrcChecker<'a> {
     /// Create a new PCB DRC checker.
     pub fn new(layout: &'a Layout, rules: PcbDesignRules) -> Self {
-        Self { layout, rules }
+        Self {
+            layout,
+            rules,
+            custom_rules: Cow::Owned(CustomRuleSet::default()),
+            effective_rules: OnceLock::new(),
+            net_classes: Cow::Owned(HashMap::new()),
+            nets: Cow::Owned(Vec::new()),
+            constraints: Cow::Owned(Vec::new()),
+            scope: None,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+    
+    /// Apply custom rules on top of the design rules.
+    pub fn with_custom_rules(mut self, custom_rules: CustomRuleSet) -> Self {
+        self.custom_rules = Cow::Owned(custom_rules);
+        self.effective_rules = OnceLock::new();
+        self
+    }
+    
+    /// Set the net class of each net, for `netclass` conditions.
+    pub fn with_net_classes(mut self, net_classes: HashMap<String, String>) -> Self {
+        self.net_classes = Cow::Owned(net_classes);
+        self
+    }
+    
+    /// Set the circuit's nets, whose types select the high-speed checks.
+    pub fn with_nets(mut self, nets: Vec<Net>) -> Self {
+        self.nets = Cow::Owned(nets);
+        self
+    }
+    
+    /// Set the routing constraints for the high-speed checks.
+    pub fn with_constraints(mut self, constraints: Vec<Constraint>) -> Self {
+        self.constraints = Cow::Owned(constraints);
+        self
+    }
+    
+    /// Set the number of worker threads (defaults to the available cores).
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    
+    /// Custom rules merged with the layout's rule area rules.
+    fn effective_rules(&self) -> &CustomRuleSet {
+        self.effective_rules.get_or_init(|| {
+            Cow::Owned(self.custom_rules.merged(&CustomRuleSet::from_areas(self.layout)))
+        })
     }
     
     /// Run all PCB DRC checks.
     pub fn check_all(&self) -> DrcReport {
-        let mut report = DrcReport::new("PCB Layout", "pcb");
+        self.check_all_timed().0
+    }
+    
+    /// Run all PCB DRC checks in parallel and report the time spent in each.
+    ///
+    /// Checks run as independent jobs, with copper clearance split into
+    /// tiles per layer and block of items. Violations are merged in job
+    /// order, so the report is the same for any number of threads.
+    pub fn check_all_timed(&self) -> (DrcReport, DrcTimings) {
+        let started = Instant::now();
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
+        let clearance = self.clearance_data(collect_copper(self.layout));
+        let mask = self.mask_openings(&clearance.items);
+        let prepare = started.elapsed();
         
-        self.check_track_widths(&mut report);
-        self.check_track_clearances(&mut report);
-        self.check_via_rules(&mut report);
-        self.check_edge_clearances(&mut report);
-        self.check_courtyard_overlaps(&mut report);
+        let jobs = self.jobs(&clearance);
+        let next = AtomicUsize::new(0);
+        let threads = self.threads.min(jobs.len()).max(1);
+        let worker = || {
+            let mut done = Vec::new();
+            loop {
+                let index = next.fetch_add(1, atomic::Ordering::Relaxed);
+                let Some(job) = jobs.get(index) else { break };
+                let job_started = Instant::now();
+                let mut report = DrcReport::new("PCB Layout", "pcb");
+                self.run_job(job, &ctx, &clearance, &mask, &mut report);
+                done.push((index, report.violations, job_started.elapsed()));
+            }
+            done
+        };
+        let mut done: Vec<(usize, Vec<DrcViolation>, Duration)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|_| scope.spawn(worker)).collect();
+            workers.into_iter()
+                .flat_map(|w| w.join().expect("DRC worker panicked"))
+                .collect()
+        });
+        done.sort_by_key(|(index, ..)| *index);
         
-        report
+        let mut report = DrcReport::new("PCB Layout", "pcb");
+        let mut timings = DrcTimings {
+            checks: DrcCheck::ALL.iter()
+                .map(|&check| CheckTiming { check, duration: Duration::ZERO, violations: 0 })
+                .collect(),
+            prepare,
+            total: Duration::ZERO,
+            threads,
+        };
+        let slot = |check: DrcCheck| DrcCheck::ALL.iter().position(|&c| c == check).expect("check listed in ALL");
+        for (index, violations, duration) in done {
+            let timing = &mut timings.checks[slot(jobs[index].check)];
+            timing.duration += duration;
+            timing.violations += violations.len();
+            report.violations.extend(violations);
+        }
+        timings.total = started.elapsed();
+        (report, timings)
+    }
+    
+    /// Jobs for a full check, in report order.
+    fn jobs(&self, clearance: &ClearanceData) -> Vec<DrcJob> {
+        let mut jobs = Vec::new();
+        for &check in DrcCheck::ALL {
+            if check != DrcCheck::CopperClearance {
+                jobs.push(DrcJob { check, tile: None });
+                continue;
+            }
+            for layer in 0..clearance.layers.len() {
+                for start in (0..clearance.items.len()).step_by(CLEARANCE_TILE_ITEMS) {
+                    let rows = start..(start + CLEARANCE_TILE_ITEMS).min(clearance.items.len());
+                    jobs.push(DrcJob { check, tile: Some((layer, rows)) });
+                }
+            }
+        }
+        jobs
+    }
+    
+    fn run_job(
+        &self,
+        job: &DrcJob,
+        ctx: &RuleContext,
+        clearance: &ClearanceData,
+        mask: &MaskOpenings,
+        report: &mut DrcReport,
+    ) {
+        match job.check {
+            DrcCheck::TrackWidths => self.check_track_widths(report),
+            DrcCheck::CopperClearance => {
+                if let Some((layer, rows)) = &job.tile {
+                    self.check_clearance_tile(ctx, clearance, *layer, rows.clone(), report);
+                }
+            }
+            DrcCheck::ViaRules => self.check_via_rules(report),
+            DrcCheck::Outline => self.check_outline(report),
+            DrcCheck::EdgeClearance => self.check_edge_clearances(&clearance.items, report),
+            DrcCheck::Keepout => self.check_keepouts(&clearance.items, report),
+            DrcCheck::Courtyard => self.check_courtyard_overlaps(report),
+            DrcCheck::Silkscreen => self.check_silkscreen(mask, report),
+            DrcCheck::SolderMask => self.check_solder_mask(mask, report),
+            DrcCheck::Paste => self.check_paste(report),
+            DrcCheck::HighSpeed => self.check_high_speed(report),
+            DrcCheck::Connectivity => {
+                report.violations.extend(self.connectivity_violations(None).into_iter().map(|(_, v)| v));
+            }
+        }
+    }
+    
+    /// Check the high-speed rules for the nets and constraints.
+    fn check_high_speed(&self, report: &mut DrcReport) {
+        if self.scope.is_none() {
+            report.violations.extend(HighSpeedChecker::new(self.layout, &self.nets, &self.constraints).check());
+        }
+    }
+    
+    /// Run the checks whose violations are located at the items involved,
+    /// which incremental DRC reruns around changed items.
+    fn check_local(&self, clearance: &ClearanceData, mask: &MaskOpenings, report: &mut DrcReport) {
+        self.check_track_widths(report);
+        self.check_copper_clearances(clearance, report);
+        self.check_via_rules(report);
+        self.check_outline(report);
+        self.check_edge_clearances(&clearance.items, report);
+        self.check_keepouts(&clearance.items, report);
+        self.check_courtyard_overlaps(report);
+        self.check_silkscreen(mask, report);
+        self.check_solder_mask(mask, report);
+        self.check_paste(report);
+    }
+    
+    /// Whether an item with these bounds lies in the checked regions.
+    fn in_scope(&self, bounds: Option<&BoundingBox>) -> bool {
+        match (&self.scope, bounds) {
+            (None, _) => true,
+            (Some(regions), Some(bounds)) => regions.iter().any(|r| r.intersects(bounds)),
+            (Some(_), None) => false,
+        }
+    }
+    
+    /// Check proposed tracks and vias, such as a route being drawn, against
+    /// the existing copper without adding them to the layout. Covers track
+    /// width, via size, copper clearance, edge clearance and rule areas.
+    pub fn check_proposed(&self, traces: &[Trace], vias: &[Via]) -> Vec<DrcViolation> {
+        self.check_proposed_near(&CopperIndex::new(self.layout), traces, vias)
+    }
+    
+    /// `check_proposed` against indexed copper of the layout.
+    fn check_proposed_near(&self, existing: &CopperIndex, traces: &[Trace], vias: &[Via]) -> Vec<DrcViolation> {
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
+        let copper = self.layout.copper_layer_names();
+        let proposed: Vec<CopperItem> = traces.iter().map(CopperItem::from_trace)
+            .chain(vias.iter().map(|via| CopperItem::from_via(via, &copper)))
+            .collect();
+        let max_clearance = self.max_clearance(existing.max_clearance.max(item_clearance(&proposed)));
+        let edges = self.layout.outline.as_ref().map(BoardEdges::from_outline);
+        let areas = self.keepout_areas();
+        let mut report = DrcReport::new("Proposed Route", "pcb");
+        
+        for trace in traces {
+            let subject = trace_subject(trace);
+            let (min_width, rule) = self.required(&ctx, RuleConstraint::TrackWidth,
+                self.rules.min_track_width, &subject, None, std::slice::from_ref(&trace.layer));
+            if trace.width < min_width {
+                report.violations.push(
+                    DrcViolation::new(
+                        "width.track",
+                        format!("Track width {:.3}mm is below minimum {:.3}mm{}", trace.width, min_width, rule),
+                        trace_midpoint(trace),
+                    )
+                    .with_severity(DrcSeverity::Error)
+                    .with_values(trace.width, min_width, "mm")
+                );
+            }
+        }
+        for via in vias {
+            self.check_via(&ctx, &copper, via, &mut report);
+        }
+        for item in &proposed {
+            let Some(bounds) = item.shape.bounding_box().map(|b| b.expanded(max_clearance)) else { continue };
+            for other in existing.query(&bounds) {
+                if let Some(violation) = self.check_pair(&ctx, item, other) {
+                    report.violations.push(violation);
+                }
+            }
+            if let Some(edges) = &edges {
+                self.check_edge_clearance(&ctx, edges, item, &mut report);
+            }
+            self.check_keepout(&areas, item, &mut report);
+        }
+        report.violations
     }
     
     /// Check minimum track widths.
     fn check_track_widths(&self, report: &mut DrcReport) {
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
         for trace in &self.layout.traces {
-            if trace.width < self.rules.min_track_width {
+            let subject = trace_subject(trace);
+            if !self.in_scope(subject.bounds.as_ref()) {
+                continue;
+            }
+            let (min_width, rule) = self.required(&ctx, RuleConstraint::TrackWidth,
+                self.rules.min_track_width, &subject, None, std::slice::from_ref(&trace.layer));
+            if trace.width < min_width {
                 let midpoint = trace_midpoint(trace);
                 report.violations.push(
                     DrcViolation::new(
                         "width.track",
-                        format!("Track width {:.3}mm is below minimum {:.3}mm", 
-                            trace.width, self.rules.min_track_width),
+                        format!("Track width {:.3}mm is below minimum {:.3}mm{}", 
+                            trace.width, min_width, rule),
                         midpoint,
                     )
                     .with_severity(DrcSeverity::Error)
-                    .with_values(trace.width, self.rules.min_track_width, "mm")
+                    .with_values(trace.width, min_width, "mm")
                     .with_fix(format!("Increase track width to at least {:.3}mm", 
-                        self.rules.min_track_width))
+                        min_width))
                 );
             }
         }
     }
     
-    /// Check track-to-track clearances.
-    fn check_track_clearances(&self, report: &mut DrcReport) {
-        let traces = &self.layout.traces;
-        
-        for i in 0..traces.len() {
-            for j in (i + 1)..traces.len() {
-                let t1 = &traces[i];
-                let t2 = &traces[j];
-                
-                // Skip if on different layers
-                if t1.layer != t2.layer {
-                    continue;
-                }
-                
-                // Skip if same net
-                if t1.net == t2.net {
+    /// Minimum for a constraint after applying the winning custom rule on
+    /// any of `layers`, with a message suffix naming that rule.
+    fn required(
+        &self,
+        ctx: &RuleContext,
+        constraint: RuleConstraint,
+        default: f64,
+        a: &RuleSubject,
+        b: Option<&RuleSubject>,
+        layers: &[String],
+    ) -> (f64, String) {
+        if self.effective_rules().is_empty() {
+            return (default, String::new());
+        }
+        match self.effective_rules().find_on_layers(constraint, ctx, a, b, layers) {
+            Some(rule) => (rule.min, format!(" (rule '{}')", rule.name)),
+            None => (default, String::new()),
+        }
+    }
+    
+    /// Check clearances between copper items of different nets using their
+    /// exact outlines, reporting overlapping copper as shorts.
+    ///
+    /// Outlines of unfilled zones stand in for their future fill, which
+    /// keeps its own clearance to other nets, so they are only checked
+    /// against other zone outlines. Filled zones are checked by their fill.
+    fn check_copper_clearances(&self, clearance: &ClearanceData, report: &mut DrcReport) {
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
+        for layer in 0..clearance.layers.len() {
+            self.check_clearance_tile(&ctx, clearance, layer, 0..clearance.items.len(), report);
+        }
+    }
+    
+    /// The copper items in scope with their broad-phase bounds.
+    fn clearance_data(&self, items: Vec<CopperItem>) -> ClearanceData {
+        let mut items: Vec<CopperItem> = items.into_iter()
+            .filter(|item| self.in_scope(item.shape.bounding_box().as_ref()))
+            .collect();
+        // Pair violations then read the same wherever the items came from
+        items.sort_by(copper_order);
+        let max_clearance = self.max_clearance(item_clearance(&items));
+        let grid = SpatialGrid::new(items.iter()
+            .map(|item| item.shape.bounding_box().map(|b| b.expanded(max_clearance)))
+            .collect());
+        let mut layers: Vec<String> = Vec::new();
+        for layer in items.iter().flat_map(|item| &item.layers) {
+            if !layers.contains(layer) {
+                layers.push(layer.clone());
+            }
+        }
+        ClearanceData { items, grid, layers }
+    }
+    
+    /// Check the pairs starting at items `rows` whose first shared layer is
+    /// `layer`, so each pair is checked in exactly one tile.
+    fn check_clearance_tile(
+        &self,
+        ctx: &RuleContext,
+        clearance: &ClearanceData,
+        layer: usize,
+        rows: Range<usize>,
+        report: &mut DrcReport,
+    ) {
+        let items = &clearance.items;
+        let layer = &clearance.layers[layer];
+        for i in rows {
+            if !items[i].layers.contains(layer) {
+                continue;
+            }
+            let Some(bounds) = clearance.grid.bounds(i) else { continue };
+            for j in clearance.grid.query(bounds).into_iter().filter(|&j| j > i) {
+                let (a, b) = (&items[i], &items[j]);
+                if a.layers.iter().find(|l| b.layers.contains(l)) != Some(layer) {
                     continue;
                 }
-                
-                // Calculate minimum distance between traces
-                if let Some(clearance) = min_trace_distance(t1, t2) {
-                    if clearance < self.rules.min_track_clearance {
-                        let midpoint = trace_midpoint(t1);
-                        report.violations.push(
-                            DrcViolation::new(
-                                "clearance.track_to_track",
-                                format!("Track clearance {:.3}mm is below minimum {:.3}mm",
-                                    clearance, self.rules.min_track_clearance),
-                                midpoint,
-                            )
-                            .with_severity(DrcSeverity::Error)
-                            .with_values(clearance, self.rules.min_track_clearance, "mm")
-                            .with_fix("Increase spacing between tracks")
-                        );
-                    }
+                if let Some(violation) = self.check_pair(ctx, a, b) {
+                    report.violations.push(violation);
                 }
             }
         }
     }
     
+    /// Clearance or short violation between two copper items, located at
+    /// the point of `a` closest to `b`.
+    fn check_pair(&self, ctx: &RuleContext, a: &CopperItem, b: &CopperItem) -> Option<DrcViolation> {
+        if a.same_net(b) || !a.shares_layer(b) {
+            return None;
+        }
+        let zone_outline = a.kind == CopperKind::Zone || b.kind == CopperKind::Zone;
+        if zone_outline && a.kind != b.kind {
+            return None;
+        }
+        
+        let distance = a.shape.distance(&b.shape);
+        let location = a.shape.contact_point(&b.shape);
+        if distance <= 0.0 {
+            return Some(
+                DrcViolation::new(
+                    "short.copper",
+                    format!("Short between {} and {}", a.label, b.label),
+                    location,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_fix("Separate the copper of the two nets")
+            );
+        }
+        
+        let (rule, default) = self.clearance_rule(a, b);
+        let layers: Vec<String> = a.layers.iter().filter(|l| b.layers.contains(l)).cloned().collect();
+        let (required, custom) = self.required(ctx, RuleConstraint::Clearance, default,
+            &RuleSubject::from_copper(a, self.layout),
+            Some(&RuleSubject::from_copper(b, self.layout)), &layers);
+        (distance < required).then(|| {
+            DrcViolation::new(
+                rule,
+                format!("Clearance {:.3}mm between {} and {} is below minimum {:.3}mm{}",
+                    distance, a.label, b.label, required, custom),
+                location,
+            )
+            .with_severity(DrcSeverity::Error)
+            .with_values(distance, required, "mm")
+            .with_fix(format!("Increase spacing between {} and {}", a.kind.name(), b.kind.name()))
+        })
+    }
+    
+    /// Rule id and minimum clearance for a pair of copper items.
+    fn clearance_rule(&self, a: &CopperItem, b: &CopperItem) -> (&'static str, f64) {
+        use CopperKind::*;
+        
+        let (first, second) = if a.kind <= b.kind { (a, b) } else { (b, a) };
+        match (first.kind, second.kind) {
+            (Track, Track) => ("clearance.track_to_track", self.rules.min_track_clearance),
+            (Track, Via) => ("clearance.track_to_via", self.rules.min_track_to_via_clearance),
+            (Track, Pad) => ("clearance.track_to_pad", self.rules.min_track_to_pad_clearance),
+            (Via, Via) => ("clearance.via_to_via", self.rules.min_via_clearance),
+            (Via, Pad) => ("clearance.via_to_pad", self.rules.min_track_to_via_clearance),
+            (Pad, Pad) => ("clearance.pad_to_pad", self.rules.min_track_to_pad_clearance),
+            (_, Teardrop) => ("clearance.teardrop", self.rules.min_track_clearance),
+            _ => {
+                let zone = if matches!(first.kind, Zone | ZoneFill) { first } else { second };
+                ("clearance.zone", zone.clearance.unwrap_or(self.rules.min_track_clearance))
+            }
+        }
+    }
+    
+    /// Largest clearance any rule can require given the largest
+    /// item-specific clearance, for broad-phase filtering.
+    fn max_clearance(&self, item_max: f64) -> f64 {
+        [
+            self.rules.min_track_clearance,
+            self.rules.min_track_to_pad_clearance,
+            self.rules.min_track_to_via_clearance,
+            self.rules.min_via_clearance,
+            self.effective_rules().max_min(RuleConstraint::Clearance),
+            item_max,
+        ]
+        .into_iter()
+        .fold(0.0, f64::max)
+    }
+    
     /// Check via rules (diameter, drill, annular ring).
     fn check_via_rules(&self, report: &mut DrcReport) {
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
+        let copper = self.layout.copper_layer_names();
         for via in &self.layout.vias {
-            // Check via diameter
-            if via.pad < self.rules.min_via_diameter {
+            if self.in_scope(via_subject(via).bounds.as_ref()) {
+                self.check_via(&ctx, &copper, via, report);
+            }
+        }
+    }
+    
+    /// Check one via's diameter, drill and annular ring.
+    fn check_via(&self, ctx: &RuleContext, copper: &[String], via: &Via, report: &mut DrcReport) {
+        let subject = via_subject(via);
+        let layers = via.copper_layers(copper);
+        
+        // Check via diameter
+        let (min_diameter, rule) = self.required(ctx, RuleConstraint::ViaDiameter,
+            self.rules.min_via_diameter, &subject, None, &layers);
+        if via.pad < min_diameter {
+            report.violations.push(
+                DrcViolation::new(
+                    "size.via_diameter",
+                    format!("Via diameter {:.3}mm is below minimum {:.3}mm{}",
+                        via.pad, min_diameter, rule),
+                    position_to_point(&via.position),
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(via.pad, min_diameter, "mm")
+            );
+        }
+        
+        // Check via drill
+        let (min_drill, rule) = self.required(ctx, RuleConstraint::ViaDrill,
+            self.rules.min_via_drill, &subject, None, &layers);
+        if via.drill < min_drill {
+            report.violations.push(
+                DrcViolation::new(
+                    "size.via_drill",
+                    format!("Via drill {:.3}mm is below minimum {:.3}mm{}",
+                        via.drill, min_drill, rule),
+                    position_to_point(&via.position),
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(via.drill, min_drill, "mm")
+            );
+        }
+        
+        // Check annular ring
+        let annular_ring = (via.pad - via.drill) / 2.0;
+        if annular_ring < self.rules.min_annular_ring {
+            report.violations.push(
+                DrcViolation::new(
+                    "size.annular_ring",
+                    format!("Annular ring {:.3}mm is below minimum {:.3}mm",
+                        annular_ring, self.rules.min_annular_ring),
+                    position_to_point(&via.position),
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_values(annular_ring, self.rules.min_annular_ring, "mm")
+                .with_fix("Increase via diameter or decrease drill size")
+            );
+        }
+    }
+    
+    /// Check copper-to-edge clearances against the board outline, its
+    /// cutouts and slots.
+    fn check_edge_clearances(&self, items: &[CopperItem], report: &mut DrcReport) {
+        let Some(outline) = &self.layout.outline else { return };
+        let edges = BoardEdges::from_outline(outline);
+        let ctx = RuleContext::new(self.layout, &self.net_classes);
+        
+        for item in items {
+            if self.in_scope(item.shape.bounding_box().as_ref()) {
+                self.check_edge_clearance(&ctx, &edges, item, report);
+            }
+        }
+    }
+    
+    /// Check one copper item's clearance to the board edges.
+    fn check_edge_clearance(&self, ctx: &RuleContext, edges: &BoardEdges, item: &CopperItem, report: &mut DrcReport) {
+        let clearance = edges.clearance(&item.shape);
+        let (min, rule) = self.required(ctx, RuleConstraint::EdgeClearance,
+            self.rules.min_edge_clearance, &RuleSubject::from_copper(item, self.layout), None, &item.layers);
+        if clearance >= min {
+            return;
+        }
+        let message = if clearance < 0.0 {
+            format!("{} lies outside the board outline", item.label)
+        } else {
+            format!("{} is too close to board edge ({:.3}mm < {:.3}mm){}", item.label, clearance, min, rule)
+        };
+        report.violations.push(
+            DrcViolation::new("clearance.edge", message, item.shape.anchor())
+                .with_severity(DrcSeverity::Error)
+                .with_values(clearance.max(0.0), min, "mm")
+                .with_fix(format!("Move the {} away from the board edge", item.kind.name()))
+        );
+    }
+    
+    /// Check that the board outline and its cutouts are closed, simple polygons.
+    fn check_outline(&self, report: &mut DrcReport) {
+        let Some(outline) = &self.layout.outline else { return };
+        if self.scope.is_some() {
+            return;
+        }
+        let polygon = outline_polygon(outline);
+        
+        let mut invalid = |rule: &str, message: String, location: Point2D| {
+            report.violations.push(
+                DrcViolation::new(rule, message, location)
+                    .with_severity(DrcSeverity::Error)
+                    .with_fix("Redraw the board outline as a single closed shape")
+            );
+        };
+        
+        if let Some(p) = self_intersection(&polygon) {
+            invalid("outline.self_intersecting", "Board outline intersects itself".to_string(), p);
+            return;
+        }
+        if polygon_area(&polygon).abs() < 1e-9 {
+            invalid("outline.not_closed", "Board outline does not enclose an area".to_string(),
+                polygon.first().copied().unwrap_or_default());
+            return;
+        }
+        
+        for cutout in &outline.cutouts {
+            let cutout: Vec<Point2D> = cutout.iter()
+                .map(|p| Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y)))
+                .collect();
+            let location = cutout.first().copied().unwrap_or_default();
+            if let Some(p) = self_intersection(&cutout) {
+                invalid("outline.self_intersecting", "Board cutout intersects itself".to_string(), p);
+            } else if polygon_area(&cutout).abs() < 1e-9 {
+                invalid("outline.not_closed", "Board cutout does not enclose an area".to_string(), location);
+            } else if polygons_cross(&cutout, &polygon) {
+                invalid("outline.self_intersecting", "Board cutout crosses the board outline".to_string(), location);
+            } else if !point_in_polygon(&location, &polygon) {
+                invalid("outline.self_intersecting", "Board cutout lies outside the board outline".to_string(), location);
+            }
+        }
+    }
+    
+    /// Check the clearance between component courtyards on the same side.
+    fn check_courtyard_overlaps(&self, report: &mut DrcReport) {
+        let courtyards: Vec<(&PlacedComponent, BoundingBox)> = self.layout.components.iter()
+            .filter_map(|c| Some((c, c.courtyard(COURTYARD_MARGIN)?)))
+            .filter(|(_, courtyard)| self.in_scope(Some(courtyard)))
+            .collect();
+        let min = self.rules.min_courtyard_clearance;
+        
+        for (i, (c1, a)) in courtyards.iter().enumerate() {
+            for (c2, b) in &courtyards[i + 1..] {
+                // Only check components on the same layer
+                if c1.layer != c2.layer {
+                    continue;
+                }
+                
+                // Gap along the axis separating the courtyards, or corner to
+                // corner when they are apart on both axes; negative when
+                // they overlap
+                let gap_x = (a.min.x - b.max.x).max(b.min.x - a.max.x);
+                let gap_y = (a.min.y - b.max.y).max(b.min.y - a.max.y);
+                let gap = if gap_x > 0.0 && gap_y > 0.0 { gap_x.hypot(gap_y) } else { gap_x.max(gap_y) };
+                if gap < min {
+                    let between = |lo1: f64, hi1: f64, lo2: f64, hi2: f64| (lo1.max(lo2) + hi1.min(hi2)) / 2.0;
+                    let location = Point2D::new(between(a.min.x, a.max.x, b.min.x, b.max.x), between(a.min.y, a.max.y, b.min.y, b.max.y));
+                    let message = if gap < 0.0 {
+                        format!("Courtyards of {} and {} overlap", c1.reference, c2.reference)
+                    } else {
+                        format!("Courtyards of {} and {} are {:.3}mm apart (min: {:.3}mm)", c1.reference, c2.reference, gap, min)
+                    };
+                    report.violations.push(
+                        DrcViolation::new("clearance.courtyard", message, location)
+                            .with_severity(DrcSeverity::Warning)
+                            .with_values(gap.max(0.0), min, "mm")
+                    );
+                }
+            }
+        }
+    }
+    
+    /// Check tracks, vias, pads, zone fills and footprints inside rule
+    /// areas that forbid them.
+    fn check_keepouts(&self, items: &[CopperItem], report: &mut DrcReport) {
+        if self.layout.rule_areas.is_empty() {
+            return;
+        }
+        let areas = self.keepout_areas();
+        for item in items {
+            if self.in_scope(item.shape.bounding_box().as_ref()) {
+                self.check_keepout(&areas, item, report);
+            }
+        }
+        
+        for component in &self.layout.components {
+            let Some(courtyard) = component.courtyard(COURTYARD_MARGIN) else { continue };
+            if !self.in_scope(Some(&courtyard)) {
+                continue;
+            }
+            if let Some(area) = self.layout.placement_keepout(component, COURTYARD_MARGIN) {
                 report.violations.push(
                     DrcViolation::new(
-                        "size.via_diameter",
-                        format!("Via diameter {:.3}mm is below minimum {:.3}mm",
-                            via.pad, self.rules.min_via_diameter),
-                        position_to_point(&via.position),
+                        "keepout.footprint",
+                        format!("Keepout area '{}' contains footprint {}", area.name, component.reference),
+                        courtyard.center(),
                     )
                     .with_severity(DrcSeverity::Error)
-                    .with_values(via.pad, self.rules.min_via_diameter, "mm")
+                    .with_fix(format!("Move {} out of '{}'", component.reference, area.name))
                 );
             }
+        }
+    }
+    
+    /// Rule areas with their outlines in mm.
+    fn keepout_areas(&self) -> Vec<(&RuleArea, CopperShape)> {
+        self.layout.rule_areas.iter()
+            .map(|area| (area, CopperShape::polygon(area.polygon_mm())))
+            .collect()
+    }
+    
+    /// Check one copper item against the rule areas that forbid it.
+    fn check_keepout(&self, areas: &[(&RuleArea, CopperShape)], item: &CopperItem, report: &mut DrcReport) {
+        let kind = match item.kind {
+            CopperKind::Track => KeepoutKind::Track,
+            CopperKind::Via => KeepoutKind::Via,
+            CopperKind::Pad => KeepoutKind::Pad,
+            CopperKind::ZoneFill => KeepoutKind::CopperPour,
+            CopperKind::Zone | CopperKind::Teardrop => return,
+        };
+        for (area, outline) in areas {
+            if !item.layers.iter().any(|l| area.forbids(kind, l)) || item.shape.distance(outline) > 0.0 {
+                continue;
+            }
+            report.violations.push(
+                DrcViolation::new(
+                    format!("keepout.{}", kind.name()),
+                    format!("Keepout area '{}' contains {}", area.name, item.label),
+                    item.shape.contact_point(outline),
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_fix(format!("Move the {} out of '{}'", kind.name().replace('_', " "), area.name))
+            );
+        }
+    }
+    
+    /// Check connectivity: unrouted connections, dangling track ends,
+    /// floating vias and zones not connected to any pad. Copper touching two
+    /// nets is reported by the clearance check as `short.copper`.
+    ///
+    /// Returns each violation with its net; `nets` limits the check to
+    /// those nets.
+    fn connectivity_violations(&self, nets: Option<&HashSet<String>>) -> Vec<(String, DrcViolation)> {
+        const TOUCH: f64 = 1e-6;
+        let mut violations = Vec::new();
+        
+        let ratsnest = match nets {
+            Some(nets) => {
+                let mut ratsnest = Ratsnest::default();
+                ratsnest.update_nets(self.layout, nets.iter().map(String::as_str));
+                ratsnest
+            }
+            None => Ratsnest::compute(self.layout),
+        };
+        for conn in ratsnest.open_connections() {
+            violations.push((conn.net.clone(),
+                DrcViolation::new(
+                    "connectivity.unrouted",
+                    format!("Net {} has an unrouted connection ({:.3}mm)", conn.net, conn.length),
+                    conn.from,
+                )
+                .with_severity(DrcSeverity::Error)
+                .with_fix("Route the connection or join the copper islands")
+            ));
+        }
+        
+        // Group same-net copper that touches into islands
+        let items = match nets {
+            Some(nets) => collect_copper_where(self.layout, |net, _| nets.contains(net)),
+            None => collect_copper(self.layout),
+        };
+        let grid = SpatialGrid::new(items.iter()
+            .map(|item| item.shape.bounding_box().map(|b| b.expanded(TOUCH)))
+            .collect());
+        let mut islands = UnionFind::new(items.len());
+        let mut touching: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
+        for i in 0..items.len() {
+            let Some(bounds) = grid.bounds(i) else { continue };
+            for j in grid.query(bounds).into_iter().filter(|&j| j > i) {
+                let (a, b) = (&items[i], &items[j]);
+                if !a.same_net(b) || !a.shares_layer(b) {
+                    continue;
+                }
+                if a.shape.distance(&b.shape) <= TOUCH {
+                    islands.union(i, j);
+                    touching[i].push(j);
+                    touching[j].push(i);
+                }
+            }
+        }
+        let mut has_pad = vec![false; items.len()];
+        for (i, item) in items.iter().enumerate() {
+            if item.kind == CopperKind::Pad {
+                let root = islands.find(i);
+                has_pad[root] = true;
+            }
+        }
+        // A zone fill is made of many polygons; report each island once
+        let mut reported = HashSet::new();
+        
+        for (i, item) in items.iter().enumerate() {
+            match item.kind {
+                CopperKind::Track => {
+                    let ShapeCore::Segment(start, end) = &item.shape.core else { continue };
+                    for point in [start, end] {
+                        let probe = CopperShape::circle(*point, 0.0);
+                        let connected = touching[i].iter()
+                            .any(|&j| probe.distance(&items[j].shape) <= TOUCH);
+                        if !connected {
+                            violations.push((item.net.clone(),
+                                DrcViolation::new(
+                                    "connectivity.dangling_track",
+                                    format!("Track end of net {} on {} is not connected", item.net, item.layers[0]),
+                                    *point,
+                                )
+                                .with_severity(DrcSeverity::Warning)
+                                .with_fix("Connect the track end or remove the stub")
+                            ));
+                        }
+                    }
+                }
+                CopperKind::Via => {
+                    let mut layers: Vec<&String> = touching[i].iter()
+                        .flat_map(|&j| items[j].layers.iter())
+                        .filter(|l| item.layers.contains(l))
+                        .collect();
+                    layers.sort();
+                    layers.dedup();
+                    let (rule, message) = match layers.len() {
+                        0 => ("connectivity.floating_via", format!("Via of net {} is not connected to any copper", item.net)),
+                        1 => ("connectivity.dangling_via", format!("Via of net {} connects only on {}", item.net, layers[0])),
+                        _ => continue,
+                    };
+                    violations.push((item.net.clone(),
+                        DrcViolation::new(rule, message, item.shape.anchor())
+                            .with_severity(DrcSeverity::Warning)
+                            .with_fix("Connect the via on a second layer or remove it")
+                    ));
+                }
+                CopperKind::Zone | CopperKind::ZoneFill | CopperKind::Teardrop => {
+                    let root = islands.find(i);
+                    if item.kind == CopperKind::ZoneFill && !reported.insert(root) {
+                        continue;
+                    }
+                    if !has_pad[root] {
+                        violations.push((item.net.clone(),
+                            DrcViolation::new(
+                                "connectivity.zone_island",
+                                format!("{} is not connected to any pad", item.label),
+                                item.shape.anchor(),
+                            )
+                            .with_severity(DrcSeverity::Warning)
+                            .with_fix("Connect the zone to its net or remove the isolated copper")
+                        ));
+                    }
+                }
+                CopperKind::Pad => {}
+            }
+        }
+        violations
+    }
+    
+    /// Check silkscreen stroke widths, text sizes, clearance to the board
+    /// edge and overlap with exposed copper, which the fab clips away.
+    fn check_silkscreen(&self, mask: &MaskOpenings, report: &mut DrcReport) {
+        let edges = self.layout.outline.as_ref().map(BoardEdges::from_outline);
+        
+        for (owner, graphic) in self.silk_graphics() {
+            let label = match (&graphic.shape, owner) {
+                (GraphicShape::Text { text, .. }, _) => format!("silkscreen text '{}'", text),
+                (_, Some(reference)) => format!("silkscreen of {}", reference),
+                (_, None) => "board silkscreen".to_string(),
+            };
+            let shapes = graphic_shapes(&graphic);
+            let Some(location) = shapes.first().map(|s| s.anchor()) else { continue };
+            if !shapes.iter().any(|s| self.in_scope(s.bounding_box().as_ref())) {
+                continue;
+            }
             
-            // Check via drill
-            if via.drill < self.rules.min_via_drill {
+            if let GraphicShape::Text { height, .. } = graphic.shape
+                && height < self.rules.min_silk_text_height
+            {
                 report.violations.push(
                     DrcViolation::new(
-                        "size.via_drill",
-                        format!("Via drill {:.3}mm is below minimum {:.3}mm",
-                            via.drill, self.rules.min_via_drill),
-                        position_to_point(&via.position),
+                        "silk.text_height",
+                        format!("Height {:.3}mm of {} is below minimum {:.3}mm",
+                            height, label, self.rules.min_silk_text_height),
+                        location,
                     )
-                    .with_severity(DrcSeverity::Error)
-                    .with_values(via.drill, self.rules.min_via_drill, "mm")
+                    .with_severity(DrcSeverity::Warning)
+                    .with_values(height, self.rules.min_silk_text_height, "mm")
                 );
             }
-            
-            // Check annular ring
-            let annular_ring = (via.pad - via.drill) / 2.0;
-            if annular_ring < self.rules.min_annular_ring {
+            if graphic.width > 0.0 && graphic.width < self.rules.min_silk_width {
                 report.violations.push(
                     DrcViolation::new(
-                        "size.annular_ring",
-                        format!("Annular ring {:.3}mm is below minimum {:.3}mm",
-                            annular_ring, self.rules.min_annular_ring),
-                        position_to_point(&via.position),
+                        "silk.width",
+                        format!("Stroke width {:.3}mm of {} is below minimum {:.3}mm",
+                            graphic.width, label, self.rules.min_silk_width),
+                        location,
                     )
-                    .with_severity(DrcSeverity::Error)
-                    .with_values(annular_ring, self.rules.min_annular_ring, "mm")
-                    .with_fix("Increase via diameter or decrease drill size")
+                    .with_severity(DrcSeverity::Warning)
+                    .with_values(graphic.width, self.rules.min_silk_width, "mm")
                 );
             }
-        }
-        
-        // Check via-to-via clearance
-        let vias = &self.layout.vias;
-        for i in 0..vias.len() {
-            for j in (i + 1)..vias.len() {
-                let v1 = &vias[i];
-                let v2 = &vias[j];
-                
-                // Skip if same net
-                if v1.net == v2.net {
-                    continue;
-                }
-                
-                let distance = position_distance(&v1.position, &v2.position);
-                let edge_distance = distance - (v1.pad + v2.pad) / 2.0;
-                
-                if edge_distance < self.rules.min_via_clearance {
+            
+            if let Some(edges) = &edges {
+                let clearance = shapes.iter().map(|s| edges.clearance(s)).fold(f64::INFINITY, f64::min);
+                if clearance < self.rules.min_silk_to_edge {
                     report.violations.push(
                         DrcViolation::new(
-                            "clearance.via_to_via",
-                            format!("Via clearance {:.3}mm is below minimum {:.3}mm",
-                                edge_distance, self.rules.min_via_clearance),
-                            position_to_point(&v1.position),
+                            "silk.edge",
+                            format!("{} is {:.3}mm from the board edge (minimum {:.3}mm)",
+                                capitalize(&label), clearance.max(0.0), self.rules.min_silk_to_edge),
+                            location,
                         )
-                        .with_severity(DrcSeverity::Error)
-                        .with_values(edge_distance, self.rules.min_via_clearance, "mm")
+                        .with_severity(DrcSeverity::Warning)
+                        .with_values(clearance.max(0.0), self.rules.min_silk_to_edge, "mm")
                     );
                 }
             }
-        }
-    }
-    
-    /// Check copper-to-edge clearances.
-    fn check_edge_clearances(&self, report: &mut DrcReport) {
-        if let Some(outline) = &self.layout.outline {
-            if let (Some(width), Some(height)) = (outline.width, outline.height) {
-                // Check traces near edges
-                for trace in &self.layout.traces {
-                    let start = &trace.start;
-                    let end = &trace.end;
-                    
-                    // Check distance to each edge
-                    let half_width = trace.width / 2.0;
-                    
-                    // Left edge
-                    let left_clearance = f64::min(start.x, end.x) - half_width;
-                    if left_clearance < self.rules.min_edge_clearance {
-                        report.violations.push(
-                            DrcViolation::new(
-                                "clearance.edge",
-                                format!("Track too close to board edge ({:.3}mm < {:.3}mm)",
-                                    left_clearance, self.rules.min_edge_clearance),
-                                trace_midpoint(trace),
-                            )
-                            .with_severity(DrcSeverity::Error)
-                            .with_values(left_clearance, self.rules.min_edge_clearance, "mm")
-                        );
-                    }
-                    
-                    // Right edge
-                    let right_clearance = width - f64::max(start.x, end.x) - half_width;
-                    if right_clearance < self.rules.min_edge_clearance {
-                        report.violations.push(
-                            DrcViolation::new(
-                                "clearance.edge",
-                                format!("Track too close to board edge ({:.3}mm < {:.3}mm)",
-                                    right_clearance, self.rules.min_edge_clearance),
-                                trace_midpoint(trace),
-                            )
-                            .with_severity(DrcSeverity::Error)
-                            .with_values(right_clearance, self.rules.min_edge_clearance, "mm")
-                        );
-                    }
-                    
-                    // Bottom edge
-                    let bottom_clearance = f64::min(start.y, end.y) - half_width;
-                    if bottom_clearance < self.rules.min_edge_clearance {
-                        report.violations.push(
-                            DrcViolation::new(
-                                "clearance.edge",
-                                format!("Track too close to board edge ({:.3}mm < {:.3}mm)",
-                                    bottom_clearance, self.rules.min_edge_clearance),
-                                trace_midpoint(trace),
-                            )
-                            .with_severity(DrcSeverity::Error)
-                            .with_values(bottom_clearance, self.rules.min_edge_clearance, "mm")
-                        );
-                    }
-                    
-                    // Top edge
-                    let top_clearance = height - f64::max(start.y, end.y) - half_width;
-                    if top_clearance < self.rules.min_edge_clearance {
+            
+            if self.rules.check_silk_over_pads {
+                let side = graphic.side();
+                let mut near: Vec<usize> = shapes.iter()
+                    .filter_map(|s| s.bounding_box())
+                    .flat_map(|b| mask.grid.query(&b))
+                    .collect();
+                near.sort_unstable();
+                near.dedup();
+                for opening in near.into_iter().map(|i| &mask.openings[i]).filter(|o| Some(o.side) == side) {
+                    if let Some(shape) = shapes.iter().find(|s| s.distance(&opening.shape) < 0.0) {
                         report.violations.push(
                             DrcViolation::new(
-                                "clearance.edge",
-                                format!("Track too close to board edge ({:.3}mm < {:.3}mm)",
-                                    top_clearance, self.rules.min_edge_clearance),
-                                trace_midpoint(trace),
+                                "silk.over_pads",
+                                format!("{} overlaps exposed copper of {} and will be clipped",
+                                    capitalize(&label), opening.label),
+                                shape.contact_point(&opening.shape),
                             )
-                            .with_severity(DrcSeverity::Error)
-                            .with_values(top_clearance, self.rules.min_edge_clearance, "mm")
+                            .with_severity(DrcSeverity::Warning)
+                            .with_fix("Move the silkscreen off the pad")
                         );
                     }
                 }
@@ -359,41 +1046,124 @@ impl<'a> PcbDrcChecker<'a> {
         }
     }
     
-    /// Check component courtyard overlaps.
-    fn check_courtyard_overlaps(&self, report: &mut DrcReport) {
-        let components = &self.layout.components;
-        
-        for i in 0..components.len() {
-            for j in (i + 1)..components.len() {
-                let c1 = &components[i];
-                let c2 = &components[j];
-                
-                // Only check components on the same layer
-                if c1.layer != c2.layer {
+    /// Silkscreen drawings in board coordinates with the reference of the
+    /// owning component.
+    fn silk_graphics(&self) -> Vec<(Option<&str>, Graphic)> {
+        let board = self.layout.graphics.iter().map(|g| (None, g.clone()));
+        let footprints = self.layout.components.iter()
+            .flat_map(|c| c.board_graphics().into_iter().map(move |g| (Some(c.reference.as_str()), g)));
+        board.chain(footprints)
+            .filter(|(_, g)| g.layer.ends_with(".SilkS"))
+            .collect()
+    }
+    
+    /// Solder mask openings of the pads among the copper items, grown by
+    /// their mask expansion, so incremental checks build them from the
+    /// indexed copper near a change.
+    fn mask_openings(&self, items: &[CopperItem]) -> MaskOpenings {
+        let mut openings = Vec::new();
+        for item in items {
+            let Some((index, number)) = &item.pad else { continue };
+            let component = &self.layout.components[*index];
+            let Some(pad) = component.pads.iter().find(|pad| &pad.number == number) else { continue };
+            let mut shape = item.shape.clone();
+            shape.radius += pad.mask_margin.unwrap_or(self.rules.mask_expansion);
+            for side in component.pad_openings(pad, "Mask") {
+                openings.push(MaskOpening {
+                    side,
+                    shape: shape.clone(),
+                    net: pad.net.clone(),
+                    label: format!("{} pad {}", component.reference, pad.number),
+                });
+            }
+        }
+        let grid = SpatialGrid::new(openings.iter()
+            .map(|o| o.shape.bounding_box().map(|b| b.expanded(self.rules.min_mask_sliver)))
+            .collect());
+        MaskOpenings { openings, grid }
+    }
+    
+    /// Check the solder mask web between neighbouring pad openings: a web
+    /// narrower than the minimum sliver flakes off, and merged openings
+    /// between pads of different nets leave no solder dam.
+    fn check_solder_mask(&self, mask: &MaskOpenings, report: &mut DrcReport) {
+        let (openings, min) = (&mask.openings, self.rules.min_mask_sliver);
+        for i in 0..openings.len() {
+            let Some(bounds) = mask.grid.bounds(i) else { continue };
+            for j in mask.grid.query(bounds).into_iter().filter(|&j| j > i) {
+                let (a, b) = (&openings[i], &openings[j]);
+                if a.side != b.side {
                     continue;
                 }
-                
-                // Simple bounding box overlap check
-                // In a real implementation, this would use actual courtyard geometry
-                let distance = position_distance(&c1.position, &c2.position);
-                
-                // Rough check - components closer than 2mm should be verified
-                if distance < self.rules.min_courtyard_clearance {
+                let web = a.shape.distance(&b.shape);
+                let location = a.shape.contact_point(&b.shape);
+                if web <= 0.0 {
+                    let same_net = a.net.is_some() && a.net == b.net;
+                    if !same_net {
+                        report.violations.push(
+                            DrcViolation::new(
+                                "mask.bridge",
+                                format!("No solder mask web between {} and {}", a.label, b.label),
+                                location,
+                            )
+                            .with_severity(DrcSeverity::Warning)
+                            .with_fix("Reduce the mask expansion so a web remains between the pads")
+                        );
+                    }
+                } else if web < min {
                     report.violations.push(
                         DrcViolation::new(
-                            "clearance.courtyard",
-                            format!("Components {} and {} may overlap (distance: {:.3}mm)",
-                                c1.reference, c2.reference, distance),
-                            position_to_point(&c1.position),
+                            "mask.sliver",
+                            format!("Solder mask web {:.3}mm between {} and {} is below minimum {:.3}mm",
+                                web, a.label, b.label, min),
+                            location,
                         )
                         .with_severity(DrcSeverity::Warning)
-                        .with_values(distance, self.rules.min_courtyard_clearance, "mm")
+                        .with_values(web, min, "mm")
+                        .with_fix("Reduce the mask expansion or merge the openings")
                     );
                 }
             }
         }
     }
     
+    /// Check paste aperture area against the pad area.
+    fn check_paste(&self, report: &mut DrcReport) {
+        for component in &self.layout.components {
+            for pad in &component.pads {
+                let area = pad.size.0 * pad.size.1;
+                if area <= 0.0 || component.pad_openings(pad, "Paste").is_empty() {
+                    continue;
+                }
+                let position = component.pad_position(pad);
+                if !self.in_scope(BoundingBox::from_points(&[position]).as_ref()) {
+                    continue;
+                }
+                let (w, h) = pad.paste_size();
+                let ratio = w * h / area;
+                let limit = if ratio < self.rules.min_paste_ratio {
+                    self.rules.min_paste_ratio
+                } else if ratio > self.rules.max_paste_ratio {
+                    self.rules.max_paste_ratio
+                } else {
+                    continue;
+                };
+                report.violations.push(
+                    DrcViolation::new(
+                        "paste.ratio",
+                        format!("Paste aperture of {} pad {} covers {:.0}% of the pad (limits {:.0}-{:.0}%)",
+                            component.reference, pad.number, ratio * 100.0,
+                            self.rules.min_paste_ratio * 100.0, self.rules.max_paste_ratio * 100.0),
+                        position,
+                    )
+                    .with_severity(DrcSeverity::Warning)
+                    .with_values(ratio, limit, "ratio")
+                    .with_fix("Adjust the pad's paste margin")
+                );
+            }
+        }
+    }
+    
     /// Get all available PCB DRC rules.
     pub fn available_rules() -> Vec<DrcRule> {
         vec![
@@ -429,6 +1199,110 @@ impl<'a> PcbDrcChecker<'a> {
                 default_severity: DrcSeverity::Error,
                 can_disable: false,
             },
+            DrcRule {
+                id: "clearance.track_to_via".to_string(),
+                name: "Track-to-Via Clearance".to_string(),
+                description: "Minimum spacing between tracks and vias".to_string(),
+                category: "Clearance".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "clearance.via_to_pad".to_string(),
+                name: "Via-to-Pad Clearance".to_string(),
+                description: "Minimum spacing between vias and pads".to_string(),
+                category: "Clearance".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "clearance.pad_to_pad".to_string(),
+                name: "Pad-to-Pad Clearance".to_string(),
+                description: "Minimum spacing between pads of different nets".to_string(),
+                category: "Clearance".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "clearance.zone".to_string(),
+                name: "Zone Clearance".to_string(),
+                description: "Minimum spacing between zones of different nets".to_string(),
+                category: "Clearance".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "short.copper".to_string(),
+                name: "Copper Short".to_string(),
+                description: "Copper of different nets must not touch or overlap".to_string(),
+                category: "Electrical".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "clearance.teardrop".to_string(),
+                name: "Teardrop Clearance".to_string(),
+                description: "Minimum spacing between teardrops and other nets".to_string(),
+                category: "Clearance".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "outline.not_closed".to_string(),
+                name: "Board Outline Closed".to_string(),
+                description: "Board outline and cutouts must enclose an area".to_string(),
+                category: "Board Outline".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "outline.self_intersecting".to_string(),
+                name: "Board Outline Self-Intersection".to_string(),
+                description: "Board outline and cutouts must not cross themselves or each other".to_string(),
+                category: "Board Outline".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "connectivity.unrouted".to_string(),
+                name: "Unrouted Connection".to_string(),
+                description: "All pads of a net must be connected".to_string(),
+                category: "Connectivity".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "connectivity.dangling_track".to_string(),
+                name: "Dangling Track".to_string(),
+                description: "Track ends must connect to other copper of the net".to_string(),
+                category: "Connectivity".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "connectivity.floating_via".to_string(),
+                name: "Floating Via".to_string(),
+                description: "Vias must connect to copper of their net".to_string(),
+                category: "Connectivity".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "connectivity.dangling_via".to_string(),
+                name: "Dangling Via".to_string(),
+                description: "Vias should connect copper on at least two layers".to_string(),
+                category: "Connectivity".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "connectivity.zone_island".to_string(),
+                name: "Isolated Zone".to_string(),
+                description: "Zones must connect to a pad of their net".to_string(),
+                category: "Connectivity".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
             DrcRule {
                 id: "clearance.courtyard".to_string(),
                 name: "Courtyard Clearance".to_string(),
@@ -477,10 +1351,768 @@ impl<'a> PcbDrcChecker<'a> {
                 default_severity: DrcSeverity::Warning,
                 can_disable: true,
             },
+            DrcRule {
+                id: "silk.width".to_string(),
+                name: "Silkscreen Stroke Width".to_string(),
+                description: "Silkscreen lines and text strokes must meet minimum width".to_string(),
+                category: "Silkscreen".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "silk.text_height".to_string(),
+                name: "Silkscreen Text Height".to_string(),
+                description: "Silkscreen text must meet minimum height to be legible".to_string(),
+                category: "Silkscreen".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "silk.edge".to_string(),
+                name: "Silkscreen to Board Edge".to_string(),
+                description: "Silkscreen must keep clear of the board edge".to_string(),
+                category: "Silkscreen".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "mask.sliver".to_string(),
+                name: "Solder Mask Sliver".to_string(),
+                description: "Solder mask web between openings must meet minimum width".to_string(),
+                category: "Solder Mask".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "mask.bridge".to_string(),
+                name: "Missing Solder Mask Web".to_string(),
+                description: "Pads of different nets should be separated by a solder mask web".to_string(),
+                category: "Solder Mask".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "paste.ratio".to_string(),
+                name: "Paste Aperture Ratio".to_string(),
+                description: "Paste aperture area relative to the pad must stay within limits".to_string(),
+                category: "Solder Paste".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "keepout.track".to_string(),
+                name: "Track in Keepout".to_string(),
+                description: "Tracks must not enter keepout areas that forbid tracks".to_string(),
+                category: "Keepout".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "keepout.via".to_string(),
+                name: "Via in Keepout".to_string(),
+                description: "Vias must not be placed in keepout areas that forbid vias".to_string(),
+                category: "Keepout".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "keepout.pad".to_string(),
+                name: "Pad in Keepout".to_string(),
+                description: "Pads must not lie in keepout areas that forbid pads".to_string(),
+                category: "Keepout".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "keepout.copper_pour".to_string(),
+                name: "Zone Fill in Keepout".to_string(),
+                description: "Zone fill must not enter keepout areas that forbid copper pour".to_string(),
+                category: "Keepout".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "keepout.footprint".to_string(),
+                name: "Footprint in Keepout".to_string(),
+                description: "Footprint courtyards must not enter keepout areas that forbid footprints".to_string(),
+                category: "Keepout".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: false,
+            },
+            DrcRule {
+                id: "hs.via_count".to_string(),
+                name: "Via Count".to_string(),
+                description: "Nets must not use more vias than their constraint allows".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "hs.stub_length".to_string(),
+                name: "Stub Length".to_string(),
+                description: "Branches off the main route of a net must not exceed the maximum stub length".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "hs.skew".to_string(),
+                name: "Differential Pair Skew".to_string(),
+                description: "Routed lengths of the two nets of a pair must match".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Error,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "hs.uncoupled_length".to_string(),
+                name: "Uncoupled Length".to_string(),
+                description: "Differential pairs should be routed alongside each other".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "hs.crosstalk".to_string(),
+                name: "Parallel Run Length".to_string(),
+                description: "Closely spaced nets must not run in parallel beyond the constraint length".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
+            DrcRule {
+                id: "hs.reference_plane".to_string(),
+                name: "Reference Plane".to_string(),
+                description: "Clock and differential tracks should not cross gaps in the adjacent plane".to_string(),
+                category: "High-Speed".to_string(),
+                default_severity: DrcSeverity::Warning,
+                can_disable: true,
+            },
         ]
     }
 }
 
+/// Items per copper clearance tile.
+const CLEARANCE_TILE_ITEMS: usize = 256;
+
+/// A group of DRC rules run as one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrcCheck {
+    /// Minimum track widths
+    TrackWidths,
+    /// Clearances and shorts between nets
+    CopperClearance,
+    /// Via diameter, drill and annular ring
+    ViaRules,
+    /// Board outline validity
+    Outline,
+    /// Copper to board edge
+    EdgeClearance,
+    /// Items inside keepout areas
+    Keepout,
+    /// Component courtyard overlaps
+    Courtyard,
+    /// Silkscreen size and placement
+    Silkscreen,
+    /// Solder mask webs
+    SolderMask,
+    /// Paste aperture ratio
+    Paste,
+    /// High-speed routing constraints
+    HighSpeed,
+    /// Unrouted and dangling copper
+    Connectivity,
+}
+
+impl DrcCheck {
+    /// All checks in report order.
+    pub const ALL: &'static [DrcCheck] = &[
+        DrcCheck::TrackWidths,
+        DrcCheck::CopperClearance,
+        DrcCheck::ViaRules,
+        DrcCheck::Outline,
+        DrcCheck::EdgeClearance,
+        DrcCheck::Keepout,
+        DrcCheck::Courtyard,
+        DrcCheck::Silkscreen,
+        DrcCheck::SolderMask,
+        DrcCheck::Paste,
+        DrcCheck::HighSpeed,
+        DrcCheck::Connectivity,
+    ];
+    
+    /// Display name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrcCheck::TrackWidths => "Track width",
+            DrcCheck::CopperClearance => "Copper clearance",
+            DrcCheck::ViaRules => "Via size",
+            DrcCheck::Outline => "Board outline",
+            DrcCheck::EdgeClearance => "Edge clearance",
+            DrcCheck::Keepout => "Keepout",
+            DrcCheck::Courtyard => "Courtyard",
+            DrcCheck::Silkscreen => "Silkscreen",
+            DrcCheck::SolderMask => "Solder mask",
+            DrcCheck::Paste => "Solder paste",
+            DrcCheck::HighSpeed => "High-speed",
+            DrcCheck::Connectivity => "Connectivity",
+        }
+    }
+}
+
+/// Time spent in one check, summed over its jobs.
+#[derive(Debug, Clone)]
+pub struct CheckTiming {
+    /// Check
+    pub check: DrcCheck,
+    /// Time spent on all threads
+    pub duration: Duration,
+    /// Violations found
+    pub violations: usize,
+}
+
+/// Timing breakdown of a DRC run.
+#[derive(Debug, Clone)]
+pub struct DrcTimings {
+    /// Per-check timings in report order
+    pub checks: Vec<CheckTiming>,
+    /// Time spent collecting copper and mask openings before the checks
+    pub prepare: Duration,
+    /// Wall-clock time of the run
+    pub total: Duration,
+    /// Worker threads used
+    pub threads: usize,
+}
+
+/// A unit of work for a worker thread.
+struct DrcJob {
+    check: DrcCheck,
+    /// Layer index and item rows of a copper clearance tile
+    tile: Option<(usize, Range<usize>)>,
+}
+
+/// Copper items shared by the clearance tiles.
+struct ClearanceData {
+    items: Vec<CopperItem>,
+    /// Item bounds grown by the largest clearance
+    grid: SpatialGrid,
+    /// Layers of the items in order of appearance
+    layers: Vec<String>,
+}
+
+/// Items touched by an edit, for `IncrementalDrc::update`.
+///
+/// Record each changed item both before and after the edit, so violations
+/// at its old position are cleared as well.
+#[derive(Debug, Clone, Default)]
+pub struct DrcChangeSet {
+    /// Bounds of the changed items in mm
+    pub regions: Vec<BoundingBox>,
+    /// Nets whose connectivity may have changed
+    pub nets: HashSet<String>,
+    /// Whether the board outline changed, which requires a full check
+    pub board: bool,
+}
+
+impl DrcChangeSet {
+    /// Create an empty change set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Record a changed track.
+    pub fn add_trace(&mut self, trace: &Trace) {
+        self.add_shape(&CopperShape::from_trace(trace));
+        self.nets.insert(trace.net.clone());
+    }
+    
+    /// Record a changed via.
+    pub fn add_via(&mut self, via: &Via) {
+        self.add_shape(&CopperShape::from_via(via));
+        self.nets.insert(via.net.clone());
+    }
+    
+    /// Record a changed copper zone.
+    pub fn add_zone(&mut self, zone: &Zone) {
+        self.add_shape(&CopperShape::from_zone(zone));
+        self.nets.insert(zone.net.clone());
+    }
+    
+    /// Record a changed component with its pads and footprint drawings.
+    pub fn add_component(&mut self, component: &PlacedComponent) {
+        for pad in &component.pads {
+            self.add_shape(&CopperShape::from_pad(component, pad));
+            if let Some(net) = &pad.net {
+                self.nets.insert(net.clone());
+            }
+        }
+        for graphic in component.board_graphics() {
+            self.add_graphic(&graphic);
+        }
+        self.regions.extend(BoundingBox::from_points(&[position_to_point(&component.position)]));
+    }
+    
+    /// Record a changed board drawing.
+    pub fn add_graphic(&mut self, graphic: &Graphic) {
+        for shape in graphic_shapes(graphic) {
+            self.add_shape(&shape);
+        }
+    }
+    
+    /// Record a change to the board outline.
+    pub fn add_outline(&mut self) {
+        self.board = true;
+    }
+    
+    /// Whether nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty() && self.nets.is_empty() && !self.board
+    }
+    
+    fn add_shape(&mut self, shape: &CopperShape) {
+        self.regions.extend(shape.bounding_box());
+    }
+}
+
+/// A DRC report kept up to date across edits.
+///
+/// The board's copper is kept on a spatial grid between updates. `update`
+/// replaces the copper inside the changed regions, drops the violations
+/// located near the changed items and reruns the local checks on the
+/// changed items and their neighbours only. Connectivity is rerun for the
+/// changed nets, and the high-speed rules for the changed nets and the
+/// nets of tracks near the change. Custom rules conditioned on a
+/// component's courtyard are only re-evaluated near the change, so run a
+/// full check before sign-off.
+pub struct IncrementalDrc {
+    rules: PcbDesignRules,
+    custom_rules: CustomRuleSet,
+    /// Custom rules merged with the layout's rule area rules
+    effective_rules: CustomRuleSet,
+    net_classes: HashMap<String, String>,
+    nets: Vec<Net>,
+    constraints: Vec<Constraint>,
+    /// Copper of the layout as of the last update
+    copper: CopperIndex,
+    report: DrcReport,
+    /// Nets each violation depends on, parallel to the report; empty for
+    /// violations located at the items involved
+    violation_nets: Vec<Vec<String>>,
+    /// Copper items evaluated by the last update
+    rechecked: usize,
+}
+
+impl IncrementalDrc {
+    /// Run a full check with the checker's rules and keep the report.
+    pub fn new(checker: PcbDrcChecker) -> Self {
+        let effective_rules = checker.effective_rules().clone();
+        let copper = CopperIndex::new(checker.layout);
+        let (report, violation_nets) = Self::full_check(&checker, &copper);
+        Self {
+            rules: checker.rules,
+            custom_rules: checker.custom_rules.into_owned(),
+            effective_rules,
+            net_classes: checker.net_classes.into_owned(),
+            nets: checker.nets.into_owned(),
+            constraints: checker.constraints.into_owned(),
+            rechecked: copper.len(),
+            copper,
+            report,
+            violation_nets,
+        }
+    }
+    
+    /// Current report.
+    pub fn report(&self) -> &DrcReport {
+        &self.report
+    }
+    
+    /// Take the current report.
+    pub fn into_report(self) -> DrcReport {
+        self.report
+    }
+    
+    /// Copper items evaluated by the last update or recheck.
+    pub fn rechecked_items(&self) -> usize {
+        self.rechecked
+    }
+    
+    /// Recheck the whole board, re-reading its copper and rule areas.
+    pub fn recheck(&mut self, layout: &Layout) {
+        self.effective_rules = self.custom_rules.merged(&CustomRuleSet::from_areas(layout));
+        self.copper = CopperIndex::new(layout);
+        let (report, violation_nets) = Self::full_check(&self.checker(layout, None), &self.copper);
+        (self.report, self.violation_nets) = (report, violation_nets);
+        self.rechecked = self.copper.len();
+    }
+    
+    /// Update the report after the edits in `changes` were applied to
+    /// `layout`.
+    pub fn update(&mut self, layout: &Layout, changes: &DrcChangeSet) {
+        if changes.board {
+            self.recheck(layout);
+            return;
+        }
+        if changes.is_empty() {
+            return;
+        }
+        self.copper.update(layout, &changes.regions);
+        
+        // Any violation involving a changed item lies within the largest
+        // clearance of it; the items involved lie within that again
+        let margin = self.recheck_margin(layout);
+        let dirty: Vec<BoundingBox> = changes.regions.iter().map(|r| r.expanded(margin)).collect();
+        let scope: Vec<BoundingBox> = dirty.iter().map(|r| r.expanded(margin)).collect();
+        let in_dirty = |p: &Point2D| dirty.iter().any(|r| r.contains(p));
+        let nearby = self.copper.near(&scope);
+        
+        // High-speed rules span whole nets; tracks near the change may have
+        // lost or gained their reference plane
+        let mut high_speed_nets = changes.nets.clone();
+        high_speed_nets.extend(nearby.iter()
+            .filter(|item| item.kind == CopperKind::Track)
+            .map(|item| item.net.clone()));
+        
+        let violations = std::mem::take(&mut self.report.violations);
+        for (violation, nets) in violations.into_iter().zip(std::mem::take(&mut self.violation_nets)) {
+            let stale = if violation.rule.starts_with("hs.") {
+                nets.iter().any(|net| high_speed_nets.contains(net))
+            } else if !nets.is_empty() {
+                nets.iter().any(|net| changes.nets.contains(net))
+            } else {
+                !violation.rule.starts_with("outline.") && in_dirty(&violation.location)
+            };
+            if !stale {
+                self.report.violations.push(violation);
+                self.violation_nets.push(nets);
+            }
+        }
+        
+        let checker = self.checker(layout, Some(scope.clone()));
+        let clearance = checker.clearance_data(nearby.into_iter().cloned().collect());
+        let mask = checker.mask_openings(&clearance.items);
+        let mut local = DrcReport::new("PCB Layout", "pcb");
+        checker.check_local(&clearance, &mask, &mut local);
+        let mut found: Vec<(Vec<String>, DrcViolation)> = local.violations.into_iter()
+            .filter(|v| in_dirty(&v.location))
+            .map(|v| (Vec::new(), v))
+            .collect();
+        found.extend(HighSpeedChecker::new(layout, &self.nets, &self.constraints)
+            .for_nets(&high_speed_nets)
+            .check_by_net());
+        if !changes.nets.is_empty() {
+            found.extend(checker.connectivity_violations(Some(&changes.nets)).into_iter()
+                .map(|(net, violation)| (vec![net], violation)));
+        }
+        self.rechecked = clearance.items.len();
+        for (nets, violation) in found {
+            self.report.violations.push(violation);
+            self.violation_nets.push(nets);
+        }
+    }
+    
+    /// Violations that proposed tracks and vias would add, for online DRC
+    /// while a route is drawn.
+    pub fn check_proposed(&self, layout: &Layout, traces: &[Trace], vias: &[Via]) -> Vec<DrcViolation> {
+        self.checker(layout, None).check_proposed_near(&self.copper, traces, vias)
+    }
+    
+    /// Checker borrowing the kept rules.
+    fn checker<'a>(&'a self, layout: &'a Layout, scope: Option<Vec<BoundingBox>>) -> PcbDrcChecker<'a> {
+        PcbDrcChecker {
+            layout,
+            rules: self.rules.clone(),
+            custom_rules: Cow::Borrowed(&self.custom_rules),
+            effective_rules: OnceLock::from(Cow::Borrowed(&self.effective_rules)),
+            net_classes: Cow::Borrowed(&self.net_classes),
+            nets: Cow::Borrowed(&self.nets),
+            constraints: Cow::Borrowed(&self.constraints),
+            scope,
+            threads: 1,
+        }
+    }
+    
+    /// Largest distance between a violation's location and the items it
+    /// involves.
+    fn recheck_margin(&self, layout: &Layout) -> f64 {
+        let mask = self.copper.mask_margin.max(self.rules.mask_expansion);
+        self.checker(layout, None).max_clearance(self.copper.max_clearance)
+            .max(self.rules.min_courtyard_clearance + 2.0 * COURTYARD_MARGIN)
+            .max(self.rules.min_mask_sliver + 2.0 * mask)
+    }
+    
+    fn full_check(checker: &PcbDrcChecker, copper: &CopperIndex) -> (DrcReport, Vec<Vec<String>>) {
+        let mut report = DrcReport::new("PCB Layout", "pcb");
+        let clearance = checker.clearance_data(copper.items().cloned().collect());
+        checker.check_local(&clearance, &checker.mask_openings(&clearance.items), &mut report);
+        let mut nets = vec![Vec::new(); report.violations.len()];
+        let high_speed = HighSpeedChecker::new(checker.layout, &checker.nets, &checker.constraints).check_by_net();
+        let connectivity = checker.connectivity_violations(None).into_iter().map(|(net, v)| (vec![net], v));
+        for (violation_nets, violation) in high_speed.into_iter().chain(connectivity) {
+            report.violations.push(violation);
+            nets.push(violation_nets);
+        }
+        (report, nets)
+    }
+}
+
+/// Copper items of a layout on a spatial grid, kept by incremental DRC
+/// between edits.
+struct CopperIndex {
+    /// Items by grid index; `None` once replaced
+    items: Vec<Option<CopperItem>>,
+    /// Exact bounds of the items
+    grid: SpatialGrid,
+    /// Replaced items still holding a slot
+    stale: usize,
+    /// Largest item-specific clearance (mm)
+    max_clearance: f64,
+    /// Largest pad solder mask margin (mm)
+    mask_margin: f64,
+    /// Component count the pad indices refer to
+    components: usize,
+}
+
+impl CopperIndex {
+    fn new(layout: &Layout) -> Self {
+        let items = collect_copper(layout);
+        let mask_margin = layout.components.iter()
+            .flat_map(|c| &c.pads)
+            .filter_map(|pad| pad.mask_margin)
+            .fold(0.0, f64::max);
+        Self {
+            grid: SpatialGrid::new(items.iter().map(|item| item.shape.bounding_box()).collect()),
+            max_clearance: item_clearance(&items),
+            items: items.into_iter().map(Some).collect(),
+            stale: 0,
+            mask_margin,
+            components: layout.components.len(),
+        }
+    }
+    
+    /// Replace the items whose bounds meet `regions` with the layout's
+    /// current copper there.
+    fn update(&mut self, layout: &Layout, regions: &[BoundingBox]) {
+        // Pads refer to their component by index
+        if layout.components.len() != self.components {
+            *self = Self::new(layout);
+            return;
+        }
+        for region in regions {
+            for i in self.grid.query(region) {
+                self.grid.remove(i);
+                self.items[i] = None;
+                self.stale += 1;
+            }
+        }
+        let meets = |shape: &CopperShape| shape.bounding_box().is_some_and(|b| regions.iter().any(|r| r.intersects(&b)));
+        for item in collect_copper_where(layout, |_, shape| meets(shape)) {
+            self.max_clearance = self.max_clearance.max(item.clearance.unwrap_or(0.0));
+            if let Some((component, number)) = &item.pad {
+                let margin = layout.components[*component].pads.iter()
+                    .find(|pad| &pad.number == number)
+                    .and_then(|pad| pad.mask_margin);
+                self.mask_margin = self.mask_margin.max(margin.unwrap_or(0.0));
+            }
+            self.grid.insert(item.shape.bounding_box());
+            self.items.push(Some(item));
+        }
+        
+        if self.stale > self.items.len() / 2 {
+            let items: Vec<CopperItem> = std::mem::take(&mut self.items).into_iter().flatten().collect();
+            self.grid = SpatialGrid::new(items.iter().map(|item| item.shape.bounding_box()).collect());
+            self.items = items.into_iter().map(Some).collect();
+            self.stale = 0;
+        }
+    }
+    
+    /// Number of items.
+    fn len(&self) -> usize {
+        self.items.len() - self.stale
+    }
+    
+    /// All items.
+    fn items(&self) -> impl Iterator<Item = &CopperItem> {
+        self.items.iter().flatten()
+    }
+    
+    /// Items whose bounds meet `region`.
+    fn query(&self, region: &BoundingBox) -> impl Iterator<Item = &CopperItem> {
+        self.grid.query(region).into_iter().filter_map(|i| self.items[i].as_ref())
+    }
+    
+    /// Items whose bounds meet any of `regions`.
+    fn near(&self, regions: &[BoundingBox]) -> Vec<&CopperItem> {
+        let mut found: Vec<usize> = regions.iter().flat_map(|r| self.grid.query(r)).collect();
+        found.sort_unstable();
+        found.dedup();
+        found.into_iter().filter_map(|i| self.items[i].as_ref()).collect()
+    }
+}
+
+/// Order of copper items by kind, position and label.
+fn copper_order(a: &CopperItem, b: &CopperItem) -> Ordering {
+    let key = |item: &CopperItem| item.shape.bounding_box().map(|b| [b.min.x, b.min.y, b.max.x, b.max.y]);
+    a.kind.cmp(&b.kind)
+        .then_with(|| match (key(a), key(b)) {
+            (Some(ka), Some(kb)) => ka.iter().zip(kb).map(|(x, y)| x.total_cmp(&y)).find(|o| o.is_ne()).unwrap_or(Ordering::Equal),
+            (ka, kb) => ka.is_some().cmp(&kb.is_some()),
+        })
+        .then_with(|| a.label.cmp(&b.label))
+}
+
+/// Largest item-specific clearance of copper items (mm).
+fn item_clearance(items: &[CopperItem]) -> f64 {
+    items.iter().filter_map(|item| item.clearance).fold(0.0, f64::max)
+}
+
+/// Pad solder mask openings with their bounds on a grid.
+struct MaskOpenings {
+    openings: Vec<MaskOpening>,
+    /// Opening bounds grown by the minimum sliver
+    grid: SpatialGrid,
+}
+
+/// A pad's solder mask opening on one side of the board.
+struct MaskOpening {
+    side: ComponentLayer,
+    shape: CopperShape,
+    net: Option<String>,
+    label: String,
+}
+
+/// Character advance of stroke-font text relative to its height.
+const TEXT_WIDTH_RATIO: f64 = 0.8;
+
+/// Outline of a drawing as shapes grown by half its stroke width. Circle
+/// and polygon outlines become segment chains; filled polygons and text
+/// boxes are solid.
+fn graphic_shapes(graphic: &Graphic) -> Vec<CopperShape> {
+    let r = graphic.width / 2.0;
+    let chain = |points: &[Point2D]| -> Vec<CopperShape> {
+        let n = points.len();
+        (0..n).map(|i| CopperShape::capsule(points[i], points[(i + 1) % n], r)).collect()
+    };
+    match &graphic.shape {
+        GraphicShape::Line { start, end } => vec![CopperShape::capsule(*start, *end, r)],
+        GraphicShape::Circle { center, radius } if graphic.width <= 0.0 => vec![CopperShape::circle(*center, *radius)],
+        GraphicShape::Circle { center, radius } => {
+            let points: Vec<Point2D> = (0..32)
+                .map(|i| {
+                    let a = i as f64 * std::f64::consts::TAU / 32.0;
+                    Point2D::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+                })
+                .collect();
+            chain(&points)
+        }
+        GraphicShape::Polygon { points } if graphic.width <= 0.0 => vec![CopperShape::polygon(points.clone())],
+        GraphicShape::Polygon { points } => chain(points),
+        GraphicShape::Text { text, position, height, rotation } => {
+            let hw = text.chars().count() as f64 * height * TEXT_WIDTH_RATIO / 2.0;
+            let hh = height / 2.0;
+            let (sin, cos) = rotation.to_radians().sin_cos();
+            let place = |x: f64, y: f64| Point2D::new(position.x + x * cos - y * sin, position.y + x * sin + y * cos);
+            let mut shape = CopperShape::polygon(vec![place(-hw, -hh), place(hw, -hh), place(hw, hh), place(-hw, hh)]);
+            shape.radius = r;
+            vec![shape]
+        }
+    }
+}
+
+/// Uppercase the first character of a message fragment.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Board edge geometry in mm.
+struct BoardEdges {
+    /// Exact circle for circular outlines
+    circle: Option<(Point2D, f64)>,
+    /// Outer polygon (unused for circles)
+    polygon: Vec<Point2D>,
+    /// Cutout and slot polygons
+    cutouts: Vec<Vec<Point2D>>,
+}
+
+impl BoardEdges {
+    fn from_outline(outline: &Outline) -> Self {
+        let to_mm = |p: &Point2D| Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y));
+        let circle = (outline.outline_type == OutlineType::Circle).then(|| {
+            let radius = outline.unit.to_mm(outline.width.unwrap_or(0.0)) / 2.0;
+            let center = outline.points.first().map(to_mm).unwrap_or(Point2D::new(radius, radius));
+            (center, radius)
+        });
+        Self {
+            circle,
+            polygon: outline_polygon(outline),
+            cutouts: outline.cutouts.iter().map(|c| c.iter().map(to_mm).collect()).collect(),
+        }
+    }
+    
+    /// Signed clearance from a shape to the board edge; negative when the
+    /// shape lies outside the board or inside a cutout.
+    fn clearance(&self, shape: &CopperShape) -> f64 {
+        let anchor = shape.anchor();
+        let mut clearance = match self.circle {
+            Some((center, radius)) => radius - shape.max_distance_from(&center),
+            None => {
+                let d = ring_distance(shape, &self.polygon);
+                if point_in_polygon(&anchor, &self.polygon) { d } else { -d.max(f64::EPSILON) }
+            }
+        };
+        for cutout in &self.cutouts {
+            let d = ring_distance(shape, cutout);
+            let d = if point_in_polygon(&anchor, cutout) { -d.max(f64::EPSILON) } else { d };
+            clearance = clearance.min(d);
+        }
+        clearance
+    }
+}
+
+/// Distance from a shape to the edges of a closed polygon.
+fn ring_distance(shape: &CopperShape, ring: &[Point2D]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| shape.distance(&CopperShape::capsule(ring[i], ring[(i + 1) % n], 0.0)))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Board outline polygon in mm.
+fn outline_polygon(outline: &Outline) -> Vec<Point2D> {
+    outline.to_polygon(128).iter()
+        .map(|p| Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y)))
+        .collect()
+}
+
+/// First vertex of an edge that crosses a non-adjacent edge, if any.
+fn self_intersection(polygon: &[Point2D]) -> Option<Point2D> {
+    let n = polygon.len();
+    for i in 0..n {
+        for j in (i + 2)..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (a, b) = (&polygon[i], &polygon[(i + 1) % n]);
+            let (c, d) = (&polygon[j], &polygon[(j + 1) % n]);
+            if segments_intersect(a, b, c, d) {
+                return Some(*a);
+            }
+        }
+    }
+    None
+}
+
+/// Whether any edges of two polygons cross or touch.
+fn polygons_cross(p: &[Point2D], q: &[Point2D]) -> bool {
+    let (n, m) = (p.len(), q.len());
+    (0..n).any(|i| {
+        (0..m).any(|j| segments_intersect(&p[i], &p[(i + 1) % n], &q[j], &q[(j + 1) % m]))
+    })
+}
+
 /// Convert Position to Point2D.
 fn position_to_point(pos: &Position) -> Point2D {
     Point2D::new(pos.x, pos.y)
@@ -494,28 +2126,25 @@ fn trace_midpoint(trace: &Trace) -> Point2D {
     )
 }
 
-/// Calculate distance between two positions.
-fn position_distance(p1: &Position, p2: &Position) -> f64 {
-    ((p2.x - p1.x).powi(2) + (p2.y - p1.y).powi(2)).sqrt()
-}
-
-/// Calculate distance between two points.
-fn point_distance(p1: &Point2D, p2: &Point2D) -> f64 {
-    ((p2.x - p1.x).powi(2) + (p2.y - p1.y).powi(2)).sqrt()
+/// Custom rule subject for a trace.
+fn trace_subject(trace: &Trace) -> RuleSubject<'_> {
+    let to_mm = |p: &Position| Point2D::new(trace.unit.to_mm(p.x), trace.unit.to_mm(p.y));
+    RuleSubject {
+        kind: CopperKind::Track,
+        net: &trace.net,
+        component: None,
+        bounds: BoundingBox::from_points(&[to_mm(&trace.start), to_mm(&trace.end)]),
+    }
 }
 
-/// Calculate minimum distance between two traces (simplified).
-fn min_trace_distance(t1: &Trace, t2: &Trace) -> Option<f64> {
-    // Simplified: calculate distance between midpoints minus half widths
-    let mid1 = trace_midpoint(t1);
-    let mid2 = trace_midpoint(t2);
-    let center_distance = point_distance(&mid1, &mid2);
-    let edge_distance = center_distance - (t1.width + t2.width) / 2.0;
-    
-    if edge_distance > 0.0 {
-        Some(edge_distance)
-    } else {
-        Some(0.0)
+/// Custom rule subject for a via.
+fn via_subject(via: &Via) -> RuleSubject<'_> {
+    let center = Point2D::new(via.unit.to_mm(via.position.x), via.unit.to_mm(via.position.y));
+    RuleSubject {
+        kind: CopperKind::Via,
+        net: &via.net,
+        component: None,
+        bounds: BoundingBox::from_points(&[center]).map(|b| b.expanded(via.unit.to_mm(via.pad) / 2.0)),
     }
 }
 
@@ -653,12 +2282,666 @@ mod tests {
         assert!(report.violations.iter().any(|v| v.rule == "clearance.track_to_track"));
     }
     
+    #[test]
+    fn test_pcb_drc_crossing_tracks_short() {
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        layout.traces.push(Trace {
+            net: "NET1".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(10.0, 10.0),
+            end: make_position(50.0, 50.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        layout.traces.push(Trace {
+            net: "NET2".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(10.0, 50.0),
+            end: make_position(50.0, 10.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        // Same crossing on another layer is fine
+        layout.traces.push(Trace {
+            net: "NET3".to_string(),
+            layer: "B.Cu".to_string(),
+            start: make_position(10.0, 30.0),
+            end: make_position(50.0, 30.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let shorts: Vec<_> = report.violations.iter().filter(|v| v.rule == "short.copper").collect();
+        assert_eq!(shorts.len(), 1);
+    }
+    
+    #[test]
+    fn test_pcb_drc_track_to_pad_clearance() {
+        use crate::layout::{Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("R1", "10k", "R_0603")
+                .at(20.0, 20.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("NET1")),
+        );
+        // Long track whose midpoint is far from the pad but whose edge is 0.1mm away
+        layout.traces.push(Trace {
+            net: "NET2".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(0.0, 20.7),
+            end: make_position(50.0, 20.7),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let violation = report.violations.iter()
+            .find(|v| v.rule == "clearance.track_to_pad")
+            .expect("track-to-pad violation");
+        assert!(violation.message.contains("0.100mm"));
+    }
+    
+    #[test]
+    fn test_pcb_drc_teardrop_clearance() {
+        use crate::teardrop::{TeardropConfig, TeardropGenerator};
+        
+        let mut layout = Layout::with_board_size(20.0, 20.0, LengthUnit::Mm);
+        layout.vias.push(Via {
+            net: "SIG".to_string(),
+            position: make_position(5.0, 5.0),
+            via_type: ViaType::Through,
+            drill: 0.4,
+            pad: 0.8,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        });
+        layout.traces.push(Trace {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(5.0, 5.0),
+            end: make_position(10.0, 5.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        // Passes 0.15mm from the teardrop tip, clear of the via itself
+        layout.traces.push(Trace {
+            net: "OTHER".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(5.7, 5.35),
+            end: make_position(5.7, 8.0),
+            width: 0.1,
+            unit: LengthUnit::Mm,
+        });
+        TeardropGenerator::new(TeardropConfig::default()).apply(&mut layout);
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        assert!(report.violations.iter().any(|v| v.rule == "clearance.teardrop"));
+    }
+    
+    fn outline_layout(outline_type: OutlineType, points: Vec<Point2D>, width: Option<f64>) -> Layout {
+        let mut layout = Layout::with_board_size(0.0, 0.0, LengthUnit::Mm);
+        layout.outline = Some(Outline {
+            outline_type,
+            points,
+            width,
+            height: None,
+            cutouts: Vec::new(),
+            unit: LengthUnit::Mm,
+        });
+        layout
+    }
+    
+    fn track(net: &str, x1: f64, y1: f64, x2: f64, y2: f64) -> Trace {
+        Trace {
+            net: net.to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(x1, y1),
+            end: make_position(x2, y2),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        }
+    }
+    
+    #[test]
+    fn test_pcb_drc_circular_outline_edge() {
+        let mut layout = outline_layout(OutlineType::Circle, vec![Point2D::new(25.0, 25.0)], Some(50.0));
+        layout.vias.push(Via {
+            net: "SIG".to_string(),
+            position: make_position(25.0, 49.6),
+            via_type: ViaType::Through,
+            drill: 0.3,
+            pad: 0.6,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        });
+        layout.traces.push(track("SIG", 20.0, 25.0, 30.0, 25.0));
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let edge: Vec<_> = report.violations.iter().filter(|v| v.rule == "clearance.edge").collect();
+        assert_eq!(edge.len(), 1);
+        assert!(edge[0].message.contains("0.100mm"));
+    }
+    
+    #[test]
+    fn test_pcb_drc_polygon_outline_and_slot() {
+        // L-shaped board with the top-right quadrant missing
+        let mut layout = outline_layout(OutlineType::Polygon, vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(40.0, 0.0),
+            Point2D::new(40.0, 20.0),
+            Point2D::new(20.0, 20.0),
+            Point2D::new(20.0, 40.0),
+            Point2D::new(0.0, 40.0),
+        ], None);
+        layout.outline.as_mut().unwrap().add_slot(Point2D::new(5.0, 5.0), Point2D::new(15.0, 5.0), 1.0);
+        layout.traces.push(track("A", 30.0, 30.0, 35.0, 30.0));
+        layout.traces.push(track("B", 10.0, 2.0, 10.0, 8.0));
+        layout.traces.push(track("C", 10.0, 30.0, 15.0, 30.0));
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let edge: Vec<_> = report.violations.iter().filter(|v| v.rule == "clearance.edge").collect();
+        assert_eq!(edge.len(), 2);
+        assert!(edge.iter().any(|v| v.message.contains("track A") && v.message.contains("outside")));
+        assert!(edge.iter().any(|v| v.message.contains("track B")));
+        assert!(!report.violations.iter().any(|v| v.rule.starts_with("outline.")));
+    }
+    
+    #[test]
+    fn test_pcb_drc_invalid_outline() {
+        let bow_tie = outline_layout(OutlineType::Polygon, vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 10.0),
+            Point2D::new(10.0, 0.0),
+            Point2D::new(0.0, 10.0),
+        ], None);
+        let report = PcbDrcChecker::new(&bow_tie, PcbDesignRules::default()).check_all();
+        assert!(report.violations.iter().any(|v| v.rule == "outline.self_intersecting"));
+        
+        let open = outline_layout(OutlineType::Polygon, vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 0.0),
+        ], None);
+        let report = PcbDrcChecker::new(&open, PcbDesignRules::default()).check_all();
+        assert!(report.violations.iter().any(|v| v.rule == "outline.not_closed"));
+    }
+    
+    #[test]
+    fn test_pcb_drc_connectivity() {
+        use crate::layout::{Pad, PlacedComponent, Zone};
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        for (reference, x) in [("TP1", 10.0), ("TP2", 30.0), ("TP3", 50.0)] {
+            layout.components.push(
+                PlacedComponent::new(reference, "", "TestPoint")
+                    .at(x, 10.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("SIG")),
+            );
+        }
+        layout.traces.push(track("SIG", 10.0, 10.0, 30.0, 10.0));
+        // Stub leaving TP2 towards nothing
+        layout.traces.push(track("SIG", 30.0, 10.0, 30.0, 20.0));
+        layout.vias.push(Via {
+            net: "SIG".to_string(),
+            position: make_position(20.0, 40.0),
+            via_type: ViaType::Through,
+            drill: 0.3,
+            pad: 0.6,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        });
+        layout.zones.push(Zone::new("SIG", "B.Cu", vec![
+            Point2D::new(40.0, 40.0),
+            Point2D::new(50.0, 40.0),
+            Point2D::new(50.0, 50.0),
+            Point2D::new(40.0, 50.0),
+        ]));
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("connectivity.unrouted"), 1);
+        assert_eq!(count("connectivity.dangling_track"), 1);
+        assert_eq!(count("connectivity.floating_via"), 1);
+        assert_eq!(count("connectivity.zone_island"), 1);
+        
+        let stub = report.violations.iter().find(|v| v.rule == "connectivity.dangling_track").unwrap();
+        assert_eq!(stub.location, Point2D::new(30.0, 20.0));
+    }
+    
+    #[test]
+    fn test_pcb_drc_connectivity_long_chain() {
+        use crate::layout::{Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        for (reference, x) in [("TP1", 5.0), ("TP2", 55.0)] {
+            layout.components.push(
+                PlacedComponent::new(reference, "", "TestPoint")
+                    .at(x, 10.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("SIG")),
+            );
+        }
+        // Many short segments, stored out of order
+        let mut segments: Vec<Trace> = (0..200)
+            .map(|i| track("SIG", 5.0 + i as f64 * 0.25, 10.0, 5.25 + i as f64 * 0.25, 10.0))
+            .collect();
+        segments.reverse();
+        layout.traces = segments;
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        assert!(!report.violations.iter().any(|v| v.rule.starts_with("connectivity.")),
+            "{:?}", report.violations);
+    }
+    
+    #[test]
+    fn test_pcb_drc_silkscreen() {
+        use crate::layout::{Graphic, Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("R1", "10k", "R_0805")
+                .at(20.0, 20.0)
+                .rotated(90.0)
+                .with_pad(Pad::smd("1", -1.0, 0.0, 1.0, 1.2).with_net("A"))
+                .with_pad(Pad::smd("2", 1.0, 0.0, 1.0, 1.2).with_net("B"))
+                // Crosses pad 2 once rotated
+                .with_graphic(Graphic::line("F.SilkS", Point2D::new(1.0, -2.0), Point2D::new(1.0, 2.0), 0.12)),
+        );
+        layout.graphics.push(Graphic::text("F.SilkS", "REV A", Point2D::new(25.0, 49.6), 0.6, 0.15));
+        // Bottom silk does not clip top pads
+        layout.graphics.push(Graphic::line("B.SilkS", Point2D::new(18.0, 21.0), Point2D::new(22.0, 21.0), 0.15));
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("silk.over_pads"), 1);
+        assert_eq!(count("silk.width"), 1);
+        assert_eq!(count("silk.text_height"), 1);
+        assert_eq!(count("silk.edge"), 1);
+        
+        let clipped = report.violations.iter().find(|v| v.rule == "silk.over_pads").unwrap();
+        assert!(clipped.message.contains("R1 pad 2"));
+    }
+    
+    #[test]
+    fn test_pcb_drc_mask_and_paste() {
+        use crate::layout::{Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        // 0.5mm pitch pads, 0.25mm wide: 0.25mm copper gap, 0.15mm mask web
+        let mut ic = PlacedComponent::new("U1", "MCU", "QFN").at(10.0, 10.0);
+        for (i, net) in ["A", "B", "C"].iter().enumerate() {
+            ic.pads.push(Pad::smd((i + 1).to_string(), i as f64 * 0.5, 0.0, 0.25, 0.8).with_net(*net));
+        }
+        layout.components.push(ic);
+        layout.components.push(
+            PlacedComponent::new("U2", "MCU", "QFN")
+                .at(30.0, 10.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 0.25, 0.8).with_net("A").with_paste_margin(-0.1))
+                .with_pad(Pad::smd("2", 0.3, 0.0, 0.25, 0.8).with_net("B")),
+        );
+        
+        let rules = PcbDesignRules {
+            min_mask_sliver: 0.2,
+            ..PcbDesignRules::default()
+        };
+        let report = PcbDrcChecker::new(&layout, rules).check_all();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("mask.sliver"), 2);
+        assert!(report.violations.iter()
+            .filter(|v| v.rule == "mask.sliver")
+            .all(|v| v.severity == DrcSeverity::Warning));
+        assert_eq!(count("mask.bridge"), 1);
+        // 0.05 x 0.6 aperture on a 0.25 x 0.8 pad is 15%
+        assert_eq!(count("paste.ratio"), 1);
+    }
+    
+    #[test]
+    fn test_pcb_drc_custom_rules() {
+        use crate::drc_rules::{CustomRule, CustomRuleSet, RuleConstraint};
+        
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(track("HV_IN", 10.0, 10.0, 30.0, 10.0));
+        layout.traces.push(track("GND", 10.0, 11.0, 30.0, 11.0));
+        layout.traces.push(track("SDA", 10.0, 30.0, 30.0, 30.0));
+        layout.traces[2].width = 0.1;
+        
+        let rules = CustomRuleSet::compile(&[
+            CustomRule::new("HV", "A.netclass == 'HV' && layer == 'outer'", RuleConstraint::Clearance, 2.0),
+            CustomRule::new("thin signals", "A.net == 'SDA'", RuleConstraint::TrackWidth, 0.08),
+        ]).unwrap();
+        let classes = HashMap::from([("HV_IN".to_string(), "HV".to_string())]);
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default())
+            .with_custom_rules(rules)
+            .with_net_classes(classes)
+            .check_all();
+        
+        let clearance = report.violations.iter()
+            .find(|v| v.rule == "clearance.track_to_track")
+            .expect("HV clearance violation");
+        assert!(clearance.message.contains("(rule 'HV')"));
+        assert!(!report.violations.iter().any(|v| v.rule == "width.track"));
+    }
+    
+    fn violation_keys(report: &DrcReport) -> Vec<String> {
+        let mut keys: Vec<String> = report.violations.iter()
+            .map(|v| format!("{} {:.4} {:.4} {}", v.rule, v.location.x, v.location.y, v.message))
+            .collect();
+        keys.sort();
+        keys
+    }
+    
+    #[test]
+    fn test_incremental_drc_matches_full_check() {
+        let mut layout = create_test_layout();
+        layout.traces.push(track("SDA", 10.0, 25.0, 40.0, 25.0));
+        layout.traces.push(track("SCL", 10.0, 40.0, 40.0, 40.0));
+        let mut drc = IncrementalDrc::new(PcbDrcChecker::new(&layout, PcbDesignRules::default()));
+        
+        // Move SCL next to SDA, then away again
+        for y in [25.3, 45.0] {
+            let mut changes = DrcChangeSet::new();
+            changes.add_trace(&layout.traces[3]);
+            layout.traces[3].start.y = y;
+            layout.traces[3].end.y = y;
+            changes.add_trace(&layout.traces[3]);
+            drc.update(&layout, &changes);
+            
+            let full = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+            assert_eq!(violation_keys(drc.report()), violation_keys(&full));
+        }
+        
+        // Moving the via onto the GND track shorts it
+        let mut changes = DrcChangeSet::new();
+        changes.add_via(&layout.vias[0]);
+        layout.vias[0].position = make_position(30.0, 12.0);
+        changes.add_via(&layout.vias[0]);
+        drc.update(&layout, &changes);
+        
+        let full = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        assert!(full.violations.iter().any(|v| v.rule == "short.copper"));
+        assert_eq!(violation_keys(drc.report()), violation_keys(&full));
+    }
+    
+    #[test]
+    fn test_incremental_drc_rechecks_only_neighbours() {
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        for i in 0..200 {
+            let (x, y) = (5.0 + (i % 20) as f64 * 4.5, 5.0 + (i / 20) as f64 * 7.0);
+            layout.traces.push(track(&format!("N{}", i), x, y, x + 3.0, y));
+        }
+        layout.traces[199].width = 0.1;
+        let mut drc = IncrementalDrc::new(PcbDrcChecker::new(&layout, PcbDesignRules::default()));
+        assert_eq!(drc.rechecked_items(), 200);
+        assert!(drc.report().violations.iter().any(|v| v.rule == "width.track"));
+        
+        // Widen the far track without telling the DRC, then move one in the
+        // opposite corner next to its neighbour
+        layout.traces[199].width = 0.25;
+        let mut changes = DrcChangeSet::new();
+        changes.add_trace(&layout.traces[0]);
+        layout.traces[0].start.y = 5.3;
+        layout.traces[0].end.y = 5.3;
+        layout.traces[0].end.x = 9.6;
+        changes.add_trace(&layout.traces[0]);
+        drc.update(&layout, &changes);
+        
+        assert!(drc.rechecked_items() <= 4, "{} items rechecked", drc.rechecked_items());
+        let report = drc.report();
+        assert!(report.violations.iter().any(|v| v.rule == "clearance.track_to_track"));
+        // The untouched track was not re-evaluated
+        assert!(report.violations.iter().any(|v| v.rule == "width.track"));
+        let full = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        assert!(!full.violations.iter().any(|v| v.rule == "width.track"));
+    }
+    
+    #[test]
+    fn test_incremental_drc_proposed_rule_areas() {
+        use crate::drc_rules::RuleConstraint;
+        use crate::layout::{Keepouts, RuleArea};
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        let square = vec![
+            Point2D::new(20.0, 20.0),
+            Point2D::new(30.0, 20.0),
+            Point2D::new(30.0, 30.0),
+            Point2D::new(20.0, 30.0),
+        ];
+        layout.rule_areas.push(RuleArea::new("no_vias", square.clone())
+            .with_keepout(Keepouts { vias: true, ..Default::default() }));
+        layout.rule_areas.push(RuleArea::new("fine", square)
+            .with_rule(None, RuleConstraint::TrackWidth, 0.3));
+        let drc = IncrementalDrc::new(PcbDrcChecker::new(&layout, PcbDesignRules::default()));
+        
+        let via = Via {
+            net: "SIG".to_string(),
+            position: make_position(25.0, 25.0),
+            via_type: ViaType::Through,
+            drill: 0.2,
+            pad: 0.6,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        };
+        let violations = drc.check_proposed(&layout, &[track("SIG", 10.0, 25.0, 25.0, 25.0)], &[via]);
+        let rules: HashSet<&str> = violations.iter().map(|v| v.rule.as_str()).collect();
+        assert!(rules.contains("keepout.via"));
+        assert!(rules.contains("size.via_drill"));
+        assert!(violations.iter().any(|v| v.rule == "width.track" && v.message.contains("rule 'fine")));
+    }
+    
+    #[test]
+    fn test_incremental_drc_connectivity() {
+        use crate::layout::{Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        for (reference, x) in [("TP1", 10.0), ("TP2", 30.0)] {
+            layout.components.push(
+                PlacedComponent::new(reference, "", "TestPoint")
+                    .at(x, 10.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("SIG")),
+            );
+        }
+        let mut drc = IncrementalDrc::new(PcbDrcChecker::new(&layout, PcbDesignRules::default()));
+        assert!(drc.report().violations.iter().any(|v| v.rule == "connectivity.unrouted"));
+        
+        layout.traces.push(track("SIG", 10.0, 10.0, 30.0, 10.0));
+        let mut changes = DrcChangeSet::new();
+        changes.add_trace(&layout.traces[0]);
+        drc.update(&layout, &changes);
+        assert!(drc.report().violations.is_empty());
+    }
+    
+    #[test]
+    fn test_incremental_drc_high_speed() {
+        use crate::net::NetType;
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        layout.traces.push(track("CLK", 10.0, 10.0, 40.0, 10.0));
+        layout.traces.push(track("DATA", 10.0, 10.5, 40.0, 10.5));
+        let checker = PcbDrcChecker::new(&layout, PcbDesignRules::default())
+            .with_nets(vec![Net::new("CLK").with_type(NetType::Clock)])
+            .with_constraints(vec![Constraint::parallel_run("type:clock", 0.5, 10.0, LengthUnit::Mm)]);
+        let mut drc = IncrementalDrc::new(checker);
+        assert!(drc.report().violations.iter().any(|v| v.rule == "hs.crosstalk"));
+        
+        layout.traces[1].end.x = 15.0;
+        let mut changes = DrcChangeSet::new();
+        changes.add_trace(&layout.traces[1]);
+        drc.update(&layout, &changes);
+        assert!(!drc.report().violations.iter().any(|v| v.rule == "hs.crosstalk"));
+    }
+    
+    #[test]
+    fn test_filled_zone_clearance() {
+        use crate::layout::{Pad, PlacedComponent, Zone};
+        use crate::zone_fill::fill_zones;
+        
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        layout.zones.push(Zone::new("GND", "F.Cu", vec![
+            Point2D::new(5.0, 5.0),
+            Point2D::new(55.0, 5.0),
+            Point2D::new(55.0, 55.0),
+            Point2D::new(5.0, 55.0),
+        ]));
+        for (reference, x, net) in [("TP1", 10.0, "SIG"), ("TP2", 50.0, "SIG"), ("TP3", 30.0, "GND")] {
+            layout.components.push(
+                PlacedComponent::new(reference, "", "TestPoint")
+                    .at(x, 30.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.5, 1.5).with_net(net)),
+            );
+        }
+        layout.traces.push(track("SIG", 10.0, 30.0, 20.0, 40.0));
+        layout.traces.push(track("SIG", 20.0, 40.0, 50.0, 30.0));
+        
+        // The outline overlaps SIG copper, which only matters once filled
+        let rules = PcbDesignRules::default();
+        let clearance = |report: &DrcReport| report.violations.iter()
+            .filter(|v| v.rule.starts_with("clearance.") || v.rule.starts_with("short."))
+            .count();
+        assert_eq!(clearance(&PcbDrcChecker::new(&layout, rules.clone()).check_all()), 0);
+        
+        fill_zones(&mut layout);
+        let report = PcbDrcChecker::new(&layout, rules.clone()).check_all();
+        assert_eq!(clearance(&report), 0, "{:?}", report.violations);
+        assert!(!report.violations.iter().any(|v| v.rule == "connectivity.zone_island"));
+        
+        // A stale fill under new copper is caught
+        layout.traces.push(track("SIG", 20.0, 10.0, 40.0, 10.0));
+        assert!(PcbDrcChecker::new(&layout, rules).check_all().violations.iter().any(|v| v.rule == "short.copper"));
+    }
+    
+    #[test]
+    fn test_keepout_violations() {
+        use crate::layout::{IslandRemoval, Keepouts, Pad, PlacedComponent, RuleArea, Zone};
+        use crate::zone_fill::fill_zones;
+        
+        let square = |x: f64, y: f64, size: f64| vec![
+            Point2D::new(x, y),
+            Point2D::new(x + size, y),
+            Point2D::new(x + size, y + size),
+            Point2D::new(x, y + size),
+        ];
+        let mut layout = Layout::with_board_size(60.0, 60.0, LengthUnit::Mm);
+        layout.rule_areas.push(RuleArea::new("no_copper", square(20.0, 20.0, 10.0))
+            .with_keepout(Keepouts { tracks: true, vias: true, copper_pour: true, ..Default::default() }));
+        layout.rule_areas.push(RuleArea::new("antenna", square(40.0, 40.0, 10.0))
+            .with_layers(&["F.Cu"])
+            .with_keepout(Keepouts { footprints: true, ..Default::default() }));
+        
+        layout.traces.push(track("SIG", 10.0, 25.0, 40.0, 25.0));
+        layout.traces.push(track("SIG", 10.0, 10.0, 40.0, 10.0));
+        for (x, y) in [(25.0, 28.0), (35.0, 35.0)] {
+            layout.vias.push(Via {
+                net: "SIG".to_string(),
+                position: make_position(x, y),
+                via_type: ViaType::Through,
+                drill: 0.3,
+                pad: 0.6,
+                start_layer: None,
+                end_layer: None,
+                unit: LengthUnit::Mm,
+            });
+        }
+        layout.components.push(
+            PlacedComponent::new("U1", "", "QFN")
+                .at(45.0, 45.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0)),
+        );
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let count = |rule: &str| report.violations.iter().filter(|v| v.rule == rule).count();
+        assert_eq!(count("keepout.track"), 1);
+        assert_eq!(count("keepout.via"), 1);
+        assert_eq!(count("keepout.footprint"), 1);
+        // The pad itself is allowed, only its footprint is not
+        assert_eq!(count("keepout.pad"), 0);
+        
+        // Zone fill leaves the copper pour keepout empty
+        layout.traces.clear();
+        layout.vias.clear();
+        let mut zone = Zone::new("GND", "F.Cu", square(5.0, 5.0, 50.0));
+        zone.island_removal = IslandRemoval::Never;
+        layout.zones.push(zone);
+        fill_zones(&mut layout);
+        assert!(!layout.zones[0].filled.is_empty());
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        assert!(!report.violations.iter().any(|v| v.rule == "keepout.copper_pour"), "{:?}", report.violations);
+    }
+    
+    #[test]
+    fn test_parallel_drc_is_deterministic() {
+        let mut layout = create_test_layout();
+        for i in 0..300 {
+            let y = 20.0 + (i % 60) as f64 * 0.3;
+            let x = 5.0 + (i / 60) as f64 * 15.0;
+            layout.traces.push(track(&format!("N{}", i), x, y, x + 10.0, y));
+        }
+        
+        let (serial, _) = PcbDrcChecker::new(&layout, PcbDesignRules::default()).with_threads(1).check_all_timed();
+        let (parallel, timings) = PcbDrcChecker::new(&layout, PcbDesignRules::default()).with_threads(4).check_all_timed();
+        let keys = |report: &DrcReport| -> Vec<String> {
+            report.violations.iter().map(|v| format!("{} {:?} {}", v.rule, v.location, v.message)).collect()
+        };
+        assert!(!serial.violations.is_empty());
+        assert_eq!(keys(&serial), keys(&parallel));
+        
+        assert_eq!(timings.threads, 4);
+        assert_eq!(timings.checks.len(), DrcCheck::ALL.len());
+        let counted: usize = timings.checks.iter().map(|t| t.violations).sum();
+        assert_eq!(counted, parallel.violations.len());
+        
+        // Timings are attributed to the check that found the violations
+        assert!(timings.checks.iter().zip(DrcCheck::ALL).all(|(t, &check)| t.check == check));
+        let copper = parallel.violations.iter()
+            .filter(|v| v.rule == "short.copper"
+                || (v.rule.starts_with("clearance.") && v.rule != "clearance.edge" && v.rule != "clearance.courtyard"))
+            .count();
+        let timing = timings.checks.iter().find(|t| t.check == DrcCheck::CopperClearance).unwrap();
+        assert!(copper > 0);
+        assert_eq!(timing.violations, copper);
+        assert!(timings.prepare <= timings.total);
+    }
+    
+    #[test]
+    fn test_pcb_drc_courtyard_clearance() {
+        use crate::layout::Pad;
+        
+        // Origins 6mm apart, but the connector reaches towards the resistor
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.components.push(PlacedComponent::new("J1", "HDR", "PinHeader_1x03").at(10.0, 10.0)
+            .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0))
+            .with_pad(Pad::smd("2", 2.5, 0.0, 1.0, 1.0))
+            .with_pad(Pad::smd("3", 5.0, 0.0, 1.0, 1.0)));
+        layout.components.push(PlacedComponent::new("R1", "10k", "R_0603").at(16.0, 10.0)
+            .with_pad(Pad::smd("1", -0.4, 0.0, 0.5, 0.5)));
+        // Far from J1 by origin and by courtyard
+        layout.components.push(PlacedComponent::new("R2", "10k", "R_0603").at(10.0, 20.0)
+            .with_pad(Pad::smd("1", 0.0, 0.0, 0.5, 0.5)));
+        // Diagonal neighbours 0.2mm apart on each axis, 0.28mm corner to corner
+        layout.components.push(PlacedComponent::new("R3", "10k", "R_0603").at(30.0, 30.0)
+            .with_pad(Pad::smd("1", 0.0, 0.0, 0.5, 0.5)));
+        layout.components.push(PlacedComponent::new("R4", "10k", "R_0603").at(31.2, 31.2)
+            .with_pad(Pad::smd("1", 0.0, 0.0, 0.5, 0.5)));
+        
+        let report = PcbDrcChecker::new(&layout, PcbDesignRules::default()).check_all();
+        let courtyard: Vec<&DrcViolation> = report.violations.iter().filter(|v| v.rule == "clearance.courtyard").collect();
+        assert_eq!(courtyard.len(), 1);
+        assert!(courtyard[0].message.contains("J1 and R1 overlap"));
+        assert!(courtyard[0].location.x > 15.0 && courtyard[0].location.x < 15.6);
+    }
+    
     #[test]
     fn test_pcb_drc_jlcpcb_rules() {
         let rules = PcbDesignRules::jlcpcb();
         
         assert_eq!(rules.min_track_width, 0.127);
         assert_eq!(rules.min_via_drill, 0.2);
+        assert_eq!(PcbDesignRules::osh_park().min_edge_clearance, 0.381);
+        assert_eq!(PcbDesignRules::osh_park().min_via_clearance, 0.254);
     }
     
     #[test]
@@ -0,0 +1,688 @@
+//! Component Placement Tools.
+//!
+//! Operations over the placed footprints of a layout: align and distribute
+//! a selection, grid and polar arrays, clustering by schematic sheet or
+//! functional block, flipping to the other side, and moving a part next to
+//! the parts it connects to. Component extents are their courtyards: the
+//! courtyard drawing, or the pad bounding box grown by `COURTYARD_MARGIN`.
+//!
+//! Locked components are never moved: operations over a selection leave
+//! them in place, operations on a single component report an error.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::drc_rules::COURTYARD_MARGIN;
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D, Position};
+use crate::layout::{ComponentLayer, Layout, PlacedComponent};
+use crate::schematic::SchematicSheet;
+
+/// Placement error.
+#[derive(Debug, Clone)]
+pub struct PlacementError {
+    /// Error description
+    pub message: String,
+}
+
+impl PlacementError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Placement error: {}", self.message)
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// Result type for placement operations.
+pub type PlacementResult<T> = Result<T, PlacementError>;
+
+/// Edge or center line to align a selection on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    /// Leftmost courtyard edge
+    Left,
+    /// Rightmost courtyard edge
+    Right,
+    /// Topmost courtyard edge (smallest y)
+    Top,
+    /// Bottommost courtyard edge (largest y)
+    Bottom,
+    /// Mean of the courtyard centers, on a vertical line
+    CenterX,
+    /// Mean of the courtyard centers, on a horizontal line
+    CenterY,
+}
+
+/// Direction to distribute a selection along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    /// Along x
+    Horizontal,
+    /// Along y
+    Vertical,
+}
+
+/// Components placed together, such as the parts of one schematic sheet
+/// or functional block.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementGroup {
+    /// Group name
+    pub name: String,
+
+    /// Reference designators
+    pub references: Vec<String>,
+}
+
+impl PlacementGroup {
+    /// Create a group.
+    pub fn new(name: impl Into<String>, references: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            references: references.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    /// One group per schematic sheet, holding the references of its
+    /// symbols. Power symbols (`#` references) are skipped and multi-unit
+    /// symbols counted once.
+    pub fn from_sheets(sheets: &[SchematicSheet]) -> Vec<Self> {
+        sheets.iter()
+            .map(|sheet| Self::from_symbols(&sheet.name, sheet.symbols.iter().map(|s| s.reference.as_str())))
+            .filter(|group| !group.references.is_empty())
+            .collect()
+    }
+
+    fn from_symbols<'s>(name: &str, references: impl Iterator<Item = &'s str>) -> Self {
+        let mut group = Self { name: name.to_string(), references: Vec::new() };
+        for reference in references {
+            if !reference.starts_with('#') && !group.references.iter().any(|r| r == reference) {
+                group.references.push(reference.to_string());
+            }
+        }
+        group
+    }
+}
+
+/// Placement settings (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementConfig {
+    /// Gap between courtyards when packing or searching for a free spot
+    pub spacing: f64,
+
+    /// Gap between clustered groups
+    pub group_spacing: f64,
+
+    /// Grid step of the free-spot search
+    pub search_step: f64,
+
+    /// Farthest distance from the ideal position the search looks
+    pub search_radius: f64,
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        Self {
+            spacing: 0.5,
+            group_spacing: 5.0,
+            search_step: 0.5,
+            search_radius: 25.0,
+        }
+    }
+}
+
+/// Placement operations on a layout.
+pub struct ComponentPlacer<'a> {
+    layout: &'a mut Layout,
+    config: PlacementConfig,
+}
+
+impl<'a> ComponentPlacer<'a> {
+    /// Create a placer.
+    pub fn new(layout: &'a mut Layout, config: PlacementConfig) -> Self {
+        Self { layout, config }
+    }
+
+    /// Align the courtyards of the selection on a common edge or center
+    /// line.
+    pub fn align(&mut self, references: &[&str], alignment: Alignment) -> PlacementResult<()> {
+        let selection = self.select(references)?;
+        let boxes: Vec<BoundingBox> = selection.iter().map(|&i| extent(&self.layout.components[i])).collect();
+        let mean = |f: fn(&BoundingBox) -> f64| boxes.iter().map(f).sum::<f64>() / boxes.len() as f64;
+        let target = match alignment {
+            Alignment::Left => boxes.iter().map(|b| b.min.x).fold(f64::INFINITY, f64::min),
+            Alignment::Right => boxes.iter().map(|b| b.max.x).fold(f64::NEG_INFINITY, f64::max),
+            Alignment::Top => boxes.iter().map(|b| b.min.y).fold(f64::INFINITY, f64::min),
+            Alignment::Bottom => boxes.iter().map(|b| b.max.y).fold(f64::NEG_INFINITY, f64::max),
+            Alignment::CenterX => mean(|b| b.center().x),
+            Alignment::CenterY => mean(|b| b.center().y),
+        };
+        for (&i, b) in selection.iter().zip(&boxes) {
+            let (dx, dy) = match alignment {
+                Alignment::Left => (target - b.min.x, 0.0),
+                Alignment::Right => (target - b.max.x, 0.0),
+                Alignment::Top => (0.0, target - b.min.y),
+                Alignment::Bottom => (0.0, target - b.max.y),
+                Alignment::CenterX => (target - b.center().x, 0.0),
+                Alignment::CenterY => (0.0, target - b.center().y),
+            };
+            self.translate(i, dx, dy);
+        }
+        Ok(())
+    }
+
+    /// Spread the selection so the gaps between neighbouring courtyards are
+    /// equal, keeping the first and last component in place.
+    pub fn distribute(&mut self, references: &[&str], axis: Axis) -> PlacementResult<()> {
+        let mut selection: Vec<(usize, f64, f64)> = self.select(references)?.into_iter()
+            .map(|i| {
+                let b = extent(&self.layout.components[i]);
+                match axis {
+                    Axis::Horizontal => (i, b.min.x, b.max.x),
+                    Axis::Vertical => (i, b.min.y, b.max.y),
+                }
+            })
+            .collect();
+        if selection.len() < 3 {
+            return Ok(());
+        }
+        selection.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (first, last) = (selection[0], selection[selection.len() - 1]);
+        let sizes: f64 = selection.iter().map(|(_, lo, hi)| hi - lo).sum();
+        let gap = (last.2 - first.1 - sizes) / (selection.len() - 1) as f64;
+
+        let mut next = first.2 + gap;
+        for &(i, lo, hi) in &selection[1..selection.len() - 1] {
+            match axis {
+                Axis::Horizontal => self.translate(i, next - lo, 0.0),
+                Axis::Vertical => self.translate(i, 0.0, next - lo),
+            }
+            next += hi - lo + gap;
+        }
+        Ok(())
+    }
+
+    /// Copy a component into a grid of `rows` x `columns` at the given
+    /// pitch, the original being the first cell. Returns the references of
+    /// the copies, in row order.
+    pub fn grid_array(&mut self, reference: &str, rows: usize, columns: usize, pitch: (f64, f64)) -> PlacementResult<Vec<String>> {
+        let index = self.index(reference)?;
+        let mut copies = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                if row == 0 && column == 0 {
+                    continue;
+                }
+                let (dx, dy) = (column as f64 * pitch.0, row as f64 * pitch.1);
+                copies.push(self.copy(index, |c| {
+                    c.position.x += dx;
+                    c.position.y += dy;
+                }));
+            }
+        }
+        Ok(copies)
+    }
+
+    /// Copy a component `count - 1` times around `center`, `step` degrees
+    /// apart (counter-clockwise), rotating the copies with the array when
+    /// `rotate` is set. Returns the references of the copies.
+    pub fn polar_array(&mut self, reference: &str, center: Point2D, count: usize, step: f64, rotate: bool) -> PlacementResult<Vec<String>> {
+        let index = self.index(reference)?;
+        let mut copies = Vec::new();
+        for k in 1..count {
+            let angle = step * k as f64;
+            let (sin, cos) = angle.to_radians().sin_cos();
+            copies.push(self.copy(index, |c| {
+                let (dx, dy) = (c.position.x - center.x, c.position.y - center.y);
+                c.position.x = center.x + dx * cos - dy * sin;
+                c.position.y = center.y + dx * sin + dy * cos;
+                if rotate {
+                    c.rotation = (c.rotation + angle).rem_euclid(360.0);
+                }
+            }));
+        }
+        Ok(copies)
+    }
+
+    /// Move components to the other board side, mirrored about their own
+    /// origin. Front and back layers of pads and drawings are swapped.
+    pub fn flip(&mut self, references: &[&str]) -> PlacementResult<()> {
+        for i in self.select(references)? {
+            let component = &mut self.layout.components[i];
+            component.layer = match component.layer {
+                ComponentLayer::Top => ComponentLayer::Bottom,
+                ComponentLayer::Bottom => ComponentLayer::Top,
+            };
+            // Mirroring x before rotating by -r equals rotating by r and
+            // then mirroring
+            component.rotation = (-component.rotation).rem_euclid(360.0);
+            for pad in &mut component.pads {
+                for layer in &mut pad.layers {
+                    *layer = flip_layer(layer);
+                }
+            }
+            for graphic in &mut component.graphics {
+                graphic.layer = flip_layer(&graphic.layer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pack each group's components together, and the groups side by side
+    /// in rows from `origin`, wrapping at `max_width`. Returns the area each
+    /// group occupies.
+    pub fn cluster(&mut self, groups: &[PlacementGroup], origin: Point2D, max_width: f64) -> PlacementResult<Vec<(String, BoundingBox)>> {
+        // Pack every group at the origin first, then move it into place
+        let mut packed = Vec::new();
+        for group in groups {
+            let references: Vec<&str> = group.references.iter().map(String::as_str).collect();
+            let members = self.select(&references)?;
+            if members.is_empty() {
+                continue;
+            }
+            let mut members: Vec<(usize, BoundingBox)> = members.into_iter()
+                .map(|i| (i, extent(&self.layout.components[i])))
+                .collect();
+            members.sort_by(|a, b| b.1.height().total_cmp(&a.1.height()).then(a.0.cmp(&b.0)));
+            let area: f64 = members.iter().map(|(_, b)| (b.width() + self.config.spacing) * (b.height() + self.config.spacing)).sum();
+            let widest = members.iter().map(|(_, b)| b.width()).fold(0.0, f64::max);
+            let row_width = (area.sqrt() * 1.5).max(widest);
+
+            let sizes: Vec<(f64, f64)> = members.iter().map(|(_, b)| (b.width(), b.height())).collect();
+            let (spots, size) = shelf_pack(&sizes, row_width, self.config.spacing);
+            for ((i, b), spot) in members.iter().zip(&spots) {
+                self.translate(*i, spot.x - b.min.x, spot.y - b.min.y);
+            }
+            packed.push((group.name.clone(), members.into_iter().map(|(i, _)| i).collect::<Vec<_>>(), size));
+        }
+
+        let sizes: Vec<(f64, f64)> = packed.iter().map(|(_, _, size)| *size).collect();
+        let (spots, _) = shelf_pack(&sizes, max_width, self.config.group_spacing);
+        let mut areas = Vec::new();
+        for ((name, members, size), spot) in packed.into_iter().zip(spots) {
+            let (dx, dy) = (origin.x + spot.x, origin.y + spot.y);
+            for &i in &members {
+                self.translate(i, dx, dy);
+            }
+            let min = Point2D::new(dx, dy);
+            areas.push((name, BoundingBox::new(min, Point2D::new(dx + size.0, dy + size.1))));
+        }
+        Ok(areas)
+    }
+
+    /// Move a component to the free spot, in any of the four orthogonal
+    /// rotations, that minimizes the ratsnest length to the parts it
+    /// connects to. Returns the new position.
+    pub fn place_near_connected(&mut self, reference: &str) -> PlacementResult<Point2D> {
+        let index = self.index(reference)?;
+        if self.layout.components[index].locked {
+            return Err(PlacementError::new(format!("{} is locked", reference)));
+        }
+        let original = self.layout.components[index].clone();
+        if self.connection_centroid(index, &original).is_none() {
+            return Err(PlacementError::new(format!("{} has no connections to other parts", reference)));
+        }
+
+        let mut best: Option<(f64, PlacedComponent)> = None;
+        for quarter in 0..4 {
+            let mut candidate = original.clone();
+            candidate.rotation = (original.rotation + 90.0 * quarter as f64).rem_euclid(360.0);
+            // Pad offsets turn with the component, and so does the target
+            let Some(target) = self.connection_centroid(index, &candidate) else { continue };
+            let Some(spot) = self.free_spot(index, &candidate, target) else { continue };
+            candidate.position = Position { x: spot.x, y: spot.y, ..candidate.position };
+            let length = self.connection_length(index, &candidate);
+            if best.as_ref().is_none_or(|(l, _)| length < l - 1e-9) {
+                best = Some((length, candidate));
+            }
+        }
+
+        let Some((_, placed)) = best else {
+            return Err(PlacementError::new(format!("no free spot near the connections of {}", reference)));
+        };
+        let spot = placed.position.to_point2d();
+        self.layout.components[index] = placed;
+        Ok(spot)
+    }
+
+    /// Indices of the unlocked components in a selection.
+    fn select(&self, references: &[&str]) -> PlacementResult<Vec<usize>> {
+        let mut selection = Vec::new();
+        for reference in references {
+            let index = self.index(reference)?;
+            if !self.layout.components[index].locked && !selection.contains(&index) {
+                selection.push(index);
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Index of a component.
+    fn index(&self, reference: &str) -> PlacementResult<usize> {
+        self.layout.components.iter()
+            .position(|c| c.reference == reference)
+            .ok_or_else(|| PlacementError::new(format!("component {} not found", reference)))
+    }
+
+    fn translate(&mut self, index: usize, dx: f64, dy: f64) {
+        let position = &mut self.layout.components[index].position;
+        position.x += dx;
+        position.y += dy;
+    }
+
+    /// Add a copy of a component with the next free reference of its
+    /// prefix and no nets.
+    fn copy(&mut self, index: usize, place: impl FnOnce(&mut PlacedComponent)) -> String {
+        let mut copy = self.layout.components[index].clone();
+        copy.id = uuid::Uuid::new_v4();
+        copy.reference = next_reference(self.layout, &copy.reference);
+        copy.locked = false;
+        for pad in &mut copy.pads {
+            pad.net = None;
+        }
+        place(&mut copy);
+        let reference = copy.reference.clone();
+        self.layout.components.push(copy);
+        reference
+    }
+
+    /// Pads of other components sharing a net with the pads of `component`,
+    /// standing in for component `index`, as (own pad position relative to
+    /// the origin, other pad position, weight). Each net has a total weight
+    /// of one so large nets such as ground do not dominate.
+    fn connections(&self, index: usize, component: &PlacedComponent) -> Vec<(Point2D, Point2D, f64)> {
+        let mut pads_by_net: HashMap<&str, Vec<Point2D>> = HashMap::new();
+        for (i, other) in self.layout.components.iter().enumerate() {
+            if i == index {
+                continue;
+            }
+            for pad in &other.pads {
+                if let Some(net) = pad.net.as_deref() {
+                    pads_by_net.entry(net).or_default().push(other.pad_position(pad));
+                }
+            }
+        }
+
+        let mut connections = Vec::new();
+        for pad in &component.pads {
+            let Some(others) = pad.net.as_deref().and_then(|net| pads_by_net.get(net)) else { continue };
+            let own = component.pad_position(pad);
+            let offset = Point2D::new(own.x - component.position.x, own.y - component.position.y);
+            for other in others {
+                connections.push((offset, *other, 1.0 / others.len() as f64));
+            }
+        }
+        connections
+    }
+
+    /// Position putting the pads of `component`, at its rotation, closest
+    /// in the weighted least-squares sense to the pads they connect to.
+    fn connection_centroid(&self, index: usize, component: &PlacedComponent) -> Option<Point2D> {
+        let connections = self.connections(index, component);
+        let total: f64 = connections.iter().map(|c| c.2).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let x = connections.iter().map(|(own, other, w)| (other.x - own.x) * w).sum::<f64>() / total;
+        let y = connections.iter().map(|(own, other, w)| (other.y - own.y) * w).sum::<f64>() / total;
+        Some(Point2D::new(x, y))
+    }
+
+    /// Ratsnest length from each pad of `component` to the nearest pad of
+    /// its net on another component.
+    fn connection_length(&self, index: usize, component: &PlacedComponent) -> f64 {
+        let mut nearest: HashMap<String, f64> = HashMap::new();
+        for (own, other, _) in self.connections(index, component) {
+            let p = Point2D::new(component.position.x + own.x, component.position.y + own.y);
+            let key = format!("{:.6},{:.6}", own.x, own.y);
+            let d = p.distance(&other);
+            let entry = nearest.entry(key).or_insert(f64::INFINITY);
+            *entry = entry.min(d);
+        }
+        nearest.values().sum()
+    }
+
+    /// Nearest position to `target` on the search grid where the courtyard
+    /// of `component`, standing in for component `index`, stays clear of
+    /// the other components on its side, inside the board and out of
+    /// footprint keepouts.
+    fn free_spot(&self, index: usize, component: &PlacedComponent, target: Point2D) -> Option<Point2D> {
+        let step = self.config.search_step.max(0.01);
+        let steps = (self.config.search_radius / step).ceil() as i64;
+        let mut candidates: Vec<(f64, Point2D)> = Vec::new();
+        for i in -steps..=steps {
+            for j in -steps..=steps {
+                let p = Point2D::new(target.x + i as f64 * step, target.y + j as f64 * step);
+                let d = p.distance(&target);
+                if d <= self.config.search_radius {
+                    candidates.push((d, p));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let board = self.layout.outline.as_ref().map(|o| {
+            o.to_polygon(64).iter().map(|p| Point2D::new(o.unit.to_mm(p.x), o.unit.to_mm(p.y))).collect::<Vec<_>>()
+        });
+        let layer = component.layer;
+        let others: Vec<BoundingBox> = self.layout.components.iter().enumerate()
+            .filter(|(i, c)| *i != index && c.layer == layer)
+            .filter_map(|(_, c)| c.courtyard(COURTYARD_MARGIN))
+            .map(|b| b.expanded(self.config.spacing))
+            .collect();
+
+        let mut candidate = component.clone();
+        for (_, p) in candidates {
+            candidate.position.x = p.x;
+            candidate.position.y = p.y;
+            let courtyard = extent(&candidate);
+            let corners = [
+                courtyard.min,
+                Point2D::new(courtyard.max.x, courtyard.min.y),
+                courtyard.max,
+                Point2D::new(courtyard.min.x, courtyard.max.y),
+            ];
+            let on_board = board.as_ref().is_none_or(|board| corners.iter().all(|c| point_in_polygon(c, board)));
+            if on_board
+                && !others.iter().any(|b| b.intersects(&courtyard))
+                && self.layout.placement_keepout(&candidate, COURTYARD_MARGIN).is_none()
+            {
+                return Some(p);
+            }
+        }
+        None
+    }
+}
+
+/// Courtyard of a component, or its origin for components without pads.
+fn extent(component: &PlacedComponent) -> BoundingBox {
+    component.courtyard(COURTYARD_MARGIN).unwrap_or_else(|| {
+        let p = component.position.to_point2d();
+        BoundingBox::new(p, p)
+    })
+}
+
+/// Swap the front and back variants of a layer name.
+fn flip_layer(layer: &str) -> String {
+    if let Some(rest) = layer.strip_prefix("F.") {
+        format!("B.{}", rest)
+    } else if let Some(rest) = layer.strip_prefix("B.") {
+        format!("F.{}", rest)
+    } else {
+        layer.to_string()
+    }
+}
+
+/// Next unused reference with the same letter prefix as `reference`.
+fn next_reference(layout: &Layout, reference: &str) -> String {
+    let prefix = reference.trim_end_matches(|c: char| c.is_ascii_digit());
+    let highest = layout.components.iter()
+        .filter_map(|c| c.reference.strip_prefix(prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    format!("{}{}", prefix, highest + 1)
+}
+
+/// Place boxes of the given sizes in rows no wider than `max_width`,
+/// tallest row item setting the row height. Returns the top-left corner
+/// of each box and the overall size.
+fn shelf_pack(sizes: &[(f64, f64)], max_width: f64, gap: f64) -> (Vec<Point2D>, (f64, f64)) {
+    let mut spots = Vec::new();
+    let (mut x, mut y, mut row_height, mut width) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+    for &(w, h) in sizes {
+        if x > 0.0 && x + w > max_width {
+            x = 0.0;
+            y += row_height + gap;
+            row_height = 0.0;
+        }
+        spots.push(Point2D::new(x, y));
+        width = width.max(x + w);
+        row_height = row_height.max(h);
+        x += w + gap;
+    }
+    (spots, (width, y + row_height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Pad;
+    use crate::units::LengthUnit;
+
+    fn resistor(reference: &str, x: f64, y: f64) -> PlacedComponent {
+        PlacedComponent::new(reference, "10k", "R_0603")
+            .at(x, y)
+            .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9))
+            .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9))
+    }
+
+    #[test]
+    fn test_align_and_distribute() {
+        let mut layout = Layout::with_board_size(100.0, 100.0, LengthUnit::Mm);
+        layout.components.push(resistor("R1", 10.0, 10.0));
+        layout.components.push(resistor("R2", 25.0, 14.0));
+        layout.components.push(resistor("R3", 60.0, 12.0));
+        layout.components.push(resistor("R4", 40.0, 30.0));
+        layout.components[3].locked = true;
+        let mut placer = ComponentPlacer::new(&mut layout, PlacementConfig::default());
+
+        placer.align(&["R1", "R2", "R3", "R4"], Alignment::Top).unwrap();
+        placer.distribute(&["R1", "R2", "R3"], Axis::Horizontal).unwrap();
+        assert!(layout.components[..3].iter().all(|c| (c.position.y - 10.0).abs() < 1e-9));
+        assert_eq!(layout.components[3].position.y, 30.0);
+        assert!((layout.components[1].position.x - 35.0).abs() < 1e-9);
+        assert_eq!(layout.components[2].position.x, 60.0);
+    }
+
+    #[test]
+    fn test_arrays_and_flip() {
+        let mut layout = Layout::with_board_size(100.0, 100.0, LengthUnit::Mm);
+        layout.components.push(resistor("R1", 10.0, 10.0).with_pad(Pad::smd("3", 0.0, 1.0, 0.5, 0.5).with_net("VCC")));
+        layout.components.push(resistor("R7", 90.0, 90.0));
+        let mut placer = ComponentPlacer::new(&mut layout, PlacementConfig::default());
+
+        let grid = placer.grid_array("R1", 2, 3, (5.0, 4.0)).unwrap();
+        assert_eq!(grid, vec!["R8", "R9", "R10", "R11", "R12"]);
+        let polar = placer.polar_array("R1", Point2D::new(20.0, 10.0), 4, 90.0, true).unwrap();
+        assert_eq!(polar.len(), 3);
+        placer.flip(&["R1"]).unwrap();
+
+        let r12 = layout.component("R12").unwrap();
+        assert_eq!((r12.position.x, r12.position.y), (20.0, 14.0));
+        assert!(r12.pads.iter().all(|p| p.net.is_none()));
+        let r13 = layout.component("R13").unwrap();
+        assert!((r13.position.x - 20.0).abs() < 1e-9 && (r13.position.y - 0.0).abs() < 1e-9);
+        assert_eq!(r13.rotation, 90.0);
+
+        // Flipping mirrors the pads about the component origin
+        let r1 = layout.component("R1").unwrap();
+        assert_eq!(r1.layer, ComponentLayer::Bottom);
+        let pad = r1.pad_position(&r1.pads[0]);
+        assert!((pad.x - 10.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cluster_by_sheet() {
+        let mut layout = Layout::with_board_size(100.0, 100.0, LengthUnit::Mm);
+        for (i, reference) in ["R1", "R2", "R3", "C1", "C2"].iter().enumerate() {
+            layout.components.push(resistor(reference, 10.0 + 15.0 * i as f64, 50.0));
+        }
+        assert!(PlacementGroup::from_sheets(&[SchematicSheet::new("Empty")]).is_empty());
+        let power = PlacementGroup::from_symbols("Power", ["C1", "R1", "#PWR01", "R1"].into_iter());
+        assert_eq!(power.references, vec!["C1", "R1"]);
+        let groups = vec![power, PlacementGroup::new("MCU", &["R2", "R3", "C2"])];
+
+        let areas = ComponentPlacer::new(&mut layout, PlacementConfig::default())
+            .cluster(&groups, Point2D::new(5.0, 5.0), 90.0)
+            .unwrap();
+        assert_eq!(areas.len(), 2);
+        assert!(!areas[0].1.intersects(&areas[1].1));
+        for (name, area) in &areas {
+            let group = groups.iter().find(|g| &g.name == name).unwrap();
+            for reference in &group.references {
+                let courtyard = extent(layout.component(reference).unwrap());
+                assert!(courtyard.min.x >= area.min.x - 1e-9 && courtyard.max.x <= area.max.x + 1e-9);
+                assert!(courtyard.min.y >= area.min.y - 1e-9 && courtyard.max.y <= area.max.y + 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_place_near_connected() {
+        let mut layout = Layout::with_board_size(100.0, 100.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("U1", "MCU", "QFN")
+                .at(50.0, 50.0)
+                .with_pad(Pad::smd("1", -2.0, 0.0, 1.0, 1.0).with_net("A"))
+                .with_pad(Pad::smd("2", 2.0, 0.0, 1.0, 1.0).with_net("B")),
+        );
+        layout.components.push(
+            resistor("R1", 90.0, 90.0)
+                .with_pad(Pad::smd("3", -0.8, 0.0, 0.1, 0.1).with_net("A"))
+                .with_pad(Pad::smd("4", 0.8, 0.0, 0.1, 0.1).with_net("B")),
+        );
+        let spot = ComponentPlacer::new(&mut layout, PlacementConfig::default())
+            .place_near_connected("R1")
+            .unwrap();
+
+        // Lands next to U1 without overlapping it
+        assert!(spot.distance(&Point2D::new(50.0, 50.0)) < 5.0);
+        let (u1, r1) = (extent(&layout.components[0]), extent(&layout.components[1]));
+        assert!(!u1.expanded(0.5).intersects(&r1));
+    }
+
+    #[test]
+    fn test_place_near_connected_turns_offset_pads() {
+        let mut layout = Layout::with_board_size(100.0, 100.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("U1", "MCU", "QFN")
+                .at(50.0, 50.0)
+                .with_pad(Pad::smd("1", 0.0, -3.0, 1.0, 1.0).with_net("A"))
+                .with_pad(Pad::smd("2", 0.0, 3.0, 1.0, 1.0).with_net("B")),
+        );
+        // Pads far from the origin, lined up with U1 only once turned back to 0
+        layout.components.push(
+            PlacedComponent::new("J1", "HDR", "Header")
+                .at(90.0, 90.0)
+                .rotated(90.0)
+                .with_pad(Pad::smd("1", 10.0, -3.0, 0.5, 0.5).with_net("A"))
+                .with_pad(Pad::smd("2", 10.0, 3.0, 0.5, 0.5).with_net("B")),
+        );
+        ComponentPlacer::new(&mut layout, PlacementConfig::default())
+            .place_near_connected("J1")
+            .unwrap();
+
+        let (u1, j1) = (&layout.components[0], &layout.components[1]);
+        assert_eq!(j1.rotation, 0.0);
+        for (a, b) in u1.pads.iter().zip(&j1.pads) {
+            assert!(u1.pad_position(a).distance(&j1.pad_position(b)) < 3.0);
+        }
+    }
+}
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 use crate::domain::HardwareDomain;
+use crate::drc_rules::CustomRule;
+use crate::stackup::StackupConfig;
 
 /// A Hardware Tool project.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,9 +120,17 @@ pub struct PcbConfig {
     /// Layout file
     pub layout: PathBuf,
 
-    /// Stackup preset
+    /// Stackup preset name or inline stackup
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub stackup: Option<String>,
+    pub stackup: Option<StackupConfig>,
+
+    /// Fab capability profile name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fab: Option<String>,
+
+    /// Custom design rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<CustomRule>,
 }
 
 /// Library configuration.
@@ -181,4 +191,84 @@ mod tests {
         let parsed = Project::from_toml(&toml).unwrap();
         assert_eq!(parsed.project.name, "Test Project");
     }
+
+    #[test]
+    fn test_project_rules_roundtrip() {
+        let toml = r#"
+            [project]
+            name = "Board"
+            domain = "pcb"
+
+            [pcb]
+            layout = "board.kicad_pcb"
+
+            [[pcb.rules]]
+            name = "HV clearance"
+            condition = "A.netclass == 'HV' && layer == 'outer'"
+            constraint = "clearance"
+            min = 2.0
+            priority = 5
+        "#;
+        let project = Project::from_toml(toml).unwrap();
+        let rules = &project.pcb.as_ref().unwrap().rules;
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].priority, 5);
+
+        let parsed = Project::from_toml(&project.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed.pcb.unwrap().rules[0].name, "HV clearance");
+    }
+
+    #[test]
+    fn test_project_stackup() {
+        let preset = r#"
+            [project]
+            name = "Board"
+            domain = "pcb"
+
+            [pcb]
+            layout = "board.kicad_pcb"
+            stackup = "4-layer"
+        "#;
+        let project = Project::from_toml(preset).unwrap();
+        let stackup = project.pcb.as_ref().unwrap().stackup.as_ref().unwrap();
+        assert_eq!(stackup, &StackupConfig::Preset("4-layer".to_string()));
+        assert_eq!(stackup.resolve().unwrap().copper_layer_names().len(), 4);
+
+        let inline = r#"
+            [project]
+            name = "Board"
+            domain = "pcb"
+
+            [pcb]
+            layout = "board.kicad_pcb"
+
+            [pcb.stackup]
+            name = "Thin 2-layer"
+
+            [[pcb.stackup.layers]]
+            name = "F.Cu"
+            kind = "copper"
+            thickness = 0.035
+            copper_weight = 1.0
+
+            [[pcb.stackup.layers]]
+            name = "Core 1"
+            kind = "core"
+            thickness = 0.73
+            material = "FR4"
+            dielectric = [{ frequency_ghz = 1.0, dk = 4.5, df = 0.02 }]
+
+            [[pcb.stackup.layers]]
+            name = "B.Cu"
+            kind = "copper"
+            thickness = 0.035
+        "#;
+        let project = Project::from_toml(inline).unwrap();
+        let stackup = project.pcb.as_ref().unwrap().stackup.as_ref().unwrap().resolve().unwrap();
+        assert!((stackup.thickness() - 0.8).abs() < 1e-9);
+        assert_eq!(stackup.layer("Core 1").unwrap().dk(2.0), Some(4.5));
+
+        let parsed = Project::from_toml(&project.to_toml().unwrap()).unwrap();
+        assert_eq!(parsed.pcb.unwrap().stackup.unwrap().resolve().unwrap(), stackup);
+    }
 }
@@ -0,0 +1,553 @@
+//! Ratsnest computation.
+//!
+//! Tracks which pad-to-pad connections of each net are still unrouted.
+//! Copper of a net (pads, traces, vias and zones) is grouped into connected
+//! islands, and a minimum spanning tree between the islands that contain pads
+//! gives the open connections. Nets can be recomputed individually after a
+//! route is committed, so the ratsnest stays current during editing.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::copper::CopperShape;
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D, SpatialGrid};
+use crate::layout::{Layout, Zone};
+
+/// An unrouted connection between two copper islands of a net.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenConnection {
+    /// Net name
+    pub net: String,
+
+    /// Start point (on the first island)
+    pub from: Point2D,
+
+    /// Copper layers available at the start point
+    pub from_layers: Vec<String>,
+
+    /// End point (on the second island)
+    pub to: Point2D,
+
+    /// Copper layers available at the end point
+    pub to_layers: Vec<String>,
+
+    /// Straight-line length (mm)
+    pub length: f64,
+}
+
+/// Ratsnest state of a single net.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetRatsnest {
+    /// Net name
+    pub net: String,
+
+    /// Number of pads on the net
+    pub pad_count: usize,
+
+    /// Connections required to join every pad (pads - 1)
+    pub required: usize,
+
+    /// Connections still open
+    pub open: Vec<OpenConnection>,
+}
+
+impl NetRatsnest {
+    /// Whether every pad of the net is connected.
+    pub fn is_complete(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Number of routed connections.
+    pub fn routed(&self) -> usize {
+        self.required.saturating_sub(self.open.len())
+    }
+}
+
+/// Ratsnest for a whole layout.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ratsnest {
+    nets: BTreeMap<String, NetRatsnest>,
+}
+
+impl Ratsnest {
+    /// Compute the ratsnest for every net with pads.
+    pub fn compute(layout: &Layout) -> Self {
+        let mut ratsnest = Self::default();
+        for net in pad_nets(layout) {
+            ratsnest.update_net(layout, &net);
+        }
+        ratsnest
+    }
+
+    /// Recompute a single net (e.g., after `Router::commit_route`).
+    pub fn update_net(&mut self, layout: &Layout, net: &str) {
+        let state = compute_net(layout, net);
+        if state.pad_count == 0 {
+            self.nets.remove(net);
+        } else {
+            self.nets.insert(net.to_string(), state);
+        }
+    }
+
+    /// Recompute several nets.
+    pub fn update_nets<'n>(&mut self, layout: &Layout, nets: impl IntoIterator<Item = &'n str>) {
+        for net in nets {
+            self.update_net(layout, net);
+        }
+    }
+
+    /// Ratsnest state of a net.
+    pub fn net(&self, name: &str) -> Option<&NetRatsnest> {
+        self.nets.get(name)
+    }
+
+    /// All nets, ordered by name.
+    pub fn nets(&self) -> impl Iterator<Item = &NetRatsnest> {
+        self.nets.values()
+    }
+
+    /// All open connections, ordered by net name.
+    pub fn open_connections(&self) -> Vec<&OpenConnection> {
+        self.nets.values().flat_map(|n| n.open.iter()).collect()
+    }
+
+    /// Number of open connections.
+    pub fn unrouted_count(&self) -> usize {
+        self.nets.values().map(|n| n.open.len()).sum()
+    }
+
+    /// Total number of connections required to complete the board.
+    pub fn required_count(&self) -> usize {
+        self.nets.values().map(|n| n.required).sum()
+    }
+
+    /// Routing completion in percent (100 when nothing needs routing).
+    pub fn completion_percent(&self) -> f64 {
+        let required = self.required_count();
+        if required == 0 {
+            return 100.0;
+        }
+        let routed: usize = self.nets.values().map(|n| n.routed()).sum();
+        routed as f64 / required as f64 * 100.0
+    }
+}
+
+/// Sorted names of nets that have at least one pad.
+fn pad_nets(layout: &Layout) -> Vec<String> {
+    let mut nets: Vec<String> = layout
+        .components
+        .iter()
+        .flat_map(|c| c.pads.iter().filter_map(|p| p.net.clone()))
+        .filter(|n| !n.is_empty())
+        .collect();
+    nets.sort();
+    nets.dedup();
+    nets
+}
+
+/// A piece of copper belonging to the net being analysed.
+enum Item {
+    Pad {
+        component: usize,
+        pad: usize,
+        center: Point2D,
+        layers: Vec<String>,
+    },
+    Trace {
+        start: Point2D,
+        end: Point2D,
+        half_width: f64,
+        layer: String,
+    },
+    Via {
+        center: Point2D,
+        radius: f64,
+        layers: Vec<String>,
+    },
+    /// One island of a zone fill, or an unfilled zone's outline
+    Zone {
+        polygons: Vec<Vec<Point2D>>,
+        layer: String,
+    },
+}
+
+impl Item {
+    fn layers(&self) -> &[String] {
+        match self {
+            Item::Pad { layers, .. } | Item::Via { layers, .. } => layers,
+            Item::Trace { layer, .. } | Item::Zone { layer, .. } => std::slice::from_ref(layer),
+        }
+    }
+
+    /// Bounds of the item's copper.
+    fn bounds(&self, layout: &Layout) -> Option<BoundingBox> {
+        match self {
+            Item::Pad { component, pad, .. } => {
+                let c = &layout.components[*component];
+                CopperShape::from_pad(c, &c.pads[*pad]).bounding_box()
+            }
+            Item::Trace { start, end, half_width, .. } => {
+                BoundingBox::from_points(&[*start, *end]).map(|b| b.expanded(*half_width))
+            }
+            Item::Via { center, radius, .. } => BoundingBox::from_points(&[*center]).map(|b| b.expanded(*radius)),
+            Item::Zone { polygons, .. } => BoundingBox::from_points(&polygons.concat()),
+        }
+    }
+
+    /// Points a ratsnest line may start from.
+    fn anchors(&self) -> Vec<Point2D> {
+        match self {
+            Item::Pad { center, .. } | Item::Via { center, .. } => vec![*center],
+            Item::Trace { start, end, .. } => vec![*start, *end],
+            Item::Zone { .. } => Vec::new(),
+        }
+    }
+
+    /// Whether `point` lies on this item's copper (within `tolerance`).
+    fn contains(&self, layout: &Layout, point: &Point2D, tolerance: f64) -> bool {
+        match self {
+            Item::Pad { component, pad, .. } => {
+                let c = &layout.components[*component];
+                c.pad_distance(&c.pads[*pad], point) <= tolerance
+            }
+            Item::Trace { start, end, half_width, .. } => {
+                point.distance_to_segment(start, end) <= half_width + tolerance
+            }
+            Item::Via { center, radius, .. } => point.distance(center) <= radius + tolerance,
+            Item::Zone { polygons, .. } => polygons.iter().any(|p| point_in_polygon(point, p)),
+        }
+    }
+}
+
+/// Distance within which copper of a net is considered connected (mm).
+const TOLERANCE: f64 = 1e-3;
+
+/// Whether two items of the same net touch.
+fn touches(layout: &Layout, a: &Item, b: &Item) -> bool {
+    let shared = a.layers().iter().any(|l| b.layers().contains(l));
+    if !shared {
+        return false;
+    }
+    if a.anchors().iter().any(|p| b.contains(layout, p, TOLERANCE))
+        || b.anchors().iter().any(|p| a.contains(layout, p, TOLERANCE))
+    {
+        return true;
+    }
+    // Zones also connect to anything whose copper crosses their outline
+    match (a, b) {
+        (Item::Zone { polygons, .. }, other) | (other, Item::Zone { polygons, .. }) => {
+            polygons.iter().flatten().any(|p| other.contains(layout, p, TOLERANCE))
+        }
+        _ => false,
+    }
+}
+
+/// Polygons of each island of a zone's fill, or its outline when unfilled.
+fn zone_islands(zone: &Zone) -> Vec<Vec<Vec<Point2D>>> {
+    if !zone.is_filled() {
+        return vec![vec![zone.points.clone()]];
+    }
+    let shapes: Vec<CopperShape> = zone.filled.iter().map(|p| CopperShape::polygon(p.clone())).collect();
+    let grid = SpatialGrid::new(shapes.iter().map(|s| s.bounding_box().map(|b| b.expanded(1e-6))).collect());
+    let mut sets = UnionFind::new(shapes.len());
+    for i in 0..shapes.len() {
+        let Some(bounds) = grid.bounds(i) else { continue };
+        for j in grid.query(bounds).into_iter().filter(|&j| j > i) {
+            if shapes[i].distance(&shapes[j]) <= 1e-6 {
+                sets.union(i, j);
+            }
+        }
+    }
+    let mut islands: BTreeMap<usize, Vec<Vec<Point2D>>> = BTreeMap::new();
+    for (i, polygon) in zone.filled.iter().enumerate() {
+        islands.entry(sets.find(i)).or_default().push(polygon.clone());
+    }
+    islands.into_values().collect()
+}
+
+/// Compute the ratsnest state of one net.
+fn compute_net(layout: &Layout, net: &str) -> NetRatsnest {
+    let copper = layout.copper_layer_names();
+    let mut items = Vec::new();
+
+    for (ci, component) in layout.components.iter().enumerate() {
+        for (pi, pad) in component.pads.iter().enumerate() {
+            if pad.net.as_deref() == Some(net) {
+                items.push(Item::Pad {
+                    component: ci,
+                    pad: pi,
+                    center: component.pad_position(pad),
+                    layers: component.pad_copper_layers(pad, &copper),
+                });
+            }
+        }
+    }
+    let pad_count = items.len();
+
+    for trace in layout.traces.iter().filter(|t| t.net == net) {
+        items.push(Item::Trace {
+            start: trace.start.to_point2d(),
+            end: trace.end.to_point2d(),
+            half_width: trace.width / 2.0,
+            layer: trace.layer.clone(),
+        });
+    }
+    for via in layout.vias.iter().filter(|v| v.net == net) {
+        items.push(Item::Via {
+            center: via.position.to_point2d(),
+            radius: via.pad / 2.0,
+            layers: via.copper_layers(&copper),
+        });
+    }
+    for zone in layout.zones.iter().filter(|z| z.net == net && z.points.len() >= 3) {
+        for polygons in zone_islands(zone) {
+            items.push(Item::Zone {
+                polygons,
+                layer: zone.layer.clone(),
+            });
+        }
+    }
+
+    let grid = SpatialGrid::new(items.iter().map(|item| item.bounds(layout).map(|b| b.expanded(TOLERANCE))).collect());
+    let mut sets = UnionFind::new(items.len());
+    for i in 0..items.len() {
+        let Some(bounds) = grid.bounds(i) else { continue };
+        for j in grid.query(bounds).into_iter().filter(|&j| j > i) {
+            if touches(layout, &items[i], &items[j]) {
+                sets.union(i, j);
+            }
+        }
+    }
+
+    // Islands that contain pads, in pad order
+    let mut islands: Vec<usize> = Vec::new();
+    for i in 0..pad_count {
+        let root = sets.find(i);
+        if !islands.contains(&root) {
+            islands.push(root);
+        }
+    }
+    let island_of: HashMap<usize, usize> = islands.iter().enumerate().map(|(n, &root)| (root, n)).collect();
+    let anchors: Vec<(Point2D, usize, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| island_of.get(&sets.find(i)).map(|&island| (i, item, island)))
+        .flat_map(|(i, item, island)| item.anchors().into_iter().map(move |p| (p, island, i)))
+        .collect();
+
+    let open = island_tree(&anchors, islands.len())
+        .into_iter()
+        .map(|(a, b)| {
+            let ((from, _, ia), (to, _, ib)) = (anchors[a], anchors[b]);
+            OpenConnection {
+                net: net.to_string(),
+                from,
+                from_layers: items[ia].layers().to_vec(),
+                to,
+                to_layers: items[ib].layers().to_vec(),
+                length: from.distance(&to),
+            }
+        })
+        .collect();
+
+    NetRatsnest {
+        net: net.to_string(),
+        pad_count,
+        required: pad_count.saturating_sub(1),
+        open,
+    }
+}
+
+/// Minimum spanning tree between islands as pairs of indices into
+/// `anchors`, each given as (point, island, item).
+///
+/// Uses Borůvka's algorithm: every round joins each group of islands to
+/// its nearest anchor outside the group, found on a grid, so no pair of
+/// islands is compared exhaustively.
+fn island_tree(anchors: &[(Point2D, usize, usize)], island_count: usize) -> Vec<(usize, usize)> {
+    let grid = SpatialGrid::new(anchors.iter().map(|(p, ..)| BoundingBox::from_points(&[*p])).collect());
+    let mut sets = UnionFind::new(island_count);
+    let mut edges = Vec::new();
+    loop {
+        let roots: Vec<usize> = (0..island_count).map(|i| sets.find(i)).collect();
+        // Cheapest edge leaving each group, ties broken by anchor index
+        let mut cheapest: Vec<Option<(f64, usize, usize)>> = vec![None; island_count];
+        for (a, (point, island, _)) in anchors.iter().enumerate() {
+            let root = roots[*island];
+            let within = cheapest[root].map_or(f64::INFINITY, |(d, ..)| d);
+            let nearest = grid.nearest(point, within, |b| {
+                (roots[anchors[b].1] != root).then(|| point.distance(&anchors[b].0))
+            });
+            let Some((b, d)) = nearest else { continue };
+            let edge = (d, a.min(b), a.max(b));
+            if cheapest[root].is_none_or(|best| edge < best) {
+                cheapest[root] = Some(edge);
+            }
+        }
+        let mut joined = false;
+        for (_, a, b) in cheapest.into_iter().flatten() {
+            let (ra, rb) = (sets.find(anchors[a].1), sets.find(anchors[b].1));
+            if ra != rb {
+                sets.union(ra, rb);
+                edges.push((a, b));
+                joined = true;
+            }
+        }
+        if !joined {
+            return edges;
+        }
+    }
+}
+
+/// Disjoint-set forest used for copper connectivity.
+pub(crate) struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    pub(crate) fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        let mut cur = i;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) {
+        let ra = self.find(a);
+        let rb = self.find(b);
+        if ra != rb {
+            self.parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{IslandRemoval, Pad, PadConnection, PlacedComponent, Trace, Zone, ZoneFillType, ZoneKind};
+    use crate::units::LengthUnit;
+
+    fn three_pad_layout() -> Layout {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        for (i, x) in [10.0, 20.0, 40.0].iter().enumerate() {
+            layout.components.push(
+                PlacedComponent::new(format!("TP{}", i + 1), "", "TestPoint")
+                    .at(*x, 10.0)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("SIG")),
+            );
+        }
+        layout
+    }
+
+    fn trace(net: &str, x1: f64, x2: f64, y: f64) -> Trace {
+        Trace {
+            net: net.to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(x1, y),
+            end: Position::new(x2, y),
+            width: 0.25,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn test_ratsnest_unrouted_net() {
+        let layout = three_pad_layout();
+        let ratsnest = Ratsnest::compute(&layout);
+
+        let net = ratsnest.net("SIG").unwrap();
+        assert_eq!(net.pad_count, 3);
+        assert_eq!(net.open.len(), 2);
+        assert_eq!(ratsnest.completion_percent(), 0.0);
+
+        // MST picks the two short hops, not the long 10 -> 40 one
+        let total: f64 = net.open.iter().map(|c| c.length).sum();
+        assert!((total - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ratsnest_pad_grid() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        for i in 0..50 {
+            let (x, y) = ((i % 10) as f64 * 2.0 + 5.0, (i / 10) as f64 * 3.0 + 5.0);
+            layout.components.push(
+                PlacedComponent::new(format!("TP{}", i + 1), "", "TestPoint")
+                    .at(x, y)
+                    .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("SIG")),
+            );
+        }
+        let net = Ratsnest::compute(&layout).net("SIG").unwrap().clone();
+
+        // Nine 2mm hops per row and one 3mm hop between rows
+        assert_eq!(net.open.len(), 49);
+        let total: f64 = net.open.iter().map(|c| c.length).sum();
+        assert!((total - (45.0 * 2.0 + 4.0 * 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ratsnest_incremental_update() {
+        let mut layout = three_pad_layout();
+        let mut ratsnest = Ratsnest::compute(&layout);
+
+        layout.traces.push(trace("SIG", 10.0, 20.0, 10.0));
+        ratsnest.update_net(&layout, "SIG");
+
+        assert_eq!(ratsnest.unrouted_count(), 1);
+        assert!((ratsnest.completion_percent() - 50.0).abs() < 1e-9);
+        let open = &ratsnest.open_connections()[0];
+        assert!((open.from.x - 20.0).abs() < 1e-9 || (open.to.x - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ratsnest_ignores_other_nets() {
+        let mut layout = three_pad_layout();
+        layout.traces.push(trace("GND", 10.0, 40.0, 10.0));
+        let ratsnest = Ratsnest::compute(&layout);
+        assert_eq!(ratsnest.unrouted_count(), 2);
+    }
+
+    #[test]
+    fn test_ratsnest_zone_connects_pads() {
+        let mut layout = three_pad_layout();
+        layout.zones.push(Zone {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            points: vec![
+                Point2D::new(5.0, 5.0),
+                Point2D::new(45.0, 5.0),
+                Point2D::new(45.0, 15.0),
+                Point2D::new(5.0, 15.0),
+            ],
+            fill_type: ZoneFillType::Solid,
+            clearance: None,
+            min_width: None,
+            kind: ZoneKind::Copper,
+            priority: 0,
+            pad_connection: PadConnection::Thermal,
+            thermal_gap: None,
+            thermal_spoke_width: None,
+            thermal_spokes: None,
+            hatch_width: None,
+            hatch_gap: None,
+            island_removal: IslandRemoval::Always,
+            filled: Vec::new(),
+            unit: LengthUnit::Mm,
+        });
+        let ratsnest = Ratsnest::compute(&layout);
+        assert!(ratsnest.net("SIG").unwrap().is_complete());
+        assert_eq!(ratsnest.completion_percent(), 100.0);
+    }
+}
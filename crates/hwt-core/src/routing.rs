@@ -6,7 +6,14 @@
 use serde::{Deserialize, Serialize};
 
 use crate::geometry::Position;
-use crate::layout::{Layout, Trace, Via, ViaType};
+use std::collections::{HashMap, VecDeque};
+
+use crate::copper::{CopperItem, CopperShape};
+use crate::drc::DrcViolation;
+use crate::impedance::{width_map, LayerWidth};
+use crate::layout::{DrillPair, KeepoutKind, Layout, Trace, Via};
+use crate::pcb_drc::{DrcChangeSet, IncrementalDrc};
+use crate::ratsnest::Ratsnest;
 use crate::units::LengthUnit;
 
 /// Routing result type.
@@ -23,6 +30,10 @@ pub enum RoutingError {
     InvalidEndpoint(String),
     /// Layer not found
     LayerNotFound(String),
+    /// No allowed via span for a layer transition
+    InvalidVia(String),
+    /// Track or via inside a keepout area
+    Keepout(String),
     /// Route cancelled
     Cancelled,
 }
@@ -34,6 +45,8 @@ impl std::fmt::Display for RoutingError {
             RoutingError::DrcViolation(msg) => write!(f, "DRC violation: {}", msg),
             RoutingError::InvalidEndpoint(msg) => write!(f, "Invalid endpoint: {}", msg),
             RoutingError::LayerNotFound(layer) => write!(f, "Layer not found: {}", layer),
+            RoutingError::InvalidVia(msg) => write!(f, "Invalid via: {}", msg),
+            RoutingError::Keepout(msg) => write!(f, "Keepout: {}", msg),
             RoutingError::Cancelled => write!(f, "Route cancelled"),
         }
     }
@@ -112,6 +125,18 @@ pub struct RoutingConfig {
     
     /// Available trace widths for quick selection
     pub width_presets: Vec<f64>,
+
+    /// Per-layer trace widths by net class (e.g., for impedance control)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub class_widths: HashMap<String, HashMap<String, f64>>,
+}
+
+impl RoutingConfig {
+    /// Route a net class at the widths solved for each layer.
+    pub fn with_class_widths(mut self, class: &str, widths: &[LayerWidth]) -> Self {
+        self.class_widths.insert(class.to_string(), width_map(widths));
+        self
+    }
 }
 
 impl Default for RoutingConfig {
@@ -127,6 +152,7 @@ impl Default for RoutingConfig {
             snap_to_grid: true,
             grid_size: 0.1,
             width_presets: vec![0.15, 0.2, 0.25, 0.3, 0.4, 0.5, 0.8, 1.0],
+            class_widths: HashMap::new(),
         }
     }
 }
@@ -144,6 +170,17 @@ pub struct RouteSegment {
     pub width: f64,
 }
 
+/// A layer transition made during routing.
+#[derive(Debug, Clone)]
+pub struct RouteVia {
+    /// Via position
+    pub position: Position,
+    /// Layer routed before the transition
+    pub from_layer: String,
+    /// Layer routed after the transition
+    pub to_layer: String,
+}
+
 /// Active routing session state.
 #[derive(Debug, Clone)]
 pub struct RoutingSession {
@@ -156,8 +193,8 @@ pub struct RoutingSession {
     /// Route segments created so far
     pub segments: Vec<RouteSegment>,
     
-    /// Vias inserted during routing
-    pub vias: Vec<Position>,
+    /// Layer transitions inserted during routing
+    pub vias: Vec<RouteVia>,
     
     /// Current cursor position
     pub cursor: Position,
@@ -221,9 +258,16 @@ impl RoutingSession {
     }
     
     /// Insert a via at the current position and switch layers.
+    ///
+    /// The via span is resolved against the drill pairs on commit; use
+    /// `Router::insert_via` to reject unreachable layers immediately.
     pub fn insert_via(&mut self, new_layer: String) {
-        self.vias.push(self.cursor.clone());
-        self.current_layer = new_layer;
+        let from_layer = std::mem::replace(&mut self.current_layer, new_layer);
+        self.vias.push(RouteVia {
+            position: self.cursor.clone(),
+            from_layer,
+            to_layer: self.current_layer.clone(),
+        });
     }
     
     /// Change trace width.
@@ -255,16 +299,62 @@ impl RoutingSession {
     }
 }
 
+/// Track for a route segment.
+fn segment_trace(net: &str, segment: &RouteSegment) -> Trace {
+    Trace {
+        net: net.to_string(),
+        layer: segment.layer.clone(),
+        start: segment.start.clone(),
+        end: segment.end.clone(),
+        width: segment.width,
+        unit: LengthUnit::Mm,
+    }
+}
+
+/// Vias of a drill pair stack at one position.
+fn stack_vias(net: &str, position: &Position, stack: Vec<DrillPair>) -> Vec<Via> {
+    stack.into_iter()
+        .map(|pair| Via {
+            net: net.to_string(),
+            position: position.clone(),
+            via_type: pair.via_type,
+            drill: pair.drill,
+            pad: pair.pad,
+            start_layer: Some(pair.start_layer),
+            end_layer: Some(pair.end_layer),
+            unit: LengthUnit::Mm,
+        })
+        .collect()
+}
+
 /// Point-to-point router.
 pub struct Router<'a> {
     layout: &'a mut Layout,
     config: RoutingConfig,
+    net_classes: HashMap<String, String>,
 }
 
 impl<'a> Router<'a> {
     /// Create a new router.
     pub fn new(layout: &'a mut Layout, config: RoutingConfig) -> Self {
-        Self { layout, config }
+        Self { layout, config, net_classes: HashMap::new() }
+    }
+    
+    /// Set the net class of each net, for per-class trace widths.
+    pub fn with_net_classes(mut self, net_classes: HashMap<String, String>) -> Self {
+        self.net_classes = net_classes;
+        self
+    }
+    
+    /// Trace width for a net on a layer: its class width for the layer,
+    /// or the default width.
+    pub fn trace_width(&self, net: &str, layer: &str) -> f64 {
+        self.net_classes
+            .get(net)
+            .and_then(|class| self.config.class_widths.get(class))
+            .and_then(|widths| widths.get(layer))
+            .copied()
+            .unwrap_or(self.config.trace_width)
     }
     
     /// Start a new routing session.
@@ -275,7 +365,8 @@ impl<'a> Router<'a> {
         }
         
         let start = self.snap_to_grid(start);
-        Ok(RoutingSession::new(net, start, layer, self.config.trace_width))
+        let width = self.trace_width(&net, &layer);
+        Ok(RoutingSession::new(net, start, layer, width))
     }
     
     /// Calculate route segments from current point to target.
@@ -537,35 +628,166 @@ impl<'a> Router<'a> {
             return Err(RoutingError::NoPath("No segments to commit".to_string()));
         }
         
-        // Add traces
-        for segment in session.segments {
-            self.layout.traces.push(Trace {
-                net: session.net.clone(),
-                layer: segment.layer,
-                start: segment.start,
-                end: segment.end,
-                width: segment.width,
-                unit: LengthUnit::Mm,
-            });
+        // Resolve via spans and keepouts first so a failed route commits nothing
+        let vias = self.session_vias(&session)?;
+        let traces: Vec<Trace> = session.segments.iter()
+            .map(|segment| segment_trace(&session.net, segment))
+            .collect();
+        self.check_keepouts(&traces, &vias)?;
+        
+        self.layout.traces.extend(traces);
+        self.layout.vias.extend(vias);
+        
+        Ok(())
+    }
+    
+    /// Vias for the layer transitions of a session.
+    fn session_vias(&self, session: &RoutingSession) -> RoutingResult<Vec<Via>> {
+        let mut vias = Vec::new();
+        for route_via in &session.vias {
+            let stack = self.via_stack(&route_via.from_layer, &route_via.to_layer)?;
+            vias.extend(stack_vias(&session.net, &route_via.position, stack));
+        }
+        Ok(vias)
+    }
+    
+    /// Insert a via into a session after checking that an allowed via span
+    /// connects the current layer to `new_layer` outside any via keepout,
+    /// switching to the net's width on that layer.
+    pub fn insert_via(&self, session: &mut RoutingSession, new_layer: String) -> RoutingResult<()> {
+        let stack = self.via_stack(&session.current_layer, &new_layer)?;
+        self.check_keepouts(&[], &stack_vias(&session.net, &session.cursor, stack))?;
+        session.width = self.trace_width(&session.net, &new_layer);
+        session.insert_via(new_layer);
+        Ok(())
+    }
+    
+    /// Reject tracks and vias that enter a keepout area forbidding them.
+    fn check_keepouts(&self, traces: &[Trace], vias: &[Via]) -> RoutingResult<()> {
+        if self.layout.rule_areas.is_empty() {
+            return Ok(());
+        }
+        let copper = self.layout.copper_layer_names();
+        let items = traces.iter().map(|trace| (KeepoutKind::Track, CopperItem::from_trace(trace)))
+            .chain(vias.iter().map(|via| (KeepoutKind::Via, CopperItem::from_via(via, &copper))));
+        for (kind, item) in items {
+            for area in &self.layout.rule_areas {
+                if item.layers.iter().any(|l| area.forbids(kind, l))
+                    && item.shape.distance(&CopperShape::polygon(area.polygon_mm())) <= 0.0
+                {
+                    return Err(RoutingError::Keepout(format!("{} enters keepout area '{}'", item.label, area.name)));
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Select the drill pairs for a transition between two copper layers.
+    ///
+    /// A single via is preferred, using the shortest span that covers both
+    /// layers; otherwise the fewest stacked vias (e.g. a microvia stack)
+    /// that chain from one layer to the other.
+    pub fn via_stack(&self, from: &str, to: &str) -> RoutingResult<Vec<DrillPair>> {
+        let copper: Vec<String> = self.copper_layers().iter().map(|l| l.to_string()).collect();
+        let from_idx = copper.iter().position(|l| l == from)
+            .ok_or_else(|| RoutingError::LayerNotFound(from.to_string()))?;
+        let to_idx = copper.iter().position(|l| l == to)
+            .ok_or_else(|| RoutingError::LayerNotFound(to.to_string()))?;
+        if from_idx == to_idx {
+            return Ok(Vec::new());
+        }
+        
+        let mut pairs = Vec::new();
+        for pair in self.layout.effective_drill_pairs(self.config.via_drill, self.config.via_pad) {
+            let span = pair.span(&copper).map_err(RoutingError::InvalidVia)?;
+            pairs.push((span, pair));
+        }
+        // Shortest spans first, so the search favours short stubs
+        pairs.sort_by_key(|((first, last), _)| last - first);
+        
+        // Breadth-first search over layers, one via per step
+        let mut previous: Vec<Option<(usize, usize)>> = vec![None; copper.len()];
+        let mut visited = vec![false; copper.len()];
+        let mut queue = VecDeque::from([from_idx]);
+        visited[from_idx] = true;
+        while let Some(layer) = queue.pop_front() {
+            if layer == to_idx {
+                break;
+            }
+            for (i, ((first, last), _)) in pairs.iter().enumerate() {
+                if layer < *first || layer > *last {
+                    continue;
+                }
+                for next in *first..=*last {
+                    if !visited[next] {
+                        visited[next] = true;
+                        previous[next] = Some((layer, i));
+                        queue.push_back(next);
+                    }
+                }
+            }
         }
         
-        // Add vias
-        for via_pos in session.vias {
-            self.layout.vias.push(Via {
-                net: session.net.clone(),
-                position: via_pos,
-                via_type: ViaType::Through,
-                drill: self.config.via_drill,
-                pad: self.config.via_pad,
-                start_layer: None,
-                end_layer: None,
-                unit: LengthUnit::Mm,
-            });
+        if !visited[to_idx] {
+            return Err(RoutingError::InvalidVia(format!("no drill pair connects {} to {}", from, to)));
         }
+        let mut stack = Vec::new();
+        let mut layer = to_idx;
+        while let Some((prev, i)) = previous[layer] {
+            stack.push(pairs[i].1.clone());
+            layer = prev;
+        }
+        stack.reverse();
+        Ok(stack)
+    }
+    
+    /// Commit a routing session and refresh the ratsnest of its net.
+    pub fn commit_route_tracked(
+        &mut self,
+        session: RoutingSession,
+        ratsnest: &mut Ratsnest,
+    ) -> RoutingResult<()> {
+        let net = session.net.clone();
+        self.commit_route(session)?;
+        ratsnest.update_net(self.layout, &net);
+        Ok(())
+    }
+    
+    /// Commit a routing session and update an incremental DRC report for
+    /// the new tracks and vias.
+    pub fn commit_route_checked(
+        &mut self,
+        session: RoutingSession,
+        drc: &mut IncrementalDrc,
+    ) -> RoutingResult<()> {
+        let (traces, vias) = (self.layout.traces.len(), self.layout.vias.len());
+        self.commit_route(session)?;
         
+        let mut changes = DrcChangeSet::new();
+        for trace in &self.layout.traces[traces..] {
+            changes.add_trace(trace);
+        }
+        for via in &self.layout.vias[vias..] {
+            changes.add_via(via);
+        }
+        drc.update(self.layout, &changes);
         Ok(())
     }
     
+    /// Violations the session would add if committed with the route to the
+    /// cursor, for online DRC while the cursor moves. Transitions without
+    /// an allowed via span are left out.
+    pub fn preview_violations(&self, session: &RoutingSession, drc: &IncrementalDrc) -> Vec<DrcViolation> {
+        let preview = self.calculate_segments(session, session.cursor.clone());
+        let traces: Vec<Trace> = session.segments.iter()
+            .chain(&preview)
+            .filter(|segment| segment.start.x != segment.end.x || segment.start.y != segment.end.y)
+            .map(|segment| segment_trace(&session.net, segment))
+            .collect();
+        let vias = self.session_vias(session).unwrap_or_default();
+        drc.check_proposed(self.layout, &traces, &vias)
+    }
+    
     /// Get next width preset (cycle through presets).
     pub fn next_width(&self, current: f64) -> f64 {
         let presets = &self.config.width_presets;
@@ -800,6 +1022,156 @@ mod tests {
         assert_eq!(layout.vias.len(), 1);
     }
     
+    fn four_layer_layout(drill_pairs: Vec<DrillPair>) -> Layout {
+        use crate::layout::{Layer, LayerType};
+        
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        layout.layers = ["F.Cu", "In1.Cu", "In2.Cu", "B.Cu"]
+            .iter()
+            .map(|name| Layer::new(name, LayerType::Copper))
+            .collect();
+        layout.drill_pairs = drill_pairs;
+        layout
+    }
+    
+    #[test]
+    fn test_router_commit_route_via_span() {
+        use crate::layout::ViaType;
+        
+        let mut layout = four_layer_layout(vec![
+            DrillPair::new("F.Cu", "B.Cu", ViaType::Through, 0.3, 0.6),
+            DrillPair::new("F.Cu", "In1.Cu", ViaType::Micro, 0.1, 0.25),
+            DrillPair::new("In1.Cu", "In2.Cu", ViaType::Buried, 0.2, 0.45),
+        ]);
+        let mut session = RoutingSession::new(
+            "SIG".to_string(),
+            make_position(10.0, 10.0),
+            "F.Cu".to_string(),
+            0.1,
+        );
+        session.add_segment(make_position(20.0, 10.0));
+        session.insert_via("In1.Cu".to_string());
+        session.add_segment(make_position(30.0, 10.0));
+        session.insert_via("In2.Cu".to_string());
+        session.add_segment(make_position(40.0, 10.0));
+        
+        Router::new(&mut layout, RoutingConfig::default()).commit_route(session).unwrap();
+        
+        assert_eq!(layout.vias.len(), 2);
+        assert_eq!(layout.vias[0].via_type, ViaType::Micro);
+        assert_eq!(layout.vias[0].drill, 0.1);
+        assert_eq!(layout.vias[1].via_type, ViaType::Buried);
+        assert_eq!(layout.vias[1].start_layer.as_deref(), Some("In1.Cu"));
+        assert_eq!(layout.vias[1].end_layer.as_deref(), Some("In2.Cu"));
+    }
+    
+    #[test]
+    fn test_router_microvia_stack() {
+        use crate::layout::ViaType;
+        
+        let mut layout = four_layer_layout(vec![
+            DrillPair::new("F.Cu", "In1.Cu", ViaType::Micro, 0.1, 0.25),
+            DrillPair::new("In1.Cu", "In2.Cu", ViaType::Micro, 0.1, 0.25),
+        ]);
+        let router = Router::new(&mut layout, RoutingConfig::default());
+        
+        let stack = router.via_stack("F.Cu", "In2.Cu").unwrap();
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack[0].end_layer, "In1.Cu");
+        assert_eq!(stack[1].end_layer, "In2.Cu");
+        
+        let mut session = router.start_route("SIG".to_string(), make_position(0.0, 0.0), "F.Cu".to_string()).unwrap();
+        let result = router.insert_via(&mut session, "B.Cu".to_string());
+        assert!(matches!(result, Err(RoutingError::InvalidVia(_))));
+        assert_eq!(session.current_layer, "F.Cu");
+    }
+    
+    #[test]
+    fn test_router_default_through_via_layers() {
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        let router = Router::new(&mut layout, RoutingConfig::default());
+        
+        let stack = router.via_stack("B.Cu", "F.Cu").unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack[0].start_layer, "F.Cu");
+        assert_eq!(stack[0].end_layer, "B.Cu");
+        assert_eq!(stack[0].drill, 0.3);
+    }
+    
+    #[test]
+    fn test_router_commit_route_updates_ratsnest() {
+        use crate::layout::{Pad, PlacedComponent};
+        
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("TP1", "", "TestPoint")
+                .at(10.0, 10.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("VCC")),
+        );
+        layout.components.push(
+            PlacedComponent::new("TP2", "", "TestPoint")
+                .at(50.0, 10.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("VCC")),
+        );
+        let mut ratsnest = Ratsnest::compute(&layout);
+        assert_eq!(ratsnest.unrouted_count(), 1);
+        
+        let mut session = RoutingSession::new(
+            "VCC".to_string(),
+            make_position(10.0, 10.0),
+            "F.Cu".to_string(),
+            0.25,
+        );
+        session.add_segment(make_position(50.0, 10.0));
+        
+        let mut router = Router::new(&mut layout, RoutingConfig::default());
+        router.commit_route_tracked(session, &mut ratsnest).unwrap();
+        
+        assert_eq!(ratsnest.unrouted_count(), 0);
+        assert_eq!(ratsnest.completion_percent(), 100.0);
+    }
+    
+    #[test]
+    fn test_router_online_drc() {
+        use crate::pcb_drc::{IncrementalDrc, PcbDesignRules, PcbDrcChecker};
+        
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        layout.traces.push(Trace {
+            net: "GND".to_string(),
+            layer: "F.Cu".to_string(),
+            start: make_position(10.0, 20.0),
+            end: make_position(50.0, 20.0),
+            width: 0.25,
+            unit: LengthUnit::Mm,
+        });
+        let mut drc = IncrementalDrc::new(PcbDrcChecker::new(&layout, PcbDesignRules::default()));
+        
+        let mut session = RoutingSession::new(
+            "VCC".to_string(),
+            make_position(10.0, 10.0),
+            "F.Cu".to_string(),
+            0.25,
+        );
+        session.add_segment(make_position(30.0, 10.0));
+        let config = RoutingConfig {
+            snap_to_grid: false,
+            ..RoutingConfig::default()
+        };
+        
+        let mut router = Router::new(&mut layout, config);
+        session.cursor = make_position(30.0, 15.0);
+        assert!(router.preview_violations(&session, &drc).is_empty());
+        
+        // Cursor brought alongside the GND track
+        session.cursor = make_position(45.0, 19.6);
+        let violations = router.preview_violations(&session, &drc);
+        assert!(violations.iter().any(|v| v.rule == "clearance.track_to_track"));
+        
+        session.add_segment(make_position(45.0, 19.6));
+        router.commit_route_checked(session, &mut drc).unwrap();
+        assert!(drc.report().violations.iter().any(|v| v.rule == "clearance.track_to_track"));
+    }
+    
     #[test]
     fn test_router_width_presets() {
         let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
@@ -817,6 +1189,59 @@ mod tests {
         assert_eq!(router.prev_width(0.2), 0.3); // Wraps around
     }
     
+    #[test]
+    fn test_router_impedance_class_widths() {
+        use crate::impedance::{ImpedanceTarget, DEFAULT_FREQUENCY_GHZ};
+        use crate::stackup::Stackup;
+        
+        let stackup = Stackup::preset("4-layer").unwrap();
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        stackup.apply_to(&mut layout).unwrap();
+        let widths = ImpedanceTarget::Single(50.0).layer_widths(&stackup, DEFAULT_FREQUENCY_GHZ);
+        let config = RoutingConfig::default().with_class_widths("RF", &widths);
+        let classes = HashMap::from([("ANT".to_string(), "RF".to_string())]);
+        let router = Router::new(&mut layout, config).with_net_classes(classes);
+        
+        let outer = router.trace_width("ANT", "F.Cu");
+        let inner = router.trace_width("ANT", "In1.Cu");
+        assert!(inner < outer);
+        assert_eq!(router.trace_width("GND", "F.Cu"), 0.25);
+        
+        let mut session = router.start_route("ANT".to_string(), make_position(0.0, 0.0), "F.Cu".to_string()).unwrap();
+        assert_eq!(session.width, outer);
+        session.add_segment(make_position(10.0, 0.0));
+        router.insert_via(&mut session, "B.Cu".to_string()).unwrap();
+        assert_eq!(session.width, router.trace_width("ANT", "B.Cu"));
+    }
+    
+    #[test]
+    fn test_router_keepout() {
+        use crate::geometry::Point2D;
+        use crate::layout::{Keepouts, RuleArea};
+
+        let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
+        let square = vec![
+            Point2D::new(20.0, -5.0),
+            Point2D::new(30.0, -5.0),
+            Point2D::new(30.0, 5.0),
+            Point2D::new(20.0, 5.0),
+        ];
+        layout.rule_areas.push(RuleArea::new("mount", square)
+            .with_keepout(Keepouts { tracks: true, vias: true, ..Default::default() }));
+        let mut router = Router::new(&mut layout, RoutingConfig::default());
+
+        let mut session = router.start_route("SIG".to_string(), make_position(0.0, 0.0), "F.Cu".to_string()).unwrap();
+        session.add_segment(make_position(25.0, 0.0));
+        assert!(matches!(router.insert_via(&mut session, "B.Cu".to_string()), Err(RoutingError::Keepout(_))));
+        assert!(matches!(router.commit_route(session), Err(RoutingError::Keepout(_))));
+
+        let mut session = router.start_route("SIG".to_string(), make_position(0.0, 0.0), "F.Cu".to_string()).unwrap();
+        session.add_segment(make_position(15.0, 0.0));
+        router.insert_via(&mut session, "B.Cu".to_string()).unwrap();
+        router.commit_route(session).unwrap();
+        assert_eq!(layout.vias.len(), 1);
+    }
+
     #[test]
     fn test_router_copper_layers() {
         let mut layout = Layout::with_board_size(100.0, 80.0, LengthUnit::Mm);
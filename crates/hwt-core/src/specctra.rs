@@ -0,0 +1,983 @@
+//! Specctra DSN/SES Interchange.
+//!
+//! Exports a layout and its netlist as a Specctra design (DSN) for external
+//! autorouters such as Freerouting, and imports the routed session (SES)
+//! back into `layout::Trace` and `layout::Via` entries.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use crate::autoroute::AutorouteNetClass;
+use crate::geometry::Position;
+use crate::kicad::{SExpr, SExprParser};
+use crate::layout::{ComponentLayer, Layout, Pad, PadShape, PadType, Trace, Via, ViaType};
+use crate::routing::RoutingConfig;
+use crate::units::LengthUnit;
+
+/// Specctra import/export error.
+#[derive(Debug, Clone)]
+pub struct SpecctraError {
+    pub message: String,
+}
+
+impl std::fmt::Display for SpecctraError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Specctra error: {}", self.message)
+    }
+}
+
+impl std::error::Error for SpecctraError {}
+
+pub type SpecctraResult<T> = Result<T, SpecctraError>;
+
+/// Specctra unit keyword for a length unit (nanometres fall back to µm).
+fn dsn_unit_name(unit: LengthUnit) -> &'static str {
+    match unit {
+        LengthUnit::Mm => "mm",
+        LengthUnit::Mil => "mil",
+        LengthUnit::Um | LengthUnit::Nm => "um",
+        LengthUnit::Inch => "inch",
+    }
+}
+
+/// Length unit for a Specctra unit keyword.
+fn parse_dsn_unit(name: &str) -> Option<LengthUnit> {
+    match name.to_lowercase().as_str() {
+        "mm" => Some(LengthUnit::Mm),
+        "mil" => Some(LengthUnit::Mil),
+        "um" => Some(LengthUnit::Um),
+        "inch" => Some(LengthUnit::Inch),
+        "cm" => Some(LengthUnit::Mm),
+        _ => None,
+    }
+}
+
+/// Quote an identifier if Specctra would otherwise split it.
+fn quote(name: &str) -> String {
+    let needs_quotes = name.is_empty()
+        || name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '(' || c == ')' || c == '"');
+    if needs_quotes {
+        format!("\"{}\"", name.replace('"', "'"))
+    } else {
+        name.to_string()
+    }
+}
+
+/// Specctra DSN exporter.
+pub struct DsnExporter<'a> {
+    layout: &'a Layout,
+    design_name: String,
+    routing: RoutingConfig,
+    net_classes: Vec<AutorouteNetClass>,
+    unit: LengthUnit,
+}
+
+impl<'a> DsnExporter<'a> {
+    /// Create a new exporter for a layout.
+    pub fn new(layout: &'a Layout, design_name: impl Into<String>) -> Self {
+        Self {
+            layout,
+            design_name: design_name.into(),
+            routing: RoutingConfig::default(),
+            net_classes: Vec::new(),
+            unit: LengthUnit::Um,
+        }
+    }
+
+    /// Set the default width, clearance and via used for the design rules.
+    pub fn with_routing_config(mut self, routing: RoutingConfig) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Set the net classes written to the network section.
+    pub fn with_net_classes(mut self, net_classes: Vec<AutorouteNetClass>) -> Self {
+        self.net_classes = net_classes;
+        self
+    }
+
+    /// Set the unit used in the DSN file.
+    pub fn with_unit(mut self, unit: LengthUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Export to a file.
+    pub fn export_to_file<P: AsRef<Path>>(&self, path: P) -> SpecctraResult<()> {
+        fs::write(path, self.export()).map_err(|e| SpecctraError {
+            message: format!("Failed to write DSN file: {}", e),
+        })
+    }
+
+    /// Convert a length in `unit` to DSN units.
+    fn len(&self, value: f64, unit: LengthUnit) -> f64 {
+        let unit_out = if self.unit == LengthUnit::Nm {
+            LengthUnit::Um
+        } else {
+            self.unit
+        };
+        unit_out.from_mm(unit.to_mm(value))
+    }
+
+    /// Export the design as a DSN string.
+    pub fn export(&self) -> String {
+        let mut out = String::new();
+        let copper = self.layout.copper_layer_names();
+
+        writeln!(out, "(pcb {}", quote(&format!("{}.dsn", self.design_name))).unwrap();
+        writeln!(out, "  (parser").unwrap();
+        writeln!(out, "    (string_quote \")").unwrap();
+        writeln!(out, "    (space_in_quoted_tokens on)").unwrap();
+        writeln!(out, "    (host_cad \"Hardware Tool\")").unwrap();
+        writeln!(out, "    (host_version \"{}\")", env!("CARGO_PKG_VERSION")).unwrap();
+        writeln!(out, "  )").unwrap();
+        writeln!(out, "  (resolution {} 10)", dsn_unit_name(self.unit)).unwrap();
+        writeln!(out, "  (unit {})", dsn_unit_name(self.unit)).unwrap();
+
+        self.write_structure(&mut out, &copper);
+        let images = self.collect_images();
+        self.write_placement(&mut out, &images);
+        self.write_library(&mut out, &images, &copper);
+        self.write_network(&mut out);
+        self.write_wiring(&mut out, &copper);
+
+        writeln!(out, ")").unwrap();
+        out
+    }
+
+    fn write_structure(&self, out: &mut String, copper: &[String]) {
+        writeln!(out, "  (structure").unwrap();
+        for (i, layer) in copper.iter().enumerate() {
+            writeln!(out, "    (layer {}", quote(layer)).unwrap();
+            writeln!(out, "      (type signal)").unwrap();
+            writeln!(out, "      (property (index {}))", i).unwrap();
+            writeln!(out, "    )").unwrap();
+        }
+
+        if let Some(outline) = &self.layout.outline {
+            let points = outline.to_polygon(64);
+            if points.len() >= 3 {
+                write!(out, "    (boundary (path pcb 0").unwrap();
+                for p in points.iter().chain(points.first()) {
+                    write!(
+                        out,
+                        " {:.4} {:.4}",
+                        self.len(p.x, outline.unit),
+                        self.len(p.y, outline.unit)
+                    )
+                    .unwrap();
+                }
+                writeln!(out, "))").unwrap();
+            }
+        }
+
+        let vias: Vec<String> = self
+            .drill_pair_vias(copper)
+            .into_iter()
+            .map(|(pad, drill, span)| quote(&self.via_padstack_name(pad, drill, span)))
+            .collect();
+        writeln!(out, "    (via {})", vias.join(" ")).unwrap();
+        writeln!(out, "    (rule").unwrap();
+        writeln!(
+            out,
+            "      (width {:.4})",
+            self.len(self.routing.trace_width, LengthUnit::Mm)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "      (clearance {:.4})",
+            self.len(self.routing.clearance, LengthUnit::Mm)
+        )
+        .unwrap();
+        writeln!(out, "    )").unwrap();
+        writeln!(out, "  )").unwrap();
+    }
+
+    /// Group components into library images by footprint and pad set.
+    fn collect_images(&self) -> Vec<(String, Vec<usize>)> {
+        let mut images: Vec<(String, String, Vec<usize>)> = Vec::new();
+        for (ci, component) in self.layout.components.iter().enumerate() {
+            let signature = pad_signature(&component.pads);
+            if let Some(image) = images.iter_mut().find(|(name, sig, members)| {
+                *sig == signature
+                    && self.layout.components[members[0]].footprint == component.footprint
+                    && !name.is_empty()
+            }) {
+                image.2.push(ci);
+                continue;
+            }
+            let base = if component.footprint.is_empty() {
+                component.reference.clone()
+            } else {
+                component.footprint.clone()
+            };
+            let taken = images
+                .iter()
+                .filter(|(name, _, _)| name.starts_with(&base))
+                .count();
+            let name = if taken == 0 {
+                base
+            } else {
+                format!("{}_{}", base, taken)
+            };
+            images.push((name, signature, vec![ci]));
+        }
+        images
+            .into_iter()
+            .map(|(name, _, members)| (name, members))
+            .collect()
+    }
+
+    fn write_placement(&self, out: &mut String, images: &[(String, Vec<usize>)]) {
+        writeln!(out, "  (placement").unwrap();
+        for (name, members) in images {
+            writeln!(out, "    (component {}", quote(name)).unwrap();
+            for &ci in members {
+                let c = &self.layout.components[ci];
+                let side = match c.layer {
+                    ComponentLayer::Top => "front",
+                    ComponentLayer::Bottom => "back",
+                };
+                writeln!(
+                    out,
+                    "      (place {} {:.4} {:.4} {} {:.4})",
+                    quote(&c.reference),
+                    self.len(c.position.x, c.position.unit),
+                    self.len(c.position.y, c.position.unit),
+                    side,
+                    c.rotation
+                )
+                .unwrap();
+            }
+            writeln!(out, "    )").unwrap();
+        }
+        writeln!(out, "  )").unwrap();
+    }
+
+    fn write_library(&self, out: &mut String, images: &[(String, Vec<usize>)], copper: &[String]) {
+        let mut padstacks: BTreeMap<String, String> = BTreeMap::new();
+
+        writeln!(out, "  (library").unwrap();
+        for (name, members) in images {
+            let component = &self.layout.components[members[0]];
+            writeln!(out, "    (image {}", quote(name)).unwrap();
+            for pad in &component.pads {
+                let (stack_name, stack) = self.pad_padstack(pad, copper);
+                writeln!(
+                    out,
+                    "      (pin {} {} {:.4} {:.4})",
+                    quote(&stack_name),
+                    quote(&pad.number),
+                    self.len(pad.position.x, LengthUnit::Mm),
+                    self.len(pad.position.y, LengthUnit::Mm)
+                )
+                .unwrap();
+                padstacks.entry(stack_name).or_insert(stack);
+            }
+            writeln!(out, "    )").unwrap();
+        }
+
+        // Drill pair vias, class vias and every via already on the board,
+        // each over its own layer span
+        let through = (0, copper.len().saturating_sub(1));
+        let mut via_sizes = self.drill_pair_vias(copper);
+        via_sizes.extend(self.net_classes.iter().map(|c| (c.via_pad, c.via_drill, through)));
+        via_sizes.extend(self.layout.vias.iter().map(|v| {
+            (v.unit.to_mm(v.pad), v.unit.to_mm(v.drill), via_span(v, copper))
+        }));
+        for (pad, drill, span) in via_sizes {
+            let name = self.via_padstack_name(pad, drill, span);
+            let mut stack = String::new();
+            for layer in copper.iter().take(span.1 + 1).skip(span.0) {
+                writeln!(
+                    stack,
+                    "      (shape (circle {} {:.4}))",
+                    quote(layer),
+                    self.len(pad, LengthUnit::Mm)
+                )
+                .unwrap();
+            }
+            padstacks.entry(name).or_insert(stack);
+        }
+
+        for (name, shapes) in &padstacks {
+            writeln!(out, "    (padstack {}", quote(name)).unwrap();
+            write!(out, "{}", shapes).unwrap();
+            writeln!(out, "      (attach off)").unwrap();
+            writeln!(out, "    )").unwrap();
+        }
+        writeln!(out, "  )").unwrap();
+    }
+
+    /// Padstack name and shape definitions for a pad.
+    fn pad_padstack(&self, pad: &Pad, copper: &[String]) -> (String, String) {
+        let (w, h) = (
+            self.len(pad.size.0, LengthUnit::Mm),
+            self.len(pad.size.1, LengthUnit::Mm),
+        );
+        let through = pad.pad_type == PadType::ThruHole || pad.pad_type == PadType::Npth;
+        let layers: Vec<String> = if through {
+            copper.to_vec()
+        } else {
+            copper.first().cloned().into_iter().collect()
+        };
+        let side = if through { "A" } else { "T" };
+
+        let kind = match pad.shape {
+            PadShape::Circle => "Round",
+            PadShape::Oval => "Oval",
+            _ => "Rect",
+        };
+
+        // Sizes in whole micrometers so pads differing by less than one DSN
+        // unit still get their own padstack
+        let name = format!(
+            "{}[{}]Pad_{:.0}x{:.0}_um",
+            kind,
+            side,
+            LengthUnit::Um.from_mm(pad.size.0),
+            LengthUnit::Um.from_mm(pad.size.1)
+        );
+        let mut stack = String::new();
+        for layer in &layers {
+            let layer = quote(layer);
+            let shape = match pad.shape {
+                PadShape::Circle => format!("(circle {} {:.4})", layer, w),
+                PadShape::Oval if w >= h => {
+                    format!(
+                        "(path {} {:.4} {:.4} 0 {:.4} 0)",
+                        layer,
+                        h,
+                        -(w - h) / 2.0,
+                        (w - h) / 2.0
+                    )
+                }
+                PadShape::Oval => {
+                    format!(
+                        "(path {} {:.4} 0 {:.4} 0 {:.4})",
+                        layer,
+                        w,
+                        -(h - w) / 2.0,
+                        (h - w) / 2.0
+                    )
+                }
+                _ => format!(
+                    "(rect {} {:.4} {:.4} {:.4} {:.4})",
+                    layer,
+                    -w / 2.0,
+                    -h / 2.0,
+                    w / 2.0,
+                    h / 2.0
+                ),
+            };
+            writeln!(stack, "      (shape {})", shape).unwrap();
+        }
+        (name, stack)
+    }
+
+    /// Via padstack name encoding size, drill and copper layer span.
+    fn via_padstack_name(&self, pad: f64, drill: f64, (first, last): (usize, usize)) -> String {
+        format!(
+            "Via[{}-{}]_{:.0}:{:.0}_um",
+            first,
+            last,
+            LengthUnit::Um.from_mm(pad),
+            LengthUnit::Um.from_mm(drill)
+        )
+    }
+
+    /// Default vias as `(pad, drill, span)`, one per drill pair.
+    fn drill_pair_vias(&self, copper: &[String]) -> Vec<(f64, f64, (usize, usize))> {
+        self.layout
+            .effective_drill_pairs(self.routing.via_drill, self.routing.via_pad)
+            .iter()
+            .filter_map(|pair| Some((pair.pad, pair.drill, pair.span(copper).ok()?)))
+            .collect()
+    }
+
+    fn write_network(&self, out: &mut String) {
+        let mut nets: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for component in &self.layout.components {
+            for pad in &component.pads {
+                if let Some(net) = pad.net.as_deref().filter(|n| !n.is_empty()) {
+                    nets.entry(net)
+                        .or_default()
+                        .push(format!("{}-{}", component.reference, pad.number));
+                }
+            }
+        }
+
+        let through = (0, self.layout.copper_layer_names().len().saturating_sub(1));
+        writeln!(out, "  (network").unwrap();
+        for (net, pins) in &nets {
+            let pins: Vec<String> = pins.iter().map(|p| quote(p)).collect();
+            writeln!(out, "    (net {}", quote(net)).unwrap();
+            writeln!(out, "      (pins {})", pins.join(" ")).unwrap();
+            writeln!(out, "    )").unwrap();
+        }
+
+        let classified: Vec<&str> = self
+            .net_classes
+            .iter()
+            .flat_map(|c| c.nets.iter().map(String::as_str))
+            .collect();
+        let default_nets: Vec<String> = nets
+            .keys()
+            .filter(|n| !classified.contains(n))
+            .map(|n| quote(n))
+            .collect();
+
+        let mut write_class =
+            |name: &str, members: &[String], width: f64, clearance: f64, via: String| {
+                writeln!(out, "    (class {} {}", quote(name), members.join(" ")).unwrap();
+                writeln!(out, "      (circuit (use_via {}))", quote(&via)).unwrap();
+                writeln!(out, "      (rule").unwrap();
+                writeln!(out, "        (width {:.4})", width).unwrap();
+                writeln!(out, "        (clearance {:.4})", clearance).unwrap();
+                writeln!(out, "      )").unwrap();
+                writeln!(out, "    )").unwrap();
+            };
+
+        write_class(
+            "default",
+            &default_nets,
+            self.len(self.routing.trace_width, LengthUnit::Mm),
+            self.len(self.routing.clearance, LengthUnit::Mm),
+            self.via_padstack_name(self.routing.via_pad, self.routing.via_drill, through),
+        );
+        for class in &self.net_classes {
+            let members: Vec<String> = class
+                .nets
+                .iter()
+                .filter(|n| nets.contains_key(n.as_str()))
+                .map(|n| quote(n))
+                .collect();
+            write_class(
+                &class.name,
+                &members,
+                self.len(class.trace_width, LengthUnit::Mm),
+                self.len(class.clearance, LengthUnit::Mm),
+                self.via_padstack_name(class.via_pad, class.via_drill, through),
+            );
+        }
+        writeln!(out, "  )").unwrap();
+    }
+
+    fn write_wiring(&self, out: &mut String, copper: &[String]) {
+        writeln!(out, "  (wiring").unwrap();
+        for trace in &self.layout.traces {
+            writeln!(
+                out,
+                "    (wire (path {} {:.4} {:.4} {:.4} {:.4} {:.4}) (net {}) (type protect))",
+                quote(&trace.layer),
+                self.len(trace.width, trace.unit),
+                self.len(trace.start.x, trace.unit),
+                self.len(trace.start.y, trace.unit),
+                self.len(trace.end.x, trace.unit),
+                self.len(trace.end.y, trace.unit),
+                quote(&trace.net)
+            )
+            .unwrap();
+        }
+        for via in &self.layout.vias {
+            let name = self.via_padstack_name(
+                via.unit.to_mm(via.pad),
+                via.unit.to_mm(via.drill),
+                via_span(via, copper),
+            );
+            writeln!(
+                out,
+                "    (via {} {:.4} {:.4} (net {}) (type protect))",
+                quote(&name),
+                self.len(via.position.x, via.unit),
+                self.len(via.position.y, via.unit),
+                quote(&via.net)
+            )
+            .unwrap();
+        }
+        writeln!(out, "  )").unwrap();
+    }
+}
+
+/// Pad set signature used to share library images between components.
+fn pad_signature(pads: &[Pad]) -> String {
+    pads.iter()
+        .map(|p| {
+            format!(
+                "{}:{:?}:{:?}:{:.4}:{:.4}:{:.4}:{:.4}",
+                p.number, p.pad_type, p.shape, p.position.x, p.position.y, p.size.0, p.size.1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// A via read from a session file.
+#[derive(Debug, Clone)]
+struct SesVia {
+    padstack: String,
+    x: f64,
+    y: f64,
+}
+
+/// Routed wiring read from a Specctra session (SES) file.
+#[derive(Debug, Clone, Default)]
+pub struct SesSession {
+    /// Traces per net (in mm)
+    pub traces: Vec<Trace>,
+
+    /// Vias per net (in mm)
+    pub vias: Vec<Via>,
+}
+
+impl SesSession {
+    /// Nets that have wiring in the session.
+    pub fn nets(&self) -> Vec<String> {
+        let mut nets: Vec<String> = self
+            .traces
+            .iter()
+            .map(|t| t.net.clone())
+            .chain(self.vias.iter().map(|v| v.net.clone()))
+            .collect();
+        nets.sort();
+        nets.dedup();
+        nets
+    }
+
+    /// Replace the wiring of every net in the session with the routed result.
+    ///
+    /// External routers write back the complete wiring of each net they
+    /// touched, including pre-routed wires, so existing traces and vias of
+    /// those nets are removed first.
+    pub fn apply_to(&self, layout: &mut Layout) {
+        let nets = self.nets();
+        layout.traces.retain(|t| !nets.contains(&t.net));
+        layout.vias.retain(|v| !nets.contains(&v.net));
+        layout.traces.extend(self.traces.iter().cloned());
+        layout.vias.extend(self.vias.iter().cloned());
+    }
+}
+
+/// Specctra SES importer.
+pub struct SesImporter;
+
+impl SesImporter {
+    /// Import a session file, resolving layers and vias against `layout`.
+    pub fn import<P: AsRef<Path>>(
+        path: P,
+        layout: &Layout,
+        routing: &RoutingConfig,
+    ) -> SpecctraResult<SesSession> {
+        let content = fs::read_to_string(path).map_err(|e| SpecctraError {
+            message: format!("Failed to read SES file: {}", e),
+        })?;
+        Self::import_from_string(&content, layout, routing)
+    }
+
+    /// Import session content. Via drills not encoded in the padstack name
+    /// fall back to `routing.via_drill`.
+    pub fn import_from_string(
+        content: &str,
+        layout: &Layout,
+        routing: &RoutingConfig,
+    ) -> SpecctraResult<SesSession> {
+        // The parser declaration quotes a bare `"` which is not valid S-expression syntax
+        let content = content.replace("(string_quote \")", "");
+        let expr = SExprParser::new(&content)
+            .parse()
+            .map_err(|e| SpecctraError { message: e.message })?;
+        if expr.tag() != Some("session") {
+            return Err(SpecctraError {
+                message: "Not a Specctra session file".to_string(),
+            });
+        }
+
+        let routes = expr.find("routes").ok_or_else(|| SpecctraError {
+            message: "Session has no routes section".to_string(),
+        })?;
+
+        // Scale from file units to millimetres
+        let (unit, divisor) = routes
+            .find("resolution")
+            .map(|r| {
+                let unit = r
+                    .get_atom(1)
+                    .and_then(parse_dsn_unit)
+                    .unwrap_or(LengthUnit::Um);
+                let scale = if r.get_atom(1).is_some_and(|u| u.eq_ignore_ascii_case("cm")) {
+                    10.0
+                } else {
+                    1.0
+                };
+                (unit, r.get_f64(2).unwrap_or(1.0) / scale)
+            })
+            .unwrap_or((LengthUnit::Um, 1.0));
+        let to_mm = |v: f64| unit.to_mm(v / divisor);
+
+        let copper = layout.copper_layer_names();
+        let padstacks = Self::parse_padstacks(routes, &to_mm);
+
+        let mut session = SesSession::default();
+        let Some(network) = routes.find("network_out") else {
+            return Ok(session);
+        };
+
+        for net_expr in network.find_all("net") {
+            let net = net_expr.get_atom(1).unwrap_or("").to_string();
+            for wire in net_expr.find_all("wire") {
+                let Some(path) = wire.find("path") else {
+                    continue;
+                };
+                let layer = path.get_atom(1).unwrap_or("").to_string();
+                if !copper.contains(&layer) {
+                    return Err(SpecctraError {
+                        message: format!("Unknown layer {} in session", layer),
+                    });
+                }
+                let width = to_mm(path.get_f64(2).unwrap_or(0.0));
+                let coords: Vec<f64> = path
+                    .as_list()
+                    .map(|l| {
+                        l.iter()
+                            .skip(3)
+                            .filter_map(|e| e.as_atom()?.parse().ok())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let points: Vec<Position> = coords
+                    .chunks_exact(2)
+                    .map(|c| Position::new(to_mm(c[0]), to_mm(c[1])))
+                    .collect();
+                for pair in points.windows(2) {
+                    session.traces.push(Trace {
+                        net: net.clone(),
+                        layer: layer.clone(),
+                        start: pair[0].clone(),
+                        end: pair[1].clone(),
+                        width,
+                        unit: LengthUnit::Mm,
+                    });
+                }
+            }
+
+            for via_expr in net_expr.find_all("via") {
+                let via = SesVia {
+                    padstack: via_expr.get_atom(1).unwrap_or("").to_string(),
+                    x: to_mm(via_expr.get_f64(2).unwrap_or(0.0)),
+                    y: to_mm(via_expr.get_f64(3).unwrap_or(0.0)),
+                };
+                session
+                    .vias
+                    .push(Self::build_via(&net, &via, &padstacks, &copper, routing));
+            }
+        }
+
+        Ok(session)
+    }
+
+    /// Padstack diameters and layer spans from `library_out`.
+    fn parse_padstacks(
+        routes: &SExpr,
+        to_mm: &dyn Fn(f64) -> f64,
+    ) -> BTreeMap<String, (f64, Vec<String>)> {
+        let mut padstacks = BTreeMap::new();
+        let Some(library) = routes.find("library_out") else {
+            return padstacks;
+        };
+        for stack in library.find_all("padstack") {
+            let name = stack.get_atom(1).unwrap_or("").to_string();
+            let mut diameter: f64 = 0.0;
+            let mut layers = Vec::new();
+            for shape in stack.find_all("shape") {
+                if let Some(circle) = shape.find("circle") {
+                    if let Some(layer) = circle.get_atom(1) {
+                        layers.push(layer.to_string());
+                    }
+                    diameter = diameter.max(to_mm(circle.get_f64(2).unwrap_or(0.0)));
+                }
+            }
+            padstacks.insert(name, (diameter, layers));
+        }
+        padstacks
+    }
+
+    /// Build a layout via from a session via and its padstack.
+    fn build_via(
+        net: &str,
+        via: &SesVia,
+        padstacks: &BTreeMap<String, (f64, Vec<String>)>,
+        copper: &[String],
+        routing: &RoutingConfig,
+    ) -> Via {
+        let (mut pad, layers) = padstacks.get(&via.padstack).cloned().unwrap_or_default();
+        let (name_pad, name_drill) =
+            parse_via_name(&via.padstack).unwrap_or((routing.via_pad, routing.via_drill));
+        if pad <= 0.0 {
+            pad = name_pad;
+        }
+
+        let indices: Vec<usize> = layers
+            .iter()
+            .filter_map(|l| copper.iter().position(|c| c == l))
+            .collect();
+        let last = copper.len().saturating_sub(1);
+        let (start, end) = match (indices.iter().min(), indices.iter().max()) {
+            (Some(&a), Some(&b)) => (a, b),
+            _ => parse_via_span(&via.padstack)
+                .filter(|&(a, b)| a < b && b <= last)
+                .unwrap_or((0, last)),
+        };
+        let via_type = if start == 0 && end == last {
+            ViaType::Through
+        } else if start == 0 || end == last {
+            ViaType::Blind
+        } else {
+            ViaType::Buried
+        };
+        let spans_all = via_type == ViaType::Through;
+
+        Via {
+            net: net.to_string(),
+            position: Position::new(via.x, via.y),
+            via_type,
+            drill: name_drill,
+            pad,
+            start_layer: if spans_all {
+                None
+            } else {
+                copper.get(start).cloned()
+            },
+            end_layer: if spans_all {
+                None
+            } else {
+                copper.get(end).cloned()
+            },
+            unit: LengthUnit::Mm,
+        }
+    }
+}
+
+/// Copper layer index span of a via.
+fn via_span(via: &Via, copper: &[String]) -> (usize, usize) {
+    let layers = via.copper_layers(copper);
+    let index = |name: Option<&String>| name.and_then(|n| copper.iter().position(|c| c == n));
+    match (index(layers.first()), index(layers.last())) {
+        (Some(a), Some(b)) => (a, b),
+        _ => (0, copper.len().saturating_sub(1)),
+    }
+}
+
+/// Parse the `(first, last)` copper layer span of a `Via[1-2]_600:300_um`
+/// padstack name.
+fn parse_via_span(name: &str) -> Option<(usize, usize)> {
+    let span = name.strip_prefix("Via[")?.split(']').next()?;
+    let (first, last) = span.split_once('-')?;
+    Some((first.parse().ok()?, last.parse().ok()?))
+}
+
+/// Parse `(pad, drill)` in mm from a `Via[0-1]_600:300_um` padstack name.
+fn parse_via_name(name: &str) -> Option<(f64, f64)> {
+    let sizes = name.split(']').nth(1)?.trim_start_matches('_');
+    let sizes = sizes.strip_suffix("_um")?;
+    let (pad, drill) = sizes.split_once(':')?;
+    Some((
+        LengthUnit::Um.to_mm(pad.parse().ok()?),
+        LengthUnit::Um.to_mm(drill.parse().ok()?),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::PlacedComponent;
+
+    fn test_layout() -> Layout {
+        let mut layout = Layout::with_board_size(40.0, 30.0, LengthUnit::Mm);
+        for (reference, x) in [("R1", 10.0), ("R2", 30.0)] {
+            layout.components.push(
+                PlacedComponent::new(reference, "10k", "R_0603")
+                    .at(x, 15.0)
+                    .with_pad(Pad::smd("1", -0.8, 0.0, 0.8, 0.9).with_net("SIG"))
+                    .with_pad(Pad::smd("2", 0.8, 0.0, 0.8, 0.9).with_net("GND")),
+            );
+        }
+        layout
+    }
+
+    #[test]
+    fn test_dsn_export_sections() {
+        let layout = test_layout();
+        let dsn = DsnExporter::new(&layout, "board")
+            .with_net_classes(vec![AutorouteNetClass {
+                name: "Power".to_string(),
+                nets: vec!["GND".to_string()],
+                trace_width: 0.5,
+                clearance: 0.3,
+                via_drill: 0.4,
+                via_pad: 0.8,
+            }])
+            .export();
+
+        assert!(dsn.starts_with("(pcb board.dsn"));
+        assert!(dsn.contains("(layer F.Cu"));
+        assert!(dsn.contains("(boundary (path pcb 0"));
+        assert!(dsn.contains("(component R_0603"));
+        assert!(dsn.contains("(place R1 10000.0000 15000.0000 front 0.0000)"));
+        assert!(dsn.contains("(pin Rect[T]Pad_800x900_um 1 -800.0000 0.0000)"));
+        assert!(dsn.contains("(pins R1-1 R2-1)"));
+        assert!(dsn.contains("(class Power GND"));
+        assert!(dsn.contains("(width 500.0000)"));
+        assert!(dsn.contains("Via[0-1]_800:400_um"));
+
+        // The image is shared by both resistors
+        assert_eq!(dsn.matches("(image ").count(), 1);
+    }
+
+    #[test]
+    fn test_dsn_export_unit_conversion() {
+        let mut layout = test_layout();
+        layout.traces.push(Trace {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(0.0, 0.0),
+            end: Position::new(1000.0, 0.0),
+            width: 10.0,
+            unit: LengthUnit::Mil,
+        });
+        let dsn = DsnExporter::new(&layout, "board")
+            .with_unit(LengthUnit::Mm)
+            .export();
+
+        assert!(dsn.contains("(unit mm)"));
+        assert!(dsn.contains("(wire (path F.Cu 0.2540 0.0000 0.0000 25.4000 0.0000) (net SIG)"));
+    }
+
+    #[test]
+    fn test_ses_import() {
+        let layout = test_layout();
+        let ses = r#"
+(session board.ses
+  (base_design board.dsn)
+  (routes
+    (resolution um 10)
+    (parser
+      (string_quote ")
+      (host_cad "Hardware Tool")
+    )
+    (library_out
+      (padstack "Via[0-1]_600:300_um"
+        (shape (circle F.Cu 6000 0 0))
+        (shape (circle B.Cu 6000 0 0))
+        (attach off)
+      )
+    )
+    (network_out
+      (net SIG
+        (wire (path F.Cu 2500 108000 150000 200000 150000 200000 120000))
+        (wire (path B.Cu 2500 200000 120000 292000 150000))
+        (via "Via[0-1]_600:300_um" 200000 120000)
+      )
+    )
+  )
+)
+"#;
+        let session =
+            SesImporter::import_from_string(ses, &layout, &RoutingConfig::default()).unwrap();
+
+        assert_eq!(session.traces.len(), 3);
+        assert!((session.traces[0].start.x - 10.8).abs() < 1e-9);
+        assert!((session.traces[0].width - 0.25).abs() < 1e-9);
+        assert_eq!(session.traces[2].layer, "B.Cu");
+
+        assert_eq!(session.vias.len(), 1);
+        let via = &session.vias[0];
+        assert_eq!(via.via_type, ViaType::Through);
+        assert!((via.pad - 0.6).abs() < 1e-9);
+        assert!((via.drill - 0.3).abs() < 1e-9);
+        assert!((via.position.y - 12.0).abs() < 1e-9);
+
+        let mut routed = layout.clone();
+        session.apply_to(&mut routed);
+        assert_eq!(routed.traces.len(), 3);
+        assert_eq!(routed.vias.len(), 1);
+    }
+
+    #[test]
+    fn test_ses_import_rejects_unknown_layer() {
+        let layout = test_layout();
+        let ses = "(session s (routes (resolution mm 1000) (network_out (net SIG (wire (path In7.Cu 250 0 0 1000 0))))))";
+        assert!(SesImporter::import_from_string(ses, &layout, &RoutingConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_dsn_padstacks_keep_sub_unit_sizes() {
+        let mut layout = test_layout();
+        layout.components.push(
+            PlacedComponent::new("C1", "10u", "C_1206")
+                .at(20.0, 15.0)
+                .with_pad(Pad::smd("1", -1.5, 0.0, 1.2, 1.4).with_net("SIG"))
+                .with_pad(Pad::smd("2", 1.5, 0.0, 1.2, 1.4).with_net("GND")),
+        );
+        let dsn = DsnExporter::new(&layout, "board").with_unit(LengthUnit::Mm).export();
+
+        // Both round to 1x1 mm but keep separate padstacks
+        assert!(dsn.contains("(padstack Rect[T]Pad_800x900_um"));
+        assert!(dsn.contains("(padstack Rect[T]Pad_1200x1400_um"));
+        assert!(dsn.contains("(shape (rect F.Cu -0.6000 -0.7000 0.6000 0.7000))"));
+        assert!(dsn.contains("(pin Rect[T]Pad_1200x1400_um 1 -1.5000 0.0000)"));
+    }
+
+    #[test]
+    fn test_blind_via_round_trip() {
+        use crate::layout::{DrillPair, Layer, LayerType};
+
+        let mut layout = test_layout();
+        layout.layers = ["F.Cu", "In1.Cu", "In2.Cu", "B.Cu"]
+            .iter()
+            .map(|name| Layer::new(name, LayerType::Copper))
+            .collect();
+        layout.drill_pairs = vec![
+            DrillPair::new("F.Cu", "B.Cu", ViaType::Through, 0.3, 0.6),
+            DrillPair::new("F.Cu", "In1.Cu", ViaType::Blind, 0.2, 0.45),
+        ];
+        layout.vias.push(Via {
+            net: "SIG".to_string(),
+            position: Position::new(20.0, 12.0),
+            via_type: ViaType::Blind,
+            drill: 0.2,
+            pad: 0.45,
+            start_layer: Some("F.Cu".to_string()),
+            end_layer: Some("In1.Cu".to_string()),
+            unit: LengthUnit::Mm,
+        });
+        let dsn = DsnExporter::new(&layout, "board").export();
+
+        assert!(dsn.contains("(via Via[0-3]_600:300_um Via[0-1]_450:200_um)"));
+        assert!(dsn.contains("(via Via[0-1]_450:200_um 20000.0000 12000.0000 (net SIG)"));
+        let stack = dsn.split("(padstack Via[0-1]_450:200_um").nth(1).unwrap();
+        let stack = &stack[..stack.find("(attach").unwrap()];
+        assert!(stack.contains("In1.Cu") && !stack.contains("B.Cu"));
+
+        // A session without library_out keeps the span from the name
+        let ses = "(session s (routes (resolution um 10) (network_out (net SIG (via Via[0-1]_450:200_um 200000 120000)))))";
+        let session = SesImporter::import_from_string(ses, &layout, &RoutingConfig::default()).unwrap();
+        let via = &session.vias[0];
+        assert_eq!(via.via_type, ViaType::Blind);
+        assert_eq!(via.start_layer.as_deref(), Some("F.Cu"));
+        assert_eq!(via.end_layer.as_deref(), Some("In1.Cu"));
+        assert!((via.drill - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_via_name() {
+        let (pad, drill) = parse_via_name("Via[0-3]_450:200_um").unwrap();
+        assert!((pad - 0.45).abs() < 1e-9);
+        assert!((drill - 0.2).abs() < 1e-9);
+        assert!(parse_via_name("CustomVia").is_none());
+    }
+}
@@ -0,0 +1,576 @@
+//! Layer Stackup.
+//!
+//! The physical build of a board: copper, dielectric and solder mask layers
+//! from top to bottom with their thickness, copper weight and dielectric
+//! properties, plus the via spans the fab can drill. Stackups are stored in
+//! the project file, either inline or as the name of a built-in preset, and
+//! can be applied to a layout so that thickness-dependent checks, routing
+//! and exports see the same stack.
+
+use serde::{Deserialize, Serialize};
+
+use crate::layout::{DrillPair, Layer, LayerType, Layout, ViaType};
+
+/// Copper thickness per ounce of copper weight (mm per oz/ft²).
+pub const COPPER_THICKNESS_PER_OZ: f64 = 0.035;
+
+/// Names of the built-in stackup presets.
+pub const PRESETS: &[&str] = &["2-layer", "4-layer", "6-layer", "8-layer"];
+
+/// Stackup error.
+#[derive(Debug, Clone)]
+pub struct StackupError {
+    /// Error description
+    pub message: String,
+}
+
+impl StackupError {
+    fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl std::fmt::Display for StackupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Stackup error: {}", self.message)
+    }
+}
+
+impl std::error::Error for StackupError {}
+
+pub type StackupResult<T> = Result<T, StackupError>;
+
+/// Kind of stackup layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StackupLayerKind {
+    /// Copper foil or plated copper
+    Copper,
+    /// Cured laminate core
+    Core,
+    /// Prepreg bonding layer
+    Prepreg,
+    /// Solder mask coating
+    SolderMask,
+}
+
+impl StackupLayerKind {
+    /// Whether the layer is an insulating core or prepreg.
+    pub fn is_dielectric(self) -> bool {
+        matches!(self, StackupLayerKind::Core | StackupLayerKind::Prepreg)
+    }
+}
+
+/// Dielectric constant and loss tangent at one frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DielectricPoint {
+    /// Frequency (GHz)
+    pub frequency_ghz: f64,
+
+    /// Relative permittivity (Dk)
+    pub dk: f64,
+
+    /// Loss tangent (Df)
+    pub df: f64,
+}
+
+/// One layer of the stackup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackupLayer {
+    /// Layer name (e.g., "F.Cu", "Prepreg 1", "F.Mask")
+    pub name: String,
+
+    /// Layer kind
+    pub kind: StackupLayerKind,
+
+    /// Finished thickness (mm)
+    pub thickness: f64,
+
+    /// Copper weight (oz/ft²), for copper layers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copper_weight: Option<f64>,
+
+    /// Material (e.g., "FR4", "7628", "LPI")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material: Option<String>,
+
+    /// Dielectric properties by frequency
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dielectric: Vec<DielectricPoint>,
+}
+
+impl StackupLayer {
+    /// Create a copper layer with the thickness of its copper weight.
+    pub fn copper(name: &str, weight_oz: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: StackupLayerKind::Copper,
+            thickness: weight_oz * COPPER_THICKNESS_PER_OZ,
+            copper_weight: Some(weight_oz),
+            material: None,
+            dielectric: Vec::new(),
+        }
+    }
+
+    /// Create a core or prepreg layer.
+    pub fn dielectric(name: &str, kind: StackupLayerKind, thickness: f64, material: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            thickness,
+            copper_weight: None,
+            material: Some(material.to_string()),
+            dielectric: Vec::new(),
+        }
+    }
+
+    /// Create a solder mask layer.
+    pub fn solder_mask(name: &str, thickness: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: StackupLayerKind::SolderMask,
+            thickness,
+            copper_weight: None,
+            material: Some("LPI".to_string()),
+            dielectric: Vec::new(),
+        }
+    }
+
+    /// Add the Dk/Df at a frequency (GHz).
+    pub fn with_dk(mut self, frequency_ghz: f64, dk: f64, df: f64) -> Self {
+        self.dielectric.push(DielectricPoint { frequency_ghz, dk, df });
+        self.dielectric.sort_by(|a, b| a.frequency_ghz.total_cmp(&b.frequency_ghz));
+        self
+    }
+
+    /// Dielectric constant at a frequency (GHz).
+    pub fn dk(&self, frequency_ghz: f64) -> Option<f64> {
+        self.interpolate(frequency_ghz, |p| p.dk)
+    }
+
+    /// Loss tangent at a frequency (GHz).
+    pub fn df(&self, frequency_ghz: f64) -> Option<f64> {
+        self.interpolate(frequency_ghz, |p| p.df)
+    }
+
+    /// Interpolate a property linearly in log frequency, holding the end
+    /// values outside the listed range.
+    fn interpolate(&self, frequency_ghz: f64, value: impl Fn(&DielectricPoint) -> f64) -> Option<f64> {
+        let first = self.dielectric.first()?;
+        let last = self.dielectric.last()?;
+        if frequency_ghz <= first.frequency_ghz {
+            return Some(value(first));
+        }
+        if frequency_ghz >= last.frequency_ghz {
+            return Some(value(last));
+        }
+        let upper = self.dielectric.iter().position(|p| p.frequency_ghz >= frequency_ghz)?;
+        let (a, b) = (&self.dielectric[upper - 1], &self.dielectric[upper]);
+        let t = (frequency_ghz / a.frequency_ghz).ln() / (b.frequency_ghz / a.frequency_ghz).ln();
+        Some(value(a) + (value(b) - value(a)) * t)
+    }
+}
+
+/// Insulation between two copper layers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DielectricSpan {
+    /// Total dielectric height (mm)
+    pub height: f64,
+
+    /// Effective Dk of the layers in series
+    pub dk: f64,
+
+    /// Thickness-weighted loss tangent
+    pub df: f64,
+}
+
+/// A board stackup, listed from top to bottom.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stackup {
+    /// Stackup name
+    pub name: String,
+
+    /// Layers from top to bottom
+    pub layers: Vec<StackupLayer>,
+
+    /// Allowed via spans; a single through span when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drill_pairs: Vec<DrillPair>,
+}
+
+impl Stackup {
+    /// Create an empty stackup.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            layers: Vec::new(),
+            drill_pairs: Vec::new(),
+        }
+    }
+
+    /// Append a layer below the current bottom.
+    pub fn with_layer(mut self, layer: StackupLayer) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Add an allowed via span.
+    pub fn with_drill_pair(mut self, pair: DrillPair) -> Self {
+        self.drill_pairs.push(pair);
+        self
+    }
+
+    /// Built-in preset by name.
+    pub fn preset(name: &str) -> Option<Self> {
+        let copper_layers = match name {
+            "2-layer" => 2,
+            "4-layer" => 4,
+            "6-layer" => 6,
+            "8-layer" => 8,
+            _ => return None,
+        };
+        Some(preset_stackup(name, copper_layers))
+    }
+
+    /// All built-in presets.
+    pub fn presets() -> Vec<Self> {
+        PRESETS.iter().filter_map(|name| Self::preset(name)).collect()
+    }
+
+    /// Find a layer by name.
+    pub fn layer(&self, name: &str) -> Option<&StackupLayer> {
+        self.layers.iter().find(|l| l.name == name)
+    }
+
+    /// Thickness of a copper layer (mm).
+    pub fn copper_thickness(&self, name: &str) -> Option<f64> {
+        self.copper_layers().find(|l| l.name == name).map(|l| l.thickness)
+    }
+
+    /// Copper layers, top to bottom.
+    pub fn copper_layers(&self) -> impl Iterator<Item = &StackupLayer> {
+        self.layers.iter().filter(|l| l.kind == StackupLayerKind::Copper)
+    }
+
+    /// Names of the copper layers, top to bottom.
+    pub fn copper_layer_names(&self) -> Vec<String> {
+        self.copper_layers().map(|l| l.name.clone()).collect()
+    }
+
+    /// Finished board thickness, including copper and solder mask (mm).
+    pub fn thickness(&self) -> f64 {
+        self.layers.iter().map(|l| l.thickness).sum()
+    }
+
+    /// Depth of a layer's top and bottom faces below the top surface (mm).
+    pub fn layer_depth(&self, name: &str) -> Option<(f64, f64)> {
+        let mut depth = 0.0;
+        for layer in &self.layers {
+            if layer.name == name {
+                return Some((depth, depth + layer.thickness));
+            }
+            depth += layer.thickness;
+        }
+        None
+    }
+
+    /// Dielectric between two copper layers at a frequency (GHz). Layers
+    /// without Dk data are skipped in the Dk and Df averages.
+    pub fn dielectric_between(&self, a: &str, b: &str, frequency_ghz: f64) -> Option<DielectricSpan> {
+        let ia = self.layers.iter().position(|l| l.name == a)?;
+        let ib = self.layers.iter().position(|l| l.name == b)?;
+        let between = &self.layers[ia.min(ib) + 1..ia.max(ib)];
+
+        let mut height = 0.0;
+        let (mut known, mut inverse_dk, mut df) = (0.0, 0.0, 0.0);
+        for layer in between.iter().filter(|l| l.kind.is_dielectric()) {
+            height += layer.thickness;
+            if let (Some(dk), Some(loss)) = (layer.dk(frequency_ghz), layer.df(frequency_ghz)) {
+                known += layer.thickness;
+                inverse_dk += layer.thickness / dk;
+                df += layer.thickness * loss;
+            }
+        }
+        if known <= 0.0 {
+            return None;
+        }
+        Some(DielectricSpan { height, dk: known / inverse_dk, df: df / known })
+    }
+
+    /// Copper layers directly above and below a copper layer.
+    pub fn adjacent_copper(&self, name: &str) -> (Option<&StackupLayer>, Option<&StackupLayer>) {
+        let copper: Vec<&StackupLayer> = self.copper_layers().collect();
+        match copper.iter().position(|l| l.name == name) {
+            Some(i) => (i.checked_sub(1).map(|j| copper[j]), copper.get(i + 1).copied()),
+            None => (None, None),
+        }
+    }
+
+    /// Allowed via spans: the defined pairs, or a single through span with
+    /// the given via size when none are defined.
+    pub fn effective_drill_pairs(&self, via_drill: f64, via_pad: f64) -> Vec<DrillPair> {
+        DrillPair::effective(&self.drill_pairs, &self.copper_layer_names(), via_drill, via_pad)
+    }
+
+    /// Barrel length of a via span, from the top of its first copper layer
+    /// to the bottom of its last (mm).
+    pub fn via_length(&self, pair: &DrillPair) -> Option<f64> {
+        let (start_top, start_bottom) = self.layer_depth(&pair.start_layer)?;
+        let (end_top, end_bottom) = self.layer_depth(&pair.end_layer)?;
+        Some(start_bottom.max(end_bottom) - start_top.min(end_top))
+    }
+
+    /// Check that the stackup is physically consistent: copper layers are
+    /// separated by dielectric, solder mask is outermost, thicknesses are
+    /// positive and the drill pairs are valid spans.
+    pub fn validate(&self) -> StackupResult<()> {
+        let copper = self.copper_layer_names();
+        if copper.is_empty() {
+            return Err(StackupError::new(format!("{} has no copper layers", self.name)));
+        }
+
+        for (i, layer) in self.layers.iter().enumerate() {
+            if self.layers[..i].iter().any(|l| l.name == layer.name) {
+                return Err(StackupError::new(format!("Duplicate layer {}", layer.name)));
+            }
+            if layer.thickness <= 0.0 {
+                return Err(StackupError::new(format!("Layer {} has no thickness", layer.name)));
+            }
+            if layer.kind == StackupLayerKind::Copper && layer.copper_weight.is_some_and(|w| w <= 0.0) {
+                return Err(StackupError::new(format!("Layer {} has no copper weight", layer.name)));
+            }
+            if layer.dielectric.iter().any(|p| p.dk < 1.0 || p.df < 0.0 || p.frequency_ghz <= 0.0) {
+                return Err(StackupError::new(format!("Layer {} has invalid Dk/Df data", layer.name)));
+            }
+            let not_mask = |l: &StackupLayer| l.kind != StackupLayerKind::SolderMask;
+            if layer.kind == StackupLayerKind::SolderMask
+                && self.layers[..i].iter().any(not_mask)
+                && self.layers[i + 1..].iter().any(not_mask)
+            {
+                return Err(StackupError::new(format!("Solder mask {} is not on the outside", layer.name)));
+            }
+        }
+
+        let inner: Vec<&StackupLayer> =
+            self.layers.iter().filter(|l| l.kind != StackupLayerKind::SolderMask).collect();
+        for pair in inner.windows(2) {
+            let copper_pair = pair[0].kind == StackupLayerKind::Copper && pair[1].kind == StackupLayerKind::Copper;
+            if copper_pair {
+                return Err(StackupError::new(format!(
+                    "Copper layers {} and {} have no dielectric between them",
+                    pair[0].name, pair[1].name
+                )));
+            }
+        }
+        if inner.first().is_some_and(|l| l.kind != StackupLayerKind::Copper)
+            || inner.last().is_some_and(|l| l.kind != StackupLayerKind::Copper)
+        {
+            return Err(StackupError::new(format!("{} must start and end with copper", self.name)));
+        }
+
+        for pair in &self.drill_pairs {
+            pair.span(&copper).map_err(StackupError::new)?;
+        }
+        Ok(())
+    }
+
+    /// Layout layers for the stack, top to bottom.
+    pub fn to_layers(&self) -> Vec<Layer> {
+        self.layers
+            .iter()
+            .map(|l| {
+                let layer_type = match l.kind {
+                    StackupLayerKind::Copper => LayerType::Copper,
+                    StackupLayerKind::Core | StackupLayerKind::Prepreg => LayerType::Dielectric,
+                    StackupLayerKind::SolderMask => LayerType::SolderMask,
+                };
+                let mut layer = Layer::new(&l.name, layer_type);
+                layer.thickness = Some(l.thickness);
+                layer.material = match l.kind {
+                    StackupLayerKind::Copper => Some(l.material.clone().unwrap_or_else(|| "copper".to_string())),
+                    _ => l.material.clone(),
+                };
+                layer
+            })
+            .collect()
+    }
+
+    /// Replace the layout's copper and dielectric layers with the stack and
+    /// its drill pairs. Other layers keep their order after the stack; a
+    /// layout layer the stack redefines is replaced. Fails when the layout
+    /// uses a copper layer the stack does not have.
+    pub fn apply_to(&self, layout: &mut Layout) -> StackupResult<()> {
+        self.validate()?;
+        let copper = self.copper_layer_names();
+        if let Some(missing) = layout.copper_layer_names().into_iter().find(|name| !copper.contains(name)) {
+            return Err(StackupError::new(format!("Layout copper layer {} is not in {}", missing, self.name)));
+        }
+
+        let mut layers = self.to_layers();
+        let others: Vec<Layer> = layout
+            .layers
+            .drain(..)
+            .filter(|l| !matches!(l.layer_type, LayerType::Copper | LayerType::Dielectric))
+            .filter(|l| self.layer(&l.name).is_none())
+            .collect();
+        layers.extend(others);
+        layout.layers = layers;
+        if !self.drill_pairs.is_empty() {
+            layout.drill_pairs = self.drill_pairs.clone();
+        }
+        Ok(())
+    }
+}
+
+/// Stackup in the project file: a preset name or an inline definition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StackupConfig {
+    /// Built-in preset name
+    Preset(String),
+    /// Inline stackup
+    Custom(Stackup),
+}
+
+impl StackupConfig {
+    /// The configured stackup, checked for consistency.
+    pub fn resolve(&self) -> StackupResult<Stackup> {
+        let stackup = match self {
+            StackupConfig::Preset(name) => Stackup::preset(name).ok_or_else(|| {
+                StackupError::new(format!("Unknown stackup preset '{}' (presets: {})", name, PRESETS.join(", ")))
+            })?,
+            StackupConfig::Custom(stackup) => stackup.clone(),
+        };
+        stackup.validate()?;
+        Ok(stackup)
+    }
+}
+
+/// Standard 1.6mm FR4 build with 1oz outer and 0.5oz inner copper: a
+/// single core for two layers, otherwise prepreg under the outer layers and
+/// alternating cores and prepregs inside.
+fn preset_stackup(name: &str, copper_layers: usize) -> Stackup {
+    let dielectrics: &[f64] = match copper_layers {
+        2 => &[1.51],
+        4 => &[0.2, 1.075, 0.2],
+        6 => &[0.2, 0.45, 0.14, 0.45, 0.2],
+        _ => &[0.1, 0.3, 0.1525, 0.3, 0.1525, 0.3, 0.1],
+    };
+
+    let copper_name = |i: usize| match i {
+        0 => "F.Cu".to_string(),
+        i if i == copper_layers - 1 => "B.Cu".to_string(),
+        i => format!("In{}.Cu", i),
+    };
+
+    let mut stackup = Stackup::new(name).with_layer(mask("F.Mask"));
+    for (i, &thickness) in dielectrics.iter().enumerate() {
+        let outer = i == 0;
+        stackup = stackup.with_layer(StackupLayer::copper(&copper_name(i), if outer { 1.0 } else { 0.5 }));
+
+        let core = copper_layers == 2 || i % 2 == 1;
+        let layer = if core {
+            StackupLayer::dielectric(&format!("Core {}", i / 2 + 1), StackupLayerKind::Core, thickness, "FR4")
+                .with_dk(1.0, 4.6, 0.016)
+                .with_dk(10.0, 4.4, 0.02)
+        } else {
+            StackupLayer::dielectric(&format!("Prepreg {}", i / 2 + 1), StackupLayerKind::Prepreg, thickness, "2116")
+                .with_dk(1.0, 4.2, 0.018)
+                .with_dk(10.0, 4.0, 0.022)
+        };
+        stackup = stackup.with_layer(layer);
+    }
+    let bottom = copper_layers - 1;
+    stackup
+        .with_layer(StackupLayer::copper(&copper_name(bottom), 1.0))
+        .with_layer(mask("B.Mask"))
+        .with_drill_pair(DrillPair::new("F.Cu", "B.Cu", ViaType::Through, 0.3, 0.6))
+}
+
+fn mask(name: &str) -> StackupLayer {
+    StackupLayer::solder_mask(name, 0.01).with_dk(1.0, 3.8, 0.02)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fab::{FabChecker, FabProfile};
+    use crate::units::LengthUnit;
+
+    #[test]
+    fn test_presets() {
+        for stackup in Stackup::presets() {
+            stackup.validate().unwrap();
+            assert!((stackup.thickness() - 1.6).abs() < 1e-9, "{} is {}mm", stackup.name, stackup.thickness());
+        }
+
+        let four = Stackup::preset("4-layer").unwrap();
+        assert_eq!(four.copper_layer_names(), vec!["F.Cu", "In1.Cu", "In2.Cu", "B.Cu"]);
+        assert_eq!(four.layer("In1.Cu").unwrap().copper_weight, Some(0.5));
+        assert_eq!(four.layer("Prepreg 1").unwrap().kind, StackupLayerKind::Prepreg);
+        assert_eq!(four.layer("Core 1").unwrap().kind, StackupLayerKind::Core);
+        assert_eq!(four.adjacent_copper("F.Cu").1.unwrap().name, "In1.Cu");
+
+        let via = four.via_length(&four.drill_pairs[0]).unwrap();
+        assert!((via - 1.58).abs() < 1e-9);
+        assert!(Stackup::preset("3-layer").is_none());
+    }
+
+    #[test]
+    fn test_dielectric_properties() {
+        let core = StackupLayer::dielectric("Core", StackupLayerKind::Core, 1.0, "FR4")
+            .with_dk(10.0, 4.4, 0.02)
+            .with_dk(1.0, 4.6, 0.016);
+        assert_eq!(core.dk(0.1), Some(4.6));
+        assert_eq!(core.dk(20.0), Some(4.4));
+        assert!((core.dk(10f64.sqrt()).unwrap() - 4.5).abs() < 1e-9);
+        assert!(StackupLayer::copper("F.Cu", 1.0).dk(1.0).is_none());
+
+        let four = Stackup::preset("4-layer").unwrap();
+        let span = four.dielectric_between("F.Cu", "In1.Cu", 1.0).unwrap();
+        assert!((span.height - 0.2).abs() < 1e-9);
+        assert!((span.dk - 4.2).abs() < 1e-9);
+
+        let through = four.dielectric_between("F.Cu", "B.Cu", 1.0).unwrap();
+        assert!((through.height - 1.475).abs() < 1e-9);
+        assert!(through.dk > 4.2 && through.dk < 4.6);
+    }
+
+    #[test]
+    fn test_validate() {
+        let adjacent = Stackup::new("bad")
+            .with_layer(StackupLayer::copper("F.Cu", 1.0))
+            .with_layer(StackupLayer::copper("B.Cu", 1.0));
+        assert!(adjacent.validate().is_err());
+
+        let mut mask_inside = Stackup::preset("2-layer").unwrap();
+        mask_inside.layers.swap(0, 1);
+        assert!(mask_inside.validate().is_err());
+
+        let blind = Stackup::preset("4-layer")
+            .unwrap()
+            .with_drill_pair(DrillPair::new("F.Cu", "B.Cu", ViaType::Blind, 0.2, 0.4));
+        assert!(blind.validate().is_err());
+
+        assert!(StackupConfig::Preset("12-layer".to_string()).resolve().is_err());
+    }
+
+    #[test]
+    fn test_apply_to_layout() {
+        let mut layout = Layout::with_board_size(50.0, 40.0, LengthUnit::Mm);
+        let profile = FabProfile::builtin().remove(0);
+        let stackup = Stackup::preset("4-layer").unwrap();
+        stackup.apply_to(&mut layout).unwrap();
+
+        assert_eq!(layout.copper_layer_names(), stackup.copper_layer_names());
+        assert_eq!(layout.layers.iter().filter(|l| l.name == "F.Mask").count(), 1);
+        assert_eq!(layout.layers[2].layer_type, LayerType::Dielectric);
+        assert_eq!(layout.drill_pairs, stackup.drill_pairs);
+        assert!(layout.layers.iter().any(|l| l.name == "Edge.Cuts"));
+        assert!((FabChecker::new(&layout, &profile).board_thickness() - 1.6).abs() < 1e-9);
+
+        let mut six = Layout::new();
+        Stackup::preset("6-layer").unwrap().apply_to(&mut six).unwrap();
+        assert!(stackup.apply_to(&mut six).is_err());
+    }
+}
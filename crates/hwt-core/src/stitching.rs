@@ -0,0 +1,386 @@
+//! Via Stitching and Fencing.
+//!
+//! Generates through vias on a chosen net, either filling a zone or
+//! polygon with a grid or staggered pattern (stitching) or running along
+//! both sides of a trace at a fixed pitch (fencing). Candidate positions
+//! that would come within the clearance of other copper, leave the board
+//! or enter a via keepout are skipped.
+//!
+//! Zones and their fills are not obstacles: a refill clears other-net
+//! zones around the new vias and connects same-net ones.
+
+use serde::{Deserialize, Serialize};
+
+use crate::copper::{collect_copper, CopperKind, CopperShape};
+use crate::geometry::{point_in_polygon, BoundingBox, Point2D, Position};
+use crate::layout::{KeepoutKind, Layout, Via, ViaType};
+use crate::units::LengthUnit;
+
+/// Slack allowed on via spacing so neighbours exactly one pitch apart
+/// survive rounding (mm).
+const SPACING_TOLERANCE: f64 = 1e-6;
+
+/// Arrangement of stitching vias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StitchPattern {
+    /// Square grid
+    Grid,
+    /// Rows shifted by half a pitch, all neighbours one pitch apart
+    #[default]
+    Staggered,
+}
+
+/// Via stitching and fencing settings (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StitchConfig {
+    /// Stitching pattern
+    pub pattern: StitchPattern,
+
+    /// Distance between stitching vias
+    pub pitch: f64,
+
+    /// Distance between fence vias along the trace
+    pub fence_pitch: f64,
+
+    /// Distance from the trace centerline to the fence vias; by default
+    /// the closest position that keeps the clearance to the trace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fence_offset: Option<f64>,
+
+    /// Via drill diameter
+    pub drill: f64,
+
+    /// Via pad diameter
+    pub pad: f64,
+
+    /// Clearance to other copper
+    pub clearance: f64,
+
+    /// Clearance to the board edge and the stitched outline
+    pub edge_clearance: f64,
+}
+
+impl Default for StitchConfig {
+    fn default() -> Self {
+        Self {
+            pattern: StitchPattern::Staggered,
+            pitch: 2.0,
+            fence_pitch: 1.0,
+            fence_offset: None,
+            drill: 0.3,
+            pad: 0.6,
+            clearance: 0.2,
+            edge_clearance: 0.3,
+        }
+    }
+}
+
+impl StitchConfig {
+    /// Set the stitching pattern.
+    pub fn with_pattern(mut self, pattern: StitchPattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    /// Set the stitching pitch.
+    pub fn with_pitch(mut self, pitch: f64) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Set the fence pitch and, optionally, the fence offset.
+    pub fn with_fence(mut self, pitch: f64, offset: Option<f64>) -> Self {
+        self.fence_pitch = pitch;
+        self.fence_offset = offset;
+        self
+    }
+
+    /// Set the via size.
+    pub fn with_via(mut self, drill: f64, pad: f64) -> Self {
+        self.drill = drill;
+        self.pad = pad;
+        self
+    }
+}
+
+/// Via stitching and fencing generator. Generated vias are returned
+/// rather than added, so callers can preview them before extending
+/// `Layout::vias`.
+pub struct ViaStitcher {
+    config: StitchConfig,
+}
+
+impl ViaStitcher {
+    /// Create a new generator.
+    pub fn new(config: StitchConfig) -> Self {
+        Self { config }
+    }
+
+    /// Stitching vias for zone `index` on the zone's net, or none for
+    /// zones without a net.
+    pub fn stitch_zone(&self, layout: &Layout, index: usize) -> Vec<Via> {
+        let zone = &layout.zones[index];
+        if zone.net.is_empty() {
+            return Vec::new();
+        }
+        let outline: Vec<Point2D> = zone.points.iter()
+            .map(|p| Point2D::new(zone.unit.to_mm(p.x), zone.unit.to_mm(p.y)))
+            .collect();
+        self.stitch_polygon(layout, &outline, &zone.net)
+    }
+
+    /// Stitching vias on `net` filling a polygon (mm).
+    pub fn stitch_polygon(&self, layout: &Layout, polygon: &[Point2D], net: &str) -> Vec<Via> {
+        let Some(bounds) = BoundingBox::from_points(polygon) else { return Vec::new() };
+        let pitch = self.config.pitch.max(self.config.pad);
+        let row_pitch = match self.config.pattern {
+            StitchPattern::Grid => pitch,
+            StitchPattern::Staggered => pitch * 3f64.sqrt() / 2.0,
+        };
+        let inset = self.config.pad / 2.0 + self.config.edge_clearance;
+
+        // Center the lattice in the bounding box so it does not depend on
+        // where the outline starts
+        let columns = (bounds.width() / pitch).floor() as usize;
+        let rows = (bounds.height() / row_pitch).floor() as usize;
+        let x0 = bounds.min.x + (bounds.width() - columns as f64 * pitch) / 2.0;
+        let y0 = bounds.min.y + (bounds.height() - rows as f64 * row_pitch) / 2.0;
+
+        let mut placer = Placer::new(layout, &self.config, net, pitch);
+        for row in 0..=rows {
+            let shift = if self.config.pattern == StitchPattern::Staggered && row % 2 == 1 { pitch / 2.0 } else { 0.0 };
+            for column in 0..=columns {
+                let p = Point2D::new(x0 + shift + column as f64 * pitch, y0 + row as f64 * row_pitch);
+                if point_in_polygon(&p, polygon) && edge_distance(&p, polygon) >= inset {
+                    placer.place(p);
+                }
+            }
+        }
+        placer.vias
+    }
+
+    /// Fence vias on `net` along both sides of the traces at `traces`.
+    pub fn fence(&self, layout: &Layout, traces: &[usize], net: &str) -> Vec<Via> {
+        let pitch = self.config.fence_pitch.max(self.config.pad);
+        // Fence vias at corners are dropped when closer than half a pitch to
+        // those of the neighbouring segment
+        let mut placer = Placer::new(layout, &self.config, net, pitch / 2.0);
+        for &index in traces {
+            let trace = &layout.traces[index];
+            let to_mm = |v: f64| trace.unit.to_mm(v);
+            let start = Point2D::new(to_mm(trace.start.x), to_mm(trace.start.y));
+            let end = Point2D::new(to_mm(trace.end.x), to_mm(trace.end.y));
+            let length = start.distance(&end);
+            if length < 1e-9 {
+                continue;
+            }
+            let offset = self.config.fence_offset
+                .unwrap_or(to_mm(trace.width) / 2.0 + self.config.clearance + self.config.pad / 2.0);
+            let (dx, dy) = ((end.x - start.x) / length, (end.y - start.y) / length);
+            let steps = (length / pitch).floor() as usize;
+            let margin = (length - steps as f64 * pitch) / 2.0;
+            for step in 0..=steps {
+                let t = margin + step as f64 * pitch;
+                for side in [1.0, -1.0] {
+                    placer.place(Point2D::new(
+                        start.x + dx * t - dy * offset * side,
+                        start.y + dy * t + dx * offset * side,
+                    ));
+                }
+            }
+        }
+        placer.vias
+    }
+
+    /// Fence vias on `net` along both sides of every trace of `signal`.
+    pub fn fence_net(&self, layout: &Layout, signal: &str, net: &str) -> Vec<Via> {
+        let traces: Vec<usize> = layout.traces.iter().enumerate()
+            .filter(|(_, trace)| trace.net == signal)
+            .map(|(i, _)| i)
+            .collect();
+        self.fence(layout, &traces, net)
+    }
+}
+
+/// Distance from a point to the nearest edge of a polygon.
+fn edge_distance(p: &Point2D, polygon: &[Point2D]) -> f64 {
+    polygon.iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| p.distance_to_segment(a, b))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Places vias that keep clear of existing copper, the board edge, via
+/// keepouts and each other.
+struct Placer<'a> {
+    config: &'a StitchConfig,
+    net: &'a str,
+    /// Minimum distance between new via centers
+    spacing: f64,
+    obstacles: Vec<(CopperShape, BoundingBox)>,
+    keepouts: Vec<CopperShape>,
+    /// Board outline and cutouts (mm)
+    board: Option<(Vec<Point2D>, Vec<Vec<Point2D>>)>,
+    vias: Vec<Via>,
+}
+
+impl<'a> Placer<'a> {
+    fn new(layout: &Layout, config: &'a StitchConfig, net: &'a str, spacing: f64) -> Self {
+        let obstacles = collect_copper(layout).into_iter()
+            .filter(|item| !matches!(item.kind, CopperKind::Zone | CopperKind::ZoneFill))
+            .filter_map(|item| {
+                let bounds = item.shape.bounding_box()?;
+                Some((item.shape, bounds))
+            })
+            .collect();
+        let copper = layout.copper_layer_names();
+        let keepouts = layout.rule_areas.iter()
+            .filter(|area| copper.iter().any(|layer| area.forbids(KeepoutKind::Via, layer)))
+            .map(|area| CopperShape::polygon(area.polygon_mm()))
+            .collect();
+        let board = layout.outline.as_ref().map(|outline| {
+            let ring = |points: &[Point2D]| -> Vec<Point2D> {
+                points.iter().map(|p| Point2D::new(outline.unit.to_mm(p.x), outline.unit.to_mm(p.y))).collect()
+            };
+            (ring(&outline.to_polygon(64)), outline.cutouts.iter().map(|c| ring(c)).collect())
+        });
+        Self {
+            config,
+            net,
+            spacing: spacing.max(config.pad + config.clearance),
+            obstacles,
+            keepouts,
+            board,
+            vias: Vec::new(),
+        }
+    }
+
+    /// Whether a via fits at `p`.
+    fn fits(&self, p: &Point2D) -> bool {
+        let radius = self.config.pad / 2.0;
+        if let Some((outline, cutouts)) = &self.board {
+            let edge = radius + self.config.edge_clearance;
+            if outline.len() >= 3 && (!point_in_polygon(p, outline) || edge_distance(p, outline) < edge) {
+                return false;
+            }
+            if cutouts.iter().any(|c| point_in_polygon(p, c) || edge_distance(p, c) < edge) {
+                return false;
+            }
+        }
+        if self.vias.iter().any(|via| via.position.to_point2d().distance(p) < self.spacing - SPACING_TOLERANCE) {
+            return false;
+        }
+
+        let shape = CopperShape::circle(*p, radius);
+        let reach = BoundingBox::new(*p, *p).expanded(radius + self.config.clearance);
+        let blocked = self.obstacles.iter()
+            .any(|(obstacle, bounds)| bounds.intersects(&reach) && obstacle.distance(&shape) < self.config.clearance);
+        !blocked && self.keepouts.iter().all(|keepout| keepout.distance(&shape) > 0.0)
+    }
+
+    /// Add a via at `p` if it fits.
+    fn place(&mut self, p: Point2D) -> bool {
+        if !self.fits(&p) {
+            return false;
+        }
+        self.vias.push(Via {
+            net: self.net.to_string(),
+            position: Position::new(p.x, p.y),
+            via_type: ViaType::Through,
+            drill: self.config.drill,
+            pad: self.config.pad,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Trace, Zone};
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Point2D> {
+        vec![
+            Point2D::new(x, y),
+            Point2D::new(x + size, y),
+            Point2D::new(x + size, y + size),
+            Point2D::new(x, y + size),
+        ]
+    }
+
+    fn trace(net: &str, x1: f64, y1: f64, x2: f64, y2: f64) -> Trace {
+        Trace {
+            net: net.to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(x1, y1),
+            end: Position::new(x2, y2),
+            width: 0.3,
+            unit: LengthUnit::Mm,
+        }
+    }
+
+    #[test]
+    fn test_stitch_zone_patterns() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.zones.push(Zone::new("GND", "F.Cu", square(10.0, 10.0, 20.0)));
+
+        let grid = ViaStitcher::new(StitchConfig::default().with_pattern(StitchPattern::Grid)).stitch_zone(&layout, 0);
+        let staggered = ViaStitcher::new(StitchConfig::default()).stitch_zone(&layout, 0);
+        assert!(!grid.is_empty());
+        assert!(staggered.len() > grid.len());
+        assert!(grid.iter().all(|v| v.net == "GND"));
+
+        // Neighbours are never closer than the pitch
+        for vias in [&grid, &staggered] {
+            for (i, a) in vias.iter().enumerate() {
+                for b in &vias[i + 1..] {
+                    assert!(a.position.to_point2d().distance(&b.position.to_point2d()) > 2.0 - SPACING_TOLERANCE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_stitch_avoids_other_copper() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(trace("SIG", 10.0, 20.0, 30.0, 20.0));
+        let config = StitchConfig::default();
+        let vias = ViaStitcher::new(config.clone()).stitch_polygon(&layout, &square(10.0, 10.0, 20.0), "GND");
+
+        let track = CopperShape::from_trace(&layout.traces[0]);
+        assert!(!vias.is_empty());
+        for via in &vias {
+            let shape = CopperShape::circle(via.position.to_point2d(), config.pad / 2.0);
+            assert!(track.distance(&shape) >= config.clearance);
+        }
+    }
+
+    #[test]
+    fn test_fence_both_sides_of_trace() {
+        let mut layout = Layout::with_board_size(50.0, 50.0, LengthUnit::Mm);
+        layout.traces.push(trace("RF", 10.0, 20.0, 30.0, 20.0));
+        layout.traces.push(trace("RF", 30.0, 20.0, 30.0, 40.0));
+        let stitcher = ViaStitcher::new(StitchConfig::default().with_fence(1.0, Some(1.0)));
+        let vias = stitcher.fence_net(&layout, "RF", "GND");
+
+        let above = vias.iter().filter(|v| (v.position.y - 21.0).abs() < 1e-9).count();
+        let below = vias.iter().filter(|v| (v.position.y - 19.0).abs() < 1e-9).count();
+        assert!(above >= 19 && below >= 19);
+
+        // Inside the corner the two segments' fences would collide
+        for (i, a) in vias.iter().enumerate() {
+            for b in &vias[i + 1..] {
+                assert!(a.position.to_point2d().distance(&b.position.to_point2d()) >= 0.8);
+            }
+        }
+        let tracks: Vec<CopperShape> = layout.traces.iter().map(CopperShape::from_trace).collect();
+        assert!(vias.iter().all(|v| {
+            let shape = CopperShape::circle(v.position.to_point2d(), 0.3);
+            tracks.iter().all(|t| t.distance(&shape) >= 0.2)
+        }));
+    }
+}
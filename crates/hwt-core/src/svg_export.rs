@@ -6,6 +6,8 @@
 use std::fmt::Write;
 
 use crate::schematic::SchematicSheet;
+use crate::geometry::Point2D;
+use crate::ir_drop::{IrDropQuantity, IrDropReport};
 use crate::layout::Layout;
 
 /// SVG document generator.
@@ -218,6 +220,128 @@ impl SvgGenerator {
             writeln!(svg, r#"  <rect width="100%" height="100%" fill="{}"/>"#, bg).unwrap();
         }
 
+        self.write_layout_items(&mut svg, layout, layers);
+
+        // Close SVG
+        writeln!(svg, r#"</svg>"#).unwrap();
+
+        svg
+    }
+
+    /// Generate a square detail view of a PCB layout centred on a point,
+    /// marked with a red ring (e.g. a DRC violation thumbnail).
+    pub fn generate_layout_detail(&self, layout: &Layout, center: &Point2D, size_mm: f64) -> String {
+        let mut svg = String::new();
+        let (x0, y0) = (center.x - size_mm / 2.0, center.y - size_mm / 2.0);
+        let pixels = size_mm * self.scale;
+
+        // SVG header
+        writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1""#).unwrap();
+        writeln!(svg, r#"     width="{:.0}" height="{:.0}""#, pixels, pixels).unwrap();
+        writeln!(svg, r#"     viewBox="{:.3} {:.3} {:.3} {:.3}">"#, x0, y0, size_mm, size_mm).unwrap();
+
+        writeln!(svg, r#"  <title>PCB Detail</title>"#).unwrap();
+
+        // Background
+        if let Some(ref bg) = self.background {
+            writeln!(svg, r#"  <rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="{}"/>"#,
+                x0, y0, size_mm, size_mm, bg).unwrap();
+        }
+
+        self.write_layout_items(&mut svg, layout, &[]);
+
+        // Marker
+        writeln!(svg, "  <circle cx=\"{:.3}\" cy=\"{:.3}\" r=\"{:.3}\" fill=\"none\" stroke=\"#e00000\" stroke-width=\"{:.3}\"/>",
+            center.x, center.y, size_mm * 0.1, size_mm * 0.02).unwrap();
+
+        // Close SVG
+        writeln!(svg, r#"</svg>"#).unwrap();
+
+        svg
+    }
+
+    /// Generate SVG of a PCB layout overlaid with an IR-drop heat map of
+    /// one quantity, colored from blue (lowest) to red (highest). Empty
+    /// `layers` shows every layer.
+    pub fn generate_heat_map(&self, layout: &Layout, layers: &[String], report: &IrDropReport, quantity: IrDropQuantity) -> String {
+        let mut svg = String::new();
+        let pixel_width = self.width * self.scale;
+        let pixel_height = self.height * self.scale;
+
+        // SVG header
+        writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+        writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1""#).unwrap();
+        writeln!(svg, r#"     width="{:.0}" height="{:.0}""#, pixel_width, pixel_height).unwrap();
+        writeln!(svg, r#"     viewBox="0 0 {:.2} {:.2}">"#, self.width, self.height).unwrap();
+
+        writeln!(svg, r#"  <title>IR Drop {}</title>"#, self.escape_xml(&report.net)).unwrap();
+
+        // Background
+        if let Some(ref bg) = self.background {
+            writeln!(svg, r#"  <rect width="100%" height="100%" fill="{}"/>"#, bg).unwrap();
+        }
+
+        self.write_layout_items(&mut svg, layout, layers);
+
+        // Heat map: cells as squares, trace and via nodes as dots
+        let nodes: Vec<_> = report.nodes.iter()
+            .filter(|n| layers.is_empty() || layers.contains(&n.layer))
+            .collect();
+        let values: Vec<f64> = nodes.iter().map(|n| report.value(n, quantity)).collect();
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let span = if max > min { max - min } else { 1.0 };
+        let size = report.cell_size;
+
+        writeln!(svg, r#"  <g id="heat-map" fill-opacity="0.8">"#).unwrap();
+        for (node, value) in nodes.iter().zip(&values) {
+            let hue = 240.0 * (1.0 - (value - min) / span);
+            if node.cell {
+                writeln!(svg, r#"    <rect x="{:.3}" y="{:.3}" width="{:.3}" height="{:.3}" fill="hsl({:.0},100%,50%)"/>"#,
+                    node.position.x - size / 2.0, node.position.y - size / 2.0, size, size, hue).unwrap();
+            } else {
+                writeln!(svg, r#"    <circle cx="{:.3}" cy="{:.3}" r="{:.3}" fill="hsl({:.0},100%,50%)"/>"#,
+                    node.position.x, node.position.y, size / 2.0, hue).unwrap();
+            }
+        }
+        writeln!(svg, r#"  </g>"#).unwrap();
+
+        // Legend
+        if !values.is_empty() {
+            writeln!(svg, r#"  <text x="1" y="3" font-family="sans-serif" font-size="2">{}: {:.4} to {:.4}</text>"#,
+                self.escape_xml(quantity.label()), min, max).unwrap();
+        }
+
+        // Close SVG
+        writeln!(svg, r#"</svg>"#).unwrap();
+
+        svg
+    }
+
+    /// Write zones, traces, vias and components to SVG.
+    fn write_layout_items(&self, svg: &mut String, layout: &Layout, layers: &[String]) {
+        // Zones group: fills, or dashed outlines of unfilled zones
+        writeln!(svg, "  <g id=\"zones\" fill=\"#00aa00\" fill-opacity=\"0.4\">").unwrap();
+
+        for zone in &layout.zones {
+            if !layers.is_empty() && !layers.contains(&zone.layer) {
+                continue;
+            }
+            if zone.is_filled() {
+                let mut path = String::new();
+                for polygon in &zone.filled {
+                    path.push_str(&polygon_path(polygon));
+                }
+                writeln!(svg, r#"    <path d="{}"/>"#, path.trim_end()).unwrap();
+            } else if zone.points.len() >= 3 {
+                writeln!(svg, "    <path d=\"{}\" fill=\"none\" stroke=\"#00aa00\" stroke-width=\"0.1\" stroke-dasharray=\"0.5 0.5\"/>",
+                    polygon_path(&zone.points).trim_end()).unwrap();
+            }
+        }
+
+        writeln!(svg, r#"  </g>"#).unwrap();
+
         // Traces group
         writeln!(svg, "  <g id=\"traces\" stroke=\"#00aa00\" fill=\"none\">").unwrap();
 
@@ -255,11 +379,6 @@ impl SvgGenerator {
         }
 
         writeln!(svg, r#"  </g>"#).unwrap();
-
-        // Close SVG
-        writeln!(svg, r#"</svg>"#).unwrap();
-
-        svg
     }
 
     /// Write grid to SVG.
@@ -320,6 +439,16 @@ impl Default for SvgGenerator {
     }
 }
 
+/// SVG path data of a closed polygon, with a trailing space.
+fn polygon_path(points: &[Point2D]) -> String {
+    let mut path = String::new();
+    for (i, p) in points.iter().enumerate() {
+        write!(path, "{}{:.3} {:.3} ", if i == 0 { "M" } else { "L" }, p.x, p.y).unwrap();
+    }
+    path.push_str("Z ");
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,6 +492,67 @@ mod tests {
         assert!(svg.contains("PCB Layout"));
     }
 
+    #[test]
+    fn test_generate_layout_detail_svg() {
+        let layout = Layout::new();
+        let generator = SvgGenerator::new().with_scale(10.0);
+        let svg = generator.generate_layout_detail(&layout, &Point2D::new(20.0, 30.0), 4.0);
+
+        assert!(svg.contains(r#"viewBox="18.000 28.000 4.000 4.000""#));
+        assert!(svg.contains(r#"width="40""#));
+        assert!(svg.contains("#e00000"));
+    }
+
+    #[test]
+    fn test_generate_layout_zone_fill() {
+        use crate::layout::Zone;
+
+        let mut layout = Layout::new();
+        let mut zone = Zone::new("GND", "F.Cu", vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(10.0, 0.0),
+            Point2D::new(10.0, 10.0),
+        ]);
+        layout.zones.push(zone.clone());
+        let generator = SvgGenerator::new();
+        assert!(generator.generate_layout(&layout, &[]).contains("stroke-dasharray"));
+
+        zone.filled = vec![vec![Point2D::new(1.0, 1.0), Point2D::new(9.0, 1.0), Point2D::new(9.0, 8.0)]];
+        layout.zones[0] = zone;
+        let svg = generator.generate_layout(&layout, &[]);
+        assert!(svg.contains(r#"<path d="M1.000 1.000 L9.000 1.000 L9.000 8.000 Z"/>"#));
+    }
+
+    #[test]
+    fn test_generate_heat_map() {
+        use crate::ir_drop::MeshNode;
+
+        let node = |x: f64, cell: bool, voltage: f64| MeshNode {
+            layer: "F.Cu".to_string(),
+            position: Point2D::new(x, 5.0),
+            cell,
+            voltage,
+            current_density: 0.0,
+        };
+        let report = IrDropReport {
+            net: "VCC".to_string(),
+            cell_size: 0.5,
+            source_voltage: 3.3,
+            min_voltage: 3.2,
+            max_drop: 0.1,
+            total_current: 1.0,
+            power_loss: 0.1,
+            max_current_density: 0.0,
+            sinks: Vec::new(),
+            nodes: vec![node(1.0, true, 3.3), node(2.0, false, 3.2)],
+        };
+        let svg = SvgGenerator::new().generate_heat_map(&Layout::new(), &[], &report, IrDropQuantity::Drop);
+
+        assert!(svg.contains(r#"<rect x="0.750" y="4.750" width="0.500" height="0.500" fill="hsl(240,100%,50%)"/>"#));
+        assert!(svg.contains(r#"<circle cx="2.000" cy="5.000" r="0.250" fill="hsl(0,100%,50%)"/>"#));
+        assert!(svg.contains("Drop (V): 0.0000 to 0.1000"));
+    }
+
     #[test]
     fn test_svg_with_options() {
         let options = SvgExportOptions {
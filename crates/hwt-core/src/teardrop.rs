@@ -0,0 +1,360 @@
+//! Teardrop Generation.
+//!
+//! Post-processing pass that adds teardrop fillets where traces enter vias
+//! and pads. Teardrops are stored as `ZoneKind::Teardrop` zones so they can
+//! be regenerated or removed without touching user-drawn zones.
+//! The zone filler skips them; their fill is the outline itself, so outputs
+//! that plot zone fills draw them as copper.
+
+use serde::{Deserialize, Serialize};
+
+use crate::geometry::Point2D;
+use crate::layout::{Layout, Zone, ZoneKind};
+
+/// Shape of the teardrop sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TeardropStyle {
+    /// Straight sides from the pad to the track
+    Straight,
+    /// Concave sides blending into the track edges
+    #[default]
+    Curved,
+}
+
+/// Teardrop generation settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeardropConfig {
+    /// Length beyond the pad edge, relative to the pad size
+    pub length_ratio: f64,
+
+    /// Width at the pad, relative to the pad size
+    pub width_ratio: f64,
+
+    /// Maximum length beyond the pad edge (mm)
+    pub max_length: f64,
+
+    /// Skip tracks at least this wide relative to the pad size
+    pub max_track_ratio: f64,
+
+    /// Side shape
+    pub style: TeardropStyle,
+
+    /// Segments approximating each curved side
+    pub curve_segments: usize,
+
+    /// Add teardrops on vias
+    pub vias: bool,
+
+    /// Add teardrops on pads
+    pub pads: bool,
+}
+
+impl Default for TeardropConfig {
+    fn default() -> Self {
+        Self {
+            length_ratio: 0.5,
+            width_ratio: 1.0,
+            max_length: 1.0,
+            max_track_ratio: 0.9,
+            style: TeardropStyle::Curved,
+            curve_segments: 8,
+            vias: true,
+            pads: true,
+        }
+    }
+}
+
+/// A via or pad a track can end in.
+enum Anchor {
+    Via { center: Point2D, radius: f64 },
+    Pad { component: usize, pad: usize },
+}
+
+/// Teardrop generator.
+pub struct TeardropGenerator {
+    config: TeardropConfig,
+}
+
+impl TeardropGenerator {
+    /// Create a new generator.
+    pub fn new(config: TeardropConfig) -> Self {
+        Self { config }
+    }
+
+    /// Replace all teardrops in the layout with freshly generated ones.
+    /// Returns the number of teardrops added.
+    pub fn apply(&self, layout: &mut Layout) -> usize {
+        remove_teardrops(layout);
+        let teardrops = self.generate(layout);
+        let count = teardrops.len();
+        layout.zones.extend(teardrops);
+        count
+    }
+
+    /// Generate teardrop zones for every track end inside a via or pad of
+    /// the same net, without modifying the layout.
+    pub fn generate(&self, layout: &Layout) -> Vec<Zone> {
+        let copper = layout.copper_layer_names();
+        let mut anchors: Vec<(&str, Vec<String>, f64, Anchor)> = Vec::new();
+
+        if self.config.vias {
+            for via in &layout.vias {
+                let center = Point2D::new(via.unit.to_mm(via.position.x), via.unit.to_mm(via.position.y));
+                let radius = via.unit.to_mm(via.pad) / 2.0;
+                anchors.push((&via.net, via.copper_layers(&copper), radius * 2.0, Anchor::Via { center, radius }));
+            }
+        }
+        if self.config.pads {
+            for (ci, component) in layout.components.iter().enumerate() {
+                for (pi, pad) in component.pads.iter().enumerate() {
+                    let Some(net) = pad.net.as_deref() else { continue };
+                    let size = pad.size.0.min(pad.size.1);
+                    let layers = component.pad_copper_layers(pad, &copper);
+                    anchors.push((net, layers, size, Anchor::Pad { component: ci, pad: pi }));
+                }
+            }
+        }
+
+        let mut zones = Vec::new();
+        for trace in &layout.traces {
+            let start = Point2D::new(trace.unit.to_mm(trace.start.x), trace.unit.to_mm(trace.start.y));
+            let end = Point2D::new(trace.unit.to_mm(trace.end.x), trace.unit.to_mm(trace.end.y));
+            let width = trace.unit.to_mm(trace.width);
+
+            for (near, far) in [(start, end), (end, start)] {
+                for (net, layers, size, anchor) in &anchors {
+                    if *net != trace.net || !layers.contains(&trace.layer) {
+                        continue;
+                    }
+                    let distance = |p: &Point2D| anchor_distance(layout, anchor, p);
+                    if distance(&near) > 0.0 {
+                        continue;
+                    }
+                    if let Some(points) = self.teardrop_polygon(near, far, width, *size, distance) {
+                        let mut zone = Zone::new(&trace.net, &trace.layer, points.clone());
+                        zone.kind = ZoneKind::Teardrop;
+                        zone.filled = vec![points];
+                        zones.push(zone);
+                    }
+                }
+            }
+        }
+        zones
+    }
+
+    /// Teardrop outline for a track entering copper at `near`, or `None`
+    /// when the track is too wide or too short for a teardrop.
+    fn teardrop_polygon(
+        &self,
+        near: Point2D,
+        far: Point2D,
+        width: f64,
+        size: f64,
+        distance: impl Fn(&Point2D) -> f64,
+    ) -> Option<Vec<Point2D>> {
+        if width >= size * self.config.max_track_ratio {
+            return None;
+        }
+        let track_length = near.distance(&far);
+        if track_length <= 0.0 {
+            return None;
+        }
+        let d = Point2D::new((far.x - near.x) / track_length, (far.y - near.y) / track_length);
+        let n = Point2D::new(-d.y, d.x);
+        let along = |t: f64| Point2D::new(near.x + d.x * t, near.y + d.y * t);
+
+        // Distance from the track end to the copper edge along the track
+        let (mut lo, mut hi) = (0.0, track_length);
+        if distance(&along(hi)) <= 0.0 {
+            return None;
+        }
+        for _ in 0..40 {
+            let mid = (lo + hi) / 2.0;
+            if distance(&along(mid)) <= 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        let edge = hi;
+
+        let length = (self.config.length_ratio * size)
+            .min(self.config.max_length)
+            .min(track_length - edge);
+        if length <= width * 0.1 {
+            return None;
+        }
+        let half_base = (self.config.width_ratio * size).min(size).max(width) / 2.0;
+        let half_width = width / 2.0;
+        let tip = along(edge + length);
+        let offset = |p: Point2D, k: f64| Point2D::new(p.x + n.x * k, p.y + n.y * k);
+
+        let side = |sign: f64| -> Vec<Point2D> {
+            let base = offset(near, sign * half_base);
+            let end = offset(tip, sign * half_width);
+            match self.config.style {
+                TeardropStyle::Straight => vec![base, end],
+                TeardropStyle::Curved => {
+                    // Quadratic curve pulled toward the track edge at the pad boundary
+                    let control = offset(along(edge), sign * half_width);
+                    let segments = self.config.curve_segments.max(1);
+                    (0..=segments)
+                        .map(|i| {
+                            let t = i as f64 / segments as f64;
+                            let u = 1.0 - t;
+                            Point2D::new(
+                                u * u * base.x + 2.0 * u * t * control.x + t * t * end.x,
+                                u * u * base.y + 2.0 * u * t * control.y + t * t * end.y,
+                            )
+                        })
+                        .collect()
+                }
+            }
+        };
+
+        let mut points = side(1.0);
+        let mut other = side(-1.0);
+        other.reverse();
+        points.extend(other);
+        Some(points)
+    }
+}
+
+/// Signed distance from a point to a via or pad outline (negative inside).
+fn anchor_distance(layout: &Layout, anchor: &Anchor, p: &Point2D) -> f64 {
+    match anchor {
+        Anchor::Via { center, radius } => center.distance(p) - radius,
+        Anchor::Pad { component, pad } => {
+            let component = &layout.components[*component];
+            component.pad_distance(&component.pads[*pad], p)
+        }
+    }
+}
+
+/// Remove all generated teardrops. Returns the number removed.
+pub fn remove_teardrops(layout: &mut Layout) -> usize {
+    let before = layout.zones.len();
+    layout.zones.retain(|z| z.kind != ZoneKind::Teardrop);
+    before - layout.zones.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{point_in_polygon, Position};
+    use crate::layout::{Pad, PlacedComponent, Trace, Via, ViaType};
+    use crate::units::LengthUnit;
+
+    fn via_layout() -> Layout {
+        let mut layout = Layout::with_board_size(20.0, 20.0, LengthUnit::Mm);
+        layout.vias.push(Via {
+            net: "SIG".to_string(),
+            position: Position::new(5.0, 5.0),
+            via_type: ViaType::Through,
+            drill: 0.4,
+            pad: 0.8,
+            start_layer: None,
+            end_layer: None,
+            unit: LengthUnit::Mm,
+        });
+        layout.traces.push(Trace {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(5.0, 5.0),
+            end: Position::new(10.0, 5.0),
+            width: 0.2,
+            unit: LengthUnit::Mm,
+        });
+        layout
+    }
+
+    #[test]
+    fn test_via_teardrop_straight() {
+        let layout = via_layout();
+        let config = TeardropConfig {
+            style: TeardropStyle::Straight,
+            ..Default::default()
+        };
+        let zones = TeardropGenerator::new(config).generate(&layout);
+
+        assert_eq!(zones.len(), 1);
+        let zone = &zones[0];
+        assert_eq!(zone.kind, ZoneKind::Teardrop);
+        assert_eq!(zone.points.len(), 4);
+        // Base spans the via, tip ends 0.4mm past the via edge at track width
+        assert!((zone.points[0].y - 5.4).abs() < 1e-9);
+        assert!((zone.points[1].x - 5.8).abs() < 1e-6);
+        assert!((zone.points[1].y - 5.1).abs() < 1e-9);
+        assert!(point_in_polygon(&Point2D::new(5.5, 5.0), &zone.points));
+    }
+
+    #[test]
+    fn test_teardrop_plotted_as_copper() {
+        use crate::copper::{collect_copper, CopperKind};
+        use crate::svg_export::SvgGenerator;
+
+        let mut layout = via_layout();
+        TeardropGenerator::new(TeardropConfig::default()).apply(&mut layout);
+
+        let copper = collect_copper(&layout);
+        assert_eq!(copper.iter().filter(|item| item.kind == CopperKind::Teardrop).count(), 1);
+        // Drawn as a filled path, not a dashed outline
+        let svg = SvgGenerator::new().generate_layout(&layout, &[]);
+        assert!(!svg.contains("stroke-dasharray"));
+        assert!(svg.contains(r#"<path d="M5.000 5.400"#));
+    }
+
+    #[test]
+    fn test_teardrops_regenerate_and_remove() {
+        let mut layout = via_layout();
+        layout.zones.push(Zone::new("GND", "B.Cu", vec![
+            Point2D::new(0.0, 0.0),
+            Point2D::new(20.0, 0.0),
+            Point2D::new(20.0, 20.0),
+        ]));
+        let generator = TeardropGenerator::new(TeardropConfig::default());
+
+        assert_eq!(generator.apply(&mut layout), 1);
+        assert_eq!(generator.apply(&mut layout), 1);
+        assert_eq!(layout.zones.len(), 2);
+
+        assert_eq!(remove_teardrops(&mut layout), 1);
+        assert_eq!(layout.zones.len(), 1);
+        assert_eq!(layout.zones[0].kind, ZoneKind::Copper);
+    }
+
+    #[test]
+    fn test_pad_teardrop_requires_same_net_and_layer() {
+        let mut layout = Layout::with_board_size(20.0, 20.0, LengthUnit::Mm);
+        layout.components.push(
+            PlacedComponent::new("R1", "10k", "R_0805")
+                .at(5.0, 5.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.2, 1.4).with_net("SIG")),
+        );
+        let trace = |net: &str, layer: &str| Trace {
+            net: net.to_string(),
+            layer: layer.to_string(),
+            start: Position::new(5.0, 5.0),
+            end: Position::new(5.0, 10.0),
+            width: 0.25,
+            unit: LengthUnit::Mm,
+        };
+        layout.traces.push(trace("SIG", "F.Cu"));
+        layout.traces.push(trace("SIG", "B.Cu"));
+        layout.traces.push(trace("GND", "F.Cu"));
+
+        let zones = TeardropGenerator::new(TeardropConfig::default()).generate(&layout);
+        assert_eq!(zones.len(), 1);
+        assert_eq!(zones[0].layer, "F.Cu");
+        // Curved sides: two sides of curve_segments + 1 points
+        assert_eq!(zones[0].points.len(), 18);
+    }
+
+    #[test]
+    fn test_wide_track_skipped() {
+        let mut layout = via_layout();
+        layout.traces[0].width = 0.75;
+        assert!(TeardropGenerator::new(TeardropConfig::default()).generate(&layout).is_empty());
+    }
+}
@@ -0,0 +1,776 @@
+//! Copper Zone Filling.
+//!
+//! Computes the copper of each zone: the outline, kept inside the board
+//! edge, minus other-net copper grown by its clearance, with thermal
+//! reliefs or solid connections to pads of the zone's net. Features
+//! narrower than the zone's minimum width are removed, isolated islands
+//! are dropped per the zone's island setting and hatched zones keep only
+//! a grid of bars. The result is cached in `Zone::filled`.
+//!
+//! The fill is computed by a plane sweep over polygons, with round
+//! outlines replaced by circumscribed polygons so the approximation only
+//! ever adds clearance. The filled polygons are trapezoid strips merged
+//! vertically; neighbouring polygons of one island share edges.
+
+use serde::{Deserialize, Serialize};
+
+use crate::copper::{collect_copper, CopperItem, CopperKind, CopperShape, ShapeCore};
+use crate::geometry::{BoundingBox, Point2D};
+use crate::layout::{IslandRemoval, KeepoutKind, Layout, PadConnection, Zone, ZoneFillType, ZoneKind};
+use crate::ratsnest::UnionFind;
+
+/// Sides of the polygons that stand in for circles.
+const ARC_SEGMENTS: usize = 16;
+
+/// Coordinates closer than this are treated as equal (mm).
+const EPSILON: f64 = 1e-9;
+
+/// Distance at which copper counts as connected (mm).
+const TOUCH: f64 = 1e-6;
+
+/// Added to every clearance so rounding never brings the fill closer
+/// than the rule (mm).
+const MARGIN: f64 = 1e-6;
+
+/// Zone fill settings, used where a zone does not set its own (mm).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneFillConfig {
+    /// Clearance to copper of other nets
+    pub clearance: f64,
+
+    /// Minimum width of filled copper
+    pub min_width: f64,
+
+    /// Clearance to the board edge
+    pub edge_clearance: f64,
+
+    /// Gap around pads with thermal relief
+    pub thermal_gap: f64,
+
+    /// Width of thermal relief spokes
+    pub thermal_spoke_width: f64,
+
+    /// Number of thermal relief spokes
+    pub thermal_spokes: usize,
+
+    /// Hatch line width
+    pub hatch_width: f64,
+
+    /// Gap between hatch lines
+    pub hatch_gap: f64,
+}
+
+impl Default for ZoneFillConfig {
+    fn default() -> Self {
+        Self {
+            clearance: 0.2,
+            min_width: 0.25,
+            edge_clearance: 0.3,
+            thermal_gap: 0.5,
+            thermal_spoke_width: 0.5,
+            thermal_spokes: 4,
+            hatch_width: 1.0,
+            hatch_gap: 1.5,
+        }
+    }
+}
+
+/// Part a polygon plays in the sweep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// Zone outline
+    Zone,
+    /// Board outline
+    Board,
+    /// Area to keep clear
+    Cut,
+    /// Gap around a thermally relieved pad
+    Relief,
+    /// Thermal relief spoke
+    Spoke,
+    /// Hatch bar
+    Hatch,
+    /// Area left after removing narrow features
+    Keep,
+}
+
+const ROLES: usize = 7;
+
+/// Number of polygons of each role containing a point.
+type Counts = [usize; ROLES];
+
+/// Zone filler.
+pub struct ZoneFiller {
+    config: ZoneFillConfig,
+}
+
+impl ZoneFiller {
+    /// Create a new filler.
+    pub fn new(config: ZoneFillConfig) -> Self {
+        Self { config }
+    }
+
+    /// Refill every copper zone, highest priority first. Returns the
+    /// number of zones holding a fill.
+    pub fn apply(&self, layout: &mut Layout) -> usize {
+        let mut order: Vec<usize> = (0..layout.zones.len())
+            .filter(|&i| layout.zones[i].kind == ZoneKind::Copper)
+            .collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(layout.zones[i].priority));
+        for &i in &order {
+            layout.zones[i].filled = self.fill(layout, i);
+        }
+        order.iter().filter(|&&i| layout.zones[i].is_filled()).count()
+    }
+
+    /// Compute the fill of zone `index` without modifying the layout, in
+    /// the zone's unit. Higher-priority zones are cut out by their current
+    /// fill, or by their outline when unfilled.
+    pub fn fill(&self, layout: &Layout, index: usize) -> Vec<Vec<Point2D>> {
+        let zone = &layout.zones[index];
+        if zone.kind != ZoneKind::Copper || zone.fill_type == ZoneFillType::None || zone.points.len() < 3 {
+            return Vec::new();
+        }
+        let to_mm = |v: f64| zone.unit.to_mm(v);
+        let outline: Vec<Point2D> = zone.points.iter().map(|p| Point2D::new(to_mm(p.x), to_mm(p.y))).collect();
+        let Some(bounds) = BoundingBox::from_points(&outline) else { return Vec::new() };
+        let clearance = zone.clearance.map_or(self.config.clearance, to_mm) + MARGIN;
+        let min_width = zone.min_width.map_or(self.config.min_width, to_mm);
+
+        let mut shapes = Shapes::default();
+        shapes.add_ring(Role::Zone, &outline, 0.0);
+        let edge_clearance = self.config.edge_clearance + MARGIN;
+        if let Some(board) = &layout.outline {
+            let ring = |points: &[Point2D]| -> Vec<Point2D> {
+                points.iter().map(|p| Point2D::new(board.unit.to_mm(p.x), board.unit.to_mm(p.y))).collect()
+            };
+            shapes.add_ring(Role::Board, &ring(&board.to_polygon(128)), edge_clearance);
+            for cutout in &board.cutouts {
+                shapes.add_ring(Role::Cut, &ring(cutout), edge_clearance);
+            }
+        }
+
+        let same_net = |item: &CopperItem| !zone.net.is_empty() && item.net == zone.net;
+        let nearby: Vec<CopperItem> = collect_copper(layout).into_iter()
+            .filter(|item| !matches!(item.kind, CopperKind::Zone | CopperKind::ZoneFill))
+            .filter(|item| item.layers.contains(&zone.layer))
+            .filter(|item| item.shape.bounding_box().is_some_and(|b| b.intersects(&bounds.expanded(clearance + self.config.thermal_gap))))
+            .collect();
+        for item in &nearby {
+            if !same_net(item) {
+                shapes.add(Role::Cut, &item.shape, clearance.max(item.clearance.unwrap_or(0.0) + MARGIN));
+            } else if item.kind == CopperKind::Pad {
+                match zone.pad_connection {
+                    PadConnection::Solid => {}
+                    PadConnection::None => shapes.add(Role::Cut, &item.shape, clearance),
+                    PadConnection::Thermal => self.add_thermal_relief(&mut shapes, zone, layout, item, min_width),
+                }
+            }
+        }
+        for other in &layout.zones {
+            if other.kind != ZoneKind::Copper || other.layer != zone.layer
+                || other.priority <= zone.priority || other.net == zone.net
+            {
+                continue;
+            }
+            let rings = if other.is_filled() { &other.filled } else { std::slice::from_ref(&other.points) };
+            for ring in rings {
+                let ring: Vec<Point2D> = ring.iter().map(|p| Point2D::new(other.unit.to_mm(p.x), other.unit.to_mm(p.y))).collect();
+                shapes.add_ring(Role::Cut, &ring, clearance);
+            }
+        }
+        for area in &layout.rule_areas {
+            if area.forbids(KeepoutKind::CopperPour, &zone.layer) && area.overlaps_box(&bounds) {
+                shapes.add_ring(Role::Cut, &area.polygon_mm(), MARGIN);
+            }
+        }
+        let hatched = zone.fill_type == ZoneFillType::Hatched;
+        if hatched {
+            let width = zone.hatch_width.map_or(self.config.hatch_width, to_mm);
+            let gap = zone.hatch_gap.map_or(self.config.hatch_gap, to_mm);
+            shapes.add_hatch(&bounds, width, gap);
+        }
+
+        // Features narrower than the minimum width vanish when the fill is
+        // shrunk by half of it; growing the rest back restores the fill
+        let opening = min_width > EPSILON;
+        let boarded = layout.outline.is_some();
+        if opening {
+            let shrunk = sweep(&shapes.polygons(min_width / 2.0), |c| {
+                c[Role::Zone as usize] > 0
+                    && (!boarded || c[Role::Board as usize] > 0)
+                    && c[Role::Cut as usize] == 0
+                    && c[Role::Relief as usize] == 0
+            });
+            for piece in &shrunk.pieces {
+                shapes.add_grown(Role::Keep, piece, min_width / 2.0);
+            }
+        }
+        let fill = sweep(&shapes.polygons(0.0), |c| {
+            let base = c[Role::Zone as usize] > 0
+                && (!boarded || c[Role::Board as usize] > 0)
+                && c[Role::Cut as usize] == 0;
+            let solid = c[Role::Relief as usize] == 0
+                && (!opening || c[Role::Keep as usize] > 0)
+                && (!hatched || c[Role::Hatch as usize] > 0);
+            base && (solid || c[Role::Spoke as usize] > 0)
+        });
+
+        let connections: Vec<&CopperItem> = nearby.iter().filter(|item| same_net(item)).collect();
+        let mut islands = fill.islands();
+        islands.retain(|island| {
+            let connected = island.iter().any(|&p| {
+                let shape = CopperShape::polygon(fill.pieces[p].clone());
+                connections.iter().any(|item| shape.distance(&item.shape) <= TOUCH)
+            });
+            let area: f64 = island.iter().map(|&p| fill.areas[p]).sum();
+            match zone.island_removal {
+                _ if connected => true,
+                IslandRemoval::Always => false,
+                IslandRemoval::Never => true,
+                IslandRemoval::BelowArea(min) => area >= to_mm(to_mm(min)),
+            }
+        });
+        let from_mm = |v: f64| zone.unit.from_mm(v);
+        islands.into_iter()
+            .flatten()
+            .map(|p| fill.pieces[p].iter().map(|q| Point2D::new(from_mm(q.x), from_mm(q.y))).collect())
+            .collect()
+    }
+
+    /// Add the gap and spokes of a thermal relief around a pad.
+    fn add_thermal_relief(&self, shapes: &mut Shapes, zone: &Zone, layout: &Layout, pad: &CopperItem, min_width: f64) {
+        let to_mm = |v: f64| zone.unit.to_mm(v);
+        let gap = zone.thermal_gap.map_or(self.config.thermal_gap, to_mm);
+        let width = zone.thermal_spoke_width.map_or(self.config.thermal_spoke_width, to_mm);
+        let spokes = zone.thermal_spokes.unwrap_or(self.config.thermal_spokes);
+        shapes.add(Role::Relief, &pad.shape, gap);
+
+        let Some(bounds) = pad.shape.bounding_box() else { return };
+        let center = bounds.center();
+        let rotation = pad.pad.as_ref().map_or(0.0, |(component, _)| layout.components[*component].rotation);
+        let length = pad.shape.max_distance_from(&center) + gap + width.max(min_width);
+        for k in 0..spokes {
+            let angle = (rotation + 360.0 * k as f64 / spokes as f64).to_radians();
+            let end = Point2D::new(center.x + length * angle.cos(), center.y + length * angle.sin());
+            let (nx, ny) = (-angle.sin() * width / 2.0, angle.cos() * width / 2.0);
+            shapes.push(Role::Spoke, vec![
+                Point2D::new(center.x + nx, center.y + ny),
+                Point2D::new(end.x + nx, end.y + ny),
+                Point2D::new(end.x - nx, end.y - ny),
+                Point2D::new(center.x - nx, center.y - ny),
+            ]);
+        }
+    }
+}
+
+/// Refill all copper zones of a layout with default settings.
+pub fn fill_zones(layout: &mut Layout) -> usize {
+    ZoneFiller::new(ZoneFillConfig::default()).apply(layout)
+}
+
+/// Clear the fill of every copper zone. Teardrops keep theirs, which is
+/// their outline.
+pub fn clear_fills(layout: &mut Layout) {
+    for zone in layout.zones.iter_mut().filter(|z| z.kind == ZoneKind::Copper) {
+        zone.filled.clear();
+    }
+}
+
+/// Sweep input: shapes grown by a per-shape amount, plus fixed polygons.
+#[derive(Default)]
+struct Shapes {
+    grown: Vec<(Role, CopperShape, f64)>,
+    fixed: Vec<(Role, Vec<Point2D>)>,
+}
+
+impl Shapes {
+    /// Add a copper shape grown by `grow`.
+    fn add(&mut self, role: Role, shape: &CopperShape, grow: f64) {
+        self.grown.push((role, shape.clone(), grow));
+    }
+
+    /// Add a polygon whose edges are kept clear by `grow`: the area
+    /// itself for cuts, or the area shrunk by `grow` for outlines.
+    fn add_ring(&mut self, role: Role, ring: &[Point2D], grow: f64) {
+        if ring.len() < 3 {
+            return;
+        }
+        self.fixed.push((role, ring.to_vec()));
+        for i in 0..ring.len() {
+            let edge = CopperShape::capsule(ring[i], ring[(i + 1) % ring.len()], 0.0);
+            self.grown.push((Role::Cut, edge, grow));
+        }
+    }
+
+    /// Add a polygon grown by `grow`.
+    fn add_grown(&mut self, role: Role, polygon: &[Point2D], grow: f64) {
+        let mut shape = CopperShape::polygon(polygon.to_vec());
+        shape.radius = grow;
+        self.grown.push((role, shape, 0.0));
+    }
+
+    /// Add a fixed polygon.
+    fn push(&mut self, role: Role, polygon: Vec<Point2D>) {
+        self.fixed.push((role, polygon));
+    }
+
+    /// Add horizontal and vertical hatch bars over `bounds`.
+    fn add_hatch(&mut self, bounds: &BoundingBox, width: f64, gap: f64) {
+        let pitch = (width + gap).max(EPSILON);
+        let rect = |x0: f64, y0: f64, x1: f64, y1: f64| {
+            vec![Point2D::new(x0, y0), Point2D::new(x1, y0), Point2D::new(x1, y1), Point2D::new(x0, y1)]
+        };
+        let (min, max) = (bounds.min, bounds.max);
+        let mut x = min.x;
+        while x < max.x {
+            self.push(Role::Hatch, rect(x, min.y, x + width, max.y));
+            x += pitch;
+        }
+        let mut y = min.y;
+        while y < max.y {
+            self.push(Role::Hatch, rect(min.x, y, max.x, y + width));
+            y += pitch;
+        }
+    }
+
+    /// Polygons for the sweep, with cuts and reliefs grown by `extra`.
+    /// Outline edges are cuts too, so `extra` shrinks outlines.
+    fn polygons(&self, extra: f64) -> Vec<(Role, Vec<Point2D>)> {
+        let mut polygons = self.fixed.clone();
+        for (role, shape, grow) in &self.grown {
+            let grow = match role {
+                Role::Cut | Role::Relief => grow + extra,
+                _ => *grow,
+            };
+            polygons.extend(shape_polygons(shape, grow).into_iter().map(|p| (*role, p)));
+        }
+        polygons
+    }
+}
+
+/// Polygons covering a shape grown by `grow`, with circles replaced by
+/// circumscribed polygons.
+fn shape_polygons(shape: &CopperShape, grow: f64) -> Vec<Vec<Point2D>> {
+    let radius = shape.radius + grow;
+    match &shape.core {
+        ShapeCore::Segment(a, b) => {
+            if radius <= EPSILON {
+                return Vec::new();
+            }
+            vec![convex_hull(circle_points(a, radius).into_iter().chain(circle_points(b, radius)).collect())]
+        }
+        ShapeCore::Polygon(points) => {
+            if points.len() < 3 {
+                return Vec::new();
+            }
+            if radius <= EPSILON {
+                return vec![points.clone()];
+            }
+            if is_convex(points) {
+                return vec![convex_hull(points.iter().flat_map(|p| circle_points(p, radius)).collect())];
+            }
+            let mut polygons = vec![points.clone()];
+            for i in 0..points.len() {
+                let (a, b) = (&points[i], &points[(i + 1) % points.len()]);
+                polygons.push(convex_hull(circle_points(a, radius).into_iter().chain(circle_points(b, radius)).collect()));
+            }
+            polygons
+        }
+    }
+}
+
+/// Vertices of a polygon circumscribing a circle.
+fn circle_points(center: &Point2D, radius: f64) -> Vec<Point2D> {
+    let r = radius / (std::f64::consts::PI / ARC_SEGMENTS as f64).cos();
+    (0..ARC_SEGMENTS)
+        .map(|i| {
+            let angle = std::f64::consts::TAU * i as f64 / ARC_SEGMENTS as f64;
+            Point2D::new(center.x + r * angle.cos(), center.y + r * angle.sin())
+        })
+        .collect()
+}
+
+/// Convex hull (monotone chain), counter-clockwise.
+fn convex_hull(mut points: Vec<Point2D>) -> Vec<Point2D> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: &Point2D, a: &Point2D, b: &Point2D| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    let mut hull: Vec<Point2D> = Vec::with_capacity(points.len() + 1);
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<dyn Iterator<Item = &Point2D>> = if pass == 0 { Box::new(points.iter()) } else { Box::new(points.iter().rev()) };
+        for p in ordered {
+            while hull.len() >= start + 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Whether a polygon is convex.
+fn is_convex(points: &[Point2D]) -> bool {
+    let n = points.len();
+    let mut sign = 0.0;
+    for i in 0..n {
+        let (a, b, c) = (&points[i], &points[(i + 1) % n], &points[(i + 2) % n]);
+        let cross = (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x);
+        if cross.abs() <= EPSILON {
+            continue;
+        }
+        if sign * cross < 0.0 {
+            return false;
+        }
+        sign = cross;
+    }
+    true
+}
+
+/// A non-horizontal polygon edge, from its lower to its upper end.
+struct Edge {
+    a: Point2D,
+    b: Point2D,
+    polygon: usize,
+}
+
+impl Edge {
+    fn x_at(&self, y: f64) -> f64 {
+        self.a.x + (self.b.x - self.a.x) * (y - self.a.y) / (self.b.y - self.a.y)
+    }
+}
+
+/// Result of a sweep: filled polygons and how they join into islands.
+struct Fill {
+    pieces: Vec<Vec<Point2D>>,
+    areas: Vec<f64>,
+    joins: Vec<(usize, usize)>,
+}
+
+impl Fill {
+    /// Pieces grouped by island.
+    fn islands(&self) -> Vec<Vec<usize>> {
+        let mut sets = UnionFind::new(self.pieces.len());
+        for &(a, b) in &self.joins {
+            sets.union(a, b);
+        }
+        let mut islands: Vec<Vec<usize>> = Vec::new();
+        let mut index = vec![usize::MAX; self.pieces.len()];
+        for piece in 0..self.pieces.len() {
+            let root = sets.find(piece);
+            if index[root] == usize::MAX {
+                index[root] = islands.len();
+                islands.push(Vec::new());
+            }
+            islands[index[root]].push(piece);
+        }
+        islands
+    }
+}
+
+/// Compute the area where `filled` holds, given the number of polygons of
+/// each role covering a point. Polygons use the even-odd rule.
+fn sweep(polygons: &[(Role, Vec<Point2D>)], filled: impl Fn(&Counts) -> bool) -> Fill {
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut ys: Vec<f64> = Vec::new();
+    for (index, (_, points)) in polygons.iter().enumerate() {
+        for i in 0..points.len() {
+            let (p, q) = (points[i], points[(i + 1) % points.len()]);
+            ys.push(p.y);
+            if (p.y - q.y).abs() > EPSILON {
+                let (a, b) = if p.y < q.y { (p, q) } else { (q, p) };
+                edges.push(Edge { a, b, polygon: index });
+            }
+        }
+    }
+    edges.sort_by(|e, f| e.a.y.total_cmp(&f.a.y));
+    ys.sort_by(f64::total_cmp);
+    ys.dedup_by(|a, b| (*a - *b).abs() <= EPSILON);
+
+    let mut builder = PieceBuilder::default();
+    let mut inside = vec![false; polygons.len()];
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    for band in ys.windows(2) {
+        let (y0, y1) = (band[0], band[1]);
+        while next < edges.len() && edges[next].a.y <= y0 + EPSILON {
+            active.push(next);
+            next += 1;
+        }
+        active.retain(|&e| edges[e].b.y > y0 + EPSILON);
+
+        // Split the band where edges cross so their order is fixed
+        let mut cuts = crossings(&edges, &active, y0, y1);
+        cuts.push(y1);
+        let mut ya = y0;
+        for yb in cuts {
+            let ym = (ya + yb) / 2.0;
+            let mut order: Vec<(f64, usize)> = active.iter().map(|&e| (edges[e].x_at(ym), e)).collect();
+            order.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut counts: Counts = [0; ROLES];
+            let mut left = None;
+            let mut strips = Vec::new();
+            for &(_, e) in &order {
+                let polygon = edges[e].polygon;
+                let role = polygons[polygon].0 as usize;
+                inside[polygon] = !inside[polygon];
+                if inside[polygon] {
+                    counts[role] += 1;
+                } else {
+                    counts[role] -= 1;
+                }
+                match (left, filled(&counts)) {
+                    (None, true) => left = Some(e),
+                    (Some(l), false) => {
+                        let (l0, l1) = (edges[l].x_at(ya), edges[l].x_at(yb));
+                        let (r0, r1) = (edges[e].x_at(ya), edges[e].x_at(yb));
+                        if r0 - l0 > EPSILON || r1 - l1 > EPSILON {
+                            strips.push([l0, r0, l1, r1]);
+                        }
+                        left = None;
+                    }
+                    _ => {}
+                }
+            }
+            builder.band(ya, yb, strips);
+            ya = yb;
+        }
+    }
+    builder.finish()
+}
+
+/// Heights within `y0..y1` where active edges cross, in order.
+fn crossings(edges: &[Edge], active: &[usize], y0: f64, y1: f64) -> Vec<f64> {
+    let mut order: Vec<(f64, f64)> = active.iter().map(|&e| (edges[e].x_at(y0), edges[e].x_at(y1))).collect();
+    order.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    let mut ys = Vec::new();
+    for i in 1..order.len() {
+        let mut j = i;
+        while j > 0 && order[j - 1].1 > order[j].1 + EPSILON {
+            let (p, q) = (order[j - 1], order[j]);
+            let (d0, d1) = (p.0 - q.0, p.1 - q.1);
+            let y = y0 + (y1 - y0) * d0 / (d0 - d1);
+            if y > y0 + EPSILON && y < y1 - EPSILON {
+                ys.push(y);
+            }
+            order.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+    ys.sort_by(f64::total_cmp);
+    ys.dedup_by(|a, b| (*a - *b).abs() <= EPSILON);
+    ys
+}
+
+/// A piece being built from strips stacked bottom to top.
+struct Piece {
+    left: Vec<Point2D>,
+    right: Vec<Point2D>,
+    area: f64,
+}
+
+/// Merges the strips of successive bands into pieces and records which
+/// pieces touch.
+#[derive(Default)]
+struct PieceBuilder {
+    pieces: Vec<Piece>,
+    /// Top edges of the previous band: piece, left and right x
+    top: Vec<(usize, f64, f64)>,
+    joins: Vec<(usize, usize)>,
+}
+
+impl PieceBuilder {
+    /// Add the strips `[l0, r0, l1, r1]` of band `ya..yb`, left to right.
+    fn band(&mut self, ya: f64, yb: f64, strips: Vec<[f64; 4]>) {
+        let below = std::mem::take(&mut self.top);
+        let mut first = 0;
+        for [l0, r0, l1, r1] in strips {
+            while first < below.len() && below[first].2 <= l0 + EPSILON {
+                first += 1;
+            }
+            let mut continued = None;
+            let mut touching = Vec::new();
+            for &(piece, l, r) in below[first..].iter().take_while(|(_, l, _)| *l < r0 - EPSILON) {
+                if (l - l0).abs() <= EPSILON && (r - r0).abs() <= EPSILON {
+                    continued = Some(piece);
+                } else if r.min(r0) - l.max(l0) > EPSILON {
+                    touching.push(piece);
+                }
+            }
+            let area = ((r0 - l0) + (r1 - l1)) / 2.0 * (yb - ya);
+            let piece = match continued {
+                Some(piece) => {
+                    let p = &mut self.pieces[piece];
+                    p.left.push(Point2D::new(l1, yb));
+                    p.right.push(Point2D::new(r1, yb));
+                    p.area += area;
+                    piece
+                }
+                None => {
+                    self.pieces.push(Piece {
+                        left: vec![Point2D::new(l0, ya), Point2D::new(l1, yb)],
+                        right: vec![Point2D::new(r0, ya), Point2D::new(r1, yb)],
+                        area,
+                    });
+                    self.pieces.len() - 1
+                }
+            };
+            self.joins.extend(touching.into_iter().map(|other| (piece, other)));
+            self.top.push((piece, l1, r1));
+        }
+    }
+
+    fn finish(self) -> Fill {
+        let mut fill = Fill { pieces: Vec::new(), areas: Vec::new(), joins: self.joins };
+        for piece in self.pieces {
+            let mut ring = piece.left;
+            ring.extend(piece.right.into_iter().rev());
+            fill.pieces.push(simplify(ring));
+            fill.areas.push(piece.area);
+        }
+        fill
+    }
+}
+
+/// Remove repeated and collinear vertices from a ring.
+fn simplify(ring: Vec<Point2D>) -> Vec<Point2D> {
+    let mut out: Vec<Point2D> = Vec::with_capacity(ring.len());
+    for p in ring {
+        if out.last().is_some_and(|q| q.distance(&p) <= EPSILON) {
+            continue;
+        }
+        while out.len() >= 2 {
+            let (a, b) = (out[out.len() - 2], out[out.len() - 1]);
+            if ((b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)).abs() > EPSILON {
+                break;
+            }
+            out.pop();
+        }
+        out.push(p);
+    }
+    while out.len() > 1 && out[0].distance(&out[out.len() - 1]) <= EPSILON {
+        out.pop();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+    use crate::layout::{Pad, PlacedComponent, Trace};
+    use crate::units::LengthUnit;
+
+    fn square(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Point2D> {
+        vec![Point2D::new(x0, y0), Point2D::new(x1, y0), Point2D::new(x1, y1), Point2D::new(x0, y1)]
+    }
+
+    fn area(layout: &Layout, zone: usize) -> f64 {
+        layout.zones[zone].filled.iter()
+            .map(|ring| {
+                let n = ring.len();
+                (0..n).map(|i| ring[i].x * ring[(i + 1) % n].y - ring[(i + 1) % n].x * ring[i].y).sum::<f64>().abs() / 2.0
+            })
+            .sum()
+    }
+
+    fn covered(layout: &Layout, zone: usize, x: f64, y: f64) -> bool {
+        layout.zones[zone].filled.iter().any(|ring| crate::geometry::point_in_polygon(&Point2D::new(x, y), ring))
+    }
+
+    #[test]
+    fn test_fill_keeps_clearance_to_other_nets() {
+        let mut layout = Layout::with_board_size(40.0, 40.0, LengthUnit::Mm);
+        layout.zones.push(Zone::new("GND", "F.Cu", square(5.0, 5.0, 35.0, 35.0)));
+        layout.traces.push(Trace {
+            net: "SIG".to_string(),
+            layer: "F.Cu".to_string(),
+            start: Position::new(10.0, 20.0),
+            end: Position::new(30.0, 20.0),
+            width: 0.5,
+            unit: LengthUnit::Mm,
+        });
+        layout.components.push(
+            PlacedComponent::new("TP1", "", "")
+                .at(8.0, 8.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("GND")),
+        );
+
+        assert_eq!(fill_zones(&mut layout), 1);
+        let filled = area(&layout, 0);
+        assert!(filled > 850.0 && filled < 900.0, "area {}", filled);
+        assert!(!covered(&layout, 0, 20.0, 20.0));
+        assert!(!covered(&layout, 0, 20.0, 20.4));
+        assert!(covered(&layout, 0, 20.0, 20.6));
+
+        let track = CopperShape::from_trace(&layout.traces[0]);
+        for ring in &layout.zones[0].filled {
+            assert!(CopperShape::polygon(ring.clone()).distance(&track) >= 0.2 - 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_thermal_relief_and_solid_connection() {
+        let mut layout = Layout::with_board_size(40.0, 40.0, LengthUnit::Mm);
+        layout.zones.push(Zone::new("GND", "F.Cu", square(5.0, 5.0, 35.0, 35.0)));
+        layout.components.push(
+            PlacedComponent::new("TP1", "", "")
+                .at(20.0, 20.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 2.0, 2.0).with_net("GND")),
+        );
+
+        fill_zones(&mut layout);
+        // Gap beside the pad, spoke through it
+        assert!(!covered(&layout, 0, 21.25, 20.9));
+        assert!(covered(&layout, 0, 21.25, 20.0));
+        assert!(covered(&layout, 0, 20.5, 20.0));
+        assert!(!covered(&layout, 0, 20.9, 20.9));
+
+        layout.zones[0].pad_connection = PadConnection::Solid;
+        fill_zones(&mut layout);
+        assert!(covered(&layout, 0, 20.9, 20.9));
+        // Only the corners are rounded by the minimum width
+        assert!(area(&layout, 0) > 899.9);
+    }
+
+    #[test]
+    fn test_islands_priority_and_hatch() {
+        let mut layout = Layout::with_board_size(40.0, 40.0, LengthUnit::Mm);
+        layout.zones.push(Zone::new("GND", "F.Cu", square(5.0, 5.0, 35.0, 35.0)));
+        // A wall of other-net copper splits the zone; only the left part
+        // reaches a GND pad
+        layout.zones.push(Zone {
+            island_removal: IslandRemoval::Never,
+            ..Zone::new("VCC", "F.Cu", square(18.0, 0.0, 22.0, 40.0)).with_priority(1)
+        });
+        layout.components.push(
+            PlacedComponent::new("TP1", "", "")
+                .at(10.0, 20.0)
+                .with_pad(Pad::smd("1", 0.0, 0.0, 1.0, 1.0).with_net("GND")),
+        );
+
+        fill_zones(&mut layout);
+        assert!(covered(&layout, 0, 10.0, 30.0));
+        assert!(!covered(&layout, 0, 20.0, 30.0));
+        assert!(!covered(&layout, 0, 30.0, 30.0));
+        assert!(covered(&layout, 1, 20.0, 30.0));
+
+        layout.zones[0].island_removal = IslandRemoval::BelowArea(100.0);
+        fill_zones(&mut layout);
+        assert!(covered(&layout, 0, 30.0, 30.0));
+
+        layout.zones[0].fill_type = ZoneFillType::Hatched;
+        fill_zones(&mut layout);
+        assert!(covered(&layout, 0, 5.5, 30.0));
+        assert!(!covered(&layout, 0, 7.0, 7.0));
+
+        layout.zones[0].fill_type = ZoneFillType::None;
+        fill_zones(&mut layout);
+        assert!(!layout.zones[0].is_filled());
+    }
+}